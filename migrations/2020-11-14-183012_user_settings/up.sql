@@ -0,0 +1,4 @@
+create table user_settings (
+    "user" int8 primary key,
+    hide_holdings boolean not null default false
+);
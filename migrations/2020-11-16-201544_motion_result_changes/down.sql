@@ -0,0 +1 @@
+drop table motion_result_changes;
\ No newline at end of file
@@ -0,0 +1,40 @@
+create table motion_result_changes (
+    rowid serial8 primary key,
+    motion int8 not null references motions(rowid),
+    changed_at timestamptz not null,
+    is_win boolean not null,
+    yes_votes int8 not null,
+    no_votes int8 not null
+);
+
+create index on motion_result_changes (motion, changed_at);
+
+-- Rebuild the history from the vote transfers. The first row for each motion is the result it was created with.
+with running as (
+  select
+    t.rowid,
+    t.to_motion as motion,
+    t.happened_at,
+    m.is_super,
+    (sum(case when v.direction then t.to_votes else 0 end) over w)::int8 as yes_votes,
+    (sum(case when v.direction then 0 else t.to_votes end) over w)::int8 as no_votes
+  from transfers t
+  join motions m on m.rowid = t.to_motion
+  join motion_votes v on v.motion = t.to_motion and v."user" = t.from_user
+  window w as (partition by t.to_motion order by t.happened_at, t.rowid)
+), results as (
+  select
+    *,
+    case
+      when is_super then (yes_votes / 2 > no_votes or (yes_votes / 2 = no_votes and yes_votes % 2 > 0))
+      else yes_votes > no_votes
+    end as is_win
+  from running
+), flips as (
+  select *, lag(is_win) over (partition by motion order by happened_at, rowid) as prev_is_win
+  from results
+)
+insert into motion_result_changes (motion, changed_at, is_win, yes_votes, no_votes)
+select motion, happened_at, is_win, yes_votes, no_votes
+from flips
+where prev_is_win is null or prev_is_win <> is_win;
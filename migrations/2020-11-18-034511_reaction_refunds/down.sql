@@ -0,0 +1,11 @@
+alter table user_settings drop column vote_receipts;
+
+drop table reaction_votes;
+
+-- refunds can't be represented without the new transfer type
+delete from transfers where transfer_ty = 'motion_vote_refund';
+alter table transfers drop constraint refund_is_returned;
+alter table transfers drop constraint motion_matches_ty;
+alter table transfers add constraint motion_matches_ty check ((to_motion IS NOT NULL) = transfer_ty IN ('motion_create', 'motion_vote'));
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'generated', 'admin_fabricate', 'admin_give', 'give', 'command_fabricate'));
\ No newline at end of file
@@ -0,0 +1,23 @@
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'give', 'command_fabricate'));
+alter table transfers drop constraint motion_matches_ty;
+alter table transfers add constraint motion_matches_ty check ((to_motion IS NOT NULL) = transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund'));
+-- a refund gives capital back out of a motion, taking back (negative) votes
+alter table transfers add constraint refund_is_returned check ((NOT (transfer_ty = 'motion_vote_refund')) OR (from_user IS NULL and to_user IS NOT NULL and to_votes < 0));
+
+create table reaction_votes (
+    rowid bigserial primary key,
+    motion int8 not null references motions(rowid),
+    "user" int8 not null,
+    emoji text not null,
+    transfer int8 not null references transfers(rowid),
+    refund_transfer int8 references transfers(rowid),
+    votes int8 not null,
+    cost int8 not null,
+    cast_at timestamptz not null
+);
+
+create index reaction_votes_by_reaction on reaction_votes (motion, "user", emoji, cast_at);
+
+alter table user_settings add column vote_receipts text not null default 'always';
+alter table user_settings add constraint vote_receipts_enum check (vote_receipts IN ('always', 'errors', 'never'));
@@ -0,0 +1,2 @@
+drop table command_aliases;
+drop table vote_words;
\ No newline at end of file
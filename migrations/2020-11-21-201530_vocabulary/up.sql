@@ -0,0 +1,85 @@
+create table vote_words (
+    word text primary key,
+    meaning text not null,
+    constraint meaning_enum check (meaning IN ('yes', 'no', 'zero', 'ignore')),
+    constraint word_is_lowercase check (word = lower(word))
+);
+
+--const YES_WORDS, NO_WORDS, ZERO_WORDS, IGNORE_WORDS
+insert into vote_words (word, meaning) VALUES
+    ('favor', 'yes'),
+    ('for', 'yes'),
+    ('approve', 'yes'),
+    ('yes', 'yes'),
+    ('y', 'yes'),
+    ('aye', 'yes'),
+    ('yeah', 'yes'),
+    ('yeah!', 'yes'),
+    (U&'\+01F44D', 'yes'),
+    (':+1:', 'yes'),
+    (':thumbsup:', 'yes'),
+    (U&'\+01F646', 'yes'),
+    (':ok_woman:', 'yes'),
+    (U&'\2B55', 'yes'),
+    (':o:', 'yes'),
+    (U&'\+01F44C', 'yes'),
+    (':ok_hand:', 'yes'),
+    (U&'\+01F197', 'yes'),
+    (':ok:', 'yes'),
+    (U&'\2705', 'yes'),
+    ('pass', 'yes'),
+    ('neigh', 'no'),
+    ('fail', 'no'),
+    ('no', 'no'),
+    ('against', 'no'),
+    ('nay', 'no'),
+    ('no, asshole', 'no'),
+    ('no, you wanker', 'no'),
+    ('no, cunt', 'no'),
+    ('negative', 'no'),
+    ('never', 'no'),
+    ('negatory', 'no'),
+    ('veto', 'no'),
+    (U&'\+01F44E', 'no'),
+    ('deny', 'no'),
+    (':-1:', 'no'),
+    (':thumbsdown:', 'no'),
+    ('.i na go''i', 'no'),
+    (U&'\+01F645', 'no'),
+    (':no_good:', 'no'),
+    (U&'\274C', 'no'),
+    (U&'\+01F196', 'no'),
+    (':ng:', 'no'),
+    ('naw', 'no'),
+    ('zero', 'zero'),
+    ('zerovote', 'zero'),
+    ('nil', 'zero'),
+    ('nada', 'zero'),
+    ('nothing', 'zero'),
+    ('in', 'ignore'),
+    ('i', 'ignore'),
+    ('think', 'ignore'),
+    ('say', 'ignore'),
+    ('fuck', 'ignore'),
+    ('hell', 'ignore');
+
+create table command_aliases (
+    alias text primary key,
+    command text not null
+);
+
+insert into command_aliases (alias, command) VALUES
+    ('?', 'help'),
+    ('h', 'help'),
+    ('v', 'version_info'),
+    ('info', 'version_info'),
+    ('version', 'version_info'),
+    ('b', 'balances'),
+    ('bal', 'balances'),
+    ('balance', 'balances'),
+    ('i', 'balances'),
+    ('inv', 'balances'),
+    ('inventory', 'balances'),
+    ('leaderboard', 'top'),
+    ('lb', 'top'),
+    ('history_csv', 'transaction_history_csv');
@@ -0,0 +1,2 @@
+drop table guild_settings;
+alter table user_settings drop column locale;
\ No newline at end of file
@@ -0,0 +1,7 @@
+-- null means no preference, so the guild's language (or English) is used
+alter table user_settings add column locale text;
+
+create table guild_settings (
+    guild int8 primary key,
+    locale text
+);
@@ -0,0 +1,9 @@
+delete from transfers where transfer_ty = 'admin_burn';
+alter table transfers drop constraint burn_has_no_receiver;
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'give', 'command_fabricate'));
+alter table transfers drop column admin_action;
+
+drop table admin_actions;
+drop table admin_roles;
+drop table admin_users;
\ No newline at end of file
@@ -0,0 +1,32 @@
+-- who may use privileged commands: individual users, or anyone with a role in a guild
+create table admin_users (
+    "user" int8 primary key,
+    granted_by int8 not null,
+    granted_at timestamptz not null
+);
+
+create table admin_roles (
+    role int8 primary key,
+    guild int8 not null,
+    granted_by int8 not null,
+    granted_at timestamptz not null
+);
+
+-- every use of a privileged command; the transfers it made point back at it
+create table admin_actions (
+    rowid bigserial primary key,
+    actor int8 not null,
+    action text not null,
+    reason text,
+    message_id int8,
+    happened_at timestamptz not null,
+    constraint action_enum check (action IN ('fabricate', 'burn', 'adjust', 'transfer'))
+);
+
+alter table transfers add column admin_action int8 references admin_actions(rowid);
+create index transfers_by_admin_action on transfers (admin_action) where admin_action is not null;
+
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'admin_burn', 'give', 'command_fabricate'));
+-- a burn destroys items, so there's no one on the receiving end
+alter table transfers add constraint burn_has_no_receiver check ((NOT (transfer_ty = 'admin_burn')) OR (from_user IS NOT NULL and to_user IS NULL));
@@ -0,0 +1,76 @@
+drop function fungible_transfer(bigint, bigint, bigint, bigint, text, text);
+drop function fungible_fabricate(bigint, bigint, bigint, text, text);
+
+CREATE OR REPLACE FUNCTION fungible_transfer(
+        fromuser bigint,
+        touser bigint,
+        amount bigint,
+        fungible_type text,
+        comment text
+    ) returns text
+    AS $$
+    DECLARE
+        from_balance bigint;
+        to_balance bigint;
+    BEGIN
+        IF amount < 1 THEN
+            RETURN 'invalid amount';
+        END IF;
+        select balance into from_balance from balance_history where balance_history."user" = fromuser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        select balance into   to_balance from balance_history where balance_history."user" =   touser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        IF from_balance IS NULL THEN
+            from_balance := 0;
+        END IF;
+        IF to_balance IS NULL THEN
+            to_balance := 0;
+        END IF;
+        IF from_balance < amount THEN
+            RETURN 'not enough fungibles';
+        END IF;
+        from_balance := from_balance - amount;
+        to_balance := to_balance + amount;
+        INSERT INTO transfers ("from_user", "quantity", "to_user", "from_balance", "to_balance", "happened_at", "message_id", "ty", "comment", "transfer_ty")
+                       VALUES ( fromuser  ,  amount   ,  touser  ,  from_balance ,  to_balance ,  NOW()       ,  NULL       ,  fungible_type, comment, 'admin_give');
+        RETURN 'done';
+    END;
+    $$
+    LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION fungible_fabricate(
+        touser bigint,
+        amount bigint,
+        fungible_type text,
+        comment text
+    ) returns text
+    AS $$
+    DECLARE
+        from_balance bigint;
+        to_balance bigint;
+    BEGIN
+        IF amount < 1 THEN
+            RETURN 'invalid amount';
+        END IF;
+        select balance into   to_balance from balance_history where balance_history."user" =   touser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        IF to_balance IS NULL THEN
+            to_balance := 0;
+        END IF;
+        to_balance := to_balance + amount;
+        INSERT INTO transfers ("from_user", "quantity", "to_user", "from_balance", "to_balance", "happened_at", "message_id", "ty", "comment", "transfer_ty")
+                       VALUES ( NULL      ,  amount   ,  touser  ,  NULL         ,  to_balance ,  NOW()       ,  NULL       ,  fungible_type, comment, 'admin_fabricate');
+        RETURN 'done';
+    END;
+    $$
+    LANGUAGE plpgsql;
+
+drop trigger admin_actions_no_truncate on admin_actions;
+drop trigger admin_actions_append_only on admin_actions;
+drop function admin_actions_append_only;
+
+drop index admin_actions_by_target;
+drop index admin_actions_by_actor;
+
+delete from admin_actions where action IN ('grant_admin', 'revoke_admin', 'refresh_motion');
+alter table admin_actions drop constraint action_enum;
+alter table admin_actions add constraint action_enum check (action IN ('fabricate', 'burn', 'adjust', 'transfer'));
+alter table admin_actions drop column parameters;
+alter table admin_actions drop column target;
\ No newline at end of file
@@ -0,0 +1,106 @@
+alter table admin_actions add column target int8;
+alter table admin_actions add column parameters jsonb not null default '{}';
+alter table admin_actions drop constraint action_enum;
+alter table admin_actions add constraint action_enum check (action IN ('fabricate', 'burn', 'adjust', 'transfer', 'grant_admin', 'revoke_admin', 'refresh_motion'));
+
+-- fill in actions recorded before targets and parameters were
+update admin_actions set
+    target = coalesce(transfers.from_user, transfers.to_user),
+    parameters = case admin_actions.action
+        when 'adjust' then jsonb_build_object('ty', transfers.ty, 'balance', coalesce(transfers.to_balance, transfers.from_balance))
+        when 'transfer' then jsonb_build_object('ty', transfers.ty, 'amount', transfers.quantity, 'to', transfers.to_user)
+        else jsonb_build_object('ty', transfers.ty, 'amount', transfers.quantity)
+    end
+from transfers
+where transfers.admin_action = admin_actions.rowid;
+
+create index admin_actions_by_actor on admin_actions (actor, rowid);
+create index admin_actions_by_target on admin_actions (target, rowid);
+
+-- the log is append-only, so it can be trusted to say who did what
+CREATE OR REPLACE FUNCTION admin_actions_append_only() returns trigger
+    AS $$
+    BEGIN
+        RAISE EXCEPTION 'admin_actions is append-only';
+    END;
+    $$
+    LANGUAGE plpgsql;
+
+create trigger admin_actions_append_only before update or delete on admin_actions
+    for each row execute procedure admin_actions_append_only();
+create trigger admin_actions_no_truncate before truncate on admin_actions
+    for each statement execute procedure admin_actions_append_only();
+
+-- the SQL functions are an admin code path too, so they now need to know who's calling them
+drop function fungible_transfer(bigint, bigint, bigint, text, text);
+drop function fungible_fabricate(bigint, bigint, text, text);
+
+CREATE OR REPLACE FUNCTION fungible_transfer(
+        actor bigint,
+        fromuser bigint,
+        touser bigint,
+        amount bigint,
+        fungible_type text,
+        reason text
+    ) returns text
+    AS $$
+    DECLARE
+        from_balance bigint;
+        to_balance bigint;
+        action_id bigint;
+    BEGIN
+        IF amount < 1 THEN
+            RETURN 'invalid amount';
+        END IF;
+        select balance into from_balance from balance_history where balance_history."user" = fromuser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        select balance into   to_balance from balance_history where balance_history."user" =   touser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        IF from_balance IS NULL THEN
+            from_balance := 0;
+        END IF;
+        IF to_balance IS NULL THEN
+            to_balance := 0;
+        END IF;
+        IF from_balance < amount THEN
+            RETURN 'not enough fungibles';
+        END IF;
+        from_balance := from_balance - amount;
+        to_balance := to_balance + amount;
+        INSERT INTO admin_actions ("actor", "action", "target", "parameters", "reason", "happened_at")
+                           VALUES ( actor  , 'transfer', fromuser, jsonb_build_object('ty', fungible_type, 'amount', amount, 'to', touser, 'via', 'sql'), reason, NOW())
+                           RETURNING rowid INTO action_id;
+        INSERT INTO transfers ("from_user", "quantity", "to_user", "from_balance", "to_balance", "happened_at", "message_id", "ty", "comment", "transfer_ty", "admin_action")
+                       VALUES ( fromuser  ,  amount   ,  touser  ,  from_balance ,  to_balance ,  NOW()       ,  NULL       ,  fungible_type, reason, 'admin_give', action_id);
+        RETURN 'done';
+    END;
+    $$
+    LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION fungible_fabricate(
+        actor bigint,
+        touser bigint,
+        amount bigint,
+        fungible_type text,
+        reason text
+    ) returns text
+    AS $$
+    DECLARE
+        to_balance bigint;
+        action_id bigint;
+    BEGIN
+        IF amount < 1 THEN
+            RETURN 'invalid amount';
+        END IF;
+        select balance into   to_balance from balance_history where balance_history."user" =   touser and balance_history.ty = fungible_type order by happened_at desc limit 1 for update;
+        IF to_balance IS NULL THEN
+            to_balance := 0;
+        END IF;
+        to_balance := to_balance + amount;
+        INSERT INTO admin_actions ("actor", "action", "target", "parameters", "reason", "happened_at")
+                           VALUES ( actor  , 'fabricate', touser, jsonb_build_object('ty', fungible_type, 'amount', amount, 'via', 'sql'), reason, NOW())
+                           RETURNING rowid INTO action_id;
+        INSERT INTO transfers ("from_user", "quantity", "to_user", "from_balance", "to_balance", "happened_at", "message_id", "ty", "comment", "transfer_ty", "admin_action")
+                       VALUES ( NULL      ,  amount   ,  touser  ,  NULL         ,  to_balance ,  NOW()       ,  NULL       ,  fungible_type, reason, 'admin_fabricate', action_id);
+        RETURN 'done';
+    END;
+    $$
+    LANGUAGE plpgsql;
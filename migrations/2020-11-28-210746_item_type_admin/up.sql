@@ -0,0 +1,5 @@
+-- retired item types can't be given, fabricated or looked up by alias, but their history stays
+alter table item_types add column retired bool not null default false;
+
+alter table admin_actions drop constraint action_enum;
+alter table admin_actions add constraint action_enum check (action IN ('fabricate', 'burn', 'adjust', 'transfer', 'grant_admin', 'revoke_admin', 'refresh_motion', 'create_item_type', 'edit_item_type', 'retire_item_type', 'restore_item_type', 'add_alias', 'remove_alias'));
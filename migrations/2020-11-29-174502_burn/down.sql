@@ -0,0 +1,5 @@
+delete from transfers where transfer_ty = 'burn';
+alter table transfers drop constraint makes_sense;
+alter table transfers add constraint makes_sense check (to_user IS NOT NULL or from_user IS NOT NULL);
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'admin_burn', 'give', 'command_fabricate'));
\ No newline at end of file
@@ -0,0 +1,26 @@
+-- someone asking another user to pay them; paying one is an ordinary give, which it points at
+create table payment_requests (
+    rowid bigserial primary key,
+    requested_by int8 not null,
+    payer int8 not null,
+    ty text not null references item_types(name),
+    quantity int8 not null,
+    memo text,
+    requested_at timestamptz not null,
+    expires_at timestamptz not null,
+    message_id int8,
+    -- the DM asking the payer to approve, which they can react to
+    prompt_message_id int8,
+    status text not null default 'pending',
+    settled_at timestamptz,
+    transfer int8 references transfers(rowid),
+    constraint status_enum check (status IN ('pending', 'paid', 'declined', 'cancelled')),
+    constraint positive_quantity check (quantity > 0),
+    constraint not_to_self check (requested_by != payer),
+    constraint settled_when_not_pending check ((status = 'pending') = (settled_at IS NULL)),
+    constraint paid_with_transfer check ((status = 'paid') = (transfer IS NOT NULL))
+);
+
+create index payment_requests_by_payer on payment_requests (payer, rowid);
+create index payment_requests_by_requester on payment_requests (requested_by, rowid);
+create index payment_requests_by_prompt on payment_requests (prompt_message_id) where prompt_message_id is not null;
@@ -0,0 +1,2 @@
+drop table scheduled_transfer_runs;
+drop table scheduled_transfers;
@@ -0,0 +1,37 @@
+-- a give repeated every so often, eg. a salary; each occurrence is an ordinary give
+create table scheduled_transfers (
+    rowid bigserial primary key,
+    from_user int8 not null,
+    to_user int8 not null,
+    ty text not null references item_types(name),
+    quantity int8 not null,
+    memo text,
+    every_seconds int8 not null,
+    -- when the next occurrence is due; moves on whether or not that occurrence could be paid
+    next_at timestamptz not null,
+    -- no occurrences are due after this
+    until timestamptz,
+    created_at timestamptz not null,
+    message_id int8,
+    -- set once no more occurrences will come due, either because of `until` or cancelling
+    finished_at timestamptz,
+    cancelled_by int8,
+    constraint positive_quantity check (quantity > 0),
+    constraint positive_interval check (every_seconds > 0),
+    constraint not_to_self check (from_user != to_user),
+    constraint finished_when_cancelled check (cancelled_by IS NULL OR finished_at IS NOT NULL)
+);
+
+create index scheduled_transfers_by_payer on scheduled_transfers (from_user, rowid);
+create index scheduled_transfers_by_recipient on scheduled_transfers (to_user, rowid);
+create index scheduled_transfers_due on scheduled_transfers (next_at) where finished_at is null;
+
+-- one row per occurrence that came due, so that none is ever paid twice
+create table scheduled_transfer_runs (
+    schedule int8 not null references scheduled_transfers(rowid),
+    due_at timestamptz not null,
+    ran_at timestamptz not null,
+    -- null when the payer couldn't cover it and it was skipped
+    transfer int8 references transfers(rowid),
+    primary key (schedule, due_at)
+);
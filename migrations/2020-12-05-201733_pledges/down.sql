@@ -0,0 +1,6 @@
+drop table pledges;
+delete from transfers where transfer_ty IN ('pledge_escrow', 'pledge_release', 'pledge_refund');
+alter table transfers drop constraint makes_sense;
+alter table transfers add constraint makes_sense check ((to_user IS NOT NULL or from_user IS NOT NULL) and ((NOT (transfer_ty = 'burn')) OR (from_user IS NOT NULL and to_user IS NULL and to_motion IS NULL)));
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'admin_burn', 'burn', 'give', 'command_fabricate'));
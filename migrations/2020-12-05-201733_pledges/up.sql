@@ -0,0 +1,35 @@
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'admin_burn', 'burn', 'give', 'command_fabricate', 'pledge_escrow', 'pledge_release', 'pledge_refund'));
+-- a pledge goes from the pledger into escrow, where it's nobody's, then out of escrow to the
+-- recipient or back to the pledger
+alter table transfers drop constraint makes_sense;
+alter table transfers add constraint makes_sense check (
+    (to_user IS NOT NULL or from_user IS NOT NULL)
+    and ((NOT (transfer_ty = 'burn')) OR (from_user IS NOT NULL and to_user IS NULL and to_motion IS NULL))
+    and ((NOT (transfer_ty = 'pledge_escrow')) OR (from_user IS NOT NULL and to_user IS NULL))
+    and ((NOT (transfer_ty IN ('pledge_release', 'pledge_refund'))) OR (from_user IS NULL and to_user IS NOT NULL))
+);
+
+-- a promise to pay someone if a motion passes (or fails), held in escrow until it's announced
+create table pledges (
+    rowid bigserial primary key,
+    motion int8 not null references motions(rowid),
+    pledged_by int8 not null,
+    recipient int8 not null,
+    ty text not null references item_types(name),
+    quantity int8 not null,
+    -- whether it's paid if the motion passes, rather than if it fails
+    on_pass boolean not null,
+    pledged_at timestamptz not null,
+    message_id int8,
+    escrow_transfer int8 not null references transfers(rowid),
+    -- whether it went to the recipient, or back to the pledger; null until the motion is announced
+    released boolean,
+    settled_at timestamptz,
+    settle_transfer int8 references transfers(rowid),
+    constraint positive_quantity check (quantity > 0),
+    constraint not_to_self check (pledged_by != recipient),
+    constraint settled_together check ((released IS NULL) = (settled_at IS NULL) and (released IS NULL) = (settle_transfer IS NULL))
+);
+
+create index pledges_by_motion on pledges (motion, rowid);
@@ -0,0 +1,4 @@
+drop table treasury_approvals;
+drop table treasury_proposals;
+drop table treasury_members;
+drop table treasuries;
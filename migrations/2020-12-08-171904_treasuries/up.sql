@@ -0,0 +1,56 @@
+-- a shared account, which appears in the ledger (transfers, motion_votes, ...) as the user -rowid,
+-- so that it can't collide with a Discord user id
+create table treasuries (
+    rowid bigserial primary key,
+    name text not null unique,
+    -- how many members must approve a proposal for it to go through
+    threshold int8 not null,
+    created_by int8 not null,
+    created_at timestamptz not null,
+    constraint positive_threshold check (threshold > 0)
+);
+
+create table treasury_members (
+    treasury int8 not null references treasuries(rowid),
+    "user" int8 not null,
+    added_at timestamptz not null,
+    primary key (treasury, "user")
+);
+
+create index treasury_members_by_user on treasury_members ("user");
+
+-- something a member wants the treasury to do, done once enough members approve
+create table treasury_proposals (
+    rowid bigserial primary key,
+    treasury int8 not null references treasuries(rowid),
+    proposed_by int8 not null,
+    kind text not null,
+    -- what to give; votes are paid for like anyone else's
+    ty text references item_types(name),
+    quantity int8 not null,
+    to_user int8,
+    memo text,
+    motion int8 references motions(rowid),
+    direction boolean,
+    proposed_at timestamptz not null,
+    expires_at timestamptz not null,
+    message_id int8,
+    status text not null default 'pending',
+    settled_at timestamptz,
+    transfer int8 references transfers(rowid),
+    constraint kind_enum check (kind IN ('give', 'vote')),
+    constraint status_enum check (status IN ('pending', 'executed', 'cancelled')),
+    constraint positive_quantity check (quantity > 0),
+    constraint give_has_recipient check ((kind = 'give') = (to_user IS NOT NULL and ty IS NOT NULL)),
+    constraint vote_has_motion check ((kind = 'vote') = (motion IS NOT NULL and direction IS NOT NULL)),
+    constraint settled_when_not_pending check ((status = 'pending') = (settled_at IS NULL))
+);
+
+create index treasury_proposals_by_treasury on treasury_proposals (treasury, rowid);
+
+create table treasury_approvals (
+    proposal int8 not null references treasury_proposals(rowid),
+    "user" int8 not null,
+    approved_at timestamptz not null,
+    primary key (proposal, "user")
+);
@@ -0,0 +1,49 @@
+alter table transfers drop constraint transfer_ty_enum;
+alter table transfers add constraint transfer_ty_enum check (transfer_ty IN ('motion_create', 'motion_vote', 'motion_vote_refund', 'generated', 'admin_fabricate', 'admin_give', 'admin_burn', 'burn', 'give', 'command_fabricate', 'pledge_escrow', 'pledge_release', 'pledge_refund', 'pool_contribute', 'pool_refund'));
+-- contributions go from the contributor to the pool's account, and refunds come back the same way
+alter table transfers drop constraint makes_sense;
+alter table transfers add constraint makes_sense check (
+    (to_user IS NOT NULL or from_user IS NOT NULL)
+    and ((NOT (transfer_ty = 'burn')) OR (from_user IS NOT NULL and to_user IS NULL and to_motion IS NULL))
+    and ((NOT (transfer_ty = 'pledge_escrow')) OR (from_user IS NOT NULL and to_user IS NULL))
+    and ((NOT (transfer_ty IN ('pledge_release', 'pledge_refund'))) OR (from_user IS NULL and to_user IS NOT NULL))
+    and ((NOT (transfer_ty IN ('pool_contribute', 'pool_refund'))) OR (from_user IS NOT NULL and to_user IS NOT NULL))
+);
+
+-- capital raised from several users to vote one way on a motion, spent all at once from the pool's
+-- own account (see vote_pools.rs) when collection closes
+create table vote_pools (
+    rowid bigserial primary key,
+    motion int8 not null references motions(rowid),
+    direction boolean not null,
+    created_by int8 not null,
+    created_at timestamptz not null,
+    closes_at timestamptz not null,
+    settled_at timestamptz,
+    -- what it bought; null if it couldn't buy anything and refunded everyone
+    votes int8,
+    cost int8,
+    constraint bought_together check ((votes IS NULL) = (cost IS NULL)),
+    constraint settled_when_bought check (votes IS NULL or settled_at IS NOT NULL)
+);
+
+-- only one pool collects for each side of a motion at a time
+create unique index vote_pools_collecting on vote_pools (motion, direction) where settled_at IS NULL;
+create index vote_pools_due on vote_pools (closes_at) where settled_at IS NULL;
+
+create table vote_pool_contributions (
+    rowid bigserial primary key,
+    pool int8 not null references vote_pools(rowid),
+    "user" int8 not null,
+    quantity int8 not null,
+    contributed_at timestamptz not null,
+    message_id int8,
+    transfer int8 not null references transfers(rowid),
+    -- this contribution's share of what was left once the pool settled; null until then
+    refunded int8,
+    refund_transfer int8 references transfers(rowid),
+    constraint positive_quantity check (quantity > 0)
+);
+
+create index vote_pool_contributions_by_pool on vote_pool_contributions (pool, rowid);
+create index vote_pool_contributions_by_user on vote_pool_contributions ("user");
@@ -0,0 +1,40 @@
+drop table allowances;
+drop view balance_history;
+create view balance_history as
+  select
+    "rowid",
+    "from_user" as user,
+    "from_balance" as balance,
+    "quantity",
+    -1 as sign,
+    "happened_at",
+    "ty",
+    "comment",
+    "to_user" as other_party,
+    "message_id",
+    "to_motion",
+    "to_votes",
+    "transfer_ty"
+  from transfers
+  where
+    "from_user" is not null
+  union all
+  select
+    "rowid",
+    "to_user" as user,
+    "to_balance" as balance,
+    "quantity",
+    1 as sign,
+    "happened_at",
+    "ty",
+    "comment",
+    "from_user" as other_party,
+    "message_id",
+    NULL::bigint as "to_motion",
+    NULL::bigint as "to_votes",
+    "transfer_ty"
+  from transfers
+  where
+    "to_user" is not null
+;
+alter table transfers drop column acting_user;
@@ -0,0 +1,66 @@
+-- someone else who made the transfer on the user's behalf, with an allowance (see allowances.rs);
+-- null when the user acted themselves
+alter table transfers add column acting_user int8;
+
+create or replace view balance_history as
+  select
+    "rowid",
+    "from_user" as user,
+    "from_balance" as balance,
+    "quantity",
+    -1 as sign,
+    "happened_at",
+    "ty",
+    "comment",
+    "to_user" as other_party,
+    "message_id",
+    "to_motion",
+    "to_votes",
+    "transfer_ty",
+    "acting_user"
+  from transfers
+  where
+    "from_user" is not null
+  union all
+  select
+    "rowid",
+    "to_user" as user,
+    "to_balance" as balance,
+    "quantity",
+    1 as sign,
+    "happened_at",
+    "ty",
+    "comment",
+    "from_user" as other_party,
+    "message_id",
+    NULL::bigint as "to_motion",
+    NULL::bigint as "to_votes",
+    "transfer_ty",
+    "acting_user"
+  from transfers
+  where
+    "to_user" is not null
+;
+
+-- lets `spender` give or vote with up to `quantity` of what `owner` holds, as though `owner` did
+create table allowances (
+    rowid bigserial primary key,
+    owner int8 not null,
+    spender int8 not null,
+    ty text not null references item_types(name),
+    quantity int8 not null,
+    spent int8 not null default 0,
+    granted_at timestamptz not null,
+    expires_at timestamptz,
+    -- also set when a new allowance replaces this one
+    revoked_at timestamptz,
+    message_id int8,
+    constraint positive_quantity check (quantity > 0),
+    constraint within_quantity check (spent >= 0 and spent <= quantity),
+    constraint not_to_self check (owner != spender)
+);
+
+-- only one allowance of each type from one user to another at a time
+create unique index allowances_current on allowances (owner, spender, ty) where revoked_at IS NULL;
+create index allowances_by_owner on allowances (owner, rowid);
+create index allowances_by_spender on allowances (spender, rowid);
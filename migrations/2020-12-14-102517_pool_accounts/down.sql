@@ -0,0 +1 @@
+alter table vote_pools drop column account;
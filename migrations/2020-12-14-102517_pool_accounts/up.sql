@@ -0,0 +1,5 @@
+-- the ledger account a pool votes from, shared by every pool on the same side of a motion so that
+-- their votes are one run of ordinals; pools so far each kept their own
+alter table vote_pools add column account int8;
+update vote_pools set account = -1099511627776 - rowid;
+alter table vote_pools alter column account set not null;
@@ -0,0 +1,2 @@
+alter table admin_actions drop constraint action_enum;
+alter table admin_actions add constraint action_enum check (action IN ('fabricate', 'burn', 'adjust', 'transfer', 'grant_admin', 'revoke_admin', 'refresh_motion', 'create_item_type', 'edit_item_type', 'retire_item_type', 'restore_item_type', 'add_alias', 'remove_alias', 'add_vote_word', 'remove_vote_word', 'add_command_alias', 'remove_command_alias'));
@@ -0,0 +1,712 @@
+//! Who may use privileged commands, and the privileged operations themselves: creating, destroying
+//! and moving anyone's items. Admins are either users granted it directly or anyone with a granted
+//! Discord role. Everything admins do is recorded in `admin_actions`, an append-only log of who did
+//! what to whom and why, and the transfers an action made point back at it.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+
+use crate::schema;
+use crate::economy::latest_balance;
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+
+lazy_static! {
+    /// The user in `BOT_OWNER`, who is always an admin, so that someone can grant it to everyone
+    /// else.
+    static ref OWNER:Option<i64> = std::env::var("BOT_OWNER").ok()
+        .map(|id| id.parse().expect("BOT_OWNER must be a user id"));
+}
+
+/// Whether the user is an admin, either directly, through one of `roles`, or as the bot's owner.
+pub fn is_admin(conn: &PgConnection, user: i64, roles: &[i64]) -> QueryResult<bool> {
+    use schema::admin_users::dsl as audsl;
+    use schema::admin_roles::dsl as ardsl;
+    use diesel::dsl::{exists, select};
+    if *OWNER == Some(user) {
+        return Ok(true);
+    }
+    let by_user:bool = select(exists(audsl::admin_users.filter(audsl::user.eq(user)))).get_result(conn)?;
+    if by_user || roles.is_empty() {
+        return Ok(by_user);
+    }
+    select(exists(ardsl::admin_roles.filter(ardsl::role.eq_any(roles)))).get_result(conn)
+}
+
+/// Every user granted admin directly.
+pub fn admin_users(conn: &PgConnection) -> QueryResult<Vec<i64>> {
+    use schema::admin_users::dsl as audsl;
+    audsl::admin_users.select(audsl::user).order(audsl::granted_at).get_results(conn)
+}
+
+/// Every role in the guild whose members are admins.
+pub fn admin_roles(conn: &PgConnection, guild: i64) -> QueryResult<Vec<i64>> {
+    use schema::admin_roles::dsl as ardsl;
+    ardsl::admin_roles
+        .select(ardsl::role)
+        .filter(ardsl::guild.eq(guild))
+        .order(ardsl::granted_at)
+        .get_results(conn)
+}
+
+/// Every guild with admin roles.
+pub fn admin_role_guilds(conn: &PgConnection) -> QueryResult<Vec<i64>> {
+    use schema::admin_roles::dsl as ardsl;
+    ardsl::admin_roles.select(ardsl::guild).distinct().get_results(conn)
+}
+
+/// Returns whether the user wasn't already an admin.
+pub fn add_admin_user(conn: &PgConnection, actor: Actor, user: i64) -> QueryResult<bool> {
+    use schema::admin_users::dsl as audsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let inserted = diesel::insert_into(audsl::admin_users)
+            .values((
+                audsl::user.eq(user),
+                audsl::granted_by.eq(actor.user),
+                audsl::granted_at.eq(now),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted > 0 {
+            record(conn, actor, AdminAction::GrantAdmin, Some(user), json!({}), now)?;
+        }
+        Ok(inserted > 0)
+    })
+}
+
+/// Returns whether the user was an admin.
+pub fn remove_admin_user(conn: &PgConnection, actor: Actor, user: i64) -> QueryResult<bool> {
+    use schema::admin_users::dsl as audsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let deleted = diesel::delete(audsl::admin_users.filter(audsl::user.eq(user))).execute(conn)?;
+        if deleted > 0 {
+            record(conn, actor, AdminAction::RevokeAdmin, Some(user), json!({}), chrono::Utc::now())?;
+        }
+        Ok(deleted > 0)
+    })
+}
+
+/// Returns whether the role wasn't already an admin role.
+pub fn add_admin_role(conn: &PgConnection, actor: Actor, guild: i64, role: i64) -> QueryResult<bool> {
+    use schema::admin_roles::dsl as ardsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let inserted = diesel::insert_into(ardsl::admin_roles)
+            .values((
+                ardsl::role.eq(role),
+                ardsl::guild.eq(guild),
+                ardsl::granted_by.eq(actor.user),
+                ardsl::granted_at.eq(now),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted > 0 {
+            record(conn, actor, AdminAction::GrantAdmin, None, json!({ "role": role, "guild": guild }), now)?;
+        }
+        Ok(inserted > 0)
+    })
+}
+
+/// Returns whether the role was an admin role in the guild.
+pub fn remove_admin_role(conn: &PgConnection, actor: Actor, guild: i64, role: i64) -> QueryResult<bool> {
+    use schema::admin_roles::dsl as ardsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let deleted = diesel::delete(ardsl::admin_roles.filter(ardsl::role.eq(role)).filter(ardsl::guild.eq(guild)))
+            .execute(conn)?;
+        if deleted > 0 {
+            record(conn, actor, AdminAction::RevokeAdmin, None, json!({ "role": role, "guild": guild }), chrono::Utc::now())?;
+        }
+        Ok(deleted > 0)
+    })
+}
+
+/// A kind of privileged operation, see the `admin_actions` table.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AdminAction {
+    Fabricate,
+    Burn,
+    /// Fabricate or burn whatever it takes to reach a given balance
+    Adjust,
+    /// Move items between any two users
+    Transfer,
+    /// Make a user, or everyone with a role, an admin
+    GrantAdmin,
+    RevokeAdmin,
+    /// Redraw a motion's post, see `$hack_message_update`
+    RefreshMotion,
+    CreateItemType,
+    /// Change an item type's long names
+    EditItemType,
+    RetireItemType,
+    RestoreItemType,
+    AddAlias,
+    RemoveAlias,
+    /// Teach `$vote` a word, see `$vote_word`
+    AddVoteWord,
+    RemoveVoteWord,
+    /// Give a command another name, see `$alias`
+    AddCommandAlias,
+    RemoveCommandAlias,
+}
+
+impl AdminAction {
+    pub const ALL:[AdminAction; 17] = [
+        AdminAction::Fabricate,
+        AdminAction::Burn,
+        AdminAction::Adjust,
+        AdminAction::Transfer,
+        AdminAction::GrantAdmin,
+        AdminAction::RevokeAdmin,
+        AdminAction::RefreshMotion,
+        AdminAction::CreateItemType,
+        AdminAction::EditItemType,
+        AdminAction::RetireItemType,
+        AdminAction::RestoreItemType,
+        AdminAction::AddAlias,
+        AdminAction::RemoveAlias,
+        AdminAction::AddVoteWord,
+        AdminAction::RemoveVoteWord,
+        AdminAction::AddCommandAlias,
+        AdminAction::RemoveCommandAlias,
+    ];
+
+    pub fn db_name(self) -> &'static str {
+        match self {
+            AdminAction::Fabricate => "fabricate",
+            AdminAction::Burn => "burn",
+            AdminAction::Adjust => "adjust",
+            AdminAction::Transfer => "transfer",
+            AdminAction::GrantAdmin => "grant_admin",
+            AdminAction::RevokeAdmin => "revoke_admin",
+            AdminAction::RefreshMotion => "refresh_motion",
+            AdminAction::CreateItemType => "create_item_type",
+            AdminAction::EditItemType => "edit_item_type",
+            AdminAction::RetireItemType => "retire_item_type",
+            AdminAction::RestoreItemType => "restore_item_type",
+            AdminAction::AddAlias => "add_alias",
+            AdminAction::RemoveAlias => "remove_alias",
+            AdminAction::AddVoteWord => "add_vote_word",
+            AdminAction::RemoveVoteWord => "remove_vote_word",
+            AdminAction::AddCommandAlias => "add_command_alias",
+            AdminAction::RemoveCommandAlias => "remove_command_alias",
+        }
+    }
+
+    pub fn from_db_name(name: &str) -> Option<Self> {
+        AdminAction::ALL.iter().copied().find(|a| a.db_name() == name)
+    }
+
+    /// Catalog key of the action's name.
+    pub fn label(self) -> &'static str {
+        match self {
+            AdminAction::Fabricate => "admin.action.fabricate",
+            AdminAction::Burn => "admin.action.burn",
+            AdminAction::Adjust => "admin.action.adjust",
+            AdminAction::Transfer => "admin.action.transfer",
+            AdminAction::GrantAdmin => "admin.action.grant_admin",
+            AdminAction::RevokeAdmin => "admin.action.revoke_admin",
+            AdminAction::RefreshMotion => "admin.action.refresh_motion",
+            AdminAction::CreateItemType => "admin.action.create_item_type",
+            AdminAction::EditItemType => "admin.action.edit_item_type",
+            AdminAction::RetireItemType => "admin.action.retire_item_type",
+            AdminAction::RestoreItemType => "admin.action.restore_item_type",
+            AdminAction::AddAlias => "admin.action.add_alias",
+            AdminAction::RemoveAlias => "admin.action.remove_alias",
+            AdminAction::AddVoteWord => "admin.action.add_vote_word",
+            AdminAction::RemoveVoteWord => "admin.action.remove_vote_word",
+            AdminAction::AddCommandAlias => "admin.action.add_command_alias",
+            AdminAction::RemoveCommandAlias => "admin.action.remove_command_alias",
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AdminError {
+    NotPositive,
+    NegativeBalance,
+    SameUser,
+    InsufficientBalance{ balance: i64 },
+}
+
+impl Localize for AdminError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            AdminError::NotPositive => tr!(locale, "admin.not_positive").to_string(),
+            AdminError::NegativeBalance => tr!(locale, "admin.negative_balance").to_string(),
+            AdminError::SameUser => tr!(locale, "admin.same_user").to_string(),
+            AdminError::InsufficientBalance{balance} => tr!(locale, "admin.insufficient_balance", balance = locale.number(*balance)),
+        }
+    }
+}
+
+impl fmt::Display for AdminError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Who did a privileged operation, why, and in response to which message (if any).
+#[derive(Debug,Clone,Copy)]
+pub struct Actor<'a> {
+    pub user: i64,
+    pub reason: Option<&'a str>,
+    pub message_id: Option<i64>,
+}
+
+/// Adds an action to the log, returning its id. `target` is the user acted on, if any, and
+/// `parameters` whatever else it takes to say what was done.
+pub fn record(
+    conn: &PgConnection,
+    actor: Actor,
+    action: AdminAction,
+    target: Option<i64>,
+    parameters: serde_json::Value,
+    now: DateTime<Utc>,
+) -> QueryResult<i64> {
+    use schema::admin_actions::dsl as aadsl;
+    diesel::insert_into(aadsl::admin_actions).values((
+        aadsl::actor.eq(actor.user),
+        aadsl::action.eq(action.db_name()),
+        aadsl::target.eq(target),
+        aadsl::parameters.eq(parameters),
+        aadsl::reason.eq(actor.reason),
+        aadsl::message_id.eq(actor.message_id),
+        aadsl::happened_at.eq(now),
+    )).returning(aadsl::rowid).get_result(conn)
+}
+
+/// Creates items out of thin air, given the balance they're added to.
+#[allow(clippy::too_many_arguments)]
+fn mint(conn: &PgConnection, actor: Actor, action_id: i64, to_user: i64, ty: &ItemType, amount: i64, balance: i64, now: DateTime<Utc>) -> QueryResult<()> {
+    use schema::transfers::dsl as tdsl;
+    diesel::insert_into(tdsl::transfers).values((
+        tdsl::ty.eq(ty.db_name()),
+        tdsl::quantity.eq(amount),
+        tdsl::to_user.eq(to_user),
+        tdsl::to_balance.eq(balance + amount),
+        tdsl::happened_at.eq(now),
+        tdsl::message_id.eq(actor.message_id),
+        tdsl::comment.eq(actor.reason),
+        tdsl::transfer_ty.eq("admin_fabricate"),
+        tdsl::admin_action.eq(action_id),
+    )).execute(conn)?;
+    Ok(())
+}
+
+/// Destroys items, given the balance they're taken from.
+#[allow(clippy::too_many_arguments)]
+fn destroy(conn: &PgConnection, actor: Actor, action_id: i64, from_user: i64, ty: &ItemType, amount: i64, balance: i64, now: DateTime<Utc>) -> QueryResult<()> {
+    use schema::transfers::dsl as tdsl;
+    diesel::insert_into(tdsl::transfers).values((
+        tdsl::ty.eq(ty.db_name()),
+        tdsl::quantity.eq(amount),
+        tdsl::from_user.eq(from_user),
+        tdsl::from_balance.eq(balance - amount),
+        tdsl::happened_at.eq(now),
+        tdsl::message_id.eq(actor.message_id),
+        tdsl::comment.eq(actor.reason),
+        tdsl::transfer_ty.eq("admin_burn"),
+        tdsl::admin_action.eq(action_id),
+    )).execute(conn)?;
+    Ok(())
+}
+
+/// Creates `amount` of `ty` and gives it to `to_user`. Returns their new balance.
+pub fn fabricate(conn: &PgConnection, actor: Actor, to_user: i64, ty: &ItemType, amount: i64) -> QueryResult<Result<i64, AdminError>> {
+    if amount <= 0 {
+        return Ok(Err(AdminError::NotPositive));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let balance = latest_balance(conn, to_user, ty.db_name(), true)?;
+        let action_id = record(conn, actor, AdminAction::Fabricate, Some(to_user), json!({ "ty": ty.db_name(), "amount": amount }), now)?;
+        mint(conn, actor, action_id, to_user, ty, amount, balance, now)?;
+        Ok(Ok(balance + amount))
+    })
+}
+
+/// Destroys `amount` of the `ty` that `from_user` has. Returns their new balance.
+pub fn burn(conn: &PgConnection, actor: Actor, from_user: i64, ty: &ItemType, amount: i64) -> QueryResult<Result<i64, AdminError>> {
+    if amount <= 0 {
+        return Ok(Err(AdminError::NotPositive));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let balance = latest_balance(conn, from_user, ty.db_name(), true)?;
+        if balance < amount {
+            return Ok(Err(AdminError::InsufficientBalance{ balance }));
+        }
+        let action_id = record(conn, actor, AdminAction::Burn, Some(from_user), json!({ "ty": ty.db_name(), "amount": amount }), now)?;
+        destroy(conn, actor, action_id, from_user, ty, amount, balance, now)?;
+        Ok(Ok(balance - amount))
+    })
+}
+
+/// Fabricates or burns whatever it takes for `user` to have exactly `balance` of `ty`. Returns
+/// their balance before.
+pub fn adjust(conn: &PgConnection, actor: Actor, user: i64, ty: &ItemType, balance: i64) -> QueryResult<Result<i64, AdminError>> {
+    if balance < 0 {
+        return Ok(Err(AdminError::NegativeBalance));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let before = latest_balance(conn, user, ty.db_name(), true)?;
+        let action_id = record(conn, actor, AdminAction::Adjust, Some(user), json!({ "ty": ty.db_name(), "balance": balance }), now)?;
+        if balance > before {
+            mint(conn, actor, action_id, user, ty, balance - before, before, now)?;
+        } else if balance < before {
+            destroy(conn, actor, action_id, user, ty, before - balance, before, now)?;
+        }
+        Ok(Ok(before))
+    })
+}
+
+/// Moves `amount` of `ty` from one user to another, whoever they are.
+pub fn transfer(conn: &PgConnection, actor: Actor, from_user: i64, to_user: i64, ty: &ItemType, amount: i64) -> QueryResult<Result<(), AdminError>> {
+    if amount <= 0 {
+        return Ok(Err(AdminError::NotPositive));
+    }
+    if from_user == to_user {
+        return Ok(Err(AdminError::SameUser));
+    }
+    use schema::transfers::dsl as tdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        // lock in a consistent order to avoid deadlocks, like economy::give
+        let (from_balance, to_balance) = if from_user < to_user {
+            let from_balance = latest_balance(conn, from_user, ty.db_name(), true)?;
+            (from_balance, latest_balance(conn, to_user, ty.db_name(), true)?)
+        } else {
+            let to_balance = latest_balance(conn, to_user, ty.db_name(), true)?;
+            (latest_balance(conn, from_user, ty.db_name(), true)?, to_balance)
+        };
+        if from_balance < amount {
+            return Ok(Err(AdminError::InsufficientBalance{ balance: from_balance }));
+        }
+        let action_id = record(
+            conn,
+            actor,
+            AdminAction::Transfer,
+            Some(from_user),
+            json!({ "ty": ty.db_name(), "amount": amount, "to": to_user }),
+            now,
+        )?;
+        diesel::insert_into(tdsl::transfers).values((
+            tdsl::ty.eq(ty.db_name()),
+            tdsl::quantity.eq(amount),
+            tdsl::from_user.eq(from_user),
+            tdsl::from_balance.eq(from_balance - amount),
+            tdsl::to_user.eq(to_user),
+            tdsl::to_balance.eq(to_balance + amount),
+            tdsl::happened_at.eq(now),
+            tdsl::message_id.eq(actor.message_id),
+            tdsl::comment.eq(actor.reason),
+            tdsl::transfer_ty.eq("admin_give"),
+            tdsl::admin_action.eq(action_id),
+        )).execute(conn)?;
+        Ok(Ok(()))
+    })
+}
+
+/// Item types the economy itself depends on, which can't be retired.
+const BUILT_IN_ITEM_TYPES:[&str; 2] = ["pc", "gen"];
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ItemTypeError {
+    /// Empty, too long, or containing spaces
+    BadAlias,
+    /// Would be read as an amount, eg. `1k` or `half`
+    AliasIsAmount,
+    AliasTaken{ alias: String, name: String },
+    NotFound,
+    BuiltIn,
+    /// Every item type keeps its own name as an alias
+    OwnName,
+}
+
+impl Localize for ItemTypeError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            ItemTypeError::BadAlias => tr!(locale, "admin.item_type.bad_alias").to_string(),
+            ItemTypeError::AliasIsAmount => tr!(locale, "admin.item_type.alias_is_amount").to_string(),
+            ItemTypeError::AliasTaken{alias, name} => tr!(locale, "admin.item_type.alias_taken", alias = alias, name = name),
+            ItemTypeError::NotFound => tr!(locale, "admin.item_type.not_found").to_string(),
+            ItemTypeError::BuiltIn => tr!(locale, "admin.item_type.built_in").to_string(),
+            ItemTypeError::OwnName => tr!(locale, "admin.item_type.own_name").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ItemTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// An alias (or item type name, which is also an alias) as it's stored: lowercase, a single word,
+/// and nothing `$give` would take for an amount.
+pub fn normalize_alias(alias: &str) -> Result<String, ItemTypeError> {
+    let alias = alias.trim().to_lowercase();
+    if alias.is_empty() || alias.chars().count() > 32 || alias.contains(char::is_whitespace) || alias.contains('"') {
+        return Err(ItemTypeError::BadAlias);
+    }
+    // `100pc` is read as an amount followed by an item type
+    if alias.starts_with(|c:char| c.is_ascii_digit()) || crate::parser::parse_amount(&alias).is_ok() {
+        return Err(ItemTypeError::AliasIsAmount);
+    }
+    Ok(alias)
+}
+
+/// The item type an alias belongs to, if any.
+fn alias_owner(conn: &PgConnection, alias: &str) -> QueryResult<Option<String>> {
+    use schema::item_type_aliases::dsl as ita;
+    ita::item_type_aliases.select(ita::name).filter(ita::alias.eq(alias)).get_result(conn).optional()
+}
+
+/// Creates an item type, which can be referred to by its name until it has other aliases.
+pub fn create_item_type(
+    conn: &PgConnection,
+    actor: Actor,
+    name: &str,
+    long_name_plural: &str,
+    long_name_ambiguous: &str,
+) -> QueryResult<Result<String, ItemTypeError>> {
+    use schema::item_types::dsl as it;
+    use schema::item_type_aliases::dsl as ita;
+    let name = match normalize_alias(name) {
+        Ok(n) => n,
+        Err(e) => return Ok(Err(e)),
+    };
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::sql_query("LOCK TABLE item_type_aliases IN EXCLUSIVE MODE;").execute(conn)?;
+        if let Some(owner) = alias_owner(conn, &name)? {
+            return Ok(Err(ItemTypeError::AliasTaken{ alias: name, name: owner }));
+        }
+        let taken:bool = diesel::select(diesel::dsl::exists(it::item_types.filter(it::name.eq(&name)))).get_result(conn)?;
+        if taken {
+            return Ok(Err(ItemTypeError::AliasTaken{ alias: name.clone(), name }));
+        }
+        diesel::insert_into(it::item_types).values((
+            it::name.eq(&name),
+            it::long_name_plural.eq(long_name_plural),
+            it::long_name_ambiguous.eq(long_name_ambiguous),
+        )).execute(conn)?;
+        diesel::insert_into(ita::item_type_aliases).values((
+            ita::name.eq(&name),
+            ita::alias.eq(&name),
+        )).execute(conn)?;
+        record(conn, actor, AdminAction::CreateItemType, None, json!({
+            "ty": name,
+            "long_name_plural": long_name_plural,
+            "long_name_ambiguous": long_name_ambiguous,
+        }), chrono::Utc::now())?;
+        Ok(Ok(name))
+    })
+}
+
+/// Renames an item type, as far as its long names go; its name is fixed by its history.
+pub fn edit_item_type(
+    conn: &PgConnection,
+    actor: Actor,
+    name: &str,
+    long_name_plural: &str,
+    long_name_ambiguous: &str,
+) -> QueryResult<Result<(), ItemTypeError>> {
+    use schema::item_types::dsl as it;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let before:Option<(String, String)> = it::item_types
+            .select((it::long_name_plural, it::long_name_ambiguous))
+            .filter(it::name.eq(name))
+            .for_update()
+            .get_result(conn)
+            .optional()?;
+        let (plural_before, ambiguous_before) = match before {
+            Some(b) => b,
+            None => return Ok(Err(ItemTypeError::NotFound)),
+        };
+        diesel::update(it::item_types.filter(it::name.eq(name))).set((
+            it::long_name_plural.eq(long_name_plural),
+            it::long_name_ambiguous.eq(long_name_ambiguous),
+        )).execute(conn)?;
+        record(conn, actor, AdminAction::EditItemType, None, json!({
+            "ty": name,
+            "before": { "long_name_plural": plural_before, "long_name_ambiguous": ambiguous_before },
+            "after": { "long_name_plural": long_name_plural, "long_name_ambiguous": long_name_ambiguous },
+        }), chrono::Utc::now())?;
+        Ok(Ok(()))
+    })
+}
+
+/// Retires an item type, or brings it back. Holdings of a retired type stay where they are.
+pub fn set_item_type_retired(conn: &PgConnection, actor: Actor, name: &str, retired: bool) -> QueryResult<Result<(), ItemTypeError>> {
+    use schema::item_types::dsl as it;
+    if BUILT_IN_ITEM_TYPES.contains(&name) {
+        return Ok(Err(ItemTypeError::BuiltIn));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let updated = diesel::update(it::item_types.filter(it::name.eq(name)))
+            .set(it::retired.eq(retired))
+            .execute(conn)?;
+        if updated == 0 {
+            return Ok(Err(ItemTypeError::NotFound));
+        }
+        let action = if retired { AdminAction::RetireItemType } else { AdminAction::RestoreItemType };
+        record(conn, actor, action, None, json!({ "ty": name }), chrono::Utc::now())?;
+        Ok(Ok(()))
+    })
+}
+
+/// Lets `alias` be used for the item type `name`. Returns the alias as it was stored.
+pub fn add_item_type_alias(conn: &PgConnection, actor: Actor, name: &str, alias: &str) -> QueryResult<Result<String, ItemTypeError>> {
+    use schema::item_types::dsl as it;
+    use schema::item_type_aliases::dsl as ita;
+    let alias = match normalize_alias(alias) {
+        Ok(a) => a,
+        Err(e) => return Ok(Err(e)),
+    };
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::sql_query("LOCK TABLE item_type_aliases IN EXCLUSIVE MODE;").execute(conn)?;
+        let exists:bool = diesel::select(diesel::dsl::exists(it::item_types.filter(it::name.eq(name)))).get_result(conn)?;
+        if !exists {
+            return Ok(Err(ItemTypeError::NotFound));
+        }
+        if let Some(owner) = alias_owner(conn, &alias)? {
+            return Ok(Err(ItemTypeError::AliasTaken{ alias, name: owner }));
+        }
+        diesel::insert_into(ita::item_type_aliases).values((
+            ita::name.eq(name),
+            ita::alias.eq(&alias),
+        )).execute(conn)?;
+        record(conn, actor, AdminAction::AddAlias, None, json!({ "ty": name, "alias": alias }), chrono::Utc::now())?;
+        Ok(Ok(alias))
+    })
+}
+
+/// Stops `alias` from referring to an item type.
+pub fn remove_item_type_alias(conn: &PgConnection, actor: Actor, alias: &str) -> QueryResult<Result<(), ItemTypeError>> {
+    use schema::item_type_aliases::dsl as ita;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let name = match alias_owner(conn, alias)? {
+            Some(n) => n,
+            None => return Ok(Err(ItemTypeError::NotFound)),
+        };
+        if name == alias {
+            return Ok(Err(ItemTypeError::OwnName));
+        }
+        diesel::delete(ita::item_type_aliases.filter(ita::alias.eq(alias))).execute(conn)?;
+        record(conn, actor, AdminAction::RemoveAlias, None, json!({ "ty": name, "alias": alias }), chrono::Utc::now())?;
+        Ok(Ok(()))
+    })
+}
+
+/// One entry in the admin log.
+#[derive(Debug,Clone,Queryable)]
+pub struct LoggedAction {
+    pub rowid: i64,
+    pub actor: i64,
+    pub action: String,
+    pub target: Option<i64>,
+    pub parameters: serde_json::Value,
+    pub reason: Option<String>,
+    pub message_id: Option<i64>,
+    pub happened_at: DateTime<Utc>,
+}
+
+/// A transfer made by a logged action.
+#[derive(Debug,Clone,Queryable)]
+pub struct ActionTransfer {
+    pub rowid: i64,
+    pub ty: String,
+    pub quantity: i64,
+    pub from_user: Option<i64>,
+    pub to_user: Option<i64>,
+}
+
+/// Which entries of the admin log to show. Each filter that's set must match.
+#[derive(Debug,Clone,Copy,Default)]
+pub struct LogFilter {
+    pub actor: Option<i64>,
+    pub target: Option<i64>,
+    pub action: Option<AdminAction>,
+}
+
+/// Up to `limit` entries of the admin log older than `before` (if given), newest first, each with
+/// the transfers it made.
+pub fn action_log(
+    conn: &PgConnection,
+    filter: LogFilter,
+    before: Option<i64>,
+    limit: i64,
+) -> QueryResult<Vec<(LoggedAction, Vec<ActionTransfer>)>> {
+    use schema::admin_actions::dsl as aadsl;
+    use schema::transfers::dsl as tdsl;
+    let mut query = aadsl::admin_actions
+        .select((
+            aadsl::rowid,
+            aadsl::actor,
+            aadsl::action,
+            aadsl::target,
+            aadsl::parameters,
+            aadsl::reason,
+            aadsl::message_id,
+            aadsl::happened_at,
+        ))
+        .order(aadsl::rowid.desc())
+        .limit(limit)
+        .into_boxed();
+    if let Some(actor) = filter.actor {
+        query = query.filter(aadsl::actor.eq(actor));
+    }
+    if let Some(target) = filter.target {
+        query = query.filter(aadsl::target.eq(target));
+    }
+    if let Some(action) = filter.action {
+        query = query.filter(aadsl::action.eq(action.db_name()));
+    }
+    if let Some(before) = before {
+        query = query.filter(aadsl::rowid.lt(before));
+    }
+    let actions:Vec<LoggedAction> = query.get_results(conn)?;
+
+    let ids:Vec<i64> = actions.iter().map(|a| a.rowid).collect();
+    let transfers:Vec<(Option<i64>, ActionTransfer)> = tdsl::transfers
+        .select((tdsl::admin_action, (tdsl::rowid, tdsl::ty, tdsl::quantity, tdsl::from_user, tdsl::to_user)))
+        .filter(tdsl::admin_action.eq_any(ids))
+        .order(tdsl::rowid)
+        .get_results(conn)?;
+    Ok(actions.into_iter().map(|action| {
+        let made = transfers.iter()
+            .filter(|(id, _)| *id == Some(action.rowid))
+            .map(|(_, t)| t.clone())
+            .collect();
+        (action, made)
+    }).collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn action_names_round_trip() {
+        for &action in &AdminAction::ALL {
+            assert_eq!(AdminAction::from_db_name(action.db_name()), Some(action));
+        }
+        assert_eq!(AdminAction::from_db_name("delete_everything"), None);
+    }
+
+    #[test]
+    fn normalizes_aliases() {
+        assert_eq!(normalize_alias(" StatusBucks "), Ok(String::from("statusbucks")));
+        assert_eq!(normalize_alias("$b"), Ok(String::from("$b")));
+        assert_eq!(normalize_alias("status bucks"), Err(ItemTypeError::BadAlias));
+        assert_eq!(normalize_alias(""), Err(ItemTypeError::BadAlias));
+        assert_eq!(normalize_alias("1k"), Err(ItemTypeError::AliasIsAmount));
+        assert_eq!(normalize_alias("2fast"), Err(ItemTypeError::AliasIsAmount));
+        assert_eq!(normalize_alias("half"), Err(ItemTypeError::AliasIsAmount));
+    }
+}
@@ -0,0 +1,311 @@
+//! Spending allowances: `$allow whip 500 pc 7d` lets the whip give or vote with up to 500 of the
+//! user's pc on their behalf. Whatever the whip does is recorded as the user's own transfer, with
+//! the whip as its `acting_user`. `economy::give_on_behalf` and `economy::vote_on_behalf` draw the
+//! allowance down in the same transaction as the spending, so it can never be overspent.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::schema;
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct Allowance {
+    pub rowid:i64,
+    pub owner:i64,
+    pub spender:i64,
+    pub ty:String,
+    pub quantity:i64,
+    pub spent:i64,
+    pub granted_at:DateTime<Utc>,
+    pub expires_at:Option<DateTime<Utc>>,
+    pub revoked_at:Option<DateTime<Utc>>,
+    pub message_id:Option<i64>,
+}
+
+impl Allowance {
+    pub fn remaining(&self) -> i64 {
+        self.quantity - self.spent
+    }
+
+    /// Whether it can still be spent from, even if there's nothing left of it.
+    pub fn is_active(&self, now:DateTime<Utc>) -> bool {
+        self.revoked_at.is_none() && self.expires_at.map_or(true, |expires_at| expires_at > now)
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum AllowanceError {
+    NotPositive,
+    ToSelf,
+    ExpiresBeforeStart,
+    /// There's nothing to revoke
+    NotFound,
+    /// The spender has no current allowance of that type from the owner
+    NotAllowed,
+    Exceeded{ remaining: i64 },
+}
+
+impl Localize for AllowanceError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            AllowanceError::NotPositive => tr!(locale, "allowance.not_positive").to_string(),
+            AllowanceError::ToSelf => tr!(locale, "allowance.to_self").to_string(),
+            AllowanceError::ExpiresBeforeStart => tr!(locale, "allowance.expires_before_start").to_string(),
+            AllowanceError::NotFound => tr!(locale, "allowance.not_found").to_string(),
+            AllowanceError::NotAllowed => tr!(locale, "allowance.not_allowed").to_string(),
+            AllowanceError::Exceeded{remaining} => tr!(locale, "allowance.exceeded", remaining = locale.number(*remaining)),
+        }
+    }
+}
+
+impl fmt::Display for AllowanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Lets `spender` spend up to `quantity` of what `owner` holds of `ty`, until `expires_at` if
+/// given. Replaces any allowance of that type `owner` had already given them, starting over.
+pub fn grant(
+    conn:&PgConnection,
+    owner:i64,
+    spender:i64,
+    ty:&ItemType,
+    quantity:i64,
+    expires_at:Option<DateTime<Utc>>,
+    message_id:Option<i64>,
+) -> QueryResult<Result<Allowance, AllowanceError>> {
+    use schema::allowances::dsl as adsl;
+    if quantity <= 0 {
+        return Ok(Err(AllowanceError::NotPositive));
+    }
+    if owner == spender {
+        return Ok(Err(AllowanceError::ToSelf));
+    }
+    let now = chrono::Utc::now();
+    if expires_at.map_or(false, |expires_at| expires_at <= now) {
+        return Ok(Err(AllowanceError::ExpiresBeforeStart));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::update(
+            adsl::allowances
+                .filter(adsl::owner.eq(owner))
+                .filter(adsl::spender.eq(spender))
+                .filter(adsl::ty.eq(ty.db_name()))
+                .filter(adsl::revoked_at.is_null())
+        ).set(adsl::revoked_at.eq(now)).execute(conn)?;
+        diesel::insert_into(adsl::allowances).values((
+            adsl::owner.eq(owner),
+            adsl::spender.eq(spender),
+            adsl::ty.eq(ty.db_name()),
+            adsl::quantity.eq(quantity),
+            adsl::granted_at.eq(now),
+            adsl::expires_at.eq(expires_at),
+            adsl::message_id.eq(message_id),
+        )).get_result(conn).map(Ok)
+    })
+}
+
+/// Revokes every current allowance `owner` has given `spender`, or just the one of type `ty`.
+/// Returns what was revoked.
+pub fn revoke(conn:&PgConnection, owner:i64, spender:i64, ty:Option<&str>) -> QueryResult<Result<Vec<Allowance>, AllowanceError>> {
+    use schema::allowances::dsl as adsl;
+    let now = chrono::Utc::now();
+    let revoked:Vec<Allowance> = match ty {
+        Some(ty) => diesel::update(
+            adsl::allowances
+                .filter(adsl::owner.eq(owner))
+                .filter(adsl::spender.eq(spender))
+                .filter(adsl::ty.eq(ty))
+                .filter(adsl::revoked_at.is_null())
+        ).set(adsl::revoked_at.eq(now)).get_results(conn)?,
+        None => diesel::update(
+            adsl::allowances
+                .filter(adsl::owner.eq(owner))
+                .filter(adsl::spender.eq(spender))
+                .filter(adsl::revoked_at.is_null())
+        ).set(adsl::revoked_at.eq(now)).get_results(conn)?,
+    };
+    if revoked.is_empty() {
+        return Ok(Err(AllowanceError::NotFound));
+    }
+    Ok(Ok(revoked))
+}
+
+/// Revokes one allowance by id, as its owner.
+pub fn revoke_id(conn:&PgConnection, owner:i64, allowance_id:i64) -> QueryResult<Result<Allowance, AllowanceError>> {
+    use schema::allowances::dsl as adsl;
+    diesel::update(
+        adsl::allowances
+            .filter(adsl::rowid.eq(allowance_id))
+            .filter(adsl::owner.eq(owner))
+            .filter(adsl::revoked_at.is_null())
+    )
+        .set(adsl::revoked_at.eq(chrono::Utc::now()))
+        .get_result(conn)
+        .optional()
+        .map(|a| a.ok_or(AllowanceError::NotFound))
+}
+
+/// The allowances the user has given that can still be spent from, oldest first.
+pub fn granted_by(conn:&PgConnection, owner:i64) -> QueryResult<Vec<Allowance>> {
+    use schema::allowances::dsl as adsl;
+    let now = chrono::Utc::now();
+    let allowances:Vec<Allowance> = adsl::allowances
+        .filter(adsl::owner.eq(owner))
+        .filter(adsl::revoked_at.is_null())
+        .order(adsl::rowid.asc())
+        .get_results(conn)?;
+    Ok(allowances.into_iter().filter(|a| a.is_active(now)).collect())
+}
+
+/// The allowances the user has been given that can still be spent from, oldest first.
+pub fn granted_to(conn:&PgConnection, spender:i64) -> QueryResult<Vec<Allowance>> {
+    use schema::allowances::dsl as adsl;
+    let now = chrono::Utc::now();
+    let allowances:Vec<Allowance> = adsl::allowances
+        .filter(adsl::spender.eq(spender))
+        .filter(adsl::revoked_at.is_null())
+        .order(adsl::rowid.asc())
+        .get_results(conn)?;
+    Ok(allowances.into_iter().filter(|a| a.is_active(now)).collect())
+}
+
+/// The allowance of `ty` from `owner` to `spender` that can be spent from at `now`, if any, locked
+/// until the end of the transaction.
+pub fn current(conn:&PgConnection, owner:i64, spender:i64, ty:&str, now:DateTime<Utc>) -> QueryResult<Option<Allowance>> {
+    use schema::allowances::dsl as adsl;
+    let allowance:Option<Allowance> = adsl::allowances
+        .filter(adsl::owner.eq(owner))
+        .filter(adsl::spender.eq(spender))
+        .filter(adsl::ty.eq(ty))
+        .filter(adsl::revoked_at.is_null())
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+    Ok(allowance.filter(|a| a.is_active(now)))
+}
+
+/// Counts `amount` of `ty` against the allowance from `owner` to `spender`. Must be called in the
+/// same transaction as whatever spends it.
+pub fn draw(
+    conn:&PgConnection,
+    owner:i64,
+    spender:i64,
+    ty:&str,
+    amount:i64,
+    now:DateTime<Utc>,
+) -> QueryResult<Result<Allowance, AllowanceError>> {
+    use schema::allowances::dsl as adsl;
+    let allowance = match current(conn, owner, spender, ty, now)? {
+        Some(a) => a,
+        None => return Ok(Err(AllowanceError::NotAllowed)),
+    };
+    if amount > allowance.remaining() {
+        return Ok(Err(AllowanceError::Exceeded{ remaining: allowance.remaining() }));
+    }
+    diesel::update(adsl::allowances.filter(adsl::rowid.eq(allowance.rowid)))
+        .set(adsl::spent.eq(allowance.spent + amount))
+        .get_result(conn)
+        .map(Ok)
+}
+
+/// The item type allowed, even if it's since been retired.
+pub fn item_type_of(conn:&PgConnection, allowance:&Allowance) -> QueryResult<ItemType> {
+    use schema::item_types::dsl as itdsl;
+    itdsl::item_types.filter(itdsl::name.eq(&allowance.ty)).get_result(conn)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::economy::{self, GiveError};
+    use crate::test_db;
+
+    const OWNER:i64 = 1001;
+    const SPENDER:i64 = 1002;
+    const RECIPIENT:i64 = 1003;
+
+    fn allow(conn:&PgConnection, quantity:i64) -> Allowance {
+        let pc = test_db::item_type(conn, "pc");
+        grant(conn, OWNER, SPENDER, &pc, quantity, None, None).unwrap().unwrap()
+    }
+
+    fn spent(conn:&PgConnection, allowance_id:i64) -> i64 {
+        use schema::allowances::dsl as adsl;
+        adsl::allowances.filter(adsl::rowid.eq(allowance_id)).select(adsl::spent).get_result(conn).unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn drawing_counts_against_the_limit() {
+        let conn = test_db::connection();
+        let a = allow(&conn, 500);
+        let now = chrono::Utc::now();
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 200, now).unwrap().unwrap().remaining(), 300);
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 301, now).unwrap(), Err(AllowanceError::Exceeded{ remaining: 300 }));
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 300, now).unwrap().unwrap().remaining(), 0);
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 1, now).unwrap(), Err(AllowanceError::Exceeded{ remaining: 0 }));
+        assert_eq!(spent(&conn, a.rowid), 500);
+    }
+
+    #[test]
+    #[ignore]
+    fn only_current_allowances_can_be_drawn() {
+        let conn = test_db::connection();
+        let now = chrono::Utc::now();
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 1, now).unwrap(), Err(AllowanceError::NotAllowed));
+        let pc = test_db::item_type(&conn, "pc");
+        let expiring = grant(&conn, OWNER, SPENDER, &pc, 500, Some(now + chrono::Duration::days(1)), None).unwrap().unwrap();
+        let after_expiry = expiring.expires_at.unwrap();
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 1, after_expiry).unwrap(), Err(AllowanceError::NotAllowed));
+        assert_eq!(draw(&conn, SPENDER, OWNER, "pc", 1, now).unwrap(), Err(AllowanceError::NotAllowed));
+        revoke(&conn, OWNER, SPENDER, None).unwrap().unwrap();
+        assert_eq!(draw(&conn, OWNER, SPENDER, "pc", 1, now).unwrap(), Err(AllowanceError::NotAllowed));
+    }
+
+    #[test]
+    #[ignore]
+    fn giving_on_behalf_draws_it_down() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, OWNER, "pc", 1000);
+        let owner_before = test_db::balance(&conn, OWNER, "pc");
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let a = allow(&conn, 500);
+        let pc = test_db::item_type(&conn, "pc");
+        economy::give_on_behalf(&conn, SPENDER, OWNER, RECIPIENT, &pc, 200, None, None).unwrap().unwrap();
+        assert_eq!(spent(&conn, a.rowid), 200);
+        assert_eq!(test_db::balance(&conn, OWNER, "pc"), owner_before - 200);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 200);
+        assert_eq!(
+            economy::give_on_behalf(&conn, SPENDER, OWNER, RECIPIENT, &pc, 301, None, None).unwrap(),
+            Err(GiveError::Allowance(AllowanceError::Exceeded{ remaining: 300 })),
+        );
+        assert_eq!(test_db::balance(&conn, OWNER, "pc"), owner_before - 200);
+    }
+
+    #[test]
+    #[ignore]
+    fn a_draw_is_undone_with_the_spending() {
+        let conn = test_db::connection();
+        let owner_before = test_db::balance(&conn, OWNER, "pc");
+        let a = allow(&conn, owner_before + 500);
+        let pc = test_db::item_type(&conn, "pc");
+        // the allowance covers it but the owner can't, so the give fails after drawing
+        assert_eq!(
+            economy::give_on_behalf(&conn, SPENDER, OWNER, RECIPIENT, &pc, owner_before as u64 + 1, None, None).unwrap(),
+            Err(GiveError::InsufficientBalance),
+        );
+        assert_eq!(spent(&conn, a.rowid), 0);
+        let rolled_back = conn.transaction::<(), _, _>(|| {
+            draw(&conn, OWNER, SPENDER, "pc", 100, chrono::Utc::now())?.unwrap();
+            Err(diesel::result::Error::RollbackTransaction)
+        });
+        assert!(matches!(rolled_back, Err(diesel::result::Error::RollbackTransaction)));
+        assert_eq!(spent(&conn, a.rowid), 0);
+    }
+}
@@ -1,19 +1,22 @@
-use crate::schema;
 use crate::view_schema;
-use crate::damm;
 
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 use serenity::client::Client;
 use serenity::model::misc::Mentionable;
-use serenity::model::channel::Message;
-use serenity::model::id::UserId;
+use serenity::model::channel::{Message, ReactionType};
+use serenity::model::id::{RoleId, UserId};
 use serenity::prelude::{EventHandler, Context};
 use serenity::framework::standard::{
     StandardFramework,
     CommandResult,
+    CommandOptions,
+    CheckResult,
+    DispatchError,
+    Reason,
     macros::{
+        check,
         command,
         group
     },
@@ -21,9 +24,22 @@ use serenity::framework::standard::{
 };
 use regex::Regex;
 
-use diesel::connection::Connection;
-
-use crate::is_win::is_win;
+use crate::vote_cost::VoteAmount;
+use crate::i18n::{Locale, Localize};
+use crate::admin::{self, Actor};
+use crate::payment_requests::{self, PaymentRequest};
+use crate::scheduled_transfers::{self, ScheduleStatus};
+use crate::allowances::{self, Allowance};
+use crate::pledges;
+use crate::treasuries::{self, ProposalStatus};
+use crate::vote_pools::{self, PoolStatus};
+use crate::help::CommandHelp;
+use crate::parser::{self, VoteWord};
+use crate::vocabulary;
+use crate::stats;
+use crate::settings;
+use crate::vote_emoji::{self, EmojiKey, SpecialEmojiAction, VoteEmoji};
+use crate::economy::{self, BoxError, ChatFrontend, MotionStatus};
 
 struct DbPoolKey;
 impl serenity::prelude::TypeMapKey for DbPoolKey {
@@ -31,37 +47,64 @@ impl serenity::prelude::TypeMapKey for DbPoolKey {
 }
 
 #[group]
-#[commands(ping, give, force_give, balances, motion, supermotion, vote, hack_message_update, help, version_info)]
+#[commands(ping, give, force_give, burn, request, requests, pay, decline, schedule, allow, revoke, spend, balances, top, profile, privacy, receipts, language, server_language, motion, supermotion, vote, pledge, fund, treasury, flip, check_emoji, vote_word, alias, hack_message_update, help, version_info, fabricate, force_burn, adjust, force_transfer, admins)]
 struct General;
 
 #[group]
-#[commands(fabricate)]
+#[commands(transaction_history_csv)]
 struct Debug;
 
 use std::env;
 
-struct Handler;
+/// Rewrites aliases from the `command_aliases` table into the commands they stand for, then hands
+/// the message on to the standard framework.
+struct AliasingFramework {
+    inner: StandardFramework,
+    prefix: &'static str,
+    pool: Arc<diesel::r2d2::Pool<diesel::r2d2::ConnectionManager<diesel::PgConnection>>>,
+}
 
-#[derive(Debug,Clone,Copy,PartialEq,Eq)]
-enum SpecialEmojiAction {
-    Direction(bool),
-    Amount(u64),
+impl serenity::framework::Framework for AliasingFramework {
+    fn dispatch(&mut self, ctx: Context, mut msg: Message, threadpool: &threadpool::ThreadPool) {
+        if msg.content.starts_with(self.prefix) {
+            let rest = &msg.content[self.prefix.len()..];
+            let name_len = rest.find(char::is_whitespace).unwrap_or_else(|| rest.len());
+            let name = &rest[..name_len];
+            let command = self.pool.get()
+                .map_err(BoxError::from)
+                .and_then(|conn| Ok(vocabulary::command_for_alias(&*conn, name)?));
+            let rewritten = match command {
+                Ok(Some(command)) => Some(format!("{}{}{}", self.prefix, command, &rest[name_len..])),
+                Ok(None) => None,
+                Err(e) => {
+                    warn!("Could not look up command alias {:?}: {:?}", name, e);
+                    None
+                },
+            };
+            if let Some(content) = rewritten {
+                msg.content = content;
+            }
+        }
+        self.inner.dispatch(ctx, msg, threadpool);
+    }
 }
 
+/// Every name a command in the General group can be called by, not counting aliases.
+fn command_names() -> Vec<&'static str> {
+    GENERAL_GROUP.options.commands.iter().flat_map(|c| c.options.names.iter().copied()).collect()
+}
+
+struct Handler;
+
 lazy_static! {
     static ref USER_PING_RE:Regex = Regex::new(r"^\s*<@!?(\d+)>\s*$").unwrap();
-    static ref SPECIAL_EMOJI:std::collections::HashMap<u64,SpecialEmojiAction> = hashmap!{
-        770749957723783169 => SpecialEmojiAction::Amount(1),
-        770750097793089596 => SpecialEmojiAction::Amount(2),
-        770750182874021888 => SpecialEmojiAction::Amount(5),
-        770750211281780776 => SpecialEmojiAction::Amount(10),
-        770750297621921802 => SpecialEmojiAction::Amount(20),
-        770750316530499604 => SpecialEmojiAction::Amount(50),
-        770750332946874388 => SpecialEmojiAction::Amount(100),
-        770750231776198698 => SpecialEmojiAction::Amount(10),
-        770749937960747029 => SpecialEmojiAction::Amount(1),
-        770750552291410000 => SpecialEmojiAction::Direction(true),
-        770750576257531914 => SpecialEmojiAction::Direction(false),
+    static ref VOTE_EMOJI:Vec<VoteEmoji> = vote_emoji::from_env();
+    /// How long after voting by reaction a user can take the vote back by removing the reaction.
+    /// Set by `REACTION_REFUND_SECONDS`, where 0 turns refunds off.
+    static ref REACTION_REFUND_WINDOW:Option<chrono::Duration> = match env::var("REACTION_REFUND_SECONDS") {
+        Ok(secs) => Some(chrono::Duration::seconds(secs.parse().expect("REACTION_REFUND_SECONDS must be a whole number of seconds")))
+            .filter(|w| *w > chrono::Duration::zero()),
+        Err(_) => Some(chrono::Duration::seconds(60)),
     };
 }
 
@@ -69,15 +112,22 @@ lazy_static! {
 lazy_static! {
     pub static ref GENERATE_EVERY:chrono::Duration = chrono::Duration::seconds(30);
     pub static ref MOTION_EXPIRATION:chrono::Duration = chrono::Duration::minutes(20);
+    pub static ref PAYMENT_REQUEST_EXPIRATION:chrono::Duration = chrono::Duration::minutes(30);
+    pub static ref MIN_SCHEDULE_INTERVAL:chrono::Duration = chrono::Duration::minutes(1);
+    pub static ref TREASURY_PROPOSAL_EXPIRATION:chrono::Duration = chrono::Duration::minutes(30);
+    pub static ref POOL_COLLECTION_WINDOW:chrono::Duration = chrono::Duration::minutes(5);
 }
 
 #[cfg(not(feature = "debug"))]
 lazy_static! {
     pub static ref GENERATE_EVERY:chrono::Duration = chrono::Duration::hours(24);
     pub static ref MOTION_EXPIRATION:chrono::Duration = chrono::Duration::hours(48);
+    pub static ref PAYMENT_REQUEST_EXPIRATION:chrono::Duration = chrono::Duration::days(7);
+    pub static ref MIN_SCHEDULE_INTERVAL:chrono::Duration = chrono::Duration::hours(1);
+    pub static ref TREASURY_PROPOSAL_EXPIRATION:chrono::Duration = chrono::Duration::days(7);
+    pub static ref POOL_COLLECTION_WINDOW:chrono::Duration = chrono::Duration::hours(12);
 }
 
-const VOTE_BASE_COST:u16 = 40;
 #[cfg(not(feature = "debug"))]
 const MOTIONS_CHANNEL:u64 = 609093491150028800; //bureaucracy channel
 #[cfg(feature = "debug")]
@@ -86,7 +136,20 @@ const MOTIONS_CHANNEL:u64 = 609093491150028800; //bureaucracy channel
 //const MOTIONS_CHANNEL:u64 = 560918427091468387; //spam channel
 const MOTIONS_CHANNEL:u64 = 770726979456466954; //pluto-beta-messages in CONceptualization
 
+#[cfg(not(feature = "debug"))]
+pub const PREFIX:&str = "$";
+#[cfg(feature = "debug")]
+pub const PREFIX:&str = "&";
+
+/// How long replies in the motions channel, sent when a reactor can't be DM'd, stay up.
+const EPHEMERAL_REPLY_DURATION:Duration = Duration::from_secs(30);
+
+/// Reactions to the DM about a payment request that pay or decline it.
+const PAY_EMOJI:&str = "\u{2705}";
+const DECLINE_EMOJI:&str = "\u{274C}";
+
 trait FromCommandArgs : Sized {
+    /// Errs with the catalog key of what went wrong.
     fn from_command_args(ctx: &Context, msg: &Message, arg: &str) -> Result<Self, &'static str>;
 }
 
@@ -139,7 +202,166 @@ impl FromCommandArgs for UserId {
                 }
             }
         }
-        Err("Could not find any User.")
+        Err("user.not_found")
+    }
+}
+
+/// The language a message should be replied to in.
+fn locale_of(conn: &diesel::PgConnection, msg: &Message) -> diesel::QueryResult<Locale> {
+    settings::locale_for(conn, msg.author.id.0 as i64, msg.guild_id.map(|g| g.0 as i64))
+}
+
+/// The language of the guild `MOTIONS_CHANNEL` is in, which motions are posted in.
+pub fn motions_locale(cache: &serenity::cache::CacheRwLock, conn: &diesel::PgConnection) -> diesel::QueryResult<Locale> {
+    let guild_id = cache.read().guild_channel(MOTIONS_CHANNEL).map(|c| c.read().guild_id.0 as i64);
+    Ok(match guild_id {
+        Some(guild_id) => settings::guild_locale(conn, guild_id)?.unwrap_or_default(),
+        None => Locale::default(),
+    })
+}
+
+/// A language code such as `es`, or `default` for no preference.
+fn parse_locale(word: &str) -> Result<Option<Locale>, parser::ParseError> {
+    if let Some(locale) = Locale::from_code(word) {
+        return Ok(Some(locale));
+    }
+    let mut choices:Vec<(&str, Option<Locale>)> = Locale::ALL.iter().map(|l| (l.code(), Some(*l))).collect();
+    choices.push(("default", None));
+    parser::parse_choice(word, &choices)
+}
+
+/// Refers to whoever holds an account in the ledger: a mention for a user, or a treasury or vote
+/// pool by number, since they have no Discord user to mention.
+fn account_mention(locale:Locale, account:i64) -> String {
+    match (treasuries::treasury_of(account), vote_pools::pool_of(account)) {
+        (Some(treasury_id), _) => tr!(locale, "treasury.account", id = treasury_id),
+        (_, Some(pool_id)) => tr!(locale, "pool.account", id = pool_id),
+        _ => UserId::from(account as u64).mention(),
+    }
+}
+
+fn available_locales() -> String {
+    Locale::ALL.iter().map(|l| format!("`{}` ({})", l.code(), l.name())).collect::<Vec<_>>().join(", ")
+}
+
+/// Operates the economy on Discord, posting motions to `MOTIONS_CHANNEL` in the given language.
+#[derive(Debug,Clone,Copy)]
+pub struct DiscordFrontend<C>(pub C, pub Locale);
+
+impl<C: serenity::http::CacheHttp + Copy> ChatFrontend for DiscordFrontend<C> {
+    fn post_motion(&self, status:&MotionStatus, motioned_by:i64) -> Result<i64, BoxError> {
+        let locale = self.1;
+        let motioned_by = UserId::from(motioned_by as u64);
+        let bot_msg = serenity::model::id::ChannelId(MOTIONS_CHANNEL).send_message(self.0.http(), |m| {
+            m.content(tr!(
+                locale,
+                "motion.called",
+                user = motioned_by.mention(),
+                motion = status.damm_id(),
+            )).embed(|e| {
+                e.field(status.kind_label(locale), &status.text, false)
+                .field(tr!(locale, "motion.votes"), status.tally_text(locale), false)
+                .field(motioned_by.mention(), tr!(locale, "motion.voter_for", votes = locale.number(1)), true)
+            })
+        })?;
+        Ok(bot_msg.id.0 as i64)
+    }
+
+    fn motion_posted(&self, post_id:i64, _status:&MotionStatus) -> Result<(), BoxError> {
+        let mut emojis:Vec<&VoteEmoji> = VOTE_EMOJI.iter().collect();
+        emojis.sort_by_key(|e| e.sort_key());
+        for emoji in emojis {
+            serenity::model::id::ChannelId::from(MOTIONS_CHANNEL)
+                .create_reaction(
+                    self.0.http(),
+                    post_id as u64,
+                    emoji.reaction_type()
+                )?;
+        }
+        Ok(())
+    }
+
+    fn update_motion(&self, post_id:i64, status:&MotionStatus) -> Result<(), BoxError> {
+        let locale = self.1;
+        let mut msg = self.0.http().get_message(MOTIONS_CHANNEL, post_id as u64)?;
+        msg.edit(self.0, |m| {
+            m.embed(|e| {
+                e.field(status.kind_label(locale), &status.text, false);
+                e.field(tr!(locale, "motion.votes"), status.tally_text(locale), false);
+                for vote in &status.votes[0..std::cmp::min(status.votes.len(),21)] {
+                    let key = if vote.direction { "motion.voter_for" } else { "motion.voter_against" };
+                    e.field(account_mention(locale, vote.user), tr!(locale, key, votes = locale.number(vote.amount)), true);
+                }
+
+                if status.votes.len() > 21 {
+                    e.field(tr!(locale, "motion.note"), tr!(locale, "motion.too_many_voters"), false);
+                }
+                e
+            })
+        })?;
+        Ok(())
+    }
+
+    fn announce_result(&self, status:&MotionStatus, at:chrono::DateTime<chrono::Utc>) -> Result<i64, BoxError> {
+        let locale = self.1;
+        let pass = status.is_win();
+        let announce_msg = serenity::model::id::ChannelId::from(MOTIONS_CHANNEL).send_message(self.0.http(), |m| {
+            m.embed(|e| {
+                e.title(tr!(
+                    locale,
+                    if pass { "motion.result_passed" } else { "motion.result_failed" },
+                    motion = status.damm_id(),
+                ));
+                if pass { e.description(&status.text); }
+                e.timestamp(&at);
+                if pass {
+                    e.field(tr!(locale, "motion.votes"), status.tally_text(locale), false);
+                }
+                e
+            })
+        })?;
+        Ok(announce_msg.id.0 as i64)
+    }
+
+    fn dm_user(&self, user:i64, text:&str) -> Result<(), BoxError> {
+        UserId::from(user as u64).create_dm_channel(self.0)?.say(self.0.http(), text)?;
+        Ok(())
+    }
+
+    fn mention(&self, user:i64) -> String {
+        account_mention(self.1, user)
+    }
+}
+
+/// Tells a user how their reaction vote went, as they've asked to be told. Falls back to a
+/// short-lived reply in the channel they reacted in if they can't be DM'd.
+fn notify_reactor<T: Localize, E: Localize>(ctx: &Context, r: &serenity::model::channel::Reaction, resp: Result<T, E>) {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get().unwrap();
+    let pref = settings::vote_receipts(&*conn, r.user_id.0 as i64).unwrap();
+    // motions, and so reactions that vote, are only ever in the motions channel
+    let motions_locale = motions_locale(&ctx.cache, &*conn).unwrap();
+    let locale = settings::user_locale(&*conn, r.user_id.0 as i64).unwrap().unwrap_or(motions_locale);
+    let (text, is_error) = match resp {
+        Ok(outcome) => (outcome.localize(locale), false),
+        Err(e) => (e.localize(locale), true),
+    };
+    if !pref.wants(is_error) {
+        return;
+    }
+    if let Err(e) = DiscordFrontend(ctx, motions_locale).dm_user(r.user_id.0 as i64, &text) {
+        info!("Could not DM {}, replying in channel instead: {:?}", r.user_id, e);
+        match r.channel_id.say(ctx, format!("{} {}", r.user_id.mention(), text)) {
+            Ok(reply) => {
+                let ctx = ctx.clone();
+                thread::spawn(move || {
+                    thread::sleep(EPHEMERAL_REPLY_DURATION);
+                    if let Err(e) = reply.delete(&ctx) {
+                        warn!("Could not delete reply {}: {:?}", reply.id, e);
+                    }
+                });
+            },
+            Err(e) => warn!("Could not reply to {} in {}: {:?}", r.user_id, r.channel_id, e),
+        }
     }
 }
 
@@ -152,34 +374,62 @@ impl EventHandler for Handler {
             return;
         }
         let message_id = r.message_id;
-        if let serenity::model::channel::ReactionType::Custom{animated: _, id, name: _} = r.emoji {
-            if let Some(action) = SPECIAL_EMOJI.get(&id.0) {
-                match action {
-                    SpecialEmojiAction::Direction(dir) => vote_direction = Some(*dir),
-                    SpecialEmojiAction::Amount(a) => vote_count = *a,
-                }
+        if let ReactionType::Unicode(emoji) = &r.emoji {
+            if emoji == PAY_EMOJI || emoji == DECLINE_EMOJI {
                 let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get().unwrap();
-                let resp = vote_common(
-                    &*conn,
-                    vote_direction,
-                    vote_count as i64,
-                    user_id.0 as i64,//user_id,
-                    None, //motion_id:Option<i64>,
-                    Some(message_id.0 as i64), //message_id:Option<i64>,
-                    None, //command_message_id:Option<i64>,
-                );
-                user_id.create_dm_channel(&ctx).unwrap().say(&ctx, resp).unwrap();
+                if let Some(request) = payment_requests::by_prompt(&*conn, message_id.0 as i64).unwrap() {
+                    let locale = match settings::user_locale(&*conn, user_id.0 as i64).unwrap() {
+                        Some(locale) => locale,
+                        None => motions_locale(&ctx.cache, &*conn).unwrap(),
+                    };
+                    let text = settle_request(&ctx, &*conn, locale, request.rowid, user_id, emoji == PAY_EMOJI, None).unwrap();
+                    if let Err(e) = r.channel_id.say(&ctx, text) {
+                        warn!("Could not reply to {} in {}: {:?}", user_id, r.channel_id, e);
+                    }
+                    return;
+                }
+            }
+        }
+        if let Some(vote_emoji) = VOTE_EMOJI.iter().find(|e| e.matches(&r.emoji)) {
+            match vote_emoji.action {
+                SpecialEmojiAction::Direction(dir) => vote_direction = Some(dir),
+                SpecialEmojiAction::Amount(a) => vote_count = a,
             }
+            let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get().unwrap();
+            let resp = economy::vote(
+                &*conn,
+                vote_direction,
+                VoteAmount::Exact(vote_count as i64),
+                user_id.0 as i64,//user_id,
+                None, //motion_id:Option<i64>,
+                Some(message_id.0 as i64), //post_id:Option<i64>,
+                None, //command_message_id:Option<i64>,
+                Some(&vote_emoji.db_key()), //reaction_emoji:Option<&str>,
+            ).unwrap();
+            notify_reactor(&ctx, &r, resp);
         }
     }
-}
 
-fn nth_vote_cost(n:i64) -> Result<i64,()> {
-    let res:f64 = (VOTE_BASE_COST as f64) * (1.05f64).powf((n-1) as f64);
-    if res < 0.0 || res > 4611686018427388000.0 {
-        Err(())
-    } else {
-        Ok(res as i64)
+    fn reaction_remove(&self, ctx: Context, r: serenity::model::channel::Reaction) {
+        let window = match *REACTION_REFUND_WINDOW {
+            Some(w) => w,
+            None => return,
+        };
+        if r.user_id == ctx.cache.read().user.id {
+            return;
+        }
+        if let Some(vote_emoji) = VOTE_EMOJI.iter().find(|e| e.matches(&r.emoji)) {
+            let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get().unwrap();
+            if let Some(resp) = economy::refund_reaction_vote(
+                &*conn,
+                r.user_id.0 as i64,
+                r.message_id.0 as i64,
+                &vote_emoji.db_key(),
+                window,
+            ).unwrap() {
+                notify_reactor(&ctx, &r, resp);
+            }
+        }
     }
 }
 
@@ -187,6 +437,12 @@ pub fn bot_main() {
     lazy_static::initialize(&GENERATE_EVERY);
     lazy_static::initialize(&USER_PING_RE);
     lazy_static::initialize(&MOTION_EXPIRATION);
+    lazy_static::initialize(&PAYMENT_REQUEST_EXPIRATION);
+    lazy_static::initialize(&MIN_SCHEDULE_INTERVAL);
+    lazy_static::initialize(&TREASURY_PROPOSAL_EXPIRATION);
+    lazy_static::initialize(&POOL_COLLECTION_WINDOW);
+    lazy_static::initialize(&VOTE_EMOJI);
+    lazy_static::initialize(&REACTION_REFUND_WINDOW);
 
     let pool = diesel::r2d2::Builder::new().build(
         diesel::r2d2::ConnectionManager::<diesel::PgConnection>::new(
@@ -195,18 +451,7 @@ pub fn bot_main() {
     ).expect("could not build DB pool");
     let arc_pool = Arc::new(pool);
 
-    {
-        let conn = arc_pool.get().unwrap();
-        use schema::single::dsl::*;
-        use diesel::prelude::*;
-        use diesel::dsl::*;
-        if !(select(exists(single.filter(enforce_single_row))).get_result(&*conn).unwrap():bool) {
-            insert_into(single).values((
-                enforce_single_row.eq(true),
-                last_gen.eq(chrono::Utc::now())
-            )).execute(&*conn).unwrap();
-        }
-    }
+    economy::init(&*arc_pool.get().unwrap()).unwrap();
 
     // Login with a bot token from the environment
     let mut client = Client::new(&env::var("DISCORD_TOKEN").expect("token"), Handler)
@@ -214,16 +459,17 @@ pub fn bot_main() {
     let mut write_handle = client.data.write();
     write_handle.insert::<DbPoolKey>(Arc::clone(&arc_pool));
     drop(write_handle);
-    #[cfg(feature = "debug")]
-    let prefix = "&";
-    #[cfg(not(feature = "debug"))]
-    let prefix = "$";
     let current_user = client.cache_and_http.http.get_current_user().expect("I don't know who I am!");
     let mut framework = StandardFramework::new()
     .configure(|c| {
-        c.prefix(prefix).allow_dm(true).on_mention(Some(current_user.id))
+        c.prefix(PREFIX).allow_dm(true).on_mention(Some(current_user.id))
     })
-    .on_dispatch_error(|_ctx, msg, err| {
+    .on_dispatch_error(|ctx, msg, err| {
+        if let DispatchError::CheckFailed(_, Reason::User(reason)) = &err {
+            if let Err(e) = msg.reply(ctx, reason) {
+                warn!("Could not explain a failed check: {:?}", e);
+            }
+        }
         println!(
             "{:?}\nerr'd with {:?}",
             msg, err
@@ -237,144 +483,95 @@ pub fn bot_main() {
     framework = framework.group(&GENERAL_GROUP);
     #[cfg(feature = "debug")]
     { framework = framework.group(&GENERAL_GROUP).group(&DEBUG_GROUP); }
-    client.with_framework(framework);
+    client.with_framework(AliasingFramework{ inner: framework, prefix: PREFIX, pool: Arc::clone(&arc_pool) });
 
     let cnh = Arc::clone(&client.cache_and_http);
     let announce_threads_conn = arc_pool.get().unwrap();
     thread::spawn(move || {
-        use diesel::prelude::*;
-        use schema::motions::dsl as mdsl;
-        use schema::motion_votes::dsl as mvdsl;
         let conn = announce_threads_conn;
         
         loop {
             std::thread::sleep(Duration::from_millis(500));
-            let now = chrono::Utc::now();
-            let motions:Vec<(String, i64, bool)> = mdsl::motions
-                .filter(mdsl::announcement_message_id.is_null())
-                .filter(mdsl::last_result_change.lt(now - *MOTION_EXPIRATION))
-                .select((mdsl::motion_text, mdsl::rowid, mdsl::is_super))
-                .get_results(&*conn).unwrap();
-            for (motion_text, motion_id, is_super) in &motions {
-                #[derive(Queryable,Debug)]
-                struct MotionVote {
-                    user:i64,
-                    amount:i64,
-                    direction:bool,
-                }
-                let votes:Vec<MotionVote> = mvdsl::motion_votes
-                    .filter(mvdsl::motion.eq(motion_id))
-                    .select((mvdsl::user, mvdsl::amount, mvdsl::direction))
-                    .get_results(&*conn).unwrap();
-                let mut yes_votes = 0;
-                let mut no_votes = 0;
-                for vote in &votes {
-                    if vote.direction {
-                        yes_votes += vote.amount;
-                    } else {
-                        no_votes += vote.amount;
-                    }
-                }
-                let pass = is_win(yes_votes, no_votes, *is_super);
-                let pass_msg = if pass { "PASSED" } else { "FAILED" }; 
-                let announce_msg = serenity::model::id::ChannelId::from(MOTIONS_CHANNEL).send_message(&cnh.http, |m| {
-                    m.embed(|e| {
-                        e.title(
-                            format!(
-                                "Vote ended! Motion #{} has {}.",
-                                damm::add_to_str(motion_id.to_string()), 
-                                pass_msg,
-                            )
-                        );
-                        if pass { e.description(motion_text); }
-                        e.timestamp(&now);
-                        if pass {
-                            e.field("Votes", format!("**for {}**/{} against", yes_votes, no_votes), false);
-                        }else{
-                            e.field("Votes", format!("**against {}**/{} for", no_votes, yes_votes), false);
-                        }
-                        e
-                    })
-                }).unwrap();
-
-                diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(
-                    mdsl::announcement_message_id.eq(announce_msg.id.0 as i64)
-                ).execute(&*conn).unwrap();
+            let locale = match motions_locale(&cnh.cache, &*conn) {
+                Ok(l) => l,
+                Err(e) => {
+                    warn!("Could not look up the motions locale: {:?}", e);
+                    continue
+                },
+            };
+            let frontend = DiscordFrontend(&*cnh.http, locale);
+            if let Err(e) = economy::announce_expired_motions(&frontend, &*conn, *MOTION_EXPIRATION, locale) {
+                warn!("Could not announce expired motions: {:?}", e);
             }
+            if let Err(e) = economy::update_changed_motions(&frontend, &*conn) {
+                warn!("Could not update changed motions: {:?}", e);
+            }
+        }
+    });
+
+    let schedules_cnh = Arc::clone(&client.cache_and_http);
+    let schedules_conn = arc_pool.get().unwrap();
+    thread::spawn(move || {
+        let conn = schedules_conn;
 
-            let mmids:Vec<i64> = mdsl::motions
-                .filter(mdsl::announcement_message_id.is_null())
-                .filter(mdsl::needs_update)
-                .select(mdsl::bot_message_id)
-                .get_results(&*conn)
-                .unwrap();
-            for mmid in &mmids {
-                let mut motion_message = cnh.http.get_message(MOTIONS_CHANNEL, *mmid as u64).unwrap();
-                update_motion_message(Arc::clone(&cnh.http), &*conn, &mut motion_message).unwrap(); 
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let locale = match motions_locale(&schedules_cnh.cache, &*conn) {
+                Ok(l) => l,
+                Err(e) => {
+                    warn!("Could not look up the motions locale: {:?}", e);
+                    continue
+                },
+            };
+            let frontend = DiscordFrontend(&*schedules_cnh.http, locale);
+            match scheduled_transfers::run_due(&frontend, &*conn, locale) {
+                Ok(0) => (),
+                Ok(paid) => info!("Paid {} scheduled transfers", paid),
+                Err(e) => warn!("Could not run scheduled transfers: {:?}", e),
             }
+        }
+    });
+
+    let pools_cnh = Arc::clone(&client.cache_and_http);
+    let pools_conn = arc_pool.get().unwrap();
+    thread::spawn(move || {
+        let conn = pools_conn;
 
+        loop {
+            std::thread::sleep(Duration::from_secs(1));
+            let locale = match motions_locale(&pools_cnh.cache, &*conn) {
+                Ok(l) => l,
+                Err(e) => {
+                    warn!("Could not look up the motions locale: {:?}", e);
+                    continue
+                },
+            };
+            let frontend = DiscordFrontend(&*pools_cnh.http, locale);
+            match vote_pools::run_due(&frontend, &*conn, locale) {
+                Ok(0) => (),
+                Ok(settled) => info!("Settled {} vote pools", settled),
+                Err(e) => warn!("Could not settle vote pools: {:?}", e),
+            }
         }
     });
 
     let threads_conn = arc_pool.get().unwrap();
     thread::spawn(move || {
-        // use schema::gen::dsl as gdsl;
-        use schema::transfers::dsl as tdsl;
-        use diesel::prelude::*;
-        use view_schema::balance_history::dsl as bhdsl;
-        use schema::single::dsl as sdsl;
         let conn = threads_conn;
 
         loop {
-            /* not properly locking, but should only have one thread trying to access */
             std::thread::sleep(Duration::from_millis(500));
-            let now = chrono::Utc::now();
-            let last_gen:chrono::DateTime<chrono::Utc> = sdsl::single.select(sdsl::last_gen).get_result(&*conn).unwrap();
-            if now - last_gen < *GENERATE_EVERY {
+            let start_chrono = chrono::Utc::now();
+            let start_instant = std::time::Instant::now();
+            if !economy::generate_if_due(&*conn, *GENERATE_EVERY).unwrap() {
                 thread::sleep(std::time::Duration::from_secs(1));
                 continue
             }
-            eprintln!("Generating some political capital!");
-            let start_chrono = chrono::Utc::now();
-            let start_instant = std::time::Instant::now();
-            conn.transaction::<_, diesel::result::Error, _>(|| {
-                diesel::sql_query("LOCK TABLE transfers IN EXCLUSIVE MODE;").execute(&*conn)?;
-
-                let users:Vec<Option<i64>> = tdsl::transfers.select(tdsl::to_user).distinct().filter(tdsl::ty.eq("gen")).filter(tdsl::to_user.is_not_null()).get_results(&*conn).unwrap();
-                for userid_o in &users {
-                    let userid = userid_o.unwrap();
-                    let balance = |ty_str:&'static str| {
-                        bhdsl::balance_history
-                            .select(bhdsl::balance)
-                            .filter(bhdsl::user.eq(userid))
-                            .filter(bhdsl::ty.eq(ty_str))
-                            .order(bhdsl::happened_at.desc())
-                            .limit(1)
-                            .get_result(&*conn)
-                            .optional()
-                            .unwrap()
-                            .unwrap_or(0):i64
-                    };
-                    let gen_balance = balance("gen");
-                    let pc_balance = balance("pc");
-                    diesel::insert_into(tdsl::transfers).values((
-                        tdsl::ty.eq("pc"),
-                        tdsl::quantity.eq(gen_balance),
-                        tdsl::to_user.eq(userid),
-                        tdsl::to_balance.eq(pc_balance + gen_balance),
-                        tdsl::happened_at.eq(now),
-                        tdsl::transfer_ty.eq("generated"),
-                    )).execute(&*conn).unwrap();
-                }
-
-                diesel::update(sdsl::single).set(sdsl::last_gen.eq(last_gen + *GENERATE_EVERY)).execute(&*conn)?;
-                
-                Ok(())
-            }).unwrap();
             let end_instant = std::time::Instant::now();
             let end_chrono = chrono::Utc::now();
             let chrono_dur = end_chrono - start_chrono;
 
+            eprintln!("Generated some political capital!");
             eprintln!("PC generation took {} kernel seconds/{} RTC seconds", (end_instant - start_instant).as_secs_f64(), chrono_dur);
         }
     });
@@ -392,406 +589,1412 @@ pub fn bot_main() {
     }
 }
 
-fn update_motion_message(
-    ctx: impl serenity::http::CacheHttp,
-    conn: &diesel::pg::PgConnection,
-    msg: &mut serenity::model::channel::Message
-) -> CommandResult {
-    use schema::motions::dsl as mdsl;
-    use schema::motion_votes::dsl as mvdsl;
-    use diesel::prelude::*;
-    
-    let (motion_text, motion_id, is_super) = mdsl::motions.filter(mdsl::bot_message_id.eq(msg.id.0 as i64)).select((mdsl::motion_text, mdsl::rowid, mdsl::is_super)).get_result(conn)?:(String, i64, bool);
-    #[derive(Queryable,Debug)]
-    struct MotionVote {
-        user:i64,
-        amount:i64,
-        direction:bool,
-    }
-    let mut votes:Vec<MotionVote> = mvdsl::motion_votes.filter(mvdsl::motion.eq(motion_id)).select((mvdsl::user, mvdsl::amount, mvdsl::direction)).get_results(conn)?;
-    let mut yes_votes = 0;
-    let mut no_votes = 0;
-    for vote in &votes {
-        if vote.direction {
-            yes_votes += vote.amount;
-        } else {
-            no_votes += vote.amount;
-        }
+/// Lets bot admins through: users granted it with `$admins`, and anyone with a granted role.
+#[check]
+#[name = "Admin"]
+fn admin_check(ctx: &mut Context, msg: &Message, _: &mut Args, _: &CommandOptions) -> CheckResult {
+    let roles:Vec<i64> = msg.member.as_ref()
+        .map(|m| m.roles.iter().map(|r| r.0 as i64).collect())
+        .unwrap_or_default();
+    let res = ctx.data.read().get::<DbPoolKey>().unwrap().get()
+        .map_err(BoxError::from)
+        .and_then(|conn| Ok((
+            admin::is_admin(&*conn, msg.author.id.0 as i64, &roles)?,
+            locale_of(&*conn, msg)?,
+        )));
+    match res {
+        Ok((true, _)) => CheckResult::Success,
+        Ok((false, locale)) => CheckResult::new_user(tr!(locale, "admin.not_admin")),
+        Err(e) => CheckResult::new_log(format!("Could not check for admin: {:?}", e)),
     }
-    votes.sort_unstable_by_key(|v| -v.amount);
-    let pass = is_win(yes_votes, no_votes, is_super);
-    let cap_label = if is_super { "Supermotion" } else { "Simple Motion" };
-    msg.edit(ctx, |m| {
-        m.embed(|e| {
-            e.field(cap_label, motion_text, false);
-            if pass {
-                e.field("Votes", format!("**for {}**/{} against", yes_votes, no_votes), false);
-            } else {
-                e.field("Votes", format!("**against {}**/{} for", no_votes, yes_votes), false);
-            }
-            for vote in &votes[0..std::cmp::min(votes.len(),21)] {
-                e.field(serenity::model::id::UserId::from(vote.user as u64), format!("{} {}", vote.amount, if vote.direction {"for"} else {"against"}), true);
-            }
-
-            if votes.len() > 21 {
-                e.field("Note", "There are more users that have voted, but there are too many to display here.", false);
-            }
-            e
-        })
-    }).unwrap();
-    let target = mdsl::motions.filter(mdsl::bot_message_id.eq(msg.id.0 as i64));
-    diesel::update(target).set(mdsl::needs_update.eq(false)).execute(conn).unwrap();
-    Ok(())
 }
 
 #[command]
+#[checks(Admin)]
 #[num_args(1)]
-fn hack_message_update(ctx: &mut Context, _msg: &Message, mut args: Args) -> CommandResult {
+fn hack_message_update(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
     let motion_message_id:u64 = args.single()?;
-    let mut motion_message = ctx.http.get_message(MOTIONS_CHANNEL, motion_message_id)?;
     let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
-    update_motion_message(ctx, &*conn, &mut motion_message) 
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: None, message_id: Some(msg.id.0 as i64) };
+    admin::record(&*conn, actor, admin::AdminAction::RefreshMotion, None, serde_json::json!({ "post": motion_message_id }), chrono::Utc::now())?;
+    economy::refresh_motion_post(&DiscordFrontend(&*ctx, motions_locale(&ctx.cache, &*conn)?), &*conn, motion_message_id as i64)?;
+    Ok(())
 }
 
 #[command]
 fn ping(ctx: &mut Context, msg: &Message) -> CommandResult {
-    msg.reply(ctx, "The use of such childish terminology to describe a professional sport played in the olympics such as table tennis is downright offensive to the athletes that have dedicated their lives to perfecting the art. Furthermore, usage of the sport as some inane way to check presence in computer networks and programs would imply that anyone can return a serve as long as they're present, which further degredates the athletes that work day and night to compete for championship tournaments throughout the world.\n\nIn response to your *serve*, I hit back a full force spinball corner return. Don't even try to hit it back.")?;
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    msg.reply(ctx, tr!(locale_of(&*conn, msg)?, "ping.reply"))?;
 
     Ok(())
 }
 
+/// The reason given after a command's other arguments, if any.
+fn reason_of(tokens: &[String]) -> Option<String> {
+    if tokens.is_empty() { None } else { Some(tokens.join(" ")) }
+}
+
+/// Creates items out of thin air, for the caller or another user.
 #[command]
-#[num_args(2)]
-fn fabricate(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
-    use diesel::prelude::*;
-    use schema::item_types::dsl as it;
-    use schema::item_type_aliases::dsl as ita;
+#[checks(Admin)]
+#[min_args(2)]
+fn fabricate(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
     let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
 
-    let ty:ItemType;
-    let ty_str:String = args.single()?;
-    let alias:Option<ItemType> = ita::item_type_aliases
-        .inner_join(it::item_types)
-        .select(it::item_types::all_columns())
-        .filter(ita::alias.eq(&ty_str))
-        .get_result(&*conn)
-        .optional()?;
-    if let Some(it) = alias{
-        ty = it;
-    } else {
-        return Err("Unrecognized type".into());
-    }
-    let how_many:i64 = args.single()?;
-    if how_many <= 0 {
-        return Err("fuck".into());
-    }
-    let user:UserId;
-    if args.remaining() > 0 {
-        let user_str = args.single()?:String;
-        user = UserId::from_command_args(ctx, msg, &user_str)?;
-    }else{
-        user = msg.author.id;
-    }
-
-    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
-    conn.transaction::<_, diesel::result::Error, _>(|| {
-        use view_schema::balance_history::dsl as bh;
-        use schema::transfers::dsl as tdsl;
-        let prev_balance:i64 = view_schema::balance_history::table
-          .select(bh::balance)
-          .filter(bh::user.eq(user.0 as i64))
-          .filter(bh::ty.eq(ty.db_name()))
-          .order(bh::happened_at.desc())
-          .limit(1)
-          .for_update()
-          .get_result(&*conn)
-          .optional()?
-          .unwrap_or(0);
-        
-        diesel::insert_into(tdsl::transfers).values((
-            tdsl::quantity.eq(how_many),
-            tdsl::to_user.eq(msg.author.id.0 as i64),
-            tdsl::to_balance.eq(prev_balance + how_many),
-            tdsl::happened_at.eq(chrono::Utc::now()),
-            tdsl::message_id.eq(msg.id.0 as i64),
-            tdsl::ty.eq(ty.db_name()),
-            tdsl::transfer_ty.eq("command_fabricate"),
-        )).execute(&*conn)?;
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (ty_str, how_many_str, user_str, reason) = match tokens.as_slice() {
+        [ty_str, how_many_str] => (ty_str, how_many_str, None, None),
+        [ty_str, how_many_str, user_str, reason @ ..] => (ty_str, how_many_str, Some(user_str), reason_of(reason)),
+        _ => return Err(tr!(locale, "fabricate.usage").into()),
+    };
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    let how_many = parser::parse_number(how_many_str).map_err(|e| e.localize(locale))? as i64;
+    let user = match user_str {
+        Some(user_str) => UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?,
+        None => msg.author.id,
+    };
 
-        Ok(())
-    })?;
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: reason.as_deref(), message_id: Some(msg.id.0 as i64) };
+    let balance = admin::fabricate(&*conn, actor, user.0 as i64, &ty, how_many)?.map_err(|e| e.localize(locale))?;
 
-    msg.reply(&ctx, "Fabricated.")?;
+    msg.reply(&ctx, tr!(
+        locale,
+        "fabricate.done",
+        amount = locale.number(how_many),
+        items = ty.long_name_ambiguous,
+        user = user.mention(),
+        balance = locale.number(balance),
+    ))?;
 
     Ok(())
 }
 
+/// Destroys some of what a user holds.
 #[command]
-#[aliases("?","h")]
-fn help(ctx: &mut Context, msg: &Message) -> CommandResult {
-    msg.reply(&ctx, "For help see https://github.com/consortium-chat/plutocradroid/blob/master/README.md#commands")?;
-    Ok(())
-}
+#[checks(Admin)]
+#[min_args(3)]
+fn force_burn(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
 
-#[command]
-#[aliases("v","info","version")]
-fn version_info(ctx: &mut Context, msg: &Message) -> CommandResult {
-    msg.reply(&ctx, format!(
-        "Plutocradroid {} commit {} built for {} at {}.\nhttps://github.com/consortium-chat/plutocradroid",
-        env!("VERGEN_SEMVER_LIGHTWEIGHT"),
-        env!("VERGEN_SHA_SHORT"),
-        env!("VERGEN_TARGET_TRIPLE"),
-        env!("VERGEN_BUILD_TIMESTAMP"),
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, amount_str, ty_str, reason) = match tokens.as_slice() {
+        [user_str, amount_str, ty_str, reason @ ..] => (user_str, amount_str, ty_str, reason_of(reason)),
+        _ => return Err(tr!(locale, "force_burn.usage").into()),
+    };
+    let user = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    let amount = parser::parse_amount(amount_str).map_err(|e| e.localize(locale))?
+        .resolve(economy::balance_of(&*conn, user.0 as i64, &ty)?);
+
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: reason.as_deref(), message_id: Some(msg.id.0 as i64) };
+    let balance = admin::burn(&*conn, actor, user.0 as i64, &ty, amount)?.map_err(|e| e.localize(locale))?;
+
+    msg.reply(&ctx, tr!(
+        locale,
+        "force_burn.done",
+        amount = locale.number(amount),
+        items = ty.long_name_ambiguous,
+        user = user.mention(),
+        balance = locale.number(balance),
     ))?;
+
     Ok(())
 }
 
+/// Sets exactly how much of something a user holds.
 #[command]
-#[aliases("b","bal","balance","i","inv","inventory")]
-fn balances(ctx: &mut Context, msg: &Message) -> CommandResult {
-    use diesel::prelude::*;
-    use view_schema::balance_history::dsl as bh;
-    use schema::item_types::dsl as it;
+#[checks(Admin)]
+#[min_args(3)]
+fn adjust(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
     let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
-    
+    let locale = locale_of(&*conn, msg)?;
 
-    let get_bal = |ty_str:&str| {
-        bh::balance_history
-        .select(bh::balance)
-        .filter(bh::user.eq(msg.author.id.0 as i64))
-        .filter(bh::ty.eq(ty_str))
-        .order(bh::happened_at.desc())
-        .limit(1)
-        .get_result(&*conn)
-        .optional()
-        .map(|opt| opt.unwrap_or(0i64)):Result<i64,_>
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, balance_str, ty_str, reason) = match tokens.as_slice() {
+        [user_str, balance_str, ty_str, reason @ ..] => (user_str, balance_str, ty_str, reason_of(reason)),
+        _ => return Err(tr!(locale, "adjust.usage").into()),
     };
-    let item_types:Vec<ItemType> = it::item_types
-        .get_results(&*conn)?;
-    let balances = (item_types.into_iter().map(|ty| get_bal(ty.db_name()).map(|bal| (ty,bal))).collect():Result<Vec<_>,_>)?;
-    // let gen_count = get_bal("gen")?;
-    // let pc_count = get_bal("pc")?;
-    msg.channel_id.send_message(&ctx, |cm| {
-        cm.embed(|e| {
-            e.title("Your balances:");
-            // e.field("Generators", gen_count, false);
-            // e.field("Capital", pc_count, false);
-            for (item_type, amount) in &balances {
-                e.field(&item_type.long_name_plural, amount, false);
-            }
-            e
-        });
-        cm
-    })?;
+    let user = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    let balance = parser::parse_number(balance_str).map_err(|e| e.localize(locale))? as i64;
+
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: reason.as_deref(), message_id: Some(msg.id.0 as i64) };
+    let before = admin::adjust(&*conn, actor, user.0 as i64, &ty, balance)?.map_err(|e| e.localize(locale))?;
+
+    msg.reply(&ctx, tr!(
+        locale,
+        "adjust.done",
+        user = user.mention(),
+        items = ty.long_name_ambiguous,
+        before = locale.number(before),
+        balance = locale.number(balance),
+    ))?;
+
     Ok(())
 }
 
-// #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-// enum ItemType {
-//     PoliticalCapital,
-//     Generator,
-// }
+/// Moves items between any two users, whether or not either of them asked for it.
+#[command]
+#[checks(Admin)]
+#[min_args(4)]
+fn force_transfer(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
 
-// impl ItemType {
-//     pub fn db_name(&self) -> &'static str {
-//         match *self {
-//             ItemType::PoliticalCapital => "pc",
-//             ItemType::Generator => "gen",
-//         }
-//     }
-// }
-#[derive(Debug, PartialEq, Eq, Clone, Queryable)]
-struct ItemType{
-    pub name: String,
-    pub long_name_plural: String,
-    pub long_name_ambiguous: String,
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (from_str, to_str, amount_str, ty_str, reason) = match tokens.as_slice() {
+        [from_str, to_str, amount_str, ty_str, reason @ ..] => (from_str, to_str, amount_str, ty_str, reason_of(reason)),
+        _ => return Err(tr!(locale, "force_transfer.usage").into()),
+    };
+    let from = UserId::from_command_args(ctx, msg, from_str).map_err(|key| tr!(locale, key))?;
+    let to = UserId::from_command_args(ctx, msg, to_str).map_err(|key| tr!(locale, key))?;
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    let amount = parser::parse_amount(amount_str).map_err(|e| e.localize(locale))?
+        .resolve(economy::balance_of(&*conn, from.0 as i64, &ty)?);
+
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: reason.as_deref(), message_id: Some(msg.id.0 as i64) };
+    admin::transfer(&*conn, actor, from.0 as i64, to.0 as i64, &ty, amount)?.map_err(|e| e.localize(locale))?;
+
+    msg.reply(&ctx, tr!(
+        locale,
+        "force_transfer.done",
+        amount = locale.number(amount),
+        items = ty.long_name_ambiguous,
+        from = from.mention(),
+        to = to.mention(),
+    ))?;
+
+    Ok(())
 }
 
-impl ItemType {
-    pub fn db_name(&self) -> &str {
-        self.name.as_str()
+/// A role in the message's guild, by mention, id or name.
+fn role_from_arg(ctx: &Context, msg: &Message, arg: &str) -> Result<RoleId, &'static str> {
+    let guild = msg.guild(&ctx.cache).ok_or("admins.role_not_found")?;
+    let guild = guild.read();
+    let digits = arg.trim_start_matches("<@&").trim_end_matches('>');
+    if let Ok(raw_id) = digits.parse():Result<u64,_> {
+        // a role from another guild would make its members admins here too
+        let role = RoleId::from(raw_id);
+        return if guild.roles.contains_key(&role) { Ok(role) } else { Err("admins.role_not_found") };
     }
+    guild.roles.values()
+        .find(|r| r.name.to_lowercase() == arg.to_lowercase())
+        .map(|r| r.id)
+        .ok_or("admins.role_not_found")
 }
 
+/// Lists, grants or takes away bot admin, from users or from everyone with a role. Admin is global,
+/// so only existing bot admins can hand it out.
 #[command]
-#[min_args(2)]
-#[max_args(3)]
-fn give(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
-    give_common(ctx, msg, args, true)
+#[only_in(guilds)]
+#[checks(Admin)]
+fn admins(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let guild_id = msg.guild_id.unwrap();
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let kinds = [("user", false), ("role", true)];
+    match tokens.as_slice() {
+        [] => {
+            let users:Vec<String> = admin::admin_users(&*conn)?
+                .into_iter()
+                .map(|u| UserId::from(u as u64).mention())
+                .collect();
+            let roles:Vec<String> = admin::admin_roles(&*conn, guild_id.0 as i64)?
+                .into_iter()
+                .map(|r| match RoleId::from(r as u64).to_role_cached(&ctx.cache) {
+                    Some(role) => role.name,
+                    None => r.to_string(),
+                })
+                .collect();
+            let none = tr!(locale, "admins.none");
+            msg.reply(&ctx, tr!(
+                locale,
+                "admins.list",
+                users = if users.is_empty() { none.to_string() } else { users.join(", ") },
+                roles = if roles.is_empty() { none.to_string() } else { roles.join(", ") },
+            ))?;
+        },
+        [action, kind, who] => {
+            let adding = parser::parse_choice(action, &[("add", true), ("remove", false)]).map_err(|e| e.localize(locale))?;
+            let is_role = parser::parse_choice(kind, &kinds).map_err(|e| e.localize(locale))?;
+            let actor = Actor{ user: msg.author.id.0 as i64, reason: None, message_id: Some(msg.id.0 as i64) };
+            let (changed, name) = if is_role {
+                let role = role_from_arg(ctx, msg, who).map_err(|key| tr!(locale, key))?;
+                let name = role.to_role_cached(&ctx.cache).map(|r| r.name).unwrap_or_else(|| role.0.to_string());
+                let changed = if adding {
+                    admin::add_admin_role(&*conn, actor, guild_id.0 as i64, role.0 as i64)?
+                } else {
+                    admin::remove_admin_role(&*conn, actor, guild_id.0 as i64, role.0 as i64)?
+                };
+                (changed, name)
+            } else {
+                let user = UserId::from_command_args(ctx, msg, who).map_err(|key| tr!(locale, key))?;
+                let changed = if adding {
+                    admin::add_admin_user(&*conn, actor, user.0 as i64)?
+                } else {
+                    admin::remove_admin_user(&*conn, actor, user.0 as i64)?
+                };
+                (changed, user.mention())
+            };
+            let key = match (adding, changed) {
+                (true, true) => "admins.added",
+                (true, false) => "admins.already",
+                (false, true) => "admins.removed",
+                (false, false) => "admins.not_admin",
+            };
+            msg.reply(&ctx, tr!(locale, key, name = name))?;
+        },
+        _ => return Err(tr!(locale, "admins.usage").into()),
+    }
+    Ok(())
 }
 
+/// Shows, adds or removes words that `$vote` understands.
 #[command]
-#[min_args(2)]
-#[max_args(3)]
-fn force_give(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
-    give_common(ctx, msg, args, false)
-}
-
-fn give_common(ctx:&mut Context, msg:&Message, mut args:Args, check_user:bool) -> CommandResult {
-    use diesel::prelude::*;
-    use schema::item_types::dsl as it;
-    use schema::item_type_aliases::dsl as ita;
+#[checks(Admin)]
+fn vote_word(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
     let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
-
-    let user_str:String = args.single()?;
-    let user = UserId::from_command_args( ctx, msg, &user_str )?;
-    if check_user && !ctx.cache.read().users.contains_key(&user) {
-        return Err("User not found".into());
-    }
-    let mut maybe_ty:Option<ItemType> = None;
-    let mut amount:Option<u64> = None;
-    for arg_result in args.iter::<String>(){
-        let arg = arg_result.unwrap();
-        let alias:Option<ItemType> = ita::item_type_aliases
-            .inner_join(it::item_types)
-            .select(it::item_types::all_columns())
-            .filter(ita::alias.eq(&arg))
-            .get_result(&*conn)
-            .optional()?;
-        if let Some(ty) = alias {
-            maybe_ty = Some(ty);
-        } else if let Some(idx) = arg.find(|c| !('0' <= c && c <= '9')) {
-            if idx == 0 {
-                return Err(format!("Invalid item type {}", arg).into());
+    let locale = locale_of(&*conn, msg)?;
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: None, message_id: Some(msg.id.0 as i64) };
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    match tokens.as_slice() {
+        [] => {
+            let words = vocabulary::vote_words(&*conn)?;
+            let mut lines:Vec<String> = Vec::new();
+            for &meaning in &[VoteWord::Yes, VoteWord::No, VoteWord::Zero, VoteWord::Ignore] {
+                let these:Vec<&str> = words.iter().filter(|(_, m)| *m == meaning).map(|(w, _)| w.as_str()).collect();
+                lines.push(format!("**{}**: {}", meaning.db_name(), these.join(", ")));
             }
-            let (count_str, ty_str) = arg.split_at(idx);
-            if !ty_str.is_empty() {
-                let alias:Option<ItemType> = ita::item_type_aliases
-                    .inner_join(it::item_types)
-                    .select(it::item_types::all_columns())
-                    .filter(ita::alias.eq(&ty_str))
-                    .get_result(&*conn)
-                    .optional()?;
-                if let Some(ty) = alias {
-                    maybe_ty = Some(ty);
-                } else {
-                    return Err(format!("Unrecognized item type {}", ty_str).into());
-                }
+            msg.reply(&ctx, lines.join("\n"))?;
+        },
+        [action, meaning, word] if parser::parse_choice(action, &[("add", ())]).is_ok() => {
+            let meaning = parser::parse_choice(meaning, &[
+                ("yes", VoteWord::Yes), ("no", VoteWord::No), ("zero", VoteWord::Zero), ("ignore", VoteWord::Ignore),
+            ]).map_err(|e| e.localize(locale))?;
+            if parser::is_reserved_vote_word(word) {
+                return Err(tr!(locale, "vote_word.reserved", word = word).into());
             }
-
-            match count_str.parse():Result<u64,_> {
-                Err(e) => return Err(format!("Bad count {:?}", e).into()),
-                Ok(val) => amount = Some(val),
+            if !vocabulary::add_vote_word(&*conn, actor, word, meaning)? {
+                return Err(tr!(locale, "vote_word.exists", word = word.to_lowercase()).into());
             }
-        }else{
-            match arg.parse():Result<u64, _> {
-                Err(e) => return Err(format!("Bad count {:?}", e).into()),
-                Ok(val) => amount = Some(val),
+            msg.reply(&ctx, tr!(locale, "vote_word.added", word = word.to_lowercase(), meaning = meaning.db_name()))?;
+        },
+        [action, word] if parser::parse_choice(action, &[("remove", ())]).is_ok() => {
+            if vocabulary::remove_vote_word(&*conn, actor, word)? {
+                msg.reply(&ctx, tr!(locale, "vote_word.removed", word = word.to_lowercase()))?;
+            } else {
+                msg.reply(&ctx, tr!(locale, "vote_word.not_found", word = word))?;
             }
-        }
+        },
+        [action, ..] => {
+            parser::parse_choice(action, &[("add", ()), ("remove", ())]).map_err(|e| e.localize(locale))?;
+            return Err(tr!(locale, "vote_word.usage").into());
+        },
     }
+    Ok(())
+}
 
-    if let (Some(amount), Some(ty)) = (amount, maybe_ty) {
-
-        let mut fail:Option<&'static str> = None;
-        let ty_copy = ty.clone();
-        conn.transaction::<_, diesel::result::Error, _>(|| {
-
-            use view_schema::balance_history::dsl as bh;
-            let mut ids = [msg.author.id.0, user.0];
-            let mut author = 0;
-            let mut dest = 1;
-            if ids[0] > ids[1] {
-                ids = [ids[1],ids[0]];
-                author = 1;
-                dest = 0;
+/// Shows, adds or removes other names for commands.
+#[command]
+#[checks(Admin)]
+fn alias(ctx: &mut Context, msg: &Message, args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let actor = Actor{ user: msg.author.id.0 as i64, reason: None, message_id: Some(msg.id.0 as i64) };
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let names = command_names();
+    match tokens.as_slice() {
+        [] => {
+            let aliases = vocabulary::command_aliases(&*conn)?;
+            let mut lines:Vec<String> = Vec::new();
+            for name in &names {
+                let these:Vec<&str> = aliases.iter().filter(|(_, c)| c == name).map(|(a, _)| a.as_str()).collect();
+                if !these.is_empty() {
+                    lines.push(format!("**{}**: {}", name, these.join(", ")));
+                }
             }
-            let balances:Vec<i64> = ids.iter().map::<Result<i64,diesel::result::Error>,_>(|id| {
-                Ok(
-                    bh::balance_history
-                        .select(bh::balance)
-                        .filter(bh::user.eq(*id as i64))
-                        .filter(bh::ty.eq(ty_copy.db_name()))
-                        .order(bh::happened_at.desc())
-                        .limit(1)
-                        .for_update()
-                        .get_result(&*conn)
-                        .optional()?
-                        .unwrap_or(0i64)
-                )
-            }).collect::<Result<_,_>>()?;
-            let sender_balance = balances[author];
-            let dest_balance = balances[dest];
-            if sender_balance < amount as i64 {
-                fail = Some("Insufficient balance.");
-                return Ok(());
+            if lines.is_empty() {
+                lines.push(String::from(tr!(locale, "alias.none")));
             }
-
-            use schema::transfers;
-            #[derive(Insertable, Debug)]
-            #[table_name = "transfers"]
-            struct Transfer {
-                from_user:i64,
-                quantity:i64,
-                to_user:i64,
-                from_balance:i64,
-                to_balance:i64,
-                happened_at:chrono::DateTime<chrono::Utc>,
-                message_id:i64,
-                ty:String,
-                transfer_ty:&'static str,
+            msg.reply(&ctx, lines.join("\n"))?;
+        },
+        [action, alias, command] if parser::parse_choice(action, &[("add", ())]).is_ok() => {
+            let command = command.trim_start_matches('$').to_lowercase();
+            if !names.iter().any(|n| *n == command) {
+                let known:Vec<(&str, ())> = names.iter().map(|n| (*n, ())).collect();
+                parser::parse_choice(&command, &known).map_err(|e| e.localize(locale))?;
             }
-
-            let from_balance;
-            let to_balance;
-            if msg.author.id == user {
-                from_balance = sender_balance;
-                to_balance = sender_balance;
-            }else{
-                from_balance = sender_balance - amount as i64;
-                to_balance = dest_balance + amount as i64;
+            if names.iter().any(|n| n == alias) {
+                return Err(tr!(locale, "alias.is_command", alias = alias).into());
             }
+            if alias.contains(char::is_whitespace) || alias.is_empty() {
+                return Err(tr!(locale, "alias.not_one_word").into());
+            }
+            if !vocabulary::add_command_alias(&*conn, actor, alias, &command)? {
+                return Err(tr!(locale, "alias.exists", alias = alias).into());
+            }
+            msg.reply(&ctx, tr!(locale, "alias.added", alias = alias, command = command))?;
+        },
+        [action, alias] if parser::parse_choice(action, &[("remove", ())]).is_ok() => {
+            if vocabulary::remove_command_alias(&*conn, actor, alias)? {
+                msg.reply(&ctx, tr!(locale, "alias.removed", alias = alias))?;
+            } else {
+                msg.reply(&ctx, tr!(locale, "alias.not_found", alias = alias))?;
+            }
+        },
+        [action, ..] => {
+            parser::parse_choice(action, &[("add", ()), ("remove", ())]).map_err(|e| e.localize(locale))?;
+            return Err(tr!(locale, "alias.usage").into());
+        },
+    }
+    Ok(())
+}
 
-            let t = Transfer {
-                from_user: msg.author.id.0 as i64,
-                quantity: amount as i64,
-                to_user: user.0 as i64,
-                from_balance,
-                to_balance,
-                happened_at: chrono::Utc::now(),
-                message_id: msg.id.0 as i64,
-                ty: ty.db_name().into(),
-                transfer_ty: "give",
-            };
-
-            diesel::insert_into(schema::transfers::table).values(&t).execute(&*conn)?;
-
-            Ok(())
-        })?;
-        if let Some(fail_msg) = fail {
-            msg.reply(&ctx, fail_msg)?;
-        }else{
-            msg.reply(&ctx, format!(
-                "Successfully transferred {} {} to {}.",
-                amount,
-                &ty.long_name_ambiguous,
-                user.mention()
-            ))?;
+/// Checks that every configured vote emoji can be used on motions in this guild.
+#[command]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+#[num_args(0)]
+fn check_emoji(ctx: &mut Context, msg: &Message) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let guild_emoji = msg.guild_id.unwrap().emojis(&ctx)?;
+    let mut problems:Vec<String> = Vec::new();
+    for emoji in VOTE_EMOJI.iter() {
+        if let EmojiKey::Custom{id, ..} = &emoji.key {
+            if !guild_emoji.iter().any(|e| e.id.0 == *id) {
+                problems.push(tr!(locale, "check_emoji.not_in_guild", id = id, action = format!("{:?}", emoji.action)));
+            }
         }
-    } else if amount.is_none() {
-        return Err("Amount not provided.".into());
+    }
+    for &dir in &[true, false] {
+        if !VOTE_EMOJI.iter().any(|e| e.action == SpecialEmojiAction::Direction(dir)) {
+            problems.push(String::from(tr!(locale, if dir { "check_emoji.no_yes" } else { "check_emoji.no_no" })));
+        }
+    }
+    if !VOTE_EMOJI.iter().any(|e| if let SpecialEmojiAction::Amount(_) = e.action { true } else { false }) {
+        problems.push(String::from(tr!(locale, "check_emoji.no_amounts")));
+    }
+    if problems.is_empty() {
+        msg.reply(&ctx, tr!(locale, "check_emoji.ok", count = VOTE_EMOJI.len()))?;
     } else {
-        return Err("Type not provided.".into());
+        msg.reply(&ctx, problems.join("\n"))?;
     }
-    
     Ok(())
 }
 
-#[cfg(feature = "debug")]
+/// Lists the commands, or explains how to use one of them.
 #[command]
-#[aliases("history_csv")]
-fn transaction_history_csv(ctx:&mut Context, msg:&Message, _args:Args) -> CommandResult {
-    use diesel::prelude::*;
-    use view_schema::balance_history::dsl as bhdsl;
-    #[derive(Debug,Queryable)]
-    struct DbTransaction{
-        pub balance:i64,
-        pub quantity:i64,
-        pub sign:i32,
-        pub happened_at:chrono::DateTime<chrono::Utc>,
-        pub ty:String,
-        pub comment:Option<String>,
-        pub other_party:Option<i64>,
+#[max_args(1)]
+fn help(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    if args.is_empty() {
+        let lines:Vec<String> = crate::help::available().map(|c| {
+            let admin = if c.access.restriction().is_some() { tr!(locale, "help.admin_marker") } else { "" };
+            format!("`{}{}`{} {}", PREFIX, c.name, admin, tr!(locale, c.summary))
+        }).collect();
+        msg.channel_id.send_message(&ctx, |cm| {
+            cm.embed(|e| {
+                e.title(tr!(locale, "help.title"));
+                e.description(lines.join("\n"));
+                e.footer(|f| f.text(tr!(locale, "help.more", command = format!("{}help <command>", PREFIX))));
+                e
+            });
+            cm
+        })?;
+        return Ok(());
+    }
+    let word:String = args.single()?;
+    let name = word.trim_start_matches(PREFIX).to_lowercase();
+    let command = match crate::help::find(&name) {
+        Some(c) => c,
+        None => match vocabulary::command_for_alias(&*conn, &name)? {
+            Some(aliased) => crate::help::find(&aliased).ok_or_else(|| tr!(locale, "help.unknown", command = &name))?,
+            None => return Err(match parser::did_you_mean(&name, crate::help::available().map(|c| c.name)) {
+                Some(suggestion) => tr!(locale, "help.unknown_suggestion", command = name, suggestion = suggestion),
+                None => tr!(locale, "help.unknown", command = name),
+            }.into()),
+        },
+    };
+    let aliases:Vec<String> = vocabulary::command_aliases(&*conn)?
+        .into_iter()
+        .filter(|(_, c)| c == command.name)
+        .map(|(a, _)| format!("`{}{}`", PREFIX, a))
+        .collect();
+    msg.channel_id.send_message(&ctx, |cm| {
+        cm.embed(|e| {
+            describe_command(e, locale, command, &aliases);
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+/// Fills an embed with everything `$help <command>` says about a command.
+fn describe_command(e: &mut serenity::builder::CreateEmbed, locale: Locale, command: &CommandHelp, aliases: &[String]) {
+    let code_block = |lines:&[&str]| format!(
+        "```\n{}\n```",
+        lines.iter().map(|l| format!("{}{}", PREFIX, l)).collect::<Vec<_>>().join("\n"),
+    );
+    e.title(format!("{}{}", PREFIX, command.name));
+    e.description(tr!(locale, command.summary));
+    e.field(tr!(locale, "help.usage"), code_block(command.usage), false);
+    if !command.args.is_empty() {
+        let args:Vec<String> = command.args.iter().map(|(arg, key)| format!("`{}` {}", arg, tr!(locale, *key))).collect();
+        e.field(tr!(locale, "help.arguments"), args.join("\n"), false);
+    }
+    if !aliases.is_empty() {
+        e.field(tr!(locale, "help.aliases"), aliases.join(", "), false);
+    }
+    if !command.examples.is_empty() {
+        e.field(tr!(locale, "help.examples"), code_block(command.examples), false);
+    }
+    if let Some(restriction) = command.access.restriction() {
+        e.footer(|f| f.text(tr!(locale, restriction)));
+    }
+}
+
+#[command]
+fn version_info(ctx: &mut Context, msg: &Message) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    msg.reply(&ctx, format!(
+        "{}.\nhttps://github.com/consortium-chat/plutocradroid",
+        tr!(
+            locale_of(&*conn, msg)?,
+            "version.built",
+            version = env!("VERGEN_SEMVER_LIGHTWEIGHT"),
+            commit = env!("VERGEN_SHA_SHORT"),
+            target = env!("VERGEN_TARGET_TRIPLE"),
+            at = env!("VERGEN_BUILD_TIMESTAMP"),
+        ),
+    ))?;
+    Ok(())
+}
+
+#[command]
+fn balances(ctx: &mut Context, msg: &Message) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let balances = economy::balances_of(&*conn, msg.author.id.0 as i64)?;
+    // let gen_count = get_bal("gen")?;
+    // let pc_count = get_bal("pc")?;
+    msg.channel_id.send_message(&ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "balances.title"));
+            // e.field("Generators", gen_count, false);
+            // e.field("Capital", pc_count, false);
+            for (item_type, amount) in &balances {
+                e.field(&item_type.long_name_plural, locale.number(*amount), false);
+            }
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+
+#[command]
+#[max_args(1)]
+fn top(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let board_name:String = if args.is_empty() { String::from("pc") } else { args.single()? };
+    let board = match stats::Leaderboard::from_name(&*conn, &board_name)? {
+        Some(b) => b,
+        None => {
+            let aliases = economy::item_type_aliases(&*conn)?;
+            let names = aliases.iter().map(String::as_str).chain(vec!["spent", "passed"]);
+            return Err(match parser::did_you_mean(&board_name, names) {
+                Some(name) => tr!(locale, "top.unknown_suggestion", board = board_name, suggestion = name),
+                None => tr!(locale, "top.unknown", board = board_name),
+            }.into());
+        },
+    };
+    let standings = stats::leaderboard(&*conn, &board, 10)?;
+    let mut lines = String::new();
+    for (i, standing) in standings.iter().enumerate() {
+        lines.push_str(&format!("{}. {} {}\n", i+1, account_mention(locale, standing.user), locale.number(standing.value)));
+    }
+    if standings.is_empty() {
+        lines.push_str(tr!(locale, "top.empty"));
+    }
+    msg.channel_id.send_message(&ctx, |cm| {
+        cm.embed(|e| {
+            e.title(board.title(locale));
+            e.description(lines);
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+#[command]
+#[max_args(1)]
+fn profile(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let user = if args.is_empty() {
+        msg.author.id
+    } else {
+        let user_str:String = args.single()?;
+        UserId::from_command_args(ctx, msg, &user_str).map_err(|key| tr!(locale, key))?
+    };
+    let profile = stats::profile(&*conn, user.0 as i64)?;
+    let show_holdings = !profile.holdings_hidden || user == msg.author.id;
+    msg.channel_id.send_message(&ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "profile.title"));
+            e.description(user.mention());
+            if show_holdings {
+                for (item_type, amount) in &profile.balances {
+                    e.field(&item_type.long_name_plural, locale.number(*amount), true);
+                }
+            } else {
+                e.field(tr!(locale, "profile.holdings"), tr!(locale, "profile.hidden"), false);
+            }
+            e.field(tr!(locale, "profile.motions_created"), locale.number(profile.motions_created), true);
+            e.field(tr!(locale, "profile.motions_passed"), locale.number(profile.motions_passed), true);
+            e.field(tr!(locale, "profile.votes_cast"), locale.number(profile.votes_cast), true);
+            e.field(tr!(locale, "profile.capital_spent"), locale.number(profile.capital_spent), true);
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+#[command]
+#[max_args(1)]
+fn privacy(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let user_id = msg.author.id.0 as i64;
+    if args.is_empty() {
+        let hidden = settings::holdings_hidden(&*conn, user_id)?;
+        msg.reply(&ctx, tr!(locale, if hidden { "privacy.hidden" } else { "privacy.shown" }))?;
+        return Ok(());
+    }
+    let word:String = args.single()?;
+    let hide = parser::parse_choice(&word, &[
+        ("hide", true), ("hidden", true), ("private", true), ("on", true),
+        ("show", false), ("shown", false), ("public", false), ("off", false),
+    ]).map_err(|e| tr!(locale, "privacy.expected", error = e.localize(locale)))?;
+    settings::set_holdings_hidden(&*conn, user_id, hide)?;
+    msg.reply(&ctx, tr!(locale, if hide { "privacy.now_hidden" } else { "privacy.now_shown" }))?;
+    Ok(())
+}
+
+// #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+// enum ItemType {
+//     PoliticalCapital,
+//     Generator,
+// }
+
+// impl ItemType {
+//     pub fn db_name(&self) -> &'static str {
+//         match *self {
+//             ItemType::PoliticalCapital => "pc",
+//             ItemType::Generator => "gen",
+//         }
+//     }
+// }
+
+#[command]
+#[max_args(1)]
+fn receipts(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    use settings::ReceiptPref;
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let user_id = msg.author.id.0 as i64;
+    let describe = |pref:ReceiptPref| tr!(locale, match pref {
+        ReceiptPref::Always => "receipts.always",
+        ReceiptPref::ErrorsOnly => "receipts.errors",
+        ReceiptPref::Never => "receipts.never",
+    });
+    if args.is_empty() {
+        msg.reply(&ctx, describe(settings::vote_receipts(&*conn, user_id)?))?;
+        return Ok(());
+    }
+    let word:String = args.single()?;
+    let pref = parser::parse_choice(&word, &[
+        ("always", ReceiptPref::Always), ("all", ReceiptPref::Always), ("on", ReceiptPref::Always),
+        ("errors", ReceiptPref::ErrorsOnly), ("error", ReceiptPref::ErrorsOnly), ("failures", ReceiptPref::ErrorsOnly),
+        ("never", ReceiptPref::Never), ("none", ReceiptPref::Never), ("off", ReceiptPref::Never),
+    ]).map_err(|e| tr!(locale, "receipts.expected", error = e.localize(locale)))?;
+    settings::set_vote_receipts(&*conn, user_id, pref)?;
+    msg.reply(&ctx, describe(pref))?;
+    Ok(())
+}
+
+/// Shows or picks the language the bot speaks to you in.
+#[command]
+#[max_args(1)]
+fn language(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    if args.is_empty() {
+        msg.reply(&ctx, tr!(locale, "language.current", language = locale.name(), available = available_locales()))?;
+        return Ok(());
+    }
+    let word:String = args.single()?;
+    let choice = parse_locale(&word)
+        .map_err(|e| tr!(locale, "language.expected", error = e.localize(locale), available = available_locales()))?;
+    settings::set_user_locale(&*conn, msg.author.id.0 as i64, choice)?;
+    let locale = locale_of(&*conn, msg)?;
+    msg.reply(&ctx, tr!(locale, if choice.is_some() { "language.set" } else { "language.reset" }, language = locale.name()))?;
+    Ok(())
+}
+
+/// Shows or picks the language the bot speaks in this guild, to users who haven't picked their own.
+#[command]
+#[only_in(guilds)]
+#[required_permissions("MANAGE_GUILD")]
+#[max_args(1)]
+fn server_language(ctx: &mut Context, msg: &Message, mut args: Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let guild_id = msg.guild_id.unwrap().0 as i64;
+    if args.is_empty() {
+        let guild_locale = settings::guild_locale(&*conn, guild_id)?.unwrap_or_default();
+        msg.reply(&ctx, tr!(locale, "server_language.current", language = guild_locale.name(), available = available_locales()))?;
+        return Ok(());
+    }
+    let word:String = args.single()?;
+    let choice = parse_locale(&word)
+        .map_err(|e| tr!(locale, "language.expected", error = e.localize(locale), available = available_locales()))?;
+    settings::set_guild_locale(&*conn, guild_id, choice)?;
+    let locale = locale_of(&*conn, msg)?;
+    msg.reply(&ctx, tr!(locale, "server_language.set", language = choice.unwrap_or_default().name()))?;
+    Ok(())
+}
+
+#[command]
+#[min_args(2)]
+fn give(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    give_common(ctx, msg, args, false)
+}
+
+/// Like `$give`, but to any user id, whether or not the bot can see such a user. Recorded as an
+/// admin transfer.
+#[command]
+#[checks(Admin)]
+#[min_args(2)]
+fn force_give(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    give_common(ctx, msg, args, true)
+}
+
+/// Destroys some of what the caller holds.
+#[command]
+#[min_args(2)]
+fn burn(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (amount_str, ty_str, reason) = match tokens.as_slice() {
+        [amount_str, ty_str, reason @ ..] => (amount_str, ty_str, reason_of(reason)),
+        _ => return Err(tr!(locale, "burn.usage").into()),
+    };
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    let amount = parser::parse_amount(amount_str).map_err(|e| e.localize(locale))?
+        .resolve(economy::balance_of(&*conn, msg.author.id.0 as i64, &ty)?);
+
+    let balance = economy::burn(
+        &*conn,
+        msg.author.id.0 as i64,
+        &ty,
+        amount,
+        reason.as_deref(),
+        Some(msg.id.0 as i64),
+    )?.map_err(|e| e.localize(locale))?;
+
+    msg.reply(&ctx, tr!(
+        locale,
+        "burn.done",
+        amount = locale.number(amount),
+        items = ty.long_name_ambiguous,
+        balance = locale.number(balance),
+    ))?;
+
+    Ok(())
+}
+
+fn give_common(ctx:&mut Context, msg:&Message, args:Args, forced:bool) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, rest) = tokens.split_first().ok_or_else(|| tr!(locale, "give.no_user"))?;
+    let user = UserId::from_command_args( ctx, msg, user_str ).map_err(|key| tr!(locale, key))?;
+    if !forced && !ctx.cache.read().users.contains_key(&user) {
+        return Err(tr!(locale, "give.user_not_found").into());
+    }
+    let (amount, ty_name, memo) = parser::parse_give_with_memo(rest).map_err(|e| e.localize(locale))?;
+    let ty = match ty_name {
+        Some(name) => economy::find_item_type(&*conn, &name)?.map_err(|e| e.localize(locale))?,
+        None => return Err(tr!(locale, "give.no_type").into()),
+    };
+    let amount = amount.resolve(economy::balance_of(&*conn, msg.author.id.0 as i64, &ty)?).max(0) as u64;
+
+    let res = if forced {
+        let actor = Actor{ user: msg.author.id.0 as i64, reason: memo.as_deref(), message_id: Some(msg.id.0 as i64) };
+        admin::transfer(&*conn, actor, msg.author.id.0 as i64, user.0 as i64, &ty, amount as i64)?.map_err(|e| e.localize(locale))
+    } else {
+        economy::give(&*conn, msg.author.id.0 as i64, user.0 as i64, &ty, amount, Some(msg.id.0 as i64), memo.as_deref())?.map_err(|e| e.localize(locale))
+    };
+    match (res, memo) {
+        (Err(e), _) => {
+            msg.reply(&ctx, e)?;
+        },
+        (Ok(()), None) => {
+            msg.reply(&ctx, tr!(
+                locale,
+                "give.done",
+                amount = locale.number(amount as i64),
+                items = ty.long_name_ambiguous,
+                user = user.mention(),
+            ))?;
+        },
+        (Ok(()), Some(memo)) => {
+            msg.reply(&ctx, tr!(
+                locale,
+                "give.done_memo",
+                amount = locale.number(amount as i64),
+                items = ty.long_name_ambiguous,
+                user = user.mention(),
+                memo = memo,
+            ))?;
+        },
+    }
+    
+    Ok(())
+}
+
+/// Asks another user to pay you, DMing them so they can pay or decline by reacting.
+#[command]
+#[min_args(2)]
+fn request(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, rest) = tokens.split_first().ok_or_else(|| tr!(locale, "give.no_user"))?;
+    let payer = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    if !ctx.cache.read().users.contains_key(&payer) {
+        return Err(tr!(locale, "give.user_not_found").into());
+    }
+    let (amount, ty_name, memo) = parser::parse_give_with_memo(rest).map_err(|e| e.localize(locale))?;
+    let ty = match ty_name {
+        Some(name) => economy::find_item_type(&*conn, &name)?.map_err(|e| e.localize(locale))?,
+        None => return Err(tr!(locale, "give.no_type").into()),
+    };
+    // a share of the payer's balance isn't ours to know
+    let quantity = match amount {
+        parser::Amount::Exact(n) => n as i64,
+        _ => return Err(tr!(locale, "request.exact_amount").into()),
+    };
+
+    let request = payment_requests::create(
+        &*conn,
+        msg.author.id.0 as i64,
+        payer.0 as i64,
+        &ty,
+        quantity,
+        memo.as_deref(),
+        Some(msg.id.0 as i64),
+        *PAYMENT_REQUEST_EXPIRATION,
+    )?.map_err(|e| e.localize(locale))?;
+
+    let key = match prompt_payer(ctx, &*conn, &request, &ty.long_name_ambiguous) {
+        Ok(()) => "request.sent",
+        Err(e) => {
+            info!("Could not DM {} about request {}: {:?}", payer, request.rowid, e);
+            "request.sent_no_dm"
+        },
+    };
+    msg.reply(&ctx, tr!(
+        locale,
+        key,
+        amount = locale.number(quantity),
+        items = ty.long_name_ambiguous,
+        user = payer.mention(),
+        id = request.rowid,
+        expires = locale.datetime(request.expires_at),
+        command = format!("{}pay {}", PREFIX, request.rowid),
+    ))?;
+
+    Ok(())
+}
+
+/// DMs the payer of a new request, with reactions to pay or decline it.
+fn prompt_payer(ctx:&Context, conn:&diesel::PgConnection, request:&PaymentRequest, items:&str) -> Result<(), BoxError> {
+    let locale = match settings::user_locale(conn, request.payer)? {
+        Some(locale) => locale,
+        None => motions_locale(&ctx.cache, conn)?,
+    };
+    let requester = UserId::from(request.requested_by as u64).mention();
+    let text = format!(
+        "{}\n{}",
+        request.prompt(locale, &requester, items),
+        tr!(locale, "request.react", pay = PAY_EMOJI, decline = DECLINE_EMOJI),
+    );
+    let prompt = UserId::from(request.payer as u64).create_dm_channel(ctx)?.say(ctx, text)?;
+    payment_requests::set_prompt(conn, request.rowid, prompt.id.0 as i64)?;
+    for emoji in &[PAY_EMOJI, DECLINE_EMOJI] {
+        prompt.react(ctx, ReactionType::Unicode(String::from(*emoji)))?;
+    }
+    Ok(())
+}
+
+/// Pays or declines a request as `user`, letting the other person know. Returns what to tell `user`.
+fn settle_request(
+    ctx:&Context,
+    conn:&diesel::PgConnection,
+    locale:Locale,
+    request_id:i64,
+    user:UserId,
+    paying:bool,
+    message_id:Option<i64>,
+) -> Result<String, BoxError> {
+    let res = if paying {
+        payment_requests::pay(conn, request_id, user.0 as i64, message_id)?
+    } else {
+        payment_requests::decline(conn, request_id, user.0 as i64)?
+    };
+    let request = match res {
+        Ok(r) => r,
+        Err(e) => return Ok(e.localize(locale)),
+    };
+    let items = payment_requests::item_type_of(conn, &request)?.long_name_ambiguous;
+    let other = if request.payer == user.0 as i64 { request.requested_by } else { request.payer };
+    let motions_locale = motions_locale(&ctx.cache, conn)?;
+    let other_locale = settings::user_locale(conn, other)?.unwrap_or(motions_locale);
+    let notice = request.notice(other_locale, &user.mention(), &items);
+    if let Err(e) = DiscordFrontend(ctx, motions_locale).dm_user(other, &notice) {
+        info!("Could not DM {} about request {}: {:?}", other, request.rowid, e);
+    }
+    Ok(request.outcome(locale, &UserId::from(other as u64).mention(), &items))
+}
+
+/// Pays a request someone sent you.
+#[command]
+#[num_args(1)]
+fn pay(ctx:&mut Context, msg:&Message, mut args:Args) -> CommandResult {
+    settle_command(ctx, msg, &args.single::<String>()?, true)
+}
+
+/// Turns down a request someone sent you, or withdraws one you sent.
+#[command]
+#[num_args(1)]
+fn decline(ctx:&mut Context, msg:&Message, mut args:Args) -> CommandResult {
+    settle_command(ctx, msg, &args.single::<String>()?, false)
+}
+
+fn settle_command(ctx:&mut Context, msg:&Message, id_str:&str, paying:bool) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let request_id = parser::parse_number(id_str.trim_start_matches('#')).map_err(|e| e.localize(locale))? as i64;
+    let text = settle_request(ctx, &*conn, locale, request_id, msg.author.id, paying, Some(msg.id.0 as i64))?;
+    msg.reply(&ctx, text)?;
+    Ok(())
+}
+
+/// Lists the pending requests you've sent and been sent.
+#[command]
+#[num_args(0)]
+fn requests(ctx:&mut Context, msg:&Message) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let user_id = msg.author.id.0 as i64;
+    let now = chrono::Utc::now();
+    let pending:Vec<PaymentRequest> = payment_requests::involving(&*conn, user_id, 100)?
+        .into_iter()
+        .filter(|r| r.status(now) == payment_requests::RequestStatus::Pending)
+        .collect();
+    let mut lines:Vec<String> = Vec::new();
+    for request in pending.iter().take(15) {
+        let items = payment_requests::item_type_of(&*conn, request)?.long_name_ambiguous;
+        let incoming = request.payer == user_id;
+        let other = UserId::from((if incoming { request.requested_by } else { request.payer }) as u64);
+        lines.push(tr!(
+            locale,
+            if incoming { "request.list_incoming" } else { "request.list_outgoing" },
+            id = request.rowid,
+            user = other.mention(),
+            amount = locale.number(request.quantity),
+            items = items,
+            expires = locale.datetime(request.expires_at),
+        ));
+        if let Some(memo) = &request.memo {
+            lines.push(format!("> {}", memo));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(String::from(tr!(locale, "request.none")));
+    }
+    msg.channel_id.send_message(&ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "request.list_title"));
+            e.description(lines.join("\n"));
+            e.footer(|f| f.text(tr!(locale, "request.list_footer", pay = format!("{}pay <id>", PREFIX), decline = format!("{}decline <id>", PREFIX))));
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum ScheduleAction {
+    Give,
+    List,
+    Cancel,
+}
+
+/// Sets up a give repeated every so often, or lists or cancels them.
+#[command]
+fn schedule(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let actions = [("give", ScheduleAction::Give), ("list", ScheduleAction::List), ("cancel", ScheduleAction::Cancel)];
+    let (action, rest) = match tokens.split_first() {
+        Some((action, rest)) => (parser::parse_choice(action, &actions).map_err(|e| e.localize(locale))?, rest),
+        None => (ScheduleAction::List, &[][..]),
+    };
+    match (action, rest) {
+        (ScheduleAction::Give, rest) => schedule_give(ctx, msg, &*conn, locale, rest),
+        (ScheduleAction::List, []) => schedule_list(ctx, msg, &*conn, locale),
+        (ScheduleAction::Cancel, [id_str]) => {
+            let schedule_id = parser::parse_number(id_str.trim_start_matches('#')).map_err(|e| e.localize(locale))? as i64;
+            let text = cancel_schedule(ctx, &*conn, locale, schedule_id, msg.author.id)?;
+            msg.reply(&ctx, text)?;
+            Ok(())
+        },
+        _ => Err(tr!(locale, "schedule.usage").into()),
+    }
+}
+
+/// `$schedule give <user> <amount> <type> [memo] every <interval> [until <date>]`
+fn schedule_give(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale, args:&[String]) -> CommandResult {
+    let (user_str, rest) = args.split_first().ok_or_else(|| tr!(locale, "give.no_user"))?;
+    let to_user = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    if !ctx.cache.read().users.contains_key(&to_user) {
+        return Err(tr!(locale, "give.user_not_found").into());
+    }
+    // the last `every`, since a memo could have one too
+    let every_at = rest.iter().rposition(|t| t.eq_ignore_ascii_case("every")).ok_or_else(|| tr!(locale, "schedule.usage"))?;
+    let (amount, ty_name, memo) = parser::parse_give_with_memo(&rest[..every_at]).map_err(|e| e.localize(locale))?;
+    let ty = match ty_name {
+        Some(name) => economy::find_item_type(conn, &name)?.map_err(|e| e.localize(locale))?,
+        None => return Err(tr!(locale, "give.no_type").into()),
+    };
+    // a share of the balance would be a different amount every time
+    let quantity = match amount {
+        parser::Amount::Exact(n) => n as i64,
+        _ => return Err(tr!(locale, "schedule.exact_amount").into()),
+    };
+    let (every_str, until_str) = match &rest[every_at + 1..] {
+        [every_str] => (every_str, None),
+        [every_str, until_word, date_str] if until_word.eq_ignore_ascii_case("until") => (every_str, Some(date_str)),
+        _ => return Err(tr!(locale, "schedule.usage").into()),
+    };
+    let every = parser::parse_interval(every_str).map_err(|e| e.localize(locale))?;
+    let until = match until_str {
+        // through the end of that day
+        Some(s) => Some(chrono::DateTime::<chrono::Utc>::from_utc(
+            parser::parse_date(s).map_err(|e| e.localize(locale))?.and_hms(23, 59, 59),
+            chrono::Utc,
+        )),
+        None => None,
+    };
+
+    let schedule = scheduled_transfers::create(
+        conn,
+        msg.author.id.0 as i64,
+        to_user.0 as i64,
+        &ty,
+        quantity,
+        memo.as_deref(),
+        every,
+        until,
+        Some(msg.id.0 as i64),
+        *MIN_SCHEDULE_INTERVAL,
+    )?.map_err(|e| e.localize(locale))?;
+
+    let command = format!("{}schedule cancel {}", PREFIX, schedule.rowid);
+    let text = match schedule.until {
+        Some(until) => tr!(
+            locale,
+            "schedule.created_until",
+            id = schedule.rowid,
+            amount = locale.number(quantity),
+            items = ty.long_name_ambiguous,
+            user = to_user.mention(),
+            every = parser::format_interval(every),
+            until = locale.datetime(until),
+            command = command,
+        ),
+        None => tr!(
+            locale,
+            "schedule.created",
+            id = schedule.rowid,
+            amount = locale.number(quantity),
+            items = ty.long_name_ambiguous,
+            user = to_user.mention(),
+            every = parser::format_interval(every),
+            command = command,
+        ),
+    };
+    msg.reply(ctx, text)?;
+    Ok(())
+}
+
+/// Lists the active schedules you pay or are paid by.
+fn schedule_list(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale) -> CommandResult {
+    let user_id = msg.author.id.0 as i64;
+    let mut lines:Vec<String> = Vec::new();
+    let schedules = scheduled_transfers::involving(conn, user_id, 100)?;
+    for schedule in schedules.iter().filter(|s| s.status() == ScheduleStatus::Active).take(15) {
+        let items = scheduled_transfers::item_type_of(conn, schedule)?.long_name_ambiguous;
+        let outgoing = schedule.from_user == user_id;
+        let other = UserId::from((if outgoing { schedule.to_user } else { schedule.from_user }) as u64);
+        lines.push(tr!(
+            locale,
+            if outgoing { "schedule.list_outgoing" } else { "schedule.list_incoming" },
+            id = schedule.rowid,
+            user = other.mention(),
+            amount = locale.number(schedule.quantity),
+            items = items,
+            every = parser::format_interval(schedule.every()),
+            next = locale.datetime(schedule.next_at),
+        ));
+        if let Some(memo) = &schedule.memo {
+            lines.push(format!("> {}", memo));
+        }
+    }
+    if lines.is_empty() {
+        lines.push(String::from(tr!(locale, "schedule.none")));
+    }
+    msg.channel_id.send_message(ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "schedule.list_title"));
+            e.description(lines.join("\n"));
+            e.footer(|f| f.text(tr!(locale, "schedule.list_footer", cancel = format!("{}schedule cancel <id>", PREFIX))));
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+/// Cancels a schedule as `user`, letting the other person know. Returns what to tell `user`.
+fn cancel_schedule(
+    ctx:&Context,
+    conn:&diesel::PgConnection,
+    locale:Locale,
+    schedule_id:i64,
+    user:UserId,
+) -> Result<String, BoxError> {
+    let schedule = match scheduled_transfers::cancel(conn, schedule_id, user.0 as i64)? {
+        Ok(s) => s,
+        Err(e) => return Ok(e.localize(locale)),
+    };
+    let items = scheduled_transfers::item_type_of(conn, &schedule)?.long_name_ambiguous;
+    let other = if schedule.from_user == user.0 as i64 { schedule.to_user } else { schedule.from_user };
+    let motions_locale = motions_locale(&ctx.cache, conn)?;
+    let other_locale = settings::user_locale(conn, other)?.unwrap_or(motions_locale);
+    let notice = tr!(
+        other_locale,
+        "schedule.notice_cancelled",
+        id = schedule.rowid,
+        user = user.mention(),
+        amount = other_locale.number(schedule.quantity),
+        items = items,
+    );
+    if let Err(e) = DiscordFrontend(ctx, motions_locale).dm_user(other, &notice) {
+        info!("Could not DM {} about scheduled transfer {}: {:?}", other, schedule.rowid, e);
+    }
+    Ok(tr!(locale, "schedule.cancelled", id = schedule.rowid))
+}
+
+/// Reads when an allowance stops, either as a length of time from now, eg. `7d`, or as a date,
+/// through the end of that day. `None` if it's neither, or further off than can be represented.
+fn parse_expiry(s:&str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(interval) = parser::parse_interval(s) {
+        return chrono::Utc::now().checked_add_signed(interval);
+    }
+    parser::parse_date(s).ok().map(|date| chrono::DateTime::<chrono::Utc>::from_utc(date.and_hms(23, 59, 59), chrono::Utc))
+}
+
+/// Lets someone spend some of what you hold on gives and votes for you, or lists the allowances
+/// you've given and been given.
+#[command]
+fn allow(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, amount_str, ty_str, expires_str) = match tokens.as_slice() {
+        [] => return allow_list(ctx, msg, &*conn, locale),
+        [user_str, amount_str, ty_str] => (user_str, amount_str, ty_str, None),
+        [user_str, amount_str, ty_str, expires_str] => (user_str, amount_str, ty_str, Some(expires_str)),
+        _ => return Err(tr!(locale, "allowance.usage").into()),
+    };
+    let spender = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    if !ctx.cache.read().users.contains_key(&spender) {
+        return Err(tr!(locale, "give.user_not_found").into());
+    }
+    let ty = economy::find_item_type(&*conn, ty_str)?.map_err(|e| e.localize(locale))?;
+    // a share of the balance would keep changing
+    let quantity = match parser::parse_amount(amount_str).map_err(|e| e.localize(locale))? {
+        parser::Amount::Exact(n) => n as i64,
+        _ => return Err(tr!(locale, "allowance.exact_amount").into()),
+    };
+    let expires_at = match expires_str {
+        Some(s) => Some(parse_expiry(s).ok_or_else(|| tr!(locale, "allowance.bad_expiry", value = s))?),
+        None => None,
+    };
+
+    let allowance = allowances::grant(
+        &*conn,
+        msg.author.id.0 as i64,
+        spender.0 as i64,
+        &ty,
+        quantity,
+        expires_at,
+        Some(msg.id.0 as i64),
+    )?.map_err(|e| e.localize(locale))?;
+
+    let revoke = format!("{}revoke <user>", PREFIX);
+    let text = match allowance.expires_at {
+        Some(expires_at) => tr!(
+            locale,
+            "allowance.granted_until",
+            user = spender.mention(),
+            amount = locale.number(quantity),
+            items = ty.long_name_ambiguous,
+            expires = locale.datetime(expires_at),
+            revoke = revoke,
+        ),
+        None => tr!(
+            locale,
+            "allowance.granted",
+            user = spender.mention(),
+            amount = locale.number(quantity),
+            items = ty.long_name_ambiguous,
+            revoke = revoke,
+        ),
+    };
+    msg.reply(&ctx, text)?;
+
+    let motions_locale = motions_locale(&ctx.cache, &*conn)?;
+    let spender_locale = settings::user_locale(&*conn, spender.0 as i64)?.unwrap_or(motions_locale);
+    let notice = tr!(
+        spender_locale,
+        "allowance.notice_granted",
+        user = msg.author.id.mention(),
+        amount = spender_locale.number(quantity),
+        items = ty.long_name_ambiguous,
+        give = format!("{}spend <user> give <recipient> <amount> <type>", PREFIX),
+        vote = format!("{}spend <user> vote <motion id> <direction> <votes>", PREFIX),
+    );
+    if let Err(e) = DiscordFrontend(&*ctx, motions_locale).dm_user(spender.0 as i64, &notice) {
+        info!("Could not DM {} about allowance {}: {:?}", spender, allowance.rowid, e);
+    }
+    Ok(())
+}
+
+/// One line of `$allow`'s list.
+fn allowance_line(conn:&diesel::PgConnection, locale:Locale, key:&'static str, other:i64, allowance:&Allowance) -> Result<String, BoxError> {
+    let items = allowances::item_type_of(conn, allowance)?.long_name_ambiguous;
+    let mut line = tr!(
+        locale,
+        key,
+        user = UserId::from(other as u64).mention(),
+        remaining = locale.number(allowance.remaining()),
+        amount = locale.number(allowance.quantity),
+        items = items,
+    );
+    if let Some(expires_at) = allowance.expires_at {
+        line.push(' ');
+        line.push_str(&tr!(locale, "allowance.expires", expires = locale.datetime(expires_at)));
+    }
+    Ok(line)
+}
+
+/// Lists the allowances you've given and been given.
+fn allow_list(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale) -> CommandResult {
+    let user_id = msg.author.id.0 as i64;
+    let mut lines:Vec<String> = Vec::new();
+    for allowance in allowances::granted_by(conn, user_id)? {
+        lines.push(allowance_line(conn, locale, "allowance.list_given", allowance.spender, &allowance)?);
+    }
+    for allowance in allowances::granted_to(conn, user_id)? {
+        lines.push(allowance_line(conn, locale, "allowance.list_received", allowance.owner, &allowance)?);
+    }
+    if lines.is_empty() {
+        lines.push(String::from(tr!(locale, "allowance.none")));
+    }
+    msg.channel_id.send_message(ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "allowance.list_title"));
+            e.description(lines.join("\n"));
+            e.footer(|f| f.text(tr!(locale, "allowance.list_footer", revoke = format!("{}revoke <user> [type]", PREFIX))));
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+/// Takes back the allowances you gave someone, or just the one for a single item type.
+#[command]
+#[min_args(1)]
+#[max_args(2)]
+fn revoke(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (user_str, ty_str) = match tokens.as_slice() {
+        [user_str] => (user_str, None),
+        [user_str, ty_str] => (user_str, Some(ty_str)),
+        _ => return Err(tr!(locale, "allowance.revoke_usage").into()),
+    };
+    let spender = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    let ty = match ty_str {
+        Some(s) => Some(economy::find_item_type(&*conn, s)?.map_err(|e| e.localize(locale))?),
+        None => None,
+    };
+
+    let revoked = allowances::revoke(
+        &*conn,
+        msg.author.id.0 as i64,
+        spender.0 as i64,
+        ty.as_ref().map(|t| t.db_name()),
+    )?.map_err(|e| e.localize(locale))?;
+    let items = revoked.iter()
+        .map(|a| allowances::item_type_of(&*conn, a).map(|t| t.long_name_ambiguous))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(", ");
+    msg.reply(&ctx, tr!(locale, "allowance.revoked", user = spender.mention(), items = &items))?;
+
+    let motions_locale = motions_locale(&ctx.cache, &*conn)?;
+    let spender_locale = settings::user_locale(&*conn, spender.0 as i64)?.unwrap_or(motions_locale);
+    let notice = tr!(spender_locale, "allowance.notice_revoked", user = msg.author.id.mention(), items = items);
+    if let Err(e) = DiscordFrontend(&*ctx, motions_locale).dm_user(spender.0 as i64, &notice) {
+        info!("Could not DM {} about revoked allowances: {:?}", spender, e);
+    }
+    Ok(())
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum SpendAction {
+    Give,
+    Vote,
+}
+
+/// Gives or votes with some of what another user holds, out of an allowance they gave you. It's
+/// recorded as theirs, with you as the one who acted.
+#[command]
+#[min_args(3)]
+fn spend(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (owner_str, action_str, rest) = match tokens.as_slice() {
+        [owner_str, action_str, rest @ ..] => (owner_str, action_str, rest),
+        _ => return Err(tr!(locale, "allowance.spend_usage").into()),
+    };
+    let owner = UserId::from_command_args(ctx, msg, owner_str).map_err(|key| tr!(locale, key))?;
+    let actions = [("give", SpendAction::Give), ("vote", SpendAction::Vote)];
+    let action = parser::parse_choice(action_str, &actions).map_err(|e| e.localize(locale))?;
+    let acting_user = msg.author.id.0 as i64;
+
+    let text = match action {
+        SpendAction::Give => {
+            let (user_str, rest) = rest.split_first().ok_or_else(|| tr!(locale, "give.no_user"))?;
+            let to_user = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+            if !ctx.cache.read().users.contains_key(&to_user) {
+                return Err(tr!(locale, "give.user_not_found").into());
+            }
+            let (amount, ty_name, memo) = parser::parse_give_with_memo(rest).map_err(|e| e.localize(locale))?;
+            let ty = match ty_name {
+                Some(name) => economy::find_item_type(&*conn, &name)?.map_err(|e| e.localize(locale))?,
+                None => return Err(tr!(locale, "give.no_type").into()),
+            };
+            // `all` and shares are of what you're allowed to spend, if that's less than they hold
+            let allowed = allowances::current(&*conn, owner.0 as i64, acting_user, ty.db_name(), chrono::Utc::now())?
+                .map_or(0, |a| a.remaining());
+            let amount = amount.resolve(economy::balance_of(&*conn, owner.0 as i64, &ty)?.min(allowed)).max(0) as u64;
+            match economy::give_on_behalf(
+                &*conn,
+                acting_user,
+                owner.0 as i64,
+                to_user.0 as i64,
+                &ty,
+                amount,
+                Some(msg.id.0 as i64),
+                memo.as_deref(),
+            )? {
+                Ok(_) => tr!(
+                    locale,
+                    "allowance.gave",
+                    amount = locale.number(amount as i64),
+                    items = ty.long_name_ambiguous,
+                    user = to_user.mention(),
+                    owner = owner.mention(),
+                ),
+                Err(e) => e.localize(locale),
+            }
+        },
+        SpendAction::Vote => {
+            let (checksummed_motion_id, rest) = rest.split_first().ok_or_else(|| tr!(locale, "vote.no_motion_id"))?;
+            let motion_id = parser::parse_motion_id(checksummed_motion_id).map_err(|e| e.localize(locale))?;
+            let (vote_direction, amount) = parser::parse_vote_args(&vocabulary::vote_vocabulary(&*conn)?, rest)
+                .map_err(|e| e.localize(locale))?;
+            match economy::vote_on_behalf(
+                &*conn,
+                acting_user,
+                vote_direction,
+                amount,
+                owner.0 as i64,
+                motion_id,
+                Some(msg.id.0 as i64),
+            )? {
+                Ok(outcome) => tr!(locale, "allowance.voted", owner = owner.mention(), outcome = outcome.localize(locale)),
+                Err(e) => e.localize(locale),
+            }
+        },
+    };
+    msg.reply(&ctx, text)?;
+    Ok(())
+}
+
+/// Sends your whole transaction history as a CSV file.
+#[command]
+fn transaction_history_csv(ctx:&mut Context, msg:&Message, _args:Args) -> CommandResult {
+    use diesel::prelude::*;
+    use view_schema::balance_history::dsl as bhdsl;
+    #[derive(Debug,Queryable)]
+    struct DbTransaction{
+        pub balance:i64,
+        pub quantity:i64,
+        pub sign:i32,
+        pub happened_at:chrono::DateTime<chrono::Utc>,
+        pub ty:String,
+        pub comment:Option<String>,
+        pub other_party:Option<i64>,
     }
     #[derive(Debug,Serialize)]
     struct CsvTransaction{
@@ -857,441 +2060,459 @@ fn supermotion(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
 }
 
 fn motion_common(ctx:&mut Context, msg:&Message, args:Args, is_super: bool) -> CommandResult {
-    use diesel::prelude::*;
-    use schema::motions::dsl as mdsl;
-    use schema::motion_votes::dsl as mvdsl;
-    use schema::transfers::dsl as tdsl;
-    use view_schema::balance_history::dsl as bhdsl;
-    let motion_text = args.rest();
-    let mut motion_message_outer:Option<_> = None;
     let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let frontend = DiscordFrontend(&*ctx, motions_locale(&ctx.cache, &*conn)?);
+    if let Err(e) = economy::create_motion(&frontend, &*conn, msg.author.id.0 as i64, msg.id.0 as i64, args.rest(), is_super)? {
+        msg.reply(&ctx, e.localize(locale_of(&*conn, msg)?))?;
+    }
+    Ok(())
+}
 
-    let now = chrono::Utc::now();
-    conn.transaction::<_, diesel::result::Error, _>(|| {
-        let balance:i64 = bhdsl::balance_history
-            .select(bhdsl::balance)
-            .filter(bhdsl::ty.eq("pc"))
-            .filter(bhdsl::user.eq(msg.author.id.0 as i64))
-            .order(bhdsl::happened_at.desc())
-            .limit(1)
-            .for_update()
-            .get_result(&*conn)?;
-        
-        if balance < VOTE_BASE_COST as i64 {
-            msg.reply(&ctx, "You don't have enough capital.").unwrap();
-            return Err(diesel::result::Error::RollbackTransaction);
-        }
-
-        let motion_id:i64 = diesel::insert_into(schema::motion_ids::table).default_values().returning(schema::motion_ids::dsl::rowid).get_result(&*conn)?;
 
-        let cap_label = if is_super { "Supermotion" } else { "Simple Motion" };
-        let bot_msg = serenity::model::id::ChannelId(MOTIONS_CHANNEL).send_message(&ctx, |m| {
-            m.content(format!(
-                "A motion has been called by {}\n`$vote {}` to vote!",
-                msg.author.mention(),
-                damm::add_to_str(motion_id.to_string()),
-            )).embed(|e| {
-                e.field(cap_label, motion_text, false)
-                .field("Votes", "**for 1**/0 against", false)
-                .field(msg.author.mention(), "1 for", true)
-            })
-        }).unwrap();
-
-        motion_message_outer = Some(bot_msg.clone());
-
-        let motion_id:i64 = diesel::insert_into(mdsl::motions).values((
-            mdsl::rowid.eq(motion_id),
-            mdsl::command_message_id.eq(msg.id.0 as i64),
-            mdsl::bot_message_id.eq(bot_msg.id.0 as i64),
-            mdsl::motion_text.eq(motion_text),
-            mdsl::motioned_at.eq(now),
-            mdsl::last_result_change.eq(now),
-            mdsl::is_super.eq(is_super),
-        )).returning(mdsl::rowid).get_result(&*conn)?;
-
-        diesel::insert_into(mvdsl::motion_votes).values((
-            mvdsl::user.eq(msg.author.id.0 as i64),
-            mvdsl::motion.eq(motion_id),
-            mvdsl::direction.eq(true),
-            mvdsl::amount.eq(1)
-        )).execute(&*conn)?;
-
-        diesel::insert_into(tdsl::transfers).values((
-            tdsl::from_user.eq(msg.author.id.0 as i64),
-            tdsl::from_balance.eq(balance),
-            tdsl::ty.eq("pc"),
-            tdsl::quantity.eq(VOTE_BASE_COST as i64),
-            tdsl::happened_at.eq(chrono::Utc::now()),
-            tdsl::message_id.eq(msg.id.0 as i64),
-            tdsl::to_motion.eq(motion_id),
-            tdsl::to_votes.eq(1),
-            tdsl::transfer_ty.eq("motion_create"),
-        )).execute(&*conn)?;
+#[command]
+#[min_args(1)]
+fn vote(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (checksummed_motion_id, rest) = tokens.split_first().ok_or_else(|| tr!(locale, "vote.no_motion_id"))?;
+    let motion_id = parser::parse_motion_id(checksummed_motion_id).map_err(|e| e.localize(locale))?;
+    let (vote_direction, amount) = parser::parse_vote_args(&vocabulary::vote_vocabulary(&*conn)?, rest)
+        .map_err(|e| e.localize(locale))?;
+
+    let response = match economy::vote(
+        &*conn,
+        vote_direction,
+        amount,
+        msg.author.id.0 as i64,
+        Some(motion_id),
+        None,
+        Some(msg.id.0 as i64),
+        None,
+    )? {
+        Ok(outcome) => outcome.localize(locale),
+        Err(e) => e.localize(locale),
+    };
+    msg.reply(&ctx, response)?;
+    Ok(())
+}
 
-        Ok(())
-    })?;
+/// Promises some of what you hold to someone if a motion passes, or if it fails, holding it in
+/// escrow until the motion is announced.
+#[command]
+#[min_args(5)]
+fn pledge(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let usage = || tr!(locale, "pledge.usage");
+    let (checksummed_motion_id, rest) = tokens.split_first().ok_or_else(usage)?;
+    let motion_id = parser::parse_motion_id(checksummed_motion_id).map_err(|e| e.localize(locale))?;
+    let to_at = rest.iter().position(|t| t.eq_ignore_ascii_case("to")).ok_or_else(usage)?;
+    let (user_str, on_pass) = match &rest[to_at + 1..] {
+        [user_str, if_word, result] if if_word.eq_ignore_ascii_case("if") => {
+            let results = [("pass", true), ("passes", true), ("fail", false), ("fails", false)];
+            (user_str, parser::parse_choice(result, &results).map_err(|e| e.localize(locale))?)
+        },
+        _ => return Err(usage().into()),
+    };
+    let recipient = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+    if !ctx.cache.read().users.contains_key(&recipient) {
+        return Err(tr!(locale, "give.user_not_found").into());
+    }
+    let (amount, ty_name) = parser::parse_give_args(&rest[..to_at]).map_err(|e| e.localize(locale))?;
+    let ty = match ty_name {
+        Some(name) => economy::find_item_type(&*conn, &name)?.map_err(|e| e.localize(locale))?,
+        None => return Err(tr!(locale, "give.no_type").into()),
+    };
+    let quantity = amount.resolve(economy::balance_of(&*conn, msg.author.id.0 as i64, &ty)?);
+
+    let pledge = pledges::create(
+        &*conn,
+        motion_id,
+        msg.author.id.0 as i64,
+        recipient.0 as i64,
+        &ty,
+        quantity,
+        on_pass,
+        Some(msg.id.0 as i64),
+    )?.map_err(|e| e.localize(locale))?;
+    msg.reply(&ctx, tr!(
+        locale,
+        if pledge.on_pass { "pledge.done_pass" } else { "pledge.done_fail" },
+        amount = locale.number(pledge.quantity),
+        items = ty.long_name_ambiguous,
+        user = recipient.mention(),
+        motion = crate::damm::add_to_str(pledge.motion.to_string()),
+    ))?;
+    Ok(())
+}
 
-    //let mut motion_message = ctx.http.get_message(MOTIONS_CHANNEL, motion_id_outer.unwrap() as u64)?;
-    if let Some(mut motion_message) = motion_message_outer {
-        update_motion_message(&ctx, &*conn, &mut motion_message)?;
-        let mut emojis:Vec<_> = (*SPECIAL_EMOJI).iter().collect();
-        emojis.sort_unstable_by_key(|(_,a)| match *a {
-            SpecialEmojiAction::Direction(false) => -2,
-            SpecialEmojiAction::Direction(true) => -1,
-            SpecialEmojiAction::Amount(a) => (*a) as i64
-        });
-        for (emoji_id, _) in emojis {
-            //dbg!(&emoji_id);
-            serenity::model::id::ChannelId::from(MOTIONS_CHANNEL)
-                .create_reaction(
-                    &ctx,
-                    &motion_message,
-                    serenity::model::channel::ReactionType::Custom{
-                        animated: false,
-                        id: (*emoji_id).into(),
-                        name: Some("no".to_string())
-                    }
-                ).unwrap()
-            ;
-        }
+/// Puts capital into the pool raised to vote one way on a motion, or lists a motion's pools.
+#[command]
+#[min_args(1)]
+fn fund(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let (checksummed_motion_id, rest) = tokens.split_first().ok_or_else(|| tr!(locale, "vote.no_motion_id"))?;
+    let motion_id = parser::parse_motion_id(checksummed_motion_id).map_err(|e| e.localize(locale))?;
+    let (direction_str, amount_str) = match rest {
+        [] => return fund_list(ctx, msg, &*conn, locale, motion_id),
+        [direction_str, amount_str] => (direction_str, amount_str),
+        _ => return Err(tr!(locale, "pool.usage").into()),
+    };
+    let direction = match vocabulary::vote_vocabulary(&*conn)?.get(direction_str) {
+        Some(VoteWord::Yes) => true,
+        Some(VoteWord::No) => false,
+        _ => return Err(tr!(locale, "pool.usage").into()),
+    };
+    let user_id = msg.author.id.0 as i64;
+    let quantity = parser::parse_amount(amount_str).map_err(|e| e.localize(locale))?
+        .resolve(economy::latest_balance(&*conn, user_id, "pc", false)?);
+
+    let (pool, _) = vote_pools::contribute(
+        &*conn,
+        motion_id,
+        direction,
+        user_id,
+        quantity,
+        Some(msg.id.0 as i64),
+        *POOL_COLLECTION_WINDOW,
+    )?.map_err(|e| e.localize(locale))?;
+    let contributions = vote_pools::contributions(&*conn, pool.rowid)?;
+    msg.reply(&ctx, tr!(
+        locale,
+        if pool.direction { "pool.contributed_for" } else { "pool.contributed_against" },
+        amount = locale.number(quantity),
+        id = pool.rowid,
+        motion = crate::damm::add_to_str(pool.motion.to_string()),
+        raised = locale.number(contributions.iter().map(|c| c.quantity).sum::<i64>()),
+        closes = locale.datetime(pool.closes_at),
+    ))?;
+    Ok(())
+}
 
+/// Lists the pools raised on a motion.
+fn fund_list(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale, motion_id:i64) -> CommandResult {
+    let now = chrono::Utc::now();
+    let motion = crate::damm::add_to_str(motion_id.to_string());
+    let mut lines:Vec<String> = Vec::new();
+    for pool in vote_pools::on_motion(conn, motion_id)? {
+        let contributions = vote_pools::contributions(conn, pool.rowid)?;
+        let raised:i64 = contributions.iter().map(|c| c.quantity).sum();
+        let mut contributors:Vec<i64> = contributions.iter().map(|c| c.user).collect();
+        contributors.sort_unstable();
+        contributors.dedup();
+        let status = match (pool.status(now), pool.votes) {
+            (PoolStatus::Collecting, _) => tr!(locale, "pool.closes", closes = locale.datetime(pool.closes_at)),
+            (PoolStatus::Bought, Some(votes)) => tr!(locale, "pool.bought", votes = locale.number(votes)),
+            (status, _) => status.localize(locale),
+        };
+        lines.push(tr!(
+            locale,
+            if pool.direction { "pool.list_line_for" } else { "pool.list_line_against" },
+            id = pool.rowid,
+            raised = locale.number(raised),
+            contributors = contributors.len(),
+            status = status,
+        ));
     }
-
+    if lines.is_empty() {
+        lines.push(String::from(tr!(locale, "pool.none")));
+    }
+    msg.channel_id.send_message(ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "pool.list_title", motion = motion));
+            e.description(lines.join("\n"));
+            e.footer(|f| f.text(tr!(locale, "pool.list_footer", fund = format!("{}fund {} for <amount>", PREFIX, motion))));
+            e
+        });
+        cm
+    })?;
     Ok(())
 }
 
-const YES_WORDS:&[&str] = &[
-    "favor", 
-    "for", 
-    "approve", 
-    "yes", 
-    "y", 
-    "aye", 
-    "yeah", 
-    "yeah!", 
-    "\u{1ff4d}", 
-    ":+1:", 
-    ":thumbsup:",
-    "\u{1f646}",
-    ":ok_woman:",
-    "\u{2b55}",
-    ":o:",
-    "\u{1f44c}",
-    ":ok_hand:",
-    "\u{1f197}",
-    ":ok:",
-    "\u{2705}",
-    "pass",
-];
-const NO_WORDS :&[&str] = &[
-    "neigh",
-    "fail",
-    "no", //no in sardinian
-    "against",
-    "no", //no in papiamento
-    "nay",
-    "no, asshole", //no in american english
-    "no, you wanker", //no in british english
-    "no, cunt", //no in australian english
-    "no", //no in catalan
-    "negative", 
-    "no", //no in italian
-    "never",
-    "no", //no in friulan 
-    "negatory", 
-    "no", //no in spanish 
-    "veto", 
-    "no", //no in ligurian
-    "\u{1f44e}", 
-    "deny",
-    ":-1:", 
-    ":thumbsdown:",
-    ".i na go'i", //no in lojban
-    "\u{1f645}",
-    ":no_good:",
-    "\u{274C}",
-    "\u{1f196}",
-    ":ng:",
-    "naw",
-];
-const ZERO_WORDS:&[&str] = &["zero", "zerovote", "nil", "nada", "nothing"];
-const IGNORE_WORDS:&[&str] = &["in", "i", "I", "think", "say", "fuck", "hell"];
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+enum TreasuryAction {
+    Create,
+    List,
+    Info,
+    Deposit,
+    Propose,
+    Approve,
+    Cancel,
+}
 
+/// Sets up, pays into and spends from treasuries, accounts shared by their members.
 #[command]
-#[min_args(1)]
-fn vote(ctx:&mut Context, msg:&Message, mut args:Args) -> CommandResult {
-    let checksummed_motion_id:String = args.single()?;
-    //dbg!(&checksummed_motion_id);
-    if let Some(digit_arr) = damm::validate(&checksummed_motion_id) {
-        let mut motion_id:i64 = 0;
-        for d in &digit_arr {
-            motion_id *= 10;
-            motion_id += *d as i64;
-        }
-        let motion_id = motion_id;
-        //dbg!(&motion_id);
-
-        let mut vote_count = 1;
-        let mut vote_direction:Option<bool> = None;
-        for args_result in args.iter::<String>() {
-            //dbg!(&args_result);
-            let arg = args_result?;
-            if YES_WORDS.contains(&&*arg) {
-                vote_direction = Some(true);
-            }else if NO_WORDS.contains(&&*arg) {
-                vote_direction = Some(false);
-            }else if ZERO_WORDS.contains(&&*arg) {
-                vote_count = 0;
-            }else if IGNORE_WORDS.contains(&&*arg) {
-                //ignore
-            }else {
-                match arg.parse():Result<u32, _> {
-                    Err(e) => return Err(e.into()),
-                    Ok(v) => vote_count = v as i64,
+fn treasury(ctx:&mut Context, msg:&Message, args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+
+    let tokens = parser::tokenize(args.rest()).map_err(|e| e.localize(locale))?;
+    let actions = [
+        ("create", TreasuryAction::Create),
+        ("list", TreasuryAction::List),
+        ("info", TreasuryAction::Info),
+        ("deposit", TreasuryAction::Deposit),
+        ("propose", TreasuryAction::Propose),
+        ("approve", TreasuryAction::Approve),
+        ("cancel", TreasuryAction::Cancel),
+    ];
+    let (action, rest) = match tokens.split_first() {
+        Some((action, rest)) => (parser::parse_choice(action, &actions).map_err(|e| e.localize(locale))?, rest),
+        None => (TreasuryAction::List, &[][..]),
+    };
+    let parse_id = |s:&str| parser::parse_number(s.trim_start_matches('#')).map(|n| n as i64).map_err(|e| e.localize(locale));
+    match (action, rest) {
+        (TreasuryAction::Create, [name, threshold_str, members @ ..]) => {
+            let threshold = parser::parse_number(threshold_str).map_err(|e| e.localize(locale))? as i64;
+            let mut member_ids = Vec::new();
+            for member_str in members {
+                let member = UserId::from_command_args(ctx, msg, member_str).map_err(|key| tr!(locale, key))?;
+                if !ctx.cache.read().users.contains_key(&member) {
+                    return Err(tr!(locale, "give.user_not_found").into());
                 }
+                member_ids.push(member.0 as i64);
             }
-        }
-        //dbg!(&vote_count, &vote_direction);
-
-        let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
-        let response = vote_common(
-            &*conn,
-            vote_direction,
-            vote_count,
-            msg.author.id.0 as i64,
-            Some(motion_id),
-            None,
-            Some(msg.id.0 as i64),
-        );
-        msg.reply(&ctx, response).unwrap();
-        
-        //msg.reply(&ctx, "Vote counted!").unwrap();
-    }else{
-        return Err("Invalid motion id, please try again.".into());
+            let treasury = treasuries::create(&*conn, name, threshold, msg.author.id.0 as i64, &member_ids)?
+                .map_err(|e| e.localize(locale))?;
+            let members = treasuries::members(&*conn, treasury.rowid)?;
+            msg.reply(&ctx, tr!(
+                locale,
+                "treasury.created",
+                treasury = treasury.name,
+                id = treasury.rowid,
+                threshold = treasury.threshold,
+                members = members.len(),
+                command = format!("{}treasury deposit {} <amount> <type>", PREFIX, treasury.name),
+            ))?;
+        },
+        (TreasuryAction::List, []) => treasury_list(ctx, msg, &*conn, locale)?,
+        (TreasuryAction::Info, [name]) => treasury_info(ctx, msg, &*conn, locale, name)?,
+        (TreasuryAction::Deposit, [name, rest @ ..]) => {
+            let treasury = find_treasury(&*conn, locale, name)?;
+            let (amount, ty_name, memo) = parser::parse_give_with_memo(rest).map_err(|e| e.localize(locale))?;
+            let ty = match ty_name {
+                Some(name) => economy::find_item_type(&*conn, &name)?.map_err(|e| e.localize(locale))?,
+                None => return Err(tr!(locale, "give.no_type").into()),
+            };
+            let amount = amount.resolve(economy::balance_of(&*conn, msg.author.id.0 as i64, &ty)?).max(0) as u64;
+            economy::give(
+                &*conn,
+                msg.author.id.0 as i64,
+                treasury.account(),
+                &ty,
+                amount,
+                Some(msg.id.0 as i64),
+                memo.as_deref(),
+            )?.map_err(|e| e.localize(locale))?;
+            msg.reply(&ctx, tr!(
+                locale,
+                "treasury.deposited",
+                amount = locale.number(amount as i64),
+                items = ty.long_name_ambiguous,
+                treasury = treasury.name,
+            ))?;
+        },
+        (TreasuryAction::Propose, [name, kind, rest @ ..]) => treasury_propose(ctx, msg, &*conn, locale, name, kind, rest)?,
+        (TreasuryAction::Approve, [id_str]) => {
+            let approval = treasuries::approve(&*conn, parse_id(id_str)?, msg.author.id.0 as i64, Some(msg.id.0 as i64))?
+                .map_err(|e| e.localize(locale))?;
+            let what = treasuries::describe(&*conn, locale, &approval.proposal, |user| account_mention(locale, user))?;
+            msg.reply(&ctx, approval.outcome(locale, &what))?;
+        },
+        (TreasuryAction::Cancel, [id_str]) => {
+            let proposal = treasuries::cancel(&*conn, parse_id(id_str)?, msg.author.id.0 as i64)?
+                .map_err(|e| e.localize(locale))?;
+            msg.reply(&ctx, tr!(locale, "treasury.proposal_cancelled", id = proposal.rowid))?;
+        },
+        _ => return Err(tr!(locale, "treasury.usage").into()),
     }
     Ok(())
 }
 
-use std::borrow::Cow;
+fn find_treasury(conn:&diesel::PgConnection, locale:Locale, name:&str) -> Result<treasuries::Treasury, BoxError> {
+    treasuries::by_name(conn, name)?.ok_or_else(|| treasuries::TreasuryError::NotFound.localize(locale).into())
+}
 
-pub fn vote_common(
-    //ctx: &mut Context,
-    conn: &diesel::PgConnection,
-    vote_direction:Option<bool>,
-    vote_count:i64,
-    user_id:i64,
-    motion_id:Option<i64>,
-    message_id:Option<i64>,
-    command_message_id:Option<i64>,
-) -> Cow<'static, str> {
-    let mut fail:Option<&'static str> = None;
-    let mut outer_cost:Option<i64> = None;
-    let mut outer_motion_id:Option<i64> = None;
-    let mut outer_vote_ordinal_start:Option<i64> = None;
-    let mut outer_vote_ordinal_end:Option<i64> = None;
-    let mut outer_direction:Option<bool> = None;
-    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
-        use diesel::prelude::*;
-        use schema::motions::dsl as mdsl;
-        use schema::motion_votes::dsl as mvdsl;
-        use view_schema::balance_history::dsl as bhdsl;
-        use schema::transfers::dsl as tdsl;
-
-        let res:Option<(i64, bool, bool, i64)> = mdsl::motions
-        .filter(mdsl::rowid.eq(motion_id.unwrap_or(-1)).or(mdsl::bot_message_id.eq(message_id.unwrap_or(-1))))
-        .select((mdsl::rowid, mdsl::announcement_message_id.is_null(), mdsl::is_super, mdsl::bot_message_id))
-        .for_update()
-        .get_result(conn)
-        .optional()?;
-        //dbg!(&res);
-
-        if let Some((motion_id, not_announced, is_super, _motion_message_id)) = res {
-            outer_motion_id = Some(motion_id);
-            if not_announced {
-                //dbg!();
-                mvdsl::motion_votes //obtain a lock on all votes
-                .select(mvdsl::amount)
-                .filter(mvdsl::motion.eq(motion_id))
-                .for_update()
-                .execute(&*conn)?;
-
-                //dbg!();
-                let voted_so_far:i64;
-                let outer_dir:bool;
-                let maybe_vote_res:Option<(bool, i64)> = mvdsl::motion_votes
-                .filter(mvdsl::motion.eq(motion_id))
-                .filter(mvdsl::user.eq(user_id))
-                .select((mvdsl::direction, mvdsl::amount))
-                .for_update()
-                .get_result(&*conn)
-                .optional()?;
-                //dbg!();
-
-                if let Some((dir, count)) = maybe_vote_res {
-                    if let Some(requested_dir) = vote_direction {
-                        if requested_dir != dir {
-                            fail = Some("You cannot change your vote.");
-                            return Err(diesel::result::Error::RollbackTransaction);
-                        }
-                    }
-                    voted_so_far = count;
-                    outer_dir = dir;
-                } else {
-                    if vote_direction.is_none() {
-                        fail = Some("You must specify how you want to vote!");
-                        return Err(diesel::result::Error::RollbackTransaction);
-                    }
-                    //dbg!();
-                    diesel::insert_into(mvdsl::motion_votes).values((
-                        mvdsl::motion.eq(motion_id),
-                        mvdsl::user.eq(user_id),
-                        mvdsl::amount.eq(0),
-                        mvdsl::direction.eq(vote_direction.unwrap()),
-                    )).on_conflict_do_nothing().execute(&*conn)?;
-                    //dbg!();
-
-                    let vote_res:(bool, i64) = mvdsl::motion_votes
-                    .filter(mvdsl::motion.eq(motion_id))
-                    .filter(mvdsl::user.eq(user_id))
-                    .select((mvdsl::direction, mvdsl::amount))
-                    .for_update()
-                    .get_result(&*conn)?;
-                    //dbg!(&vote_res);
-                    voted_so_far = vote_res.1;
-                    outer_dir = vote_res.0;
-                }
-                outer_direction = Some(outer_dir);
-
-                //dbg!(&voted_so_far, &outer_dir, &vote_count);
-                let mut cost:i64 = 0;
-                outer_vote_ordinal_start = Some(voted_so_far + 1);
-                outer_vote_ordinal_end = Some(voted_so_far + vote_count + 1);
-                let mut do_fail = false;
-                for nth in voted_so_far+1..voted_so_far+vote_count+1 {
-                    //effectively:
-                    //cost += nth_vote_cost(nth).unwrap();
-                    if let Ok(this_vote_cost) = nth_vote_cost(nth) {
-                        if let Some(new_total_cost) = cost.checked_add(this_vote_cost) {
-                            cost = new_total_cost
-                        } else {
-                            do_fail = true;
-                            break;
-                        }
-                    } else {
-                        do_fail = true;
-                        break;
-                    }
-                }
-                if do_fail {
-                    fail = Some("Integer overflow, no way you have that much pc");
-                    return Err(diesel::result::Error::RollbackTransaction);
-                }
-                //dbg!(&cost);
-                outer_cost = Some(cost);
-
-                let balance:i64 = bhdsl::balance_history
-                .select(bhdsl::balance)
-                .filter(bhdsl::user.eq(user_id))
-                .filter(bhdsl::ty.eq("pc"))
-                .order(bhdsl::happened_at.desc())
-                .limit(1)
-                .for_update()
-                .get_result(&*conn)
-                .optional()?
-                .unwrap_or(0);
-                //dbg!(&balance);
-
-                if cost > balance {
-                    fail = Some("Not enough capital.");
-                    return Err(diesel::result::Error::RollbackTransaction);
-                }
+/// Lists the treasuries you're a member of.
+fn treasury_list(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale) -> CommandResult {
+    let mut lines:Vec<String> = Vec::new();
+    for treasury in treasuries::of_member(conn, msg.author.id.0 as i64)? {
+        lines.push(tr!(
+            locale,
+            "treasury.list_line",
+            treasury = treasury.name,
+            threshold = treasury.threshold,
+            members = treasuries::members(conn, treasury.rowid)?.len(),
+        ));
+    }
+    if lines.is_empty() {
+        lines.push(String::from(tr!(locale, "treasury.none")));
+    }
+    msg.channel_id.send_message(ctx, |cm| {
+        cm.embed(|e| {
+            e.title(tr!(locale, "treasury.list_title"));
+            e.description(lines.join("\n"));
+            e.footer(|f| f.text(tr!(locale, "treasury.list_footer", info = format!("{}treasury info <name>", PREFIX))));
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
 
-                let now = chrono::Utc::now();
-
-                diesel::insert_into(tdsl::transfers).values((
-                    tdsl::ty.eq("pc"),
-                    tdsl::from_user.eq(user_id),
-                    tdsl::quantity.eq(cost),
-                    tdsl::from_balance.eq(balance - cost),
-                    tdsl::happened_at.eq(now),
-                    tdsl::message_id.eq(command_message_id),
-                    tdsl::to_motion.eq(motion_id),
-                    tdsl::to_votes.eq(vote_count),
-                    tdsl::transfer_ty.eq("motion_vote"),
-                )).execute(&*conn)?;
-                //dbg!();
-
-                use bigdecimal::{BigDecimal,ToPrimitive};
-                let get_vote_count = |dir:bool| -> Result<i64, diesel::result::Error> {
-                    let votes:Option<BigDecimal> = mvdsl::motion_votes
-                    .select(diesel::dsl::sum(mvdsl::amount))
-                    .filter(mvdsl::motion.eq(motion_id))
-                    .filter(mvdsl::direction.eq(dir))
-                    .get_result(&*conn)?;
-                    Ok(votes.map(|bd| bd.to_i64().unwrap()).unwrap_or(0))
-                };
-                let mut yes_votes = get_vote_count(true)?;
-                let mut no_votes = get_vote_count(false)?;
-                //dbg!(&yes_votes, &no_votes);
-                
-                let result_before:bool;
-                let result_after:bool;
-                result_before = is_win(yes_votes, no_votes, is_super);
-                if outer_dir {
-                    yes_votes += vote_count;
-                }else{
-                    no_votes += vote_count;
-                }
-                result_after = is_win(yes_votes, no_votes, is_super);
-
-                diesel::update(
-                    mvdsl::motion_votes.filter(mvdsl::motion.eq(motion_id)).filter(mvdsl::user.eq(user_id))
-                ).set(
-                    mvdsl::amount.eq(voted_so_far + vote_count)
-                ).execute(&*conn)?;
-                //dbg!();
-
-                if result_before != result_after {
-                    diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(
-                        mdsl::last_result_change.eq(chrono::Utc::now())
-                    ).execute(&*conn)?;
-                    //dbg!();
-                }
-                //dbg!();
-
-                // let mut motion_message = ctx.http.get_message(MOTIONS_CHANNEL, motion_message_id as u64).unwrap();
-                // update_motion_message(ctx, &*conn, &mut motion_message).unwrap(); 
-                diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(mdsl::needs_update.eq(true)).execute(&*conn).unwrap();
-            }else{
-                fail = Some("Motion has expired.");
-                return Err(diesel::result::Error::RollbackTransaction);
-            }
-        }else{
-            fail = Some("Motion not found.");
-            return Err(diesel::result::Error::RollbackTransaction);
+/// Shows a treasury's members, holdings and open proposals.
+fn treasury_info(ctx:&Context, msg:&Message, conn:&diesel::PgConnection, locale:Locale, name:&str) -> CommandResult {
+    let treasury = find_treasury(conn, locale, name)?;
+    let members = treasuries::members(conn, treasury.rowid)?;
+    let holdings:Vec<String> = economy::balances_of(conn, treasury.account())?
+        .into_iter()
+        .filter(|(_, balance)| *balance != 0)
+        .map(|(ty, balance)| format!("{} {}", locale.number(balance), ty.long_name_ambiguous))
+        .collect();
+    let now = chrono::Utc::now();
+    let mut proposal_lines:Vec<String> = Vec::new();
+    for proposal in treasuries::proposals(conn, treasury.rowid, 50)?.iter().filter(|p| p.status(now) == ProposalStatus::Pending).take(10) {
+        proposal_lines.push(tr!(
+            locale,
+            "treasury.proposal_line",
+            id = proposal.rowid,
+            what = treasuries::describe(conn, locale, proposal, |user| account_mention(locale, user))?,
+            approvals = treasuries::approvals(conn, proposal.rowid)?.len(),
+            threshold = treasury.threshold,
+            expires = locale.datetime(proposal.expires_at),
+        ));
+    }
+    msg.channel_id.send_message(ctx, |cm| {
+        cm.embed(|e| {
+            e.title(&treasury.name);
+            e.description(tr!(locale, "treasury.threshold", threshold = treasury.threshold, members = members.len()));
+            e.field(
+                tr!(locale, "treasury.members"),
+                members.iter().map(|m| account_mention(locale, *m)).collect::<Vec<_>>().join(" "),
+                false,
+            );
+            e.field(
+                tr!(locale, "treasury.holdings"),
+                if holdings.is_empty() { String::from(tr!(locale, "treasury.nothing")) } else { holdings.join("\n") },
+                false,
+            );
+            e.field(
+                tr!(locale, "treasury.pending"),
+                if proposal_lines.is_empty() { String::from(tr!(locale, "treasury.no_proposals")) } else { proposal_lines.join("\n") },
+                false,
+            );
+            e.footer(|f| f.text(tr!(locale, "treasury.info_footer", approve = format!("{}treasury approve <id>", PREFIX))));
+            e
+        });
+        cm
+    })?;
+    Ok(())
+}
+
+/// `$treasury propose <name> give <user> <amount> <type> [memo]` or
+/// `$treasury propose <name> vote <motion id> <direction> <votes>`
+fn treasury_propose(
+    ctx:&Context,
+    msg:&Message,
+    conn:&diesel::PgConnection,
+    locale:Locale,
+    name:&str,
+    kind:&str,
+    args:&[String],
+) -> CommandResult {
+    let treasury = find_treasury(conn, locale, name)?;
+    let kinds = [("give", true), ("vote", false)];
+    let spend = if parser::parse_choice(kind, &kinds).map_err(|e| e.localize(locale))? {
+        let (user_str, rest) = args.split_first().ok_or_else(|| tr!(locale, "give.no_user"))?;
+        let to_user = UserId::from_command_args(ctx, msg, user_str).map_err(|key| tr!(locale, key))?;
+        if !ctx.cache.read().users.contains_key(&to_user) {
+            return Err(tr!(locale, "give.user_not_found").into());
         }
+        let (amount, ty_name, memo) = parser::parse_give_with_memo(rest).map_err(|e| e.localize(locale))?;
+        let ty = match ty_name {
+            Some(name) => economy::find_item_type(conn, &name)?.map_err(|e| e.localize(locale))?,
+            None => return Err(tr!(locale, "give.no_type").into()),
+        };
+        // the treasury's balance may well have changed by the time it's approved
+        let quantity = match amount {
+            parser::Amount::Exact(n) => n as i64,
+            _ => return Err(tr!(locale, "treasury.exact_amount").into()),
+        };
+        treasuries::Spend::Give{ to_user: to_user.0 as i64, ty, quantity, memo }
+    } else {
+        let (checksummed_motion_id, rest) = args.split_first().ok_or_else(|| tr!(locale, "vote.no_motion_id"))?;
+        let motion = parser::parse_motion_id(checksummed_motion_id).map_err(|e| e.localize(locale))?;
+        let (direction, amount) = parser::parse_vote_args(&vocabulary::vote_vocabulary(conn)?, rest)
+            .map_err(|e| e.localize(locale))?;
+        let direction = direction.ok_or_else(|| economy::VoteError::NoDirection.localize(locale))?;
+        let votes = match amount {
+            VoteAmount::Exact(n) => n,
+            _ => return Err(tr!(locale, "treasury.exact_votes").into()),
+        };
+        treasuries::Spend::Vote{ motion, direction, votes }
+    };
 
-        Ok(())
-    });
-    if let Some(msg) = fail {
-        return Cow::Borrowed(msg);
-    }
-    txn_res.unwrap();
-    if let (Some(cost), Some(motion_id), Some(ordinal_start), Some(ordinal_end), Some(direction)) = (outer_cost, outer_motion_id, outer_vote_ordinal_start, outer_vote_ordinal_end, outer_direction) {
-        #[allow(clippy::comparison_chain)]
-        let ordinal_text = if vote_count > 1 {
-            format!(", {} to {} vote", ordinal::Ordinal(ordinal_start), ordinal::Ordinal(ordinal_end-1))
-        } else if vote_count == 1 {
-            format!(", {} vote", ordinal::Ordinal(ordinal_start))
-        } else { String::new() };
-        return Cow::Owned(format!(
-            "Voted {} times {} motion #{}{}, costing {} capital",
-            vote_count,
-            if direction { "for" } else { "against" },
-            damm::add_to_str(motion_id.to_string()),
-            ordinal_text,
-            cost,
-        ));
+    let approval = treasuries::propose(
+        conn,
+        treasury.rowid,
+        msg.author.id.0 as i64,
+        &spend,
+        Some(msg.id.0 as i64),
+        *TREASURY_PROPOSAL_EXPIRATION,
+    )?.map_err(|e| e.localize(locale))?;
+    let proposal = &approval.proposal;
+    let what = treasuries::describe(conn, locale, proposal, |user| account_mention(locale, user))?;
+    if approval.executed() {
+        msg.reply(ctx, approval.outcome(locale, &what))?;
+        return Ok(());
     }
-    Cow::Borrowed("Vote cast")
+    msg.reply(ctx, tr!(
+        locale,
+        "treasury.proposed",
+        id = proposal.rowid,
+        treasury = treasury.name,
+        what = what,
+        threshold = treasury.threshold,
+        expires = locale.datetime(proposal.expires_at),
+    ))?;
+
+    let motions_locale = motions_locale(&ctx.cache, conn)?;
+    for member in treasuries::members(conn, treasury.rowid)? {
+        if member == proposal.proposed_by {
+            continue;
+        }
+        let member_locale = settings::user_locale(conn, member)?.unwrap_or(motions_locale);
+        let notice = tr!(
+            member_locale,
+            "treasury.notice_proposed",
+            user = msg.author.id.mention(),
+            treasury = treasury.name,
+            what = treasuries::describe(conn, member_locale, proposal, |user| account_mention(member_locale, user))?,
+            id = proposal.rowid,
+            command = format!("{}treasury approve {}", PREFIX, proposal.rowid),
+        );
+        if let Err(e) = DiscordFrontend(ctx, motions_locale).dm_user(member, &notice) {
+            info!("Could not DM {} about treasury proposal {}: {:?}", member, proposal.rowid, e);
+        }
+    }
+    Ok(())
 }
+
+#[command]
+#[num_args(1)]
+fn flip(ctx:&mut Context, msg:&Message, mut args:Args) -> CommandResult {
+    let conn = ctx.data.read().get::<DbPoolKey>().unwrap().get()?;
+    let locale = locale_of(&*conn, msg)?;
+    let motion_id = parser::parse_motion_id(&args.single::<String>()?).map_err(|e| e.localize(locale))?;
+    match economy::flip_plan(&*conn, motion_id, msg.author.id.0 as i64)? {
+        Ok(plan) => msg.reply(&ctx, plan.localize(locale))?,
+        Err(e) => msg.reply(&ctx, e.localize(locale))?,
+    };
+    Ok(())
+}
+
+
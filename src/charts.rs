@@ -0,0 +1,128 @@
+//! Server-side SVG charts. These are plain inline SVG so they render under the strict CSP set by
+//! `SecureHeaders`; colors come from classes in main.css rather than style attributes.
+use maud::{html, Markup};
+
+const WIDTH:f64 = 600.0;
+const HEIGHT:f64 = 240.0;
+const MARGIN_LEFT:f64 = 70.0;
+const MARGIN_RIGHT:f64 = 10.0;
+const MARGIN_TOP:f64 = 10.0;
+const MARGIN_BOTTOM:f64 = 30.0;
+
+#[derive(Debug,Clone,Copy)]
+struct Scale {
+    min: f64,
+    max: f64,
+    from: f64,
+    to: f64,
+}
+
+impl Scale {
+    fn apply(&self, v: f64) -> f64 {
+        if (self.max - self.min).abs() < f64::EPSILON {
+            (self.from + self.to) / 2.0
+        } else {
+            self.from + (v - self.min) / (self.max - self.min) * (self.to - self.from)
+        }
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Series {
+    /// CSS class applied to the line, see `.chart-*` in main.css
+    pub class: &'static str,
+    /// Points sorted by x
+    pub points: Vec<(f64, f64)>,
+}
+
+fn y_scale(min: f64, max: f64) -> Scale {
+    Scale{ min: min.min(0.0), max, from: HEIGHT - MARGIN_BOTTOM, to: MARGIN_TOP }
+}
+
+fn axes(x: Scale, y: Scale, x_label: &dyn Fn(f64) -> String, y_label: &dyn Fn(f64) -> String) -> Markup {
+    html!{
+        line.chart-axis x1=(MARGIN_LEFT) y1=(MARGIN_TOP) x2=(MARGIN_LEFT) y2=(HEIGHT - MARGIN_BOTTOM) {}
+        line.chart-axis x1=(MARGIN_LEFT) y1=(HEIGHT - MARGIN_BOTTOM) x2=(WIDTH - MARGIN_RIGHT) y2=(HEIGHT - MARGIN_BOTTOM) {}
+        text.chart-label x=(MARGIN_LEFT - 4.0) y=(MARGIN_TOP + 10.0) text-anchor="end" { (y_label(y.max)) }
+        text.chart-label x=(MARGIN_LEFT - 4.0) y=(HEIGHT - MARGIN_BOTTOM) text-anchor="end" { (y_label(y.min)) }
+        text.chart-label x=(MARGIN_LEFT) y=(HEIGHT - 8.0) text-anchor="start" { (x_label(x.min)) }
+        text.chart-label x=(WIDTH - MARGIN_RIGHT) y=(HEIGHT - 8.0) text-anchor="end" { (x_label(x.max)) }
+    }
+}
+
+/// Draws every series on a shared pair of axes. The y axis always includes zero.
+pub fn line_chart(
+    series: &[Series],
+    x_label: impl Fn(f64) -> String,
+    y_label: impl Fn(f64) -> String,
+) -> Markup {
+    line_chart_with_markers(series, &[], x_label, y_label)
+}
+
+/// Like `line_chart`, with a vertical line drawn at each x in `markers`.
+pub fn line_chart_with_markers(
+    series: &[Series],
+    markers: &[f64],
+    x_label: impl Fn(f64) -> String,
+    y_label: impl Fn(f64) -> String,
+) -> Markup {
+    let all_points = || series.iter().flat_map(|s| s.points.iter());
+    if all_points().next().is_none() {
+        return html!{ p.no-data { "No data yet." } };
+    }
+    let x = Scale{
+        min: all_points().map(|p| p.0).fold(f64::INFINITY, f64::min),
+        max: all_points().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max),
+        from: MARGIN_LEFT,
+        to: WIDTH - MARGIN_RIGHT,
+    };
+    let y = y_scale(
+        all_points().map(|p| p.1).fold(f64::INFINITY, f64::min),
+        all_points().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max),
+    );
+    html!{
+        svg.chart xmlns="http://www.w3.org/2000/svg" viewBox=(format!("0 0 {} {}", WIDTH, HEIGHT)) role="img" {
+            (axes(x, y, &x_label, &y_label))
+            @for marker in markers {
+                line.chart-marker x1=(x.apply(*marker)) y1=(MARGIN_TOP) x2=(x.apply(*marker)) y2=(HEIGHT - MARGIN_BOTTOM) {
+                    title { (x_label(*marker)) }
+                }
+            }
+            @for s in series {
+                @let points = s.points.iter().map(|(px, py)| format!("{:.1},{:.1}", x.apply(*px), y.apply(*py))).collect::<Vec<_>>().join(" ");
+                polyline class=(s.class) points=(points) {}
+            }
+        }
+    }
+}
+
+/// Draws one bar per entry, in order. Each bar has a tooltip with its label and value.
+pub fn bar_chart(
+    bars: &[(String, f64)],
+    y_label: impl Fn(f64) -> String,
+) -> Markup {
+    if bars.is_empty() {
+        return html!{ p.no-data { "No data yet." } };
+    }
+    let x = Scale{ min: 0.0, max: bars.len() as f64, from: MARGIN_LEFT, to: WIDTH - MARGIN_RIGHT };
+    let y = y_scale(
+        bars.iter().map(|b| b.1).fold(f64::INFINITY, f64::min),
+        bars.iter().map(|b| b.1).fold(f64::NEG_INFINITY, f64::max),
+    );
+    let bar_width = (x.apply(1.0) - x.apply(0.0)) * 0.8;
+    let first_label = bars[0].0.clone();
+    let last_label = bars[bars.len() - 1].0.clone();
+    let x_label = move |v:f64| if v <= 0.0 { first_label.clone() } else { last_label.clone() };
+    html!{
+        svg.chart xmlns="http://www.w3.org/2000/svg" viewBox=(format!("0 0 {} {}", WIDTH, HEIGHT)) role="img" {
+            (axes(x, y, &x_label, &y_label))
+            @for (i, (label, value)) in bars.iter().enumerate() {
+                @let top = y.apply(value.max(0.0));
+                @let bottom = y.apply(value.min(0.0));
+                rect.chart-bar x=(format!("{:.1}", x.apply(i as f64))) y=(format!("{:.1}", top)) width=(format!("{:.1}", bar_width)) height=(format!("{:.1}", bottom - top)) {
+                    title { (label) ": " (y_label(*value)) }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1127 @@
+//! The economy itself: balances, giving, motions, voting and generation, independent of any chat
+//! platform. Users are identified by the i64 ids stored in the database, which each frontend maps
+//! its own users onto. Anything that has to be shown in chat goes through a `ChatFrontend`.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::{allowances, damm, parser, pledges, schema, view_schema, vote_cost};
+use crate::allowances::AllowanceError;
+use crate::i18n::{Locale, Localize};
+use crate::is_win::is_win;
+use crate::models::ItemType;
+use crate::parser::ParseError;
+use crate::vote_cost::{VoteAmount, VOTE_BASE_COST};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A chat platform the economy is operated through, such as Discord.
+pub trait ChatFrontend {
+    /// Posts a newly called motion, returning an id for the post. That id is stored as the motion's
+    /// `bot_message_id` and passed back to `update_motion`.
+    fn post_motion(&self, status:&MotionStatus, motioned_by:i64) -> Result<i64, BoxError>;
+
+    /// Called once a posted motion has been saved, eg. to add voting controls to the post.
+    fn motion_posted(&self, _post_id:i64, _status:&MotionStatus) -> Result<(), BoxError> {
+        Ok(())
+    }
+
+    /// Shows the latest tally on a motion's post.
+    fn update_motion(&self, post_id:i64, status:&MotionStatus) -> Result<(), BoxError>;
+
+    /// Announces that voting on a motion has ended, returning an id for the announcement.
+    fn announce_result(&self, status:&MotionStatus, at:DateTime<Utc>) -> Result<i64, BoxError>;
+
+    /// Privately tells a user something.
+    fn dm_user(&self, user:i64, text:&str) -> Result<(), BoxError>;
+
+    /// How to refer to a user in anything sent through the frontend.
+    fn mention(&self, user:i64) -> String {
+        format!("user#{}", user)
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct MotionVoter {
+    pub user: i64,
+    pub amount: i64,
+    pub direction: bool,
+}
+
+/// Everything a frontend needs to display a motion.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct MotionStatus {
+    pub motion_id: i64,
+    pub text: String,
+    pub is_super: bool,
+    pub yes_votes: i64,
+    pub no_votes: i64,
+    /// Largest first
+    pub votes: Vec<MotionVoter>,
+}
+
+impl MotionStatus {
+    pub fn damm_id(&self) -> String {
+        damm::add_to_str(self.motion_id.to_string())
+    }
+
+    pub fn is_win(&self) -> bool {
+        is_win(self.yes_votes, self.no_votes, self.is_super)
+    }
+
+    pub fn kind_label(&self, locale:Locale) -> &'static str {
+        if self.is_super { tr!(locale, "motion.kind_super") } else { tr!(locale, "motion.kind_simple") }
+    }
+
+    /// eg. "**for 3**/1 against", leading with whichever side is winning
+    pub fn tally_text(&self, locale:Locale) -> String {
+        let (yes, no) = (locale.number(self.yes_votes), locale.number(self.no_votes));
+        if self.is_win() {
+            tr!(locale, "motion.tally_passing", yes = yes, no = no)
+        } else {
+            tr!(locale, "motion.tally_failing", yes = yes, no = no)
+        }
+    }
+}
+
+pub fn motion_status(conn:&PgConnection, motion_id:i64) -> QueryResult<MotionStatus> {
+    use schema::motions::dsl as mdsl;
+    use schema::motion_votes::dsl as mvdsl;
+
+    let (text, is_super):(String, bool) = mdsl::motions
+        .filter(mdsl::rowid.eq(motion_id))
+        .select((mdsl::motion_text, mdsl::is_super))
+        .get_result(conn)?;
+    let mut votes:Vec<MotionVoter> = mvdsl::motion_votes
+        .filter(mvdsl::motion.eq(motion_id))
+        .select((mvdsl::user, mvdsl::amount, mvdsl::direction))
+        .get_results::<(i64, i64, bool)>(conn)?
+        .into_iter()
+        .map(|(user, amount, direction)| MotionVoter{ user, amount, direction })
+        .collect();
+    votes.sort_unstable_by_key(|v| -v.amount);
+    let yes_votes = votes.iter().filter(|v| v.direction).map(|v| v.amount).sum();
+    let no_votes = votes.iter().filter(|v| !v.direction).map(|v| v.amount).sum();
+    Ok(MotionStatus{ motion_id, text, is_super, yes_votes, no_votes, votes })
+}
+
+/// The user's balance of the item type named `ty`, locking the row it comes from if `for_update`.
+pub fn latest_balance(conn:&PgConnection, user:i64, ty:&str, for_update:bool) -> QueryResult<i64> {
+    use view_schema::balance_history::dsl as bhdsl;
+    let query = bhdsl::balance_history
+        .select(bhdsl::balance)
+        .filter(bhdsl::user.eq(user))
+        .filter(bhdsl::ty.eq(ty))
+        // transfers made in one go can share a timestamp, and the later of those was made last
+        .order((bhdsl::happened_at.desc(), bhdsl::rowid.desc()))
+        .limit(1);
+    let res = if for_update {
+        query.for_update().get_result(conn)
+    } else {
+        query.get_result(conn)
+    };
+    Ok(res.optional()?.unwrap_or(0))
+}
+
+/// Looks up an item type that hasn't been retired by any of its aliases, such as "pc" or "capital".
+pub fn item_type_by_alias(conn:&PgConnection, alias:&str) -> QueryResult<Option<ItemType>> {
+    use schema::item_types::dsl as it;
+    use schema::item_type_aliases::dsl as ita;
+    ita::item_type_aliases
+        .inner_join(it::item_types)
+        .select(it::item_types::all_columns())
+        .filter(ita::alias.eq(alias))
+        .filter(it::retired.eq(false))
+        .get_result(conn)
+        .optional()
+}
+
+/// Every alias item types that haven't been retired can be referred to by.
+pub fn item_type_aliases(conn:&PgConnection) -> QueryResult<Vec<String>> {
+    use schema::item_types::dsl as it;
+    use schema::item_type_aliases::dsl as ita;
+    ita::item_type_aliases
+        .inner_join(it::item_types)
+        .select(ita::alias)
+        .filter(it::retired.eq(false))
+        .get_results(conn)
+}
+
+/// Every item type, retired or not, with all of its aliases.
+pub fn item_types_with_aliases(conn:&PgConnection) -> QueryResult<Vec<(ItemType, Vec<String>)>> {
+    use schema::item_types::dsl as it;
+    use schema::item_type_aliases::dsl as ita;
+    let item_types:Vec<ItemType> = it::item_types.order(it::name).get_results(conn)?;
+    let aliases:Vec<(String, String)> = ita::item_type_aliases
+        .select((ita::name, ita::alias))
+        .order(ita::alias)
+        .get_results(conn)?;
+    Ok(item_types.into_iter().map(|ty| {
+        let these = aliases.iter().filter(|(name, _)| *name == ty.name).map(|(_, alias)| alias.clone()).collect();
+        (ty, these)
+    }).collect())
+}
+
+/// Looks up an item type as written in a command, suggesting the closest alias if there's none.
+pub fn find_item_type(conn:&PgConnection, name:&str) -> QueryResult<Result<ItemType, ParseError>> {
+    if let Some(ty) = item_type_by_alias(conn, &name.to_lowercase())? {
+        return Ok(Ok(ty));
+    }
+    let aliases = item_type_aliases(conn)?;
+    Ok(Err(ParseError::UnknownWord{
+        word: name.to_string(),
+        suggestion: parser::did_you_mean(name, aliases.iter().map(String::as_str)).map(String::from),
+    }))
+}
+
+/// The user's current balance of one item type.
+pub fn balance_of(conn:&PgConnection, user:i64, ty:&ItemType) -> QueryResult<i64> {
+    latest_balance(conn, user, ty.db_name(), false)
+}
+
+/// The user's current balance of every item type, including ones they have none of.
+pub fn balances_of(conn:&PgConnection, user:i64) -> QueryResult<Vec<(ItemType, i64)>> {
+    use schema::item_types::dsl as it;
+    let item_types:Vec<ItemType> = it::item_types
+        .get_results(conn)?;
+    item_types.into_iter().map(|ty| latest_balance(conn, user, ty.db_name(), false).map(|bal| (ty,bal))).collect()
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum GiveError {
+    InsufficientBalance,
+    Allowance(AllowanceError),
+}
+
+impl Localize for GiveError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            GiveError::InsufficientBalance => tr!(locale, "give.insufficient_balance").to_string(),
+            GiveError::Allowance(e) => e.localize(locale),
+        }
+    }
+}
+
+impl fmt::Display for GiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Moves `amount` of `ty` from one user to another. `message_id` is the message (or interaction)
+/// that asked for it, if any, and `memo` is shown to both of them alongside the transfer.
+pub fn give(
+    conn:&PgConnection,
+    from_user:i64,
+    to_user:i64,
+    ty:&ItemType,
+    amount:u64,
+    message_id:Option<i64>,
+    memo:Option<&str>,
+) -> QueryResult<Result<(), GiveError>> {
+    Ok(give_transfer(conn, from_user, to_user, ty, amount, message_id, memo)?.map(|_| ()))
+}
+
+/// Like `give`, returning the id of the transfer it made, for anything that needs to point at it.
+pub fn give_transfer(
+    conn:&PgConnection,
+    from_user:i64,
+    to_user:i64,
+    ty:&ItemType,
+    amount:u64,
+    message_id:Option<i64>,
+    memo:Option<&str>,
+) -> QueryResult<Result<i64, GiveError>> {
+    give_as(conn, None, from_user, to_user, ty, amount, message_id, memo)
+}
+
+/// Like `give_transfer`, but made by `acting_user` on `from_user`'s behalf, spending from the
+/// allowance `from_user` gave them.
+#[allow(clippy::too_many_arguments)]
+pub fn give_on_behalf(
+    conn:&PgConnection,
+    acting_user:i64,
+    from_user:i64,
+    to_user:i64,
+    ty:&ItemType,
+    amount:u64,
+    message_id:Option<i64>,
+    memo:Option<&str>,
+) -> QueryResult<Result<i64, GiveError>> {
+    give_as(conn, Some(acting_user), from_user, to_user, ty, amount, message_id, memo)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn give_as(
+    conn:&PgConnection,
+    acting_user:Option<i64>,
+    from_user:i64,
+    to_user:i64,
+    ty:&ItemType,
+    amount:u64,
+    message_id:Option<i64>,
+    memo:Option<&str>,
+) -> QueryResult<Result<i64, GiveError>> {
+    let mut fail:Option<GiveError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        // lock in a consistent order to avoid deadlocks
+        let mut ids = [from_user, to_user];
+        let mut author = 0;
+        let mut dest = 1;
+        if ids[0] > ids[1] {
+            ids = [ids[1],ids[0]];
+            author = 1;
+            dest = 0;
+        }
+        let balances:Vec<i64> = ids.iter()
+            .map(|id| latest_balance(conn, *id, ty.db_name(), true))
+            .collect::<Result<_,_>>()?;
+        let sender_balance = balances[author];
+        let dest_balance = balances[dest];
+        let now = chrono::Utc::now();
+        if let Some(acting_user) = acting_user {
+            if let Err(e) = allowances::draw(conn, from_user, acting_user, ty.db_name(), amount as i64, now)? {
+                fail = Some(GiveError::Allowance(e));
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        }
+        if sender_balance < amount as i64 {
+            fail = Some(GiveError::InsufficientBalance);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        use schema::transfers;
+        #[derive(Insertable, Debug)]
+        #[table_name = "transfers"]
+        struct Transfer {
+            from_user:i64,
+            quantity:i64,
+            to_user:i64,
+            from_balance:i64,
+            to_balance:i64,
+            happened_at:chrono::DateTime<chrono::Utc>,
+            message_id:Option<i64>,
+            ty:String,
+            comment:Option<String>,
+            transfer_ty:&'static str,
+            acting_user:Option<i64>,
+        }
+
+        let from_balance;
+        let to_balance;
+        if from_user == to_user {
+            from_balance = sender_balance;
+            to_balance = sender_balance;
+        }else{
+            from_balance = sender_balance - amount as i64;
+            to_balance = dest_balance + amount as i64;
+        }
+
+        let t = Transfer {
+            from_user,
+            quantity: amount as i64,
+            to_user,
+            from_balance,
+            to_balance,
+            happened_at: now,
+            message_id,
+            ty: ty.db_name().into(),
+            comment: memo.map(String::from),
+            transfer_ty: "give",
+            acting_user,
+        };
+
+        diesel::insert_into(schema::transfers::table).values(&t).returning(schema::transfers::rowid).get_result(conn)
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum BurnError {
+    NotPositive,
+    InsufficientBalance{ balance: i64 },
+}
+
+impl Localize for BurnError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            BurnError::NotPositive => tr!(locale, "burn.not_positive").to_string(),
+            BurnError::InsufficientBalance{balance} => tr!(locale, "burn.insufficient_balance", balance = locale.number(*balance)),
+        }
+    }
+}
+
+impl fmt::Display for BurnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Destroys `amount` of the user's own `ty`, returning what they have left. Unlike an admin's
+/// burn, this is the user's own choice, so there's nothing to log beyond the transfer itself.
+pub fn burn(
+    conn:&PgConnection,
+    user:i64,
+    ty:&ItemType,
+    amount:i64,
+    reason:Option<&str>,
+    message_id:Option<i64>,
+) -> QueryResult<Result<i64, BurnError>> {
+    use schema::transfers::dsl as tdsl;
+    if amount <= 0 {
+        return Ok(Err(BurnError::NotPositive));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let balance = latest_balance(conn, user, ty.db_name(), true)?;
+        if balance < amount {
+            return Ok(Err(BurnError::InsufficientBalance{ balance }));
+        }
+        diesel::insert_into(tdsl::transfers).values((
+            tdsl::from_user.eq(user),
+            tdsl::quantity.eq(amount),
+            tdsl::from_balance.eq(balance - amount),
+            tdsl::happened_at.eq(chrono::Utc::now()),
+            tdsl::message_id.eq(message_id),
+            tdsl::ty.eq(ty.db_name()),
+            tdsl::comment.eq(reason),
+            tdsl::transfer_ty.eq("burn"),
+        )).execute(conn)?;
+        Ok(Ok(balance - amount))
+    })
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum MotionError {
+    InsufficientCapital,
+}
+
+impl Localize for MotionError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            MotionError::InsufficientCapital => tr!(locale, "motion.insufficient_capital").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for MotionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Calls a motion: charges the motioner for its first vote and posts it through the frontend.
+/// `command_message_id` is the message (or interaction) that called it. Returns the new motion's id.
+pub fn create_motion(
+    frontend:&impl ChatFrontend,
+    conn:&PgConnection,
+    motioned_by:i64,
+    command_message_id:i64,
+    motion_text:&str,
+    is_super:bool,
+) -> Result<Result<i64, MotionError>, BoxError> {
+    use schema::motions::dsl as mdsl;
+    use schema::motion_votes::dsl as mvdsl;
+    use schema::motion_result_changes::dsl as mrcdsl;
+    use schema::transfers::dsl as tdsl;
+    let mut fail:Option<MotionError> = None;
+
+    let now = chrono::Utc::now();
+    let txn_res = conn.transaction::<_, BoxError, _>(|| {
+        let balance = latest_balance(conn, motioned_by, "pc", true)?;
+        if balance < VOTE_BASE_COST as i64 {
+            fail = Some(MotionError::InsufficientCapital);
+            return Err(diesel::result::Error::RollbackTransaction.into());
+        }
+
+        let motion_id:i64 = diesel::insert_into(schema::motion_ids::table).default_values().returning(schema::motion_ids::dsl::rowid).get_result(conn)?;
+
+        let status = MotionStatus{
+            motion_id,
+            text: motion_text.to_string(),
+            is_super,
+            yes_votes: 1,
+            no_votes: 0,
+            votes: vec![MotionVoter{ user: motioned_by, amount: 1, direction: true }],
+        };
+        let post_id = frontend.post_motion(&status, motioned_by)?;
+
+        diesel::insert_into(mdsl::motions).values((
+            mdsl::rowid.eq(motion_id),
+            mdsl::command_message_id.eq(command_message_id),
+            mdsl::bot_message_id.eq(post_id),
+            mdsl::motion_text.eq(motion_text),
+            mdsl::motioned_at.eq(now),
+            mdsl::motioned_by.eq(motioned_by),
+            mdsl::last_result_change.eq(now),
+            mdsl::is_super.eq(is_super),
+        )).execute(conn)?;
+
+        diesel::insert_into(mvdsl::motion_votes).values((
+            mvdsl::user.eq(motioned_by),
+            mvdsl::motion.eq(motion_id),
+            mvdsl::direction.eq(true),
+            mvdsl::amount.eq(1)
+        )).execute(conn)?;
+
+        diesel::insert_into(mrcdsl::motion_result_changes).values((
+            mrcdsl::motion.eq(motion_id),
+            mrcdsl::changed_at.eq(now),
+            mrcdsl::is_win.eq(status.is_win()),
+            mrcdsl::yes_votes.eq(1),
+            mrcdsl::no_votes.eq(0),
+        )).execute(conn)?;
+
+        diesel::insert_into(tdsl::transfers).values((
+            tdsl::from_user.eq(motioned_by),
+            tdsl::from_balance.eq(balance),
+            tdsl::ty.eq("pc"),
+            tdsl::quantity.eq(VOTE_BASE_COST as i64),
+            tdsl::happened_at.eq(chrono::Utc::now()),
+            tdsl::message_id.eq(command_message_id),
+            tdsl::to_motion.eq(motion_id),
+            tdsl::to_votes.eq(1),
+            tdsl::transfer_ty.eq("motion_create"),
+        )).execute(conn)?;
+
+        Ok((post_id, status))
+    });
+    if let Some(e) = fail {
+        return Ok(Err(e));
+    }
+    let (post_id, status) = txn_res?;
+
+    update_motion(frontend, conn, post_id, &status)?;
+    frontend.motion_posted(post_id, &status)?;
+
+    Ok(Ok(status.motion_id))
+}
+
+fn update_motion(frontend:&impl ChatFrontend, conn:&PgConnection, post_id:i64, status:&MotionStatus) -> Result<(), BoxError> {
+    use schema::motions::dsl as mdsl;
+    frontend.update_motion(post_id, status)?;
+    diesel::update(mdsl::motions.filter(mdsl::bot_message_id.eq(post_id)))
+        .set(mdsl::needs_update.eq(false))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Refreshes the post of the motion with the given post id.
+pub fn refresh_motion_post(frontend:&impl ChatFrontend, conn:&PgConnection, post_id:i64) -> Result<(), BoxError> {
+    use schema::motions::dsl as mdsl;
+    let motion_id:i64 = mdsl::motions
+        .filter(mdsl::bot_message_id.eq(post_id))
+        .select(mdsl::rowid)
+        .get_result(conn)?;
+    update_motion(frontend, conn, post_id, &motion_status(conn, motion_id)?)
+}
+
+/// Refreshes the post of every pending motion whose votes have changed since it was last shown.
+pub fn update_changed_motions(frontend:&impl ChatFrontend, conn:&PgConnection) -> Result<(), BoxError> {
+    use schema::motions::dsl as mdsl;
+    let motions:Vec<(i64, i64)> = mdsl::motions
+        .filter(mdsl::announcement_message_id.is_null())
+        .filter(mdsl::needs_update)
+        .select((mdsl::rowid, mdsl::bot_message_id))
+        .get_results(conn)?;
+    for (motion_id, post_id) in motions {
+        update_motion(frontend, conn, post_id, &motion_status(conn, motion_id)?)?;
+    }
+    Ok(())
+}
+
+/// Announces the result of every motion that hasn't changed result in `expiration`, settling any
+/// pledges on it. `locale` is for telling users about pledges who haven't picked a language.
+pub fn announce_expired_motions(
+    frontend:&impl ChatFrontend,
+    conn:&PgConnection,
+    expiration:chrono::Duration,
+    locale:Locale,
+) -> Result<(), BoxError> {
+    use schema::motions::dsl as mdsl;
+    let now = chrono::Utc::now();
+    let motion_ids:Vec<i64> = mdsl::motions
+        .filter(mdsl::announcement_message_id.is_null())
+        .filter(mdsl::last_result_change.lt(now - expiration))
+        .select(mdsl::rowid)
+        .get_results(conn)?;
+    for motion_id in motion_ids {
+        // votes and pledges are made under the same lock, so once it's held and the motion is still
+        // expired, the result can't change before it's announced, and no pledge can be made once
+        // these are settled
+        let settled = conn.transaction::<_, BoxError, _>(|| {
+            let (unannounced, last_result_change):(bool, DateTime<Utc>) = mdsl::motions
+                .filter(mdsl::rowid.eq(motion_id))
+                .select((mdsl::announcement_message_id.is_null(), mdsl::last_result_change))
+                .for_update()
+                .get_result(conn)?;
+            if !unannounced || last_result_change >= now - expiration {
+                // announced already, or a vote changed the result since it was picked out
+                return Ok(None);
+            }
+            let status = motion_status(conn, motion_id)?;
+            let settled = pledges::settle(conn, motion_id, status.is_win())?;
+            // posted last, so nothing but saving its id can fail and roll back once it's out
+            let announcement_id = frontend.announce_result(&status, now)?;
+            diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(
+                mdsl::announcement_message_id.eq(announcement_id)
+            ).execute(conn)?;
+            Ok(Some((status.is_win(), settled)))
+        })?;
+        if let Some((is_win, settled)) = settled {
+            pledges::notify_settled(frontend, conn, locale, is_win, &settled)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum VoteError {
+    NotFound,
+    Expired,
+    ChangedDirection,
+    NoDirection,
+    Overflow,
+    CantAffordAny,
+    InsufficientCapital,
+    Allowance(AllowanceError),
+}
+
+impl Localize for VoteError {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            VoteError::NotFound => "vote.not_found",
+            VoteError::Expired => "vote.expired",
+            VoteError::ChangedDirection => "vote.changed_direction",
+            VoteError::NoDirection => "vote.no_direction",
+            VoteError::Overflow => "vote.overflow",
+            VoteError::CantAffordAny => "vote.cant_afford_any",
+            VoteError::InsufficientCapital => "vote.insufficient_capital",
+            VoteError::Allowance(e) => return e.localize(locale),
+        }).to_string()
+    }
+}
+
+impl fmt::Display for VoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct VoteOutcome {
+    pub motion_id: i64,
+    pub direction: bool,
+    pub votes: i64,
+    pub cost: i64,
+    /// How many votes the user had cast on the motion before these
+    pub voted_before: i64,
+}
+
+impl Localize for VoteOutcome {
+    fn localize(&self, locale:Locale) -> String {
+        let first = locale.ordinal(self.voted_before + 1);
+        #[allow(clippy::comparison_chain)]
+        let ordinal_text = if self.votes > 1 {
+            tr!(locale, "vote.ordinal_range", first = first, last = locale.ordinal(self.voted_before + self.votes))
+        } else if self.votes == 1 {
+            tr!(locale, "vote.ordinal_single", first = first)
+        } else { String::new() };
+        tr!(
+            locale,
+            if self.direction { "vote.outcome_for" } else { "vote.outcome_against" },
+            votes = locale.number(self.votes),
+            motion = damm::add_to_str(self.motion_id.to_string()),
+            ordinals = ordinal_text,
+            cost = locale.number(self.cost),
+        )
+    }
+}
+
+impl fmt::Display for VoteOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+fn vote_totals(conn:&PgConnection, motion_id:i64) -> QueryResult<(i64, i64)> {
+    use schema::motion_votes::dsl as mvdsl;
+    use bigdecimal::{BigDecimal,ToPrimitive};
+    let get_vote_count = |dir:bool| -> QueryResult<i64> {
+        let votes:Option<BigDecimal> = mvdsl::motion_votes
+        .select(diesel::dsl::sum(mvdsl::amount))
+        .filter(mvdsl::motion.eq(motion_id))
+        .filter(mvdsl::direction.eq(dir))
+        .get_result(conn)?;
+        Ok(votes.map(|bd| bd.to_i64().unwrap()).unwrap_or(0))
+    };
+    Ok((get_vote_count(true)?, get_vote_count(false)?))
+}
+
+/// Records a change in whether a motion is passing, if `votes_delta` more yes (or no) votes changes it.
+fn apply_vote_change(
+    conn:&PgConnection,
+    motion_id:i64,
+    is_super:bool,
+    direction:bool,
+    votes_delta:i64,
+    now:DateTime<Utc>,
+) -> QueryResult<()> {
+    use schema::motions::dsl as mdsl;
+    use schema::motion_result_changes::dsl as mrcdsl;
+    let (mut yes_votes, mut no_votes) = vote_totals(conn, motion_id)?;
+    let result_before = is_win(yes_votes, no_votes, is_super);
+    if direction {
+        yes_votes += votes_delta;
+    } else {
+        no_votes += votes_delta;
+    }
+    let result_after = is_win(yes_votes, no_votes, is_super);
+    if result_before != result_after {
+        diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(
+            mdsl::last_result_change.eq(now)
+        ).execute(conn)?;
+        diesel::insert_into(mrcdsl::motion_result_changes).values((
+            mrcdsl::motion.eq(motion_id),
+            mrcdsl::changed_at.eq(now),
+            mrcdsl::is_win.eq(result_after),
+            mrcdsl::yes_votes.eq(yes_votes),
+            mrcdsl::no_votes.eq(no_votes),
+        )).execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Casts votes on a motion, identified either by id or by the id of its post. `command_message_id`
+/// is the message that cast the vote, if any, and `reaction_emoji` the reaction that did, if any.
+#[allow(clippy::too_many_arguments)]
+pub fn vote(
+    conn:&PgConnection,
+    vote_direction:Option<bool>,
+    amount:VoteAmount,
+    user_id:i64,
+    motion_id:Option<i64>,
+    post_id:Option<i64>,
+    command_message_id:Option<i64>,
+    reaction_emoji:Option<&str>,
+) -> QueryResult<Result<VoteOutcome, VoteError>> {
+    cast_vote(conn, None, vote_direction, amount, user_id, motion_id, post_id, command_message_id, reaction_emoji)
+}
+
+/// Casts `user_id`'s votes on a motion as `acting_user`, spending from the allowance of capital
+/// `user_id` gave them. `amount` is limited to what's left of the allowance.
+pub fn vote_on_behalf(
+    conn:&PgConnection,
+    acting_user:i64,
+    vote_direction:Option<bool>,
+    amount:VoteAmount,
+    user_id:i64,
+    motion_id:i64,
+    command_message_id:Option<i64>,
+) -> QueryResult<Result<VoteOutcome, VoteError>> {
+    cast_vote(conn, Some(acting_user), vote_direction, amount, user_id, Some(motion_id), None, command_message_id, None)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn cast_vote(
+    conn:&PgConnection,
+    acting_user:Option<i64>,
+    vote_direction:Option<bool>,
+    amount:VoteAmount,
+    user_id:i64,
+    motion_id:Option<i64>,
+    post_id:Option<i64>,
+    command_message_id:Option<i64>,
+    reaction_emoji:Option<&str>,
+) -> QueryResult<Result<VoteOutcome, VoteError>> {
+    let mut fail:Option<VoteError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        use schema::motions::dsl as mdsl;
+        use schema::motion_votes::dsl as mvdsl;
+        use schema::reaction_votes::dsl as rvdsl;
+        use schema::transfers::dsl as tdsl;
+
+        let res:Option<(i64, bool, bool)> = mdsl::motions
+        .filter(mdsl::rowid.eq(motion_id.unwrap_or(-1)).or(mdsl::bot_message_id.eq(post_id.unwrap_or(-1))))
+        .select((mdsl::rowid, mdsl::announcement_message_id.is_null(), mdsl::is_super))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+
+        let (motion_id, not_announced, is_super) = match res {
+            Some(r) => r,
+            None => {
+                fail = Some(VoteError::NotFound);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        };
+        if !not_announced {
+            fail = Some(VoteError::Expired);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        mvdsl::motion_votes //obtain a lock on all votes
+        .select(mvdsl::amount)
+        .filter(mvdsl::motion.eq(motion_id))
+        .for_update()
+        .execute(conn)?;
+
+        let voted_so_far:i64;
+        let direction:bool;
+        let maybe_vote_res:Option<(bool, i64)> = mvdsl::motion_votes
+        .filter(mvdsl::motion.eq(motion_id))
+        .filter(mvdsl::user.eq(user_id))
+        .select((mvdsl::direction, mvdsl::amount))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+
+        if let Some((dir, count)) = maybe_vote_res {
+            if let Some(requested_dir) = vote_direction {
+                if requested_dir != dir {
+                    fail = Some(VoteError::ChangedDirection);
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+            }
+            voted_so_far = count;
+            direction = dir;
+        } else {
+            let dir = match vote_direction {
+                Some(d) => d,
+                None => {
+                    fail = Some(VoteError::NoDirection);
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+            };
+            diesel::insert_into(mvdsl::motion_votes).values((
+                mvdsl::motion.eq(motion_id),
+                mvdsl::user.eq(user_id),
+                mvdsl::amount.eq(0),
+                mvdsl::direction.eq(dir),
+            )).on_conflict_do_nothing().execute(conn)?;
+
+            let vote_res:(bool, i64) = mvdsl::motion_votes
+            .filter(mvdsl::motion.eq(motion_id))
+            .filter(mvdsl::user.eq(user_id))
+            .select((mvdsl::direction, mvdsl::amount))
+            .for_update()
+            .get_result(conn)?;
+            voted_so_far = vote_res.1;
+            direction = vote_res.0;
+        }
+
+        let balance = latest_balance(conn, user_id, "pc", true)?;
+        let now = chrono::Utc::now();
+
+        // someone voting on the user's behalf can't spend more than is left of their allowance
+        let budget = match acting_user {
+            Some(acting_user) => match allowances::current(conn, user_id, acting_user, "pc", now)? {
+                Some(allowance) => balance.min(allowance.remaining()),
+                None => {
+                    fail = Some(VoteError::Allowance(AllowanceError::NotAllowed));
+                    return Err(diesel::result::Error::RollbackTransaction);
+                }
+            },
+            None => balance,
+        };
+
+        let (vote_count, cost) = match amount.resolve(voted_so_far, budget) {
+            Ok(c) => c,
+            Err(()) => {
+                fail = Some(VoteError::Overflow);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        };
+        if vote_count == 0 && amount != VoteAmount::Exact(0) {
+            fail = Some(VoteError::CantAffordAny);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        if cost > balance {
+            fail = Some(VoteError::InsufficientCapital);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        if let Some(acting_user) = acting_user {
+            if let Err(e) = allowances::draw(conn, user_id, acting_user, "pc", cost, now)? {
+                fail = Some(VoteError::Allowance(e));
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        }
+
+        let transfer_id:i64 = diesel::insert_into(tdsl::transfers).values((
+            tdsl::ty.eq("pc"),
+            tdsl::from_user.eq(user_id),
+            tdsl::quantity.eq(cost),
+            tdsl::from_balance.eq(balance - cost),
+            tdsl::happened_at.eq(now),
+            tdsl::message_id.eq(command_message_id),
+            tdsl::to_motion.eq(motion_id),
+            tdsl::to_votes.eq(vote_count),
+            tdsl::transfer_ty.eq("motion_vote"),
+            tdsl::acting_user.eq(acting_user),
+        )).returning(tdsl::rowid).get_result(conn)?;
+
+        if let (Some(emoji), true) = (reaction_emoji, vote_count > 0) {
+            diesel::insert_into(rvdsl::reaction_votes).values((
+                rvdsl::motion.eq(motion_id),
+                rvdsl::user.eq(user_id),
+                rvdsl::emoji.eq(emoji),
+                rvdsl::transfer.eq(transfer_id),
+                rvdsl::votes.eq(vote_count),
+                rvdsl::cost.eq(cost),
+                rvdsl::cast_at.eq(now),
+            )).execute(conn)?;
+        }
+
+        apply_vote_change(conn, motion_id, is_super, direction, vote_count, now)?;
+
+        diesel::update(
+            mvdsl::motion_votes.filter(mvdsl::motion.eq(motion_id)).filter(mvdsl::user.eq(user_id))
+        ).set(
+            mvdsl::amount.eq(voted_so_far + vote_count)
+        ).execute(conn)?;
+
+        diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(mdsl::needs_update.eq(true)).execute(conn)?;
+
+        Ok(VoteOutcome{ motion_id, direction, votes: vote_count, cost, voted_before: voted_so_far })
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RefundError {
+    Expired,
+    VotedSince,
+}
+
+impl Localize for RefundError {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            RefundError::Expired => "refund.expired",
+            RefundError::VotedSince => "refund.voted_since",
+        }).to_string()
+    }
+}
+
+impl fmt::Display for RefundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct RefundOutcome {
+    pub motion_id: i64,
+    pub votes: i64,
+    pub cost: i64,
+}
+
+impl Localize for RefundOutcome {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(
+            locale,
+            "refund.outcome",
+            votes = locale.number(self.votes),
+            motion = damm::add_to_str(self.motion_id.to_string()),
+            cost = locale.number(self.cost),
+        )
+    }
+}
+
+impl fmt::Display for RefundOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Takes back the votes cast by adding a reaction to a motion's post, if the reaction is removed
+/// within `window` and the user hasn't voted on the motion since. Returns None if there is no such
+/// vote to take back.
+pub fn refund_reaction_vote(
+    conn:&PgConnection,
+    user_id:i64,
+    post_id:i64,
+    emoji:&str,
+    window:chrono::Duration,
+) -> QueryResult<Option<Result<RefundOutcome, RefundError>>> {
+    let mut fail:Option<RefundError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        use schema::motions::dsl as mdsl;
+        use schema::motion_votes::dsl as mvdsl;
+        use schema::reaction_votes::dsl as rvdsl;
+        use schema::transfers::dsl as tdsl;
+
+        let res:Option<(i64, bool, bool)> = mdsl::motions
+        .filter(mdsl::bot_message_id.eq(post_id))
+        .select((mdsl::rowid, mdsl::announcement_message_id.is_null(), mdsl::is_super))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+        let (motion_id, not_announced, is_super) = match res {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+
+        let now = chrono::Utc::now();
+        let reaction_vote:Option<(i64, i64, i64, i64)> = rvdsl::reaction_votes
+        .filter(rvdsl::motion.eq(motion_id))
+        .filter(rvdsl::user.eq(user_id))
+        .filter(rvdsl::emoji.eq(emoji))
+        .filter(rvdsl::refund_transfer.is_null())
+        .filter(rvdsl::cast_at.gt(now - window))
+        .order(rvdsl::cast_at.desc())
+        .select((rvdsl::rowid, rvdsl::transfer, rvdsl::votes, rvdsl::cost))
+        .for_update()
+        .first(conn)
+        .optional()?;
+        let (reaction_vote_id, transfer_id, vote_count, cost) = match reaction_vote {
+            Some(r) => r,
+            None => return Ok(None),
+        };
+        if !not_announced {
+            fail = Some(RefundError::Expired);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        mvdsl::motion_votes //obtain a lock on all votes
+        .select(mvdsl::amount)
+        .filter(mvdsl::motion.eq(motion_id))
+        .for_update()
+        .execute(conn)?;
+
+        // Each vote costs more than the last, so only the most recent votes can be refunded at the price paid
+        let voted_since:bool = diesel::select(diesel::dsl::exists(
+            tdsl::transfers
+            .filter(tdsl::from_user.eq(user_id))
+            .filter(tdsl::to_motion.eq(motion_id))
+            .filter(tdsl::rowid.gt(transfer_id))
+        )).get_result(conn)?;
+        if voted_since {
+            fail = Some(RefundError::VotedSince);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+
+        let (direction, voted_so_far):(bool, i64) = mvdsl::motion_votes
+        .filter(mvdsl::motion.eq(motion_id))
+        .filter(mvdsl::user.eq(user_id))
+        .select((mvdsl::direction, mvdsl::amount))
+        .get_result(conn)?;
+
+        let balance = latest_balance(conn, user_id, "pc", true)?;
+
+        let refund_transfer_id:i64 = diesel::insert_into(tdsl::transfers).values((
+            tdsl::ty.eq("pc"),
+            tdsl::to_user.eq(user_id),
+            tdsl::quantity.eq(cost),
+            tdsl::to_balance.eq(balance + cost),
+            tdsl::happened_at.eq(now),
+            tdsl::to_motion.eq(motion_id),
+            tdsl::to_votes.eq(-vote_count),
+            tdsl::transfer_ty.eq("motion_vote_refund"),
+        )).returning(tdsl::rowid).get_result(conn)?;
+
+        diesel::update(rvdsl::reaction_votes.filter(rvdsl::rowid.eq(reaction_vote_id)))
+        .set(rvdsl::refund_transfer.eq(refund_transfer_id))
+        .execute(conn)?;
+
+        apply_vote_change(conn, motion_id, is_super, direction, -vote_count, now)?;
+
+        diesel::update(
+            mvdsl::motion_votes.filter(mvdsl::motion.eq(motion_id)).filter(mvdsl::user.eq(user_id))
+        ).set(
+            mvdsl::amount.eq(voted_so_far - vote_count)
+        ).execute(conn)?;
+
+        diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id))).set(mdsl::needs_update.eq(true)).execute(conn)?;
+        Ok(Some(RefundOutcome{ motion_id, votes: vote_count, cost }))
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Some(Err(e))),
+        (res, None) => res.map(|o| o.map(Ok)),
+    }
+}
+
+/// What it would take for `user_id` to flip the current result of a pending motion.
+pub fn flip_plan(
+    conn:&PgConnection,
+    motion_id:i64,
+    user_id:i64,
+) -> QueryResult<Result<vote_cost::FlipPlan, VoteError>> {
+    use schema::motions::dsl as mdsl;
+
+    let not_announced:Option<bool> = mdsl::motions
+        .filter(mdsl::rowid.eq(motion_id))
+        .select(mdsl::announcement_message_id.is_null())
+        .get_result(conn)
+        .optional()?;
+    match not_announced {
+        None => return Ok(Err(VoteError::NotFound)),
+        Some(false) => return Ok(Err(VoteError::Expired)),
+        Some(true) => (),
+    }
+
+    let status = motion_status(conn, motion_id)?;
+    let existing_vote = status.votes.iter()
+        .find(|v| v.user == user_id)
+        .map(|v| (v.direction, v.amount));
+    let balance = latest_balance(conn, user_id, "pc", false)?;
+
+    Ok(Ok(vote_cost::flip_plan(status.yes_votes, status.no_votes, status.is_super, existing_vote, balance)))
+}
+
+/// Makes sure the row generation keeps its state in exists, so that the first generation happens
+/// one `generate_every` after the economy first starts.
+pub fn init(conn:&PgConnection) -> QueryResult<()> {
+    use schema::single::dsl as sdsl;
+    use diesel::dsl::{exists, select};
+    if !(select(exists(sdsl::single.filter(sdsl::enforce_single_row))).get_result(conn)?:bool) {
+        diesel::insert_into(sdsl::single).values((
+            sdsl::enforce_single_row.eq(true),
+            sdsl::last_gen.eq(chrono::Utc::now()),
+        )).execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Pays every generator holder one capital per generator, if `generate_every` has passed since the
+/// last time. Returns whether it did.
+pub fn generate_if_due(conn:&PgConnection, generate_every:chrono::Duration) -> QueryResult<bool> {
+    use schema::transfers::dsl as tdsl;
+    use schema::single::dsl as sdsl;
+
+    /* not properly locking, but should only have one thread trying to access */
+    let now = chrono::Utc::now();
+    let last_gen:chrono::DateTime<chrono::Utc> = sdsl::single.select(sdsl::last_gen).get_result(conn)?;
+    if now - last_gen < generate_every {
+        return Ok(false);
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        diesel::sql_query("LOCK TABLE transfers IN EXCLUSIVE MODE;").execute(conn)?;
+
+        let users:Vec<Option<i64>> = tdsl::transfers.select(tdsl::to_user).distinct().filter(tdsl::ty.eq("gen")).filter(tdsl::to_user.is_not_null()).get_results(conn)?;
+        for userid_o in &users {
+            let userid = userid_o.unwrap();
+            let gen_balance = latest_balance(conn, userid, "gen", false)?;
+            let pc_balance = latest_balance(conn, userid, "pc", false)?;
+            diesel::insert_into(tdsl::transfers).values((
+                tdsl::ty.eq("pc"),
+                tdsl::quantity.eq(gen_balance),
+                tdsl::to_user.eq(userid),
+                tdsl::to_balance.eq(pc_balance + gen_balance),
+                tdsl::happened_at.eq(now),
+                tdsl::transfer_ty.eq("generated"),
+            )).execute(conn)?;
+        }
+
+        diesel::update(sdsl::single).set(sdsl::last_gen.eq(last_gen + generate_every)).execute(conn)?;
+
+        Ok(())
+    })?;
+    Ok(true)
+}
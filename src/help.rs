@@ -0,0 +1,497 @@
+//! How to use each chat command, for `$help` and web2's help page. Summaries and argument
+//! descriptions are catalog keys, so the help is translated along with everything else, while
+//! usage and examples are left as typed.
+
+/// How to use one chat command.
+#[derive(Debug)]
+pub struct CommandHelp {
+    /// The name the command is registered under, without the prefix.
+    pub name: &'static str,
+    /// Catalog key of what the command does, in a sentence or two.
+    pub summary: &'static str,
+    /// Ways of calling the command, without the prefix.
+    pub usage: &'static [&'static str],
+    /// Each placeholder used in `usage`, with the catalog key describing it.
+    pub args: &'static [(&'static str, &'static str)],
+    /// Full calls, without the prefix.
+    pub examples: &'static [&'static str],
+    pub access: Access,
+    /// Only registered in debug builds.
+    pub debug_only: bool,
+}
+
+/// Who can use a command.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Access {
+    Everyone,
+    /// Server admins, anyone with Manage Server
+    ManageServer,
+    /// Bot admins, see `crate::admin`
+    BotAdmin,
+}
+
+impl Access {
+    /// Catalog key of who can use the command, unless it's everyone.
+    pub fn restriction(self) -> Option<&'static str> {
+        match self {
+            Access::Everyone => None,
+            Access::ManageServer => Some("help.access.manage_server"),
+            Access::BotAdmin => Some("help.access.bot_admin"),
+        }
+    }
+}
+
+const USER:(&str, &str) = ("<user>", "help.arg.user");
+const AMOUNT:(&str, &str) = ("<amount>", "help.arg.amount");
+const TYPE:(&str, &str) = ("<type>", "help.arg.type");
+const MOTION_ID:(&str, &str) = ("<motion id>", "help.arg.motion_id");
+const MOTION_TEXT:(&str, &str) = ("<text>", "help.arg.motion_text");
+const REASON:(&str, &str) = ("<reason>", "help.arg.reason");
+const MEMO:(&str, &str) = ("<memo>", "help.arg.memo");
+const REQUEST_ID:(&str, &str) = ("<request id>", "help.arg.request_id");
+const SCHEDULE_ID:(&str, &str) = ("<schedule id>", "help.arg.schedule_id");
+
+/// Every command, in the order `$help` lists them.
+pub static COMMANDS:&[CommandHelp] = &[
+    CommandHelp{
+        name: "give",
+        summary: "help.give",
+        usage: &["give <user> <amount> <type>", "give <user> <amount> <type> <memo>"],
+        args: &[USER, AMOUNT, TYPE, MEMO],
+        examples: &["give shelvacu 100 pc", "give shelvacu#8719 100gen", "give 165858230327574528 1 gens", "give shelvacu half pc", "give shelvacu 500 pc for the pizza"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "force_give",
+        summary: "help.force_give",
+        usage: &["force_give <user> <amount> <type>"],
+        args: &[USER, AMOUNT, TYPE],
+        examples: &["force_give 165858230327574528 100 pc"],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "burn",
+        summary: "help.burn",
+        usage: &["burn <amount> <type>", "burn <amount> <type> <reason>"],
+        args: &[AMOUNT, TYPE, REASON],
+        examples: &["burn 100 pc", "burn half gen \"too many generators\""],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "request",
+        summary: "help.request",
+        usage: &["request <user> <amount> <type>", "request <user> <amount> <type> <memo>"],
+        args: &[USER, ("<amount>", "help.arg.whole_amount"), TYPE, MEMO],
+        examples: &["request shelvacu 500 pc", "request shelvacu 500 pc for the pizza"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "requests",
+        summary: "help.requests",
+        usage: &["requests"],
+        args: &[],
+        examples: &[],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "pay",
+        summary: "help.pay",
+        usage: &["pay <request id>"],
+        args: &[REQUEST_ID],
+        examples: &["pay 12"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "decline",
+        summary: "help.decline",
+        usage: &["decline <request id>"],
+        args: &[REQUEST_ID],
+        examples: &["decline 12"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "schedule",
+        summary: "help.schedule",
+        usage: &[
+            "schedule give <user> <amount> <type> every <interval>",
+            "schedule give <user> <amount> <type> <memo> every <interval> until <date>",
+            "schedule list",
+            "schedule cancel <schedule id>",
+        ],
+        args: &[USER, ("<amount>", "help.arg.whole_amount"), TYPE, MEMO, ("<interval>", "help.arg.interval"), ("<date>", "help.arg.date"), SCHEDULE_ID],
+        examples: &["schedule give shelvacu 100 pc every 1d", "schedule give shelvacu 500 pc rent every 1w until 2021-06-30", "schedule list", "schedule cancel 4"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "allow",
+        summary: "help.allow",
+        usage: &["allow <user> <amount> <type>", "allow <user> <amount> <type> <expires>", "allow"],
+        args: &[USER, ("<amount>", "help.arg.whole_amount"), TYPE, ("<expires>", "help.arg.expires")],
+        examples: &["allow shelvacu 500 pc", "allow shelvacu 500 pc 7d", "allow shelvacu 100 gen 2021-06-30", "allow"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "revoke",
+        summary: "help.revoke",
+        usage: &["revoke <user>", "revoke <user> <type>"],
+        args: &[USER, TYPE],
+        examples: &["revoke shelvacu", "revoke shelvacu pc"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "spend",
+        summary: "help.spend",
+        usage: &[
+            "spend <user> give <recipient> <amount> <type>",
+            "spend <user> give <recipient> <amount> <type> <memo>",
+            "spend <user> vote <motion id> <direction> <votes>",
+        ],
+        args: &[USER, ("<recipient>", "help.arg.recipient"), AMOUNT, TYPE, MEMO, MOTION_ID, ("<direction>", "help.arg.direction"), ("<votes>", "help.arg.votes")],
+        examples: &["spend shelvacu give someone 100 pc", "spend shelvacu give someone half pc for the pizza", "spend shelvacu vote 123 yes 10"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "balances",
+        summary: "help.balances",
+        usage: &["balances"],
+        args: &[],
+        examples: &[],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "top",
+        summary: "help.top",
+        usage: &["top", "top <board>"],
+        args: &[("<board>", "help.arg.board")],
+        examples: &["top gen", "top spent"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "profile",
+        summary: "help.profile",
+        usage: &["profile", "profile <user>"],
+        args: &[USER],
+        examples: &["profile shelvacu"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "privacy",
+        summary: "help.privacy",
+        usage: &["privacy", "privacy <hide|show>"],
+        args: &[],
+        examples: &["privacy hide"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "receipts",
+        summary: "help.receipts",
+        usage: &["receipts", "receipts <always|errors|never>"],
+        args: &[],
+        examples: &["receipts errors"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "language",
+        summary: "help.language",
+        usage: &["language", "language <code|default>"],
+        args: &[("<code|default>", "help.arg.language")],
+        examples: &["language es", "language default"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "server_language",
+        summary: "help.server_language",
+        usage: &["server_language", "server_language <code|default>"],
+        args: &[("<code|default>", "help.arg.language")],
+        examples: &["server_language es"],
+        access: Access::ManageServer,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "motion",
+        summary: "help.motion",
+        usage: &["motion <text>"],
+        args: &[MOTION_TEXT],
+        examples: &["motion Rename the bureaucracy channel to #paperwork"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "supermotion",
+        summary: "help.supermotion",
+        usage: &["supermotion <text>"],
+        args: &[MOTION_TEXT],
+        examples: &["supermotion Generators produce 2 pc a day"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "vote",
+        summary: "help.vote",
+        usage: &[
+            "vote <motion id> <direction> <votes>",
+            "vote <motion id> <direction>",
+            "vote <motion id> <votes>",
+            "vote <motion id>",
+        ],
+        args: &[MOTION_ID, ("<direction>", "help.arg.direction"), ("<votes>", "help.arg.votes")],
+        examples: &["vote 123 yes", "vote 123 1000 yeah", "vote 123 for budget 5000", "vote 123 max", "vote 123 against half"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "pledge",
+        summary: "help.pledge",
+        usage: &["pledge <motion id> <amount> <type> to <user> if pass", "pledge <motion id> <amount> <type> to <user> if fail"],
+        args: &[MOTION_ID, AMOUNT, TYPE, USER],
+        examples: &["pledge 123 1000 pc to shelvacu if pass", "pledge 123 half pc to shelvacu if fail"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "fund",
+        summary: "help.fund",
+        usage: &["fund <motion id> <direction> <amount>", "fund <motion id>"],
+        args: &[MOTION_ID, ("<direction>", "help.arg.direction"), ("<amount>", "help.arg.capital")],
+        examples: &["fund 123 for 500", "fund 123 against half", "fund 123"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "treasury",
+        summary: "help.treasury",
+        usage: &[
+            "treasury create <name> <threshold> <members>",
+            "treasury list",
+            "treasury info <name>",
+            "treasury deposit <name> <amount> <type> <memo>",
+            "treasury propose <name> give <user> <amount> <type> <memo>",
+            "treasury propose <name> vote <motion id> <direction> <votes>",
+            "treasury approve <proposal id>",
+            "treasury cancel <proposal id>",
+        ],
+        args: &[
+            ("<name>", "help.arg.treasury"),
+            ("<threshold>", "help.arg.threshold"),
+            ("<members>", "help.arg.members"),
+            AMOUNT,
+            TYPE,
+            MEMO,
+            USER,
+            MOTION_ID,
+            ("<direction>", "help.arg.direction"),
+            ("<votes>", "help.arg.exact_votes"),
+            ("<proposal id>", "help.arg.proposal_id"),
+        ],
+        examples: &[
+            "treasury create builders 2 shelvacu alice bob",
+            "treasury deposit builders 1000 pc",
+            "treasury propose builders give alice 500 pc materials",
+            "treasury propose builders vote 123 yes 10",
+            "treasury approve 7",
+            "treasury info builders",
+        ],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "flip",
+        summary: "help.flip",
+        usage: &["flip <motion id>"],
+        args: &[MOTION_ID],
+        examples: &["flip 123"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "check_emoji",
+        summary: "help.check_emoji",
+        usage: &["check_emoji"],
+        args: &[],
+        examples: &[],
+        access: Access::ManageServer,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "vote_word",
+        summary: "help.vote_word",
+        usage: &["vote_word", "vote_word add <yes|no|zero|ignore> <word>", "vote_word remove <word>"],
+        args: &[("<word>", "help.arg.word")],
+        examples: &["vote_word add yes aye", "vote_word add ignore \"I think\"", "vote_word remove aye"],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "alias",
+        summary: "help.alias",
+        usage: &["alias", "alias add <alias> <command>", "alias remove <alias>"],
+        args: &[("<alias>", "help.arg.alias"), ("<command>", "help.arg.command")],
+        examples: &["alias add lb top", "alias remove lb"],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "help",
+        summary: "help.help",
+        usage: &["help", "help <command>"],
+        args: &[("<command>", "help.arg.command")],
+        examples: &["help vote"],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "version_info",
+        summary: "help.version_info",
+        usage: &["version_info"],
+        args: &[],
+        examples: &[],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "ping",
+        summary: "help.ping",
+        usage: &["ping"],
+        args: &[],
+        examples: &[],
+        access: Access::Everyone,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "hack_message_update",
+        summary: "help.hack_message_update",
+        usage: &["hack_message_update <message id>"],
+        args: &[("<message id>", "help.arg.message_id")],
+        examples: &[],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "fabricate",
+        summary: "help.fabricate",
+        usage: &["fabricate <type> <amount>", "fabricate <type> <amount> <user>", "fabricate <type> <amount> <user> <reason>"],
+        args: &[TYPE, ("<amount>", "help.arg.whole_amount"), USER, REASON],
+        examples: &["fabricate pc 1000", "fabricate gen 1 shelvacu \"won the raffle\""],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "force_burn",
+        summary: "help.force_burn",
+        usage: &["force_burn <user> <amount> <type>", "force_burn <user> <amount> <type> <reason>"],
+        args: &[USER, ("<amount>", "help.arg.their_amount"), TYPE, REASON],
+        examples: &["force_burn shelvacu 100 pc", "force_burn shelvacu all gen \"duplicated by a bug\""],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "adjust",
+        summary: "help.adjust",
+        usage: &["adjust <user> <balance> <type>", "adjust <user> <balance> <type> <reason>"],
+        args: &[USER, ("<balance>", "help.arg.balance"), TYPE, REASON],
+        examples: &["adjust shelvacu 500 pc \"restoring lost capital\""],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "force_transfer",
+        summary: "help.force_transfer",
+        usage: &["force_transfer <from> <to> <amount> <type>", "force_transfer <from> <to> <amount> <type> <reason>"],
+        args: &[("<from>", "help.arg.user"), ("<to>", "help.arg.user"), ("<amount>", "help.arg.their_amount"), TYPE, REASON],
+        examples: &["force_transfer shelvacu 165858230327574528 half pc \"account recovery\""],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "admins",
+        summary: "help.admins",
+        usage: &["admins", "admins add user <user>", "admins add role <role>", "admins remove user <user>", "admins remove role <role>"],
+        args: &[USER, ("<role>", "help.arg.role")],
+        examples: &["admins add user shelvacu", "admins add role \"Bot Wranglers\"", "admins remove user shelvacu"],
+        access: Access::BotAdmin,
+        debug_only: false,
+    },
+    CommandHelp{
+        name: "transaction_history_csv",
+        summary: "help.transaction_history_csv",
+        usage: &["transaction_history_csv"],
+        args: &[],
+        examples: &[],
+        access: Access::Everyone,
+        debug_only: true,
+    },
+];
+
+/// The commands this build registers.
+pub fn available() -> impl Iterator<Item = &'static CommandHelp> {
+    COMMANDS.iter().filter(|c| cfg!(feature = "debug") || !c.debug_only)
+}
+
+pub fn find(name:&str) -> Option<&'static CommandHelp> {
+    available().find(|c| c.name == name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bot::{GENERAL_GROUP, DEBUG_GROUP};
+
+    #[test]
+    fn every_command_has_help() {
+        for (group, debug_only) in &[(&GENERAL_GROUP, false), (&DEBUG_GROUP, true)] {
+            for command in group.options.commands {
+                let name = command.options.names[0];
+                let help = COMMANDS.iter().find(|c| c.name == name).unwrap_or_else(|| panic!("${} has no help", name));
+                assert_eq!(help.debug_only, *debug_only, "${}", name);
+            }
+        }
+        for help in COMMANDS {
+            let registered = GENERAL_GROUP.options.commands.iter()
+                .chain(DEBUG_GROUP.options.commands)
+                .any(|c| c.options.names[0] == help.name);
+            assert!(registered, "${} is documented but isn't a command", help.name);
+        }
+    }
+
+    #[test]
+    fn restricted_commands_are_checked() {
+        for command in GENERAL_GROUP.options.commands.iter().chain(DEBUG_GROUP.options.commands) {
+            let name = command.options.names[0];
+            let help = match find(name) {
+                Some(h) => h,
+                None => continue,
+            };
+            let checked = command.options.checks.iter().any(|c| c.name == "Admin");
+            assert_eq!(checked, help.access == Access::BotAdmin, "${}", name);
+        }
+    }
+
+    #[test]
+    fn args_appear_in_usage() {
+        for help in COMMANDS {
+            for (arg, _) in help.args {
+                assert!(help.usage.iter().any(|u| u.contains(arg)), "${} documents {} but never uses it", help.name, arg);
+            }
+            for example in help.examples {
+                assert!(example.starts_with(help.name), "${} has example {:?}", help.name, example);
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! The message catalog: everything the bot and web2 say, in every language they speak. Catalogs
+//! live in `locales/<code>.json` as flat maps from keys like `vote.expired` to messages, in which
+//! `tr!` fills in each `{name}`. Messages missing from a catalog fall back to English.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// `tr!(locale, "key")` is the message for `key`, and `tr!(locale, "key", name = value, ..)` fills
+/// in each `{name}` as well.
+macro_rules! tr {
+    ($locale:expr, $key:expr) => {
+        $crate::i18n::Locale::text($locale, $key)
+    };
+    ($locale:expr, $key:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::Locale::text($locale, $key),
+            &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+],
+        )
+    };
+}
+
+/// A language the bot speaks.
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Hash)]
+pub enum Locale {
+    En,
+    Es,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale::En
+    }
+}
+
+lazy_static! {
+    static ref CATALOGS:HashMap<Locale, HashMap<String, String>> = Locale::ALL.iter().map(|&locale| {
+        let catalog = serde_json::from_str(locale.catalog_source())
+            .unwrap_or_else(|e| panic!("locales/{}.json is not a valid catalog: {}", locale.code(), e));
+        (locale, catalog)
+    }).collect();
+}
+
+impl Locale {
+    pub const ALL:[Locale; 2] = [Locale::En, Locale::Es];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::Es => "es",
+        }
+    }
+
+    /// The language's name for itself, eg. "Español".
+    pub fn name(self) -> &'static str {
+        match self {
+            Locale::En => "English",
+            Locale::Es => "Español",
+        }
+    }
+
+    /// Understands bare codes like `es` as well as tags like `es-MX` or `es_ES`.
+    pub fn from_code(code:&str) -> Option<Self> {
+        let language = code.trim().split(|c| c == '-' || c == '_').next().unwrap_or("").to_lowercase();
+        Locale::ALL.iter().copied().find(|l| l.code() == language)
+    }
+
+    /// The most preferred language we speak from an HTTP `Accept-Language` header.
+    pub fn from_accept_language(header:&str) -> Option<Self> {
+        let mut choices:Vec<(f32, Locale)> = header.split(',').filter_map(|entry| {
+            let mut parts = entry.split(';');
+            let locale = Locale::from_code(parts.next()?)?;
+            let quality = parts
+                .filter_map(|p| p.trim().strip_prefix("q="))
+                .next()
+                .map_or(Some(1.0), |q| q.parse().ok())?;
+            Some((quality, locale))
+        }).filter(|(quality, _)| *quality > 0.0).collect();
+        // stable, so equally preferred languages stay in the order they were given
+        choices.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+        choices.first().map(|(_, locale)| *locale)
+    }
+
+    fn catalog_source(self) -> &'static str {
+        match self {
+            Locale::En => include_str!("../locales/en.json"),
+            Locale::Es => include_str!("../locales/es.json"),
+        }
+    }
+
+    /// The message for `key`, in English if it hasn't been translated yet.
+    pub fn text(self, key:&'static str) -> &'static str {
+        CATALOGS[&self].get(key)
+            .or_else(|| CATALOGS[&Locale::En].get(key))
+            .map(String::as_str)
+            .unwrap_or_else(|| {
+                warn!("No message for {:?}", key);
+                key
+            })
+    }
+
+    /// eg. "1st" in English or "1.º" in Spanish.
+    pub fn ordinal(self, n:i64) -> String {
+        match self {
+            Locale::En => {
+                let suffix = match ((n % 100).abs(), (n % 10).abs()) {
+                    (11..=13, _) => "th",
+                    (_, 1) => "st",
+                    (_, 2) => "nd",
+                    (_, 3) => "rd",
+                    _ => "th",
+                };
+                format!("{}{}", n, suffix)
+            },
+            Locale::Es => format!("{}.º", n),
+        }
+    }
+
+    /// Groups the digits of `n` in thousands, eg. "12,345" in English or "12.345" in Spanish.
+    pub fn number(self, n:i64) -> String {
+        // Spanish leaves four digit numbers ungrouped
+        let (separator, shortest_grouped) = match self {
+            Locale::En => (',', 4),
+            Locale::Es => ('.', 5),
+        };
+        let digits = (n as i128).abs().to_string();
+        let mut grouped = String::with_capacity(digits.len() * 4 / 3 + 1);
+        if n < 0 {
+            grouped.push('-');
+        }
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 && digits.len() >= shortest_grouped {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+
+    /// `value` to `places` decimal places, eg. "0.25" in English or "0,25" in Spanish.
+    pub fn decimal(self, value:f64, places:usize) -> String {
+        let s = format!("{:.*}", places, value);
+        match self {
+            Locale::En => s,
+            Locale::Es => s.replace('.', ","),
+        }
+    }
+
+    /// `fraction` as a percentage to `places` decimal places, eg. "12.5%" or "12,5 %".
+    pub fn percent(self, fraction:f64, places:usize) -> String {
+        tr!(self, "format.percent", value = self.decimal(fraction * 100.0, places))
+    }
+
+    pub fn date(self, at:DateTime<Utc>) -> String {
+        at.format(self.text("format.date")).to_string()
+    }
+
+    pub fn datetime(self, at:DateTime<Utc>) -> String {
+        at.format(self.text("format.datetime")).to_string()
+    }
+}
+
+/// Something shown to users, such as an error or what a command did.
+pub trait Localize {
+    fn localize(&self, locale:Locale) -> String;
+}
+
+/// Replaces each `{name}` in `template` with the matching argument. Braces around anything else
+/// are left alone.
+pub fn fill(template:&str, args:&[(&str, &dyn fmt::Display)]) -> String {
+    let mut filled = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        filled.push_str(&rest[..start]);
+        let after = &rest[start+1..];
+        let placeholder = after.find('}').and_then(|end| {
+            args.iter().find(|(name, _)| *name == &after[..end]).map(|(_, value)| (value, end))
+        });
+        match placeholder {
+            Some((value, end)) => {
+                filled.push_str(&value.to_string());
+                rest = &after[end+1..];
+            },
+            None => {
+                filled.push('{');
+                rest = after;
+            },
+        }
+    }
+    filled.push_str(rest);
+    filled
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Every `{name}` in a message.
+    fn placeholders(message:&str) -> Vec<&str> {
+        let mut names:Vec<&str> = message.split('{').skip(1).filter_map(|s| s.split('}').next()).collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn catalogs_match_english() {
+        let english = &CATALOGS[&Locale::En];
+        for locale in &Locale::ALL {
+            for (key, message) in &CATALOGS[locale] {
+                let original = english.get(key).unwrap_or_else(|| panic!("{} has {:?}, which English doesn't", locale.code(), key));
+                assert_eq!(placeholders(message), placeholders(original), "{} {:?}", locale.code(), key);
+            }
+        }
+    }
+
+    #[test]
+    fn falls_back_to_english() {
+        assert_eq!(Locale::Es.text("test.missing"), "test.missing");
+        assert_eq!(Locale::Es.text("vote.expired"), "La moción ha vencido.");
+        assert_eq!(Locale::En.text("vote.expired"), "Motion has expired.");
+    }
+
+    #[test]
+    fn fills_placeholders() {
+        assert_eq!(fill("{a} and {b}", &[("a", &1), ("b", &"two")]), "1 and two");
+        assert_eq!(fill("{a}{a}", &[("a", &1)]), "11");
+        assert_eq!(fill("{c} {", &[("a", &1)]), "{c} {");
+        assert_eq!(tr!(Locale::En, "give.done", amount = 5, items = "pc", user = "x"), "Successfully transferred 5 pc to x.");
+    }
+
+    #[test]
+    fn ordinals() {
+        let cases = [(1, "1st"), (2, "2nd"), (3, "3rd"), (4, "4th"), (11, "11th"), (12, "12th"), (13, "13th"), (21, "21st"), (102, "102nd"), (111, "111th")];
+        for &(n, expected) in &cases {
+            assert_eq!(Locale::En.ordinal(n), expected);
+        }
+        assert_eq!(Locale::Es.ordinal(3), "3.º");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(Locale::En.number(0), "0");
+        assert_eq!(Locale::En.number(999), "999");
+        assert_eq!(Locale::En.number(1000), "1,000");
+        assert_eq!(Locale::En.number(-1234567), "-1,234,567");
+        assert_eq!(Locale::En.number(i64::MIN), "-9,223,372,036,854,775,808");
+        assert_eq!(Locale::Es.number(1000), "1000");
+        assert_eq!(Locale::Es.number(12345), "12.345");
+        assert_eq!(Locale::Es.decimal(0.25, 2), "0,25");
+        assert_eq!(Locale::En.percent(0.125, 1), "12.5%");
+    }
+
+    #[test]
+    fn parses_codes() {
+        assert_eq!(Locale::from_code("es"), Some(Locale::Es));
+        assert_eq!(Locale::from_code("ES-mx"), Some(Locale::Es));
+        assert_eq!(Locale::from_code("en_GB"), Some(Locale::En));
+        assert_eq!(Locale::from_code("fr"), None);
+        assert_eq!(Locale::from_accept_language("fr-CH, fr;q=0.9, es;q=0.8, en;q=0.7"), Some(Locale::Es));
+        assert_eq!(Locale::from_accept_language("en;q=0.5, es"), Some(Locale::Es));
+        assert_eq!(Locale::from_accept_language("es;q=0, en;q=0.1"), Some(Locale::En));
+        assert_eq!(Locale::from_accept_language("*"), None);
+    }
+}
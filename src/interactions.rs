@@ -0,0 +1,404 @@
+//! Discord application ("slash") commands, delivered by Discord to an HTTP endpoint on web2 instead
+//! of through the bot's gateway connection. Every request is signed with the application's Ed25519
+//! key; see https://discord.com/developers/docs/interactions/slash-commands#security-and-authorization
+use std::convert::TryFrom;
+use std::env;
+use std::io::Read;
+use std::thread;
+
+use diesel::prelude::*;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use rocket::data::{self, Data, FromDataSimple};
+use rocket::http::{ContentType, Status};
+use rocket::request::{Request, State};
+use rocket::response::content::Content;
+use rocket::Outcome;
+use serde_json::json;
+use serenity::model::id::UserId;
+
+use crate::{bot, damm, economy, parser, rocket_diesel, schema, settings, vote_cost};
+use crate::i18n::{Locale, Localize};
+use crate::vote_cost::VoteAmount;
+
+const BODY_LIMIT:u64 = 64 * 1024;
+const EPHEMERAL:u64 = 1 << 6;
+
+lazy_static! {
+    /// For calls to Discord's API on the bot's behalf, without a gateway connection.
+    pub static ref DISCORD_HTTP:serenity::http::Http = serenity::http::Http::new_with_token(
+        &env::var("DISCORD_TOKEN").expect("DISCORD_TOKEN expected")
+    );
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct InteractionUser {
+    id: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct InteractionMember {
+    user: InteractionUser,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CommandOption {
+    name: String,
+    value: serde_json::Value,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct CommandData {
+    name: String,
+    #[serde(default)]
+    options: Vec<CommandOption>,
+}
+
+impl CommandData {
+    fn option(&self, name:&str) -> Option<&serde_json::Value> {
+        self.options.iter().find(|o| o.name == name).map(|o| &o.value)
+    }
+
+    fn str_option(&self, name:&str) -> Option<&str> {
+        self.option(name).and_then(|v| v.as_str())
+    }
+
+    fn int_option(&self, name:&str) -> Option<i64> {
+        self.option(name).and_then(|v| v.as_i64())
+    }
+
+    fn bool_option(&self, name:&str) -> Option<bool> {
+        self.option(name).and_then(|v| v.as_bool())
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Interaction {
+    id: String,
+    application_id: String,
+    #[serde(rename = "type")]
+    ty: u8,
+    token: String,
+    data: Option<CommandData>,
+    /// Set when invoked in a guild
+    guild_id: Option<String>,
+    /// Set when invoked in a guild
+    member: Option<InteractionMember>,
+    /// Set when invoked in a DM
+    user: Option<InteractionUser>,
+}
+
+impl Interaction {
+    fn user_id(&self) -> Option<UserId> {
+        self.member.as_ref().map(|m| &m.user).or_else(|| self.user.as_ref())
+            .and_then(|u| u.id.parse().ok())
+            .map(UserId)
+    }
+
+    fn guild_id(&self) -> Option<i64> {
+        self.guild_id.as_ref().and_then(|id| id.parse().ok())
+    }
+}
+
+/// Reads `DISCORD_PUBLIC_KEY`, without which interactions can't be verified and so aren't served.
+/// Panics if it is set but invalid, so that misconfiguration shows up at startup.
+pub fn public_key_from_env() -> Option<PublicKey> {
+    match env::var("DISCORD_PUBLIC_KEY") {
+        Ok(key) => Some(PublicKey::from_bytes(
+            &hex::decode(key).expect("DISCORD_PUBLIC_KEY must be hex")
+        ).expect("DISCORD_PUBLIC_KEY must be an Ed25519 public key")),
+        Err(env::VarError::NotPresent) => None,
+        Err(e) => panic!("Invalid DISCORD_PUBLIC_KEY: {}", e),
+    }
+}
+
+fn verify(public_key:&PublicKey, signature_hex:&str, timestamp:&str, body:&str) -> bool {
+    let signature = match hex::decode(signature_hex).ok().and_then(|bytes| Signature::try_from(bytes.as_slice()).ok()) {
+        Some(s) => s,
+        None => return false,
+    };
+    let mut message = Vec::with_capacity(timestamp.len() + body.len());
+    message.extend_from_slice(timestamp.as_bytes());
+    message.extend_from_slice(body.as_bytes());
+    public_key.verify(&message, &signature).is_ok()
+}
+
+impl FromDataSimple for Interaction {
+    type Error = String;
+
+    fn from_data(request: &Request, data: Data) -> data::Outcome<Self, String> {
+        let public_key = match request.guard::<State<PublicKey>>() {
+            Outcome::Success(k) => k,
+            _ => return Outcome::Failure((Status::ServiceUnavailable, String::from("DISCORD_PUBLIC_KEY is not set"))),
+        };
+        let headers = request.headers();
+        let (signature, timestamp) = match (headers.get_one("X-Signature-Ed25519"), headers.get_one("X-Signature-Timestamp")) {
+            (Some(s), Some(t)) => (s, t),
+            _ => return Outcome::Failure((Status::Unauthorized, String::from("missing signature"))),
+        };
+        let mut body = String::new();
+        if let Err(e) = data.open().take(BODY_LIMIT).read_to_string(&mut body) {
+            return Outcome::Failure((Status::BadRequest, format!("{:?}", e)));
+        }
+        if !verify(&public_key, signature, timestamp, &body) {
+            return Outcome::Failure((Status::Unauthorized, String::from("bad signature")));
+        }
+        match serde_json::from_str(&body) {
+            Ok(interaction) => Outcome::Success(interaction),
+            Err(e) => Outcome::Failure((Status::BadRequest, format!("{:?}", e))),
+        }
+    }
+}
+
+fn reply(content:&str) -> Content<String> {
+    Content(ContentType::JSON, json!({
+        "type": 4, // CHANNEL_MESSAGE_WITH_SOURCE
+        "data": { "content": content, "flags": EPHEMERAL },
+    }).to_string())
+}
+
+type HandlerResult = Result<String, Box<dyn std::error::Error>>;
+
+fn give(conn:&PgConnection, locale:Locale, user:UserId, interaction_id:i64, data:&CommandData) -> HandlerResult {
+    let to_user = match data.str_option("user").and_then(|s| s.parse().ok()) {
+        Some(id) => UserId(id),
+        None => return Ok(tr!(locale, "give.user_not_found").to_string()),
+    };
+    let amount = match parser::parse_amount(data.str_option("amount").unwrap_or("")) {
+        Ok(a) => a,
+        Err(e) => return Ok(e.localize(locale)),
+    };
+    let ty_name = data.str_option("item").unwrap_or("pc");
+    let ty = match economy::find_item_type(conn, ty_name)? {
+        Ok(ty) => ty,
+        Err(e) => return Ok(e.localize(locale)),
+    };
+    let memo = match parser::parse_memo(data.str_option("memo").unwrap_or("")) {
+        Ok(m) => m,
+        Err(e) => return Ok(e.localize(locale)),
+    };
+    let amount = amount.resolve(economy::balance_of(conn, user.0 as i64, &ty)?).max(0) as u64;
+    Ok(match economy::give(conn, user.0 as i64, to_user.0 as i64, &ty, amount, Some(interaction_id), memo.as_deref())? {
+        Err(e) => e.localize(locale),
+        Ok(()) => match memo {
+            None => tr!(
+                locale,
+                "give.done",
+                amount = locale.number(amount as i64),
+                items = ty.long_name_ambiguous,
+                user = format!("<@{}>", to_user.0),
+            ),
+            Some(memo) => tr!(
+                locale,
+                "give.done_memo",
+                amount = locale.number(amount as i64),
+                items = ty.long_name_ambiguous,
+                user = format!("<@{}>", to_user.0),
+                memo = memo,
+            ),
+        },
+    })
+}
+
+fn vote(conn:&PgConnection, locale:Locale, user:UserId, data:&CommandData) -> HandlerResult {
+    let motion_id = match data.str_option("motion").and_then(|s| parser::parse_motion_id(s).ok()) {
+        Some(id) => id,
+        None => return Ok(tr!(locale, "vote.not_found").to_string()),
+    };
+    let direction = match data.str_option("direction") {
+        Some("for") => Some(true),
+        Some("against") => Some(false),
+        _ => None,
+    };
+    let amount = if data.bool_option("max") == Some(true) {
+        VoteAmount::Max
+    } else if let Some(budget) = data.int_option("budget") {
+        VoteAmount::Budget(budget.max(0))
+    } else {
+        VoteAmount::Exact(data.int_option("votes").unwrap_or(1).max(0))
+    };
+    Ok(match economy::vote(conn, direction, amount, user.0 as i64, Some(motion_id), None, None, None)? {
+        Ok(outcome) => outcome.localize(locale),
+        Err(e) => e.localize(locale),
+    })
+}
+
+fn balances(conn:&PgConnection, locale:Locale, user:UserId) -> HandlerResult {
+    let lines:Vec<String> = economy::balances_of(conn, user.0 as i64)?
+        .iter()
+        .map(|(ty, amount)| format!("{}: {}", ty.long_name_plural, locale.number(*amount)))
+        .collect();
+    Ok(format!("{}\n{}", tr!(locale, "balances.title"), lines.join("\n")))
+}
+
+fn cost(conn:&PgConnection, locale:Locale, user:UserId, data:&CommandData) -> HandlerResult {
+    use schema::motion_votes::dsl as mvdsl;
+    let votes = data.int_option("votes").unwrap_or(1).max(0);
+    let (voted_so_far, motion_desc) = match data.str_option("motion") {
+        Some(s) => {
+            let motion_id = match parser::parse_motion_id(s) {
+                Ok(id) => id,
+                Err(_) => return Ok(tr!(locale, "vote.not_found").to_string()),
+            };
+            let voted:Option<i64> = mvdsl::motion_votes
+                .select(mvdsl::amount)
+                .filter(mvdsl::motion.eq(motion_id))
+                .filter(mvdsl::user.eq(user.0 as i64))
+                .get_result(conn)
+                .optional()?;
+            (voted.unwrap_or(0), Some(damm::add_to_str(motion_id.to_string())))
+        },
+        None => (0, None),
+    };
+    let votes_text = locale.number(votes);
+    Ok(match (vote_cost::votes_cost(voted_so_far, votes), motion_desc) {
+        (Ok(cost), Some(motion)) => tr!(locale, "cost.on_motion", votes = votes_text, motion = motion, cost = locale.number(cost)),
+        (Ok(cost), None) => tr!(locale, "cost.on_new_motion", votes = votes_text, cost = locale.number(cost)),
+        (Err(()), Some(motion)) => tr!(locale, "cost.impossible_on_motion", votes = votes_text, motion = motion),
+        (Err(()), None) => tr!(locale, "cost.impossible_on_new_motion", votes = votes_text),
+    })
+}
+
+/// Posts the motion in the background and edits the deferred response once it's done, since
+/// posting and reacting takes longer than Discord waits for a response. The motion is posted in the
+/// language of the guild it was called from.
+fn motion(conn:rocket_diesel::DbConn, locale:Locale, user:UserId, interaction:&Interaction, data:&CommandData) -> Content<String> {
+    let motion_text = data.str_option("text").unwrap_or("").to_string();
+    let is_super = data.bool_option("super").unwrap_or(false);
+    let interaction_id:i64 = interaction.id.parse().unwrap_or(0);
+    let followup_url = format!(
+        "https://discord.com/api/v8/webhooks/{}/{}/messages/@original",
+        interaction.application_id,
+        interaction.token,
+    );
+    let guild_locale = match interaction.guild_id() {
+        Some(guild_id) => settings::guild_locale(&*conn, guild_id).unwrap_or_default().unwrap_or_default(),
+        None => Locale::default(),
+    };
+    thread::spawn(move || {
+        let frontend = bot::DiscordFrontend(&*DISCORD_HTTP, guild_locale);
+        let content = match economy::create_motion(&frontend, &*conn, user.0 as i64, interaction_id, &motion_text, is_super) {
+            Ok(Ok(motion_id)) => tr!(locale, "motion.called_reply", motion = damm::add_to_str(motion_id.to_string())),
+            Ok(Err(e)) => e.localize(locale),
+            Err(e) => {
+                warn!("Could not create motion from interaction: {:?}", e);
+                tr!(locale, "error.something_went_wrong").to_string()
+            },
+        };
+        let res = reqwest::blocking::Client::new()
+            .patch(&followup_url)
+            .json(&json!({ "content": content }))
+            .send();
+        if let Err(e) = res {
+            warn!("Could not edit interaction response: {:?}", e);
+        }
+    });
+    Content(ContentType::JSON, json!({
+        "type": 5, // DEFERRED_CHANNEL_MESSAGE_WITH_SOURCE
+        "data": { "flags": EPHEMERAL },
+    }).to_string())
+}
+
+#[post("/interactions", data = "<interaction>")]
+pub fn interactions(interaction: Interaction, conn: rocket_diesel::DbConn) -> Content<String> {
+    if interaction.ty == 1 { // PING
+        return Content(ContentType::JSON, json!({ "type": 1 }).to_string());
+    }
+    let (user, data) = match (interaction.user_id(), interaction.data.as_ref()) {
+        (Some(u), Some(d)) => (u, d),
+        _ => return reply(tr!(Locale::default(), "interaction.unsupported")),
+    };
+    let locale = match settings::locale_for(&*conn, user.0 as i64, interaction.guild_id()) {
+        Ok(l) => l,
+        Err(e) => {
+            warn!("Could not look up locale for {}: {:?}", user, e);
+            Locale::default()
+        },
+    };
+    let interaction_id:i64 = interaction.id.parse().unwrap_or(0);
+    let res = match data.name.as_str() {
+        "give" => give(&*conn, locale, user, interaction_id, data),
+        "vote" => vote(&*conn, locale, user, data),
+        "balances" => balances(&*conn, locale, user),
+        "cost" => cost(&*conn, locale, user, data),
+        "motion" => return motion(conn, locale, user, &interaction, data),
+        _ => Ok(tr!(locale, "interaction.unknown_command", command = data.name)),
+    };
+    match res {
+        Ok(content) => reply(&content),
+        Err(e) => {
+            warn!("Error handling /{}: {:?}", data.name, e);
+            reply(tr!(locale, "error.something_went_wrong"))
+        },
+    }
+}
+
+/// Definitions of every command handled by `interactions`, in the form Discord's API expects.
+fn command_definitions() -> serde_json::Value {
+    // option types: 3 string, 4 integer, 5 boolean, 6 user
+    json!([
+        {
+            "name": "give",
+            "description": "Give an item to another user",
+            "options": [
+                { "type": 6, "name": "user", "description": "Who to give to", "required": true },
+                { "type": 3, "name": "amount", "description": "How many to give, eg. 1000, 1k, half or all", "required": true },
+                { "type": 3, "name": "item", "description": "What to give, capital (pc) by default" },
+                { "type": 3, "name": "memo", "description": "A note both of you will see with the transfer" },
+            ],
+        },
+        {
+            "name": "vote",
+            "description": "Vote on a motion",
+            "options": [
+                { "type": 3, "name": "motion", "description": "Motion id", "required": true },
+                {
+                    "type": 3, "name": "direction", "description": "Required on your first vote",
+                    "choices": [{ "name": "for", "value": "for" }, { "name": "against", "value": "against" }],
+                },
+                { "type": 4, "name": "votes", "description": "How many votes to cast, 1 by default" },
+                { "type": 4, "name": "budget", "description": "Cast as many votes as this much capital buys" },
+                { "type": 5, "name": "max", "description": "Cast as many votes as you can afford" },
+            ],
+        },
+        {
+            "name": "motion",
+            "description": "Call a motion to be voted on",
+            "options": [
+                { "type": 3, "name": "text", "description": "What the motion is", "required": true },
+                { "type": 5, "name": "super", "description": "Require a supermajority to pass" },
+            ],
+        },
+        {
+            "name": "balances",
+            "description": "Show what you have",
+        },
+        {
+            "name": "cost",
+            "description": "Show what votes would cost you",
+            "options": [
+                { "type": 4, "name": "votes", "description": "How many votes", "required": true },
+                { "type": 3, "name": "motion", "description": "Motion id, to count votes you've already cast" },
+            ],
+        },
+    ])
+}
+
+/// Replaces the application's global commands with `command_definitions`, if
+/// `DISCORD_APPLICATION_ID` is set.
+pub fn register_commands() {
+    let application_id = match env::var("DISCORD_APPLICATION_ID") {
+        Ok(id) => id,
+        Err(_) => return,
+    };
+    let token = env::var("DISCORD_TOKEN").expect("DISCORD_TOKEN expected");
+    let res = reqwest::blocking::Client::new()
+        .put(&format!("https://discord.com/api/v8/applications/{}/commands", application_id))
+        .header("Authorization", format!("Bot {}", token))
+        .json(&command_definitions())
+        .send();
+    match res {
+        Ok(r) if r.status().is_success() => info!("Registered application commands"),
+        Ok(r) => warn!("Could not register application commands: {}", r.status()),
+        Err(e) => warn!("Could not register application commands: {:?}", e),
+    }
+}
@@ -4,10 +4,10 @@
 #[macro_use] extern crate rocket;
 #[macro_use] extern crate diesel;
 #[macro_use] extern crate lazy_static;
-#[macro_use] extern crate maplit;
 #[macro_use] extern crate serde;
 
 #[macro_use] mod statics;
+#[macro_use] mod i18n;
 
 mod models;
 mod schema;
@@ -17,7 +17,27 @@ mod rocket_diesel;
 mod bot;
 mod web2;
 mod is_win;
+mod vote_cost;
 mod static_responders;
+mod settings;
+mod stats;
+mod charts;
+mod vote_emoji;
+mod interactions;
+mod economy;
+mod parser;
+mod repl;
+mod vocabulary;
+mod help;
+mod admin;
+mod payment_requests;
+mod scheduled_transfers;
+mod pledges;
+mod treasuries;
+mod vote_pools;
+mod allowances;
+#[cfg(test)]
+mod test_db;
 
 use std::env;
 
@@ -29,8 +49,10 @@ fn main() {
         bot::bot_main();
     } else if env::var_os("RUN_WEB2") == Some("1".into()) {
         web2::main();
+    } else if env::var_os("RUN_REPL") == Some("1".into()) {
+        repl::main();
     } else {
-        eprintln!("Must specify RUN_BOT=1 or RUN_WEB2=1");
+        eprintln!("Must specify RUN_BOT=1, RUN_WEB2=1 or RUN_REPL=1");
         std::process::exit(100);
     }
 }
\ No newline at end of file
@@ -58,9 +58,31 @@ impl<'a> MotionWithCount<'a>{
     }
 }
 
+#[derive(Copy,Clone,Debug,Serialize,Queryable)]
+pub struct MotionResultChange {
+    pub changed_at:DateTime<Utc>,
+    pub is_win:bool,
+    pub yes_votes:i64,
+    pub no_votes:i64,
+}
+
 #[derive(Copy,Clone,Debug,Serialize,Queryable)]
 pub struct MotionVote {
     pub user:i64,
     pub direction:bool,
     pub amount:i64,
-}
\ No newline at end of file
+}
+#[derive(Debug, PartialEq, Eq, Clone, Queryable)]
+pub struct ItemType{
+    pub name: String,
+    pub long_name_plural: String,
+    pub long_name_ambiguous: String,
+    /// Can no longer be given or fabricated, see `admin::set_item_type_retired`
+    pub retired: bool,
+}
+
+impl ItemType {
+    pub fn db_name(&self) -> &str {
+        self.name.as_str()
+    }
+}
@@ -0,0 +1,666 @@
+//! The grammar shared by every command: tokenizing, amounts such as `1,000`, `1.5k`, `all`, `half`
+//! or `25%`, the vote command, and friendly errors that suggest what was meant.
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::damm;
+use crate::i18n::{Locale, Localize};
+use crate::vote_cost::VoteAmount;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum ParseError {
+    /// Not an amount at all
+    NotAnAmount(String),
+    /// Looks like a number, but isn't a whole one
+    NotWhole(String),
+    TooLarge(String),
+    BadPercentage(String),
+    /// A word that isn't part of the command's vocabulary, with the closest word that is
+    UnknownWord{word: String, suggestion: Option<String>},
+    /// Something was given more than once, eg. two amounts. Holds the catalog key of the message.
+    Repeated(&'static str),
+    /// Something required wasn't given. Holds the catalog key of the message.
+    Missing(&'static str),
+    BadMotionId(String),
+    UnclosedQuote,
+    MemoTooLong,
+    /// Not a length of time, eg. `1d`
+    BadInterval(String),
+    /// Not a date, eg. `2021-01-31`
+    BadDate(String),
+}
+
+impl Localize for ParseError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            ParseError::NotAnAmount(s) => tr!(locale, "parse.not_an_amount", text = s),
+            ParseError::NotWhole(s) => tr!(locale, "parse.not_whole", text = s),
+            ParseError::TooLarge(s) => tr!(locale, "parse.too_large", text = s),
+            ParseError::BadPercentage(s) => tr!(locale, "parse.bad_percentage", text = s),
+            ParseError::UnknownWord{word, suggestion: Some(suggestion)} => tr!(locale, "parse.unknown_word_suggestion", word = word, suggestion = suggestion),
+            ParseError::UnknownWord{word, suggestion: None} => tr!(locale, "parse.unknown_word", word = word),
+            ParseError::Repeated(key) | ParseError::Missing(key) => tr!(locale, *key).to_string(),
+            ParseError::BadMotionId(_) => tr!(locale, "parse.bad_motion_id").to_string(),
+            ParseError::UnclosedQuote => tr!(locale, "parse.unclosed_quote").to_string(),
+            ParseError::MemoTooLong => tr!(locale, "parse.memo_too_long", max = MEMO_MAX_CHARS),
+            ParseError::BadInterval(s) => tr!(locale, "parse.bad_interval", text = s),
+            ParseError::BadDate(s) => tr!(locale, "parse.bad_date", text = s),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+fn is_quote(c:char) -> bool {
+    c == '"' || c == '\u{201C}' || c == '\u{201D}'
+}
+
+/// Splits arguments on whitespace, except within double quotes (including the curly quotes phones
+/// like to substitute), which are removed.
+pub fn tokenize(s:&str) -> Result<Vec<String>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut current:Option<String> = None;
+    let mut in_quotes = false;
+    for c in s.chars() {
+        if is_quote(c) {
+            in_quotes = !in_quotes;
+            current.get_or_insert_with(String::new);
+        } else if c.is_whitespace() && !in_quotes {
+            tokens.extend(current.take());
+        } else {
+            current.get_or_insert_with(String::new).push(c);
+        }
+    }
+    if in_quotes {
+        return Err(ParseError::UnclosedQuote);
+    }
+    tokens.extend(current);
+    Ok(tokens)
+}
+
+/// Edits needed to turn `a` into `b`, where swapping two adjacent letters counts as one edit.
+fn edit_distance(a:&str, b:&str) -> usize {
+    let a:Vec<char> = a.chars().collect();
+    let b:Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() { row[0] = i; }
+    for (j, cell) in d[0].iter_mut().enumerate() { *cell = j; }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i-1] == b[j-1] { 0 } else { 1 };
+            d[i][j] = (d[i-1][j] + 1).min(d[i][j-1] + 1).min(d[i-1][j-1] + cost);
+            if i > 1 && j > 1 && a[i-1] == b[j-2] && a[i-2] == b[j-1] {
+                d[i][j] = d[i][j].min(d[i-2][j-2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+/// The candidate closest to `word`, if any is close enough to plausibly be a typo of it.
+pub fn did_you_mean<'a>(word:&str, candidates:impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let word = word.to_lowercase();
+    let max_distance = match word.chars().count() {
+        0..=3 => 1,
+        4..=6 => 2,
+        _ => 3,
+    };
+    candidates.into_iter()
+        .map(|c| (edit_distance(&word, &c.to_lowercase()), c))
+        .filter(|(d, _)| *d <= max_distance)
+        .min_by_key(|&(d, c)| (d, c))
+        .map(|(_, c)| c)
+}
+
+fn unknown_word<'a>(word:&str, candidates:impl IntoIterator<Item = &'a str>) -> ParseError {
+    ParseError::UnknownWord{
+        word: word.to_string(),
+        suggestion: did_you_mean(word, candidates).map(String::from),
+    }
+}
+
+/// Parses a digit string with an optional decimal part, eg. `1,234.5`, into its digits and how many
+/// of them come after the point. Commas must separate groups of three.
+fn parse_decimal(s:&str) -> Option<(u128, u32)> {
+    let (whole, frac) = match s.find('.') {
+        Some(i) => (&s[..i], &s[i+1..]),
+        None => (s, ""),
+    };
+    let groups:Vec<&str> = whole.split(',').collect();
+    let groups_ok = groups.iter().enumerate().all(|(i, g)| {
+        !g.is_empty() && g.bytes().all(|b| b.is_ascii_digit()) && (i == 0 || g.len() == 3) && (i != 0 || groups.len() == 1 || g.len() <= 3)
+    });
+    if !groups_ok || !frac.bytes().all(|b| b.is_ascii_digit()) || (s.contains('.') && frac.is_empty()) || frac.len() > 18 {
+        return None;
+    }
+    let mut digits:u128 = 0;
+    for b in groups.concat().bytes().chain(frac.bytes()) {
+        digits = digits.checked_mul(10)?.checked_add((b - b'0') as u128)?;
+    }
+    Some((digits, frac.len() as u32))
+}
+
+/// Parses a whole number, allowing thousands separators and a `k` or `m` suffix, eg. `1,000`, `15k`
+/// or `1.5m`.
+pub fn parse_number(s:&str) -> Result<u64, ParseError> {
+    let lower = s.to_lowercase();
+    let (body, multiplier) = if lower.ends_with('k') {
+        (&lower[..lower.len()-1], 1_000)
+    } else if lower.ends_with('m') {
+        (&lower[..lower.len()-1], 1_000_000)
+    } else {
+        (&lower[..], 1)
+    };
+    let (digits, frac_len) = parse_decimal(body).ok_or_else(|| ParseError::NotAnAmount(s.to_string()))?;
+    let scale = 10u128.pow(frac_len);
+    let scaled = digits.checked_mul(multiplier).ok_or_else(|| ParseError::TooLarge(s.to_string()))?;
+    if scaled % scale != 0 {
+        return Err(ParseError::NotWhole(s.to_string()));
+    }
+    let value = scaled / scale;
+    if value > i64::MAX as u128 {
+        return Err(ParseError::TooLarge(s.to_string()));
+    }
+    Ok(value as u64)
+}
+
+/// An amount of something, which may be relative to how much of it there is.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum Amount {
+    Exact(u64),
+    All,
+    /// A share of the whole, eg. `half` is 1/2 and `12.5%` is 125/1000
+    Fraction{numerator: u64, denominator: u64},
+}
+
+impl Amount {
+    /// How much this is when there's `total` available, rounding down.
+    pub fn resolve(&self, total:i64) -> i64 {
+        match *self {
+            Amount::Exact(n) => std::cmp::min(n, i64::MAX as u64) as i64,
+            Amount::All => total,
+            Amount::Fraction{numerator, denominator} => {
+                (total.max(0) as i128 * numerator as i128 / denominator as i128) as i64
+            },
+        }
+    }
+}
+
+const ALL_WORDS:&[&str] = &["all", "everything", "max", "maximum"];
+const HALF_WORDS:&[&str] = &["half"];
+
+/// Parses a number as in `parse_number`, `all`, `half` or a percentage.
+pub fn parse_amount(s:&str) -> Result<Amount, ParseError> {
+    let lower = s.to_lowercase();
+    if ALL_WORDS.contains(&&*lower) {
+        return Ok(Amount::All);
+    }
+    if HALF_WORDS.contains(&&*lower) {
+        return Ok(Amount::Fraction{numerator: 1, denominator: 2});
+    }
+    if lower.ends_with('%') {
+        let bad = || ParseError::BadPercentage(s.to_string());
+        let (digits, frac_len) = parse_decimal(&lower[..lower.len()-1]).ok_or_else(bad)?;
+        let denominator = 100u128 * 10u128.pow(frac_len);
+        if digits > denominator || denominator > u64::MAX as u128 {
+            return Err(bad());
+        }
+        return Ok(Amount::Fraction{numerator: digits as u64, denominator: denominator as u64});
+    }
+    parse_number(s).map(Amount::Exact)
+}
+
+/// Parses a motion id, including its check digit.
+pub fn parse_motion_id(s:&str) -> Result<i64, ParseError> {
+    let digits = damm::validate(s).ok_or_else(|| ParseError::BadMotionId(s.to_string()))?;
+    digits.iter().try_fold(0i64, |acc, d| acc.checked_mul(10)?.checked_add(*d as i64))
+        .ok_or_else(|| ParseError::BadMotionId(s.to_string()))
+}
+
+/// Matches a word, case-insensitively, against a command's options.
+pub fn parse_choice<T: Copy>(word:&str, choices:&[(&str, T)]) -> Result<T, ParseError> {
+    let lower = word.to_lowercase();
+    choices.iter()
+        .find(|(name, _)| *name == lower)
+        .map(|(_, value)| *value)
+        .ok_or_else(|| unknown_word(word, choices.iter().map(|(name, _)| *name)))
+}
+
+/// Parses the amount and item type of a give, in either order, eg. `100 pc`, `pc 1,000`, `100pc` or
+/// `half gen`. The item type is left as it was written, to be looked up by the caller.
+pub fn parse_give_args<S: AsRef<str>>(args:&[S]) -> Result<(Amount, Option<String>), ParseError> {
+    let mut amount:Option<Amount> = None;
+    let mut ty:Option<String> = None;
+    for arg in args {
+        let arg = arg.as_ref();
+        let (arg_amount, arg_ty) = match parse_amount(arg) {
+            Ok(a) => (Some(a), None),
+            Err(e) => {
+                let starts_numeric = arg.starts_with(|c:char| c.is_ascii_digit());
+                match arg.find(|c:char| !(c.is_ascii_digit() || c == ',' || c == '.')) {
+                    Some(idx) if starts_numeric => (Some(Amount::Exact(parse_number(&arg[..idx])?)), Some(&arg[idx..])),
+                    _ if starts_numeric => return Err(e),
+                    _ => (None, Some(arg)),
+                }
+            },
+        };
+        if let Some(a) = arg_amount {
+            if amount.replace(a).is_some() {
+                return Err(ParseError::Repeated("parse.repeated_amount"));
+            }
+        }
+        if let Some(t) = arg_ty {
+            if ty.replace(t.to_string()).is_some() {
+                return Err(ParseError::Repeated("parse.repeated_item_type"));
+            }
+        }
+    }
+    match amount {
+        Some(a) => Ok((a, ty)),
+        None => Err(ParseError::Missing("parse.missing_amount")),
+    }
+}
+
+/// Like `parse_give_args`, but anything after both the amount and the item type is a memo, eg.
+/// `100 pc for the pizza`.
+pub fn parse_give_with_memo<S: AsRef<str>>(args:&[S]) -> Result<(Amount, Option<String>, Option<String>), ParseError> {
+    for end in 1..args.len() {
+        if let Ok((amount, Some(ty))) = parse_give_args(&args[..end]) {
+            let memo:Vec<&str> = args[end..].iter().map(AsRef::as_ref).collect();
+            return Ok((amount, Some(ty), parse_memo(&memo.join(" "))?));
+        }
+    }
+    parse_give_args(args).map(|(amount, ty)| (amount, ty, None))
+}
+
+pub const MEMO_MAX_CHARS:usize = 200;
+
+/// Tidies up a memo on a transfer. Runs of whitespace and control characters become single spaces,
+/// and mentions are defused so that repeating a memo can't ping anyone. An empty memo is `None`.
+pub fn parse_memo(s:&str) -> Result<Option<String>, ParseError> {
+    let words:Vec<&str> = s.split(|c:char| c.is_whitespace() || c.is_control()).filter(|w| !w.is_empty()).collect();
+    let memo = words.join(" ");
+    if memo.chars().count() > MEMO_MAX_CHARS {
+        return Err(ParseError::MemoTooLong);
+    }
+    if memo.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(memo.replace('@', "@\u{200B}")))
+}
+
+const INTERVAL_UNITS:&[(char, i64)] = &[('w', 7 * 24 * 60 * 60), ('d', 24 * 60 * 60), ('h', 60 * 60), ('m', 60), ('s', 1)];
+const INTERVAL_WORDS:&[(&str, char)] = &[
+    ("weekly", 'w'), ("week", 'w'),
+    ("daily", 'd'), ("day", 'd'),
+    ("hourly", 'h'), ("hour", 'h'),
+];
+
+/// Parses a length of time as a count and a unit, eg. `30m`, `12h`, `1d` or `2w`, or one of a few
+/// words such as `daily`.
+pub fn parse_interval(s:&str) -> Result<chrono::Duration, ParseError> {
+    let bad = || ParseError::BadInterval(s.to_string());
+    let lower = s.to_lowercase();
+    let (count, unit) = match INTERVAL_WORDS.iter().find(|(word, _)| *word == lower) {
+        Some((_, unit)) => (1, *unit),
+        None => {
+            let unit = lower.chars().last().ok_or_else(bad)?;
+            let count:i64 = lower[..lower.len() - unit.len_utf8()].parse().map_err(|_| bad())?;
+            (count, unit)
+        },
+    };
+    let (_, seconds) = INTERVAL_UNITS.iter().find(|(u, _)| *u == unit).ok_or_else(bad)?;
+    match count.checked_mul(*seconds) {
+        // anything longer doesn't fit in a `Duration`
+        Some(total) if total > 0 && total <= chrono::Duration::max_value().num_seconds() => Ok(chrono::Duration::seconds(total)),
+        _ => Err(bad()),
+    }
+}
+
+/// Writes a length of time the way `parse_interval` reads it, in the largest unit that fits evenly.
+pub fn format_interval(interval:chrono::Duration) -> String {
+    let total = interval.num_seconds();
+    let (unit, seconds) = INTERVAL_UNITS.iter()
+        .find(|(_, seconds)| total % seconds == 0)
+        .expect("everything is a whole number of seconds");
+    format!("{}{}", total / seconds, unit)
+}
+
+/// Parses a date written as `YYYY-MM-DD`.
+pub fn parse_date(s:&str) -> Result<chrono::NaiveDate, ParseError> {
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d").map_err(|_| ParseError::BadDate(s.to_string()))
+}
+
+/// What a word in the vote vocabulary means, see the `vote_words` table.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum VoteWord {
+    Yes,
+    No,
+    /// Cast no votes, just pick a direction
+    Zero,
+    /// Filler, eg. "I think"
+    Ignore,
+}
+
+impl VoteWord {
+    pub fn db_name(self) -> &'static str {
+        match self {
+            VoteWord::Yes => "yes",
+            VoteWord::No => "no",
+            VoteWord::Zero => "zero",
+            VoteWord::Ignore => "ignore",
+        }
+    }
+
+    pub fn from_db_name(name:&str) -> Option<Self> {
+        match name {
+            "yes" => Some(VoteWord::Yes),
+            "no" => Some(VoteWord::No),
+            "zero" => Some(VoteWord::Zero),
+            "ignore" => Some(VoteWord::Ignore),
+            _ => None,
+        }
+    }
+}
+
+/// The words the vote command understands besides amounts, looked up case-insensitively.
+#[derive(Debug,Clone,Default,PartialEq,Eq)]
+pub struct VoteVocabulary {
+    words: HashMap<String, VoteWord>,
+}
+
+impl VoteVocabulary {
+    pub fn new<S: AsRef<str>>(words:impl IntoIterator<Item = (S, VoteWord)>) -> Self {
+        VoteVocabulary{
+            words: words.into_iter().map(|(w, meaning)| (w.as_ref().to_lowercase(), meaning)).collect(),
+        }
+    }
+
+    pub fn get(&self, word:&str) -> Option<VoteWord> {
+        self.words.get(&word.to_lowercase()).copied()
+    }
+}
+
+const BUDGET_WORDS:&[&str] = &["budget", "spend", "upto", "up-to"];
+
+/// Whether a word already means something to the vote command, and so can't be added to the
+/// vocabulary.
+pub fn is_reserved_vote_word(word:&str) -> bool {
+    let lower = word.to_lowercase();
+    let lower = lower.as_str();
+    BUDGET_WORDS.contains(&lower) || ALL_WORDS.contains(&lower) || HALF_WORDS.contains(&lower) || parse_amount(word).is_ok()
+}
+
+/// What to spend on votes when given an amount of capital: `all` is everything, and a share is of
+/// the voter's balance.
+fn budget_of(amount:Amount) -> VoteAmount {
+    match amount {
+        Amount::Exact(capital) => VoteAmount::Budget(std::cmp::min(capital, i64::MAX as u64) as i64),
+        Amount::All => VoteAmount::Max,
+        Amount::Fraction{numerator, denominator} => VoteAmount::Share{numerator, denominator},
+    }
+}
+
+/// Parses everything after the motion id in a vote command into a direction, if any, and an amount.
+/// A bare number is a number of votes, while `all`, `half` and percentages are shares of capital.
+pub fn parse_vote_args<S: AsRef<str>>(vocabulary:&VoteVocabulary, args:&[S]) -> Result<(Option<bool>, VoteAmount), ParseError> {
+    let mut amount = VoteAmount::Exact(1);
+    let mut vote_direction:Option<bool> = None;
+    let mut args = args.iter().map(|a| a.as_ref());
+    while let Some(arg) = args.next() {
+        match vocabulary.get(arg) {
+            Some(VoteWord::Yes) => vote_direction = Some(true),
+            Some(VoteWord::No) => vote_direction = Some(false),
+            Some(VoteWord::Zero) => amount = VoteAmount::Exact(0),
+            Some(VoteWord::Ignore) => (),
+            None if BUDGET_WORDS.contains(&&*arg.to_lowercase()) => {
+                let budget = args.next().ok_or(ParseError::Missing("parse.missing_budget"))?;
+                amount = budget_of(parse_amount(budget)?);
+            },
+            None => {
+                amount = match parse_amount(arg) {
+                    Ok(Amount::Exact(votes)) => VoteAmount::Exact(votes as i64),
+                    Ok(share) => budget_of(share),
+                    Err(ParseError::NotAnAmount(_)) => {
+                        let known = vocabulary.words.keys().map(String::as_str)
+                            .chain(BUDGET_WORDS.iter().copied())
+                            .chain(ALL_WORDS.iter().copied())
+                            .chain(HALF_WORDS.iter().copied());
+                        return Err(unknown_word(arg, known));
+                    },
+                    Err(e) => return Err(e),
+                };
+            },
+        }
+    }
+    Ok((vote_direction, amount))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vocabulary() -> VoteVocabulary {
+        VoteVocabulary::new(vec![
+            ("yes", VoteWord::Yes),
+            ("yeah", VoteWord::Yes),
+            ("for", VoteWord::Yes),
+            ("\u{1f44d}", VoteWord::Yes),
+            ("no", VoteWord::No),
+            ("nay", VoteWord::No),
+            ("against", VoteWord::No),
+            ("no, you wanker", VoteWord::No),
+            ("zero", VoteWord::Zero),
+            ("i", VoteWord::Ignore),
+            ("think", VoteWord::Ignore),
+            ("fuck", VoteWord::Ignore),
+        ])
+    }
+
+    #[test]
+    fn tokenizes() {
+        assert_eq!(tokenize("  give  shelvacu 100 pc ").unwrap(), vec!["give", "shelvacu", "100", "pc"]);
+        assert_eq!(tokenize("\"! ! Fre Sha Vaca Do\" 5 gen").unwrap(), vec!["! ! Fre Sha Vaca Do", "5", "gen"]);
+        assert_eq!(tokenize("\u{201C}no, asshole\u{201D}").unwrap(), vec!["no, asshole"]);
+        assert_eq!(tokenize("a\"b c\"d").unwrap(), vec!["ab cd"]);
+        assert_eq!(tokenize("\"\"").unwrap(), vec![""]);
+        assert_eq!(tokenize("").unwrap(), Vec::<String>::new());
+        assert_eq!(tokenize("\"oops"), Err(ParseError::UnclosedQuote));
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_number("0"), Ok(0));
+        assert_eq!(parse_number("1000"), Ok(1000));
+        assert_eq!(parse_number("1,000"), Ok(1000));
+        assert_eq!(parse_number("12,345,678"), Ok(12_345_678));
+        assert_eq!(parse_number("1k"), Ok(1000));
+        assert_eq!(parse_number("1K"), Ok(1000));
+        assert_eq!(parse_number("1.5k"), Ok(1500));
+        assert_eq!(parse_number("2m"), Ok(2_000_000));
+        assert_eq!(parse_number("1,500k"), Ok(1_500_000));
+        assert_eq!(parse_number("0.25m"), Ok(250_000));
+        assert_eq!(parse_number("9223372036854775807"), Ok(i64::MAX as u64));
+    }
+
+    #[test]
+    fn rejects_bad_numbers() {
+        assert_eq!(parse_number("1.5"), Err(ParseError::NotWhole(String::from("1.5"))));
+        assert_eq!(parse_number("1.0001k"), Err(ParseError::NotWhole(String::from("1.0001k"))));
+        assert_eq!(parse_number("9223372036854775808"), Err(ParseError::TooLarge(String::from("9223372036854775808"))));
+        assert_eq!(parse_number("99999999999999999999m"), Err(ParseError::TooLarge(String::from("99999999999999999999m"))));
+        for bad in &["", "k", "1,00", "1000,000", ",100", "100,", "1,,000", "1.", ".5", "1.2.3", "1k5", "-1", "+1", "1e3", "one", "1 000", "1kk"] {
+            assert_eq!(parse_number(bad), Err(ParseError::NotAnAmount(bad.to_string())), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn parses_amounts() {
+        assert_eq!(parse_amount("1,000"), Ok(Amount::Exact(1000)));
+        assert_eq!(parse_amount("all"), Ok(Amount::All));
+        assert_eq!(parse_amount("ALL"), Ok(Amount::All));
+        assert_eq!(parse_amount("everything"), Ok(Amount::All));
+        assert_eq!(parse_amount("half"), Ok(Amount::Fraction{numerator: 1, denominator: 2}));
+        assert_eq!(parse_amount("50%"), Ok(Amount::Fraction{numerator: 50, denominator: 100}));
+        assert_eq!(parse_amount("12.5%"), Ok(Amount::Fraction{numerator: 125, denominator: 1000}));
+        assert_eq!(parse_amount("0%"), Ok(Amount::Fraction{numerator: 0, denominator: 100}));
+        assert_eq!(parse_amount("100%"), Ok(Amount::Fraction{numerator: 100, denominator: 100}));
+        assert_eq!(parse_amount("101%"), Err(ParseError::BadPercentage(String::from("101%"))));
+        assert_eq!(parse_amount("%"), Err(ParseError::BadPercentage(String::from("%"))));
+        assert_eq!(parse_amount("lots"), Err(ParseError::NotAnAmount(String::from("lots"))));
+    }
+
+    #[test]
+    fn resolves_amounts() {
+        assert_eq!(Amount::Exact(5).resolve(3), 5);
+        assert_eq!(Amount::Exact(u64::MAX).resolve(3), i64::MAX);
+        assert_eq!(Amount::All.resolve(7), 7);
+        assert_eq!(parse_amount("half").unwrap().resolve(7), 3);
+        assert_eq!(parse_amount("12.5%").unwrap().resolve(80), 10);
+        assert_eq!(parse_amount("100%").unwrap().resolve(i64::MAX), i64::MAX);
+        assert_eq!(parse_amount("half").unwrap().resolve(-4), 0);
+    }
+
+    #[test]
+    fn suggests_close_words() {
+        assert_eq!(did_you_mean("yse", vec!["yes", "no"]), Some("yes"));
+        assert_eq!(did_you_mean("AGAINTS", vec!["for", "against"]), Some("against"));
+        assert_eq!(did_you_mean("budgte", vec!["budget", "max"]), Some("budget"));
+        assert_eq!(did_you_mean("banana", vec!["yes", "no"]), None);
+        assert_eq!(did_you_mean("xy", vec!["y"]), Some("y"));
+        assert_eq!(did_you_mean("anything", Vec::new()), None);
+    }
+
+    #[test]
+    fn parses_motion_ids() {
+        let checked = damm::add_to_str(String::from("123"));
+        assert_eq!(parse_motion_id(&checked), Ok(123));
+        let mut typo = checked.into_bytes();
+        typo.swap(0, 1);
+        let typo = String::from_utf8(typo).unwrap();
+        assert_eq!(parse_motion_id(&typo), Err(ParseError::BadMotionId(typo.clone())));
+        assert!(parse_motion_id("abc").is_err());
+    }
+
+    #[test]
+    fn parses_choices() {
+        let choices = &[("hide", true), ("show", false)];
+        assert_eq!(parse_choice("Hide", choices), Ok(true));
+        assert_eq!(parse_choice("show", choices), Ok(false));
+        assert_eq!(parse_choice("shwo", choices), Err(ParseError::UnknownWord{word: String::from("shwo"), suggestion: Some(String::from("show"))}));
+        assert_eq!(parse_choice("nope", choices), Err(ParseError::UnknownWord{word: String::from("nope"), suggestion: None}));
+    }
+
+    #[test]
+    fn parses_give_args() {
+        let pc = Some(String::from("pc"));
+        assert_eq!(parse_give_args(&["100", "pc"]), Ok((Amount::Exact(100), pc.clone())));
+        assert_eq!(parse_give_args(&["pc", "1,000"]), Ok((Amount::Exact(1000), pc.clone())));
+        assert_eq!(parse_give_args(&["100pc"]), Ok((Amount::Exact(100), pc.clone())));
+        assert_eq!(parse_give_args(&["1,000pc"]), Ok((Amount::Exact(1000), pc.clone())));
+        assert_eq!(parse_give_args(&["1k", "gen"]), Ok((Amount::Exact(1000), Some(String::from("gen")))));
+        assert_eq!(parse_give_args(&["half", "pc"]), Ok((Amount::Fraction{numerator: 1, denominator: 2}, pc.clone())));
+        assert_eq!(parse_give_args(&["all", "pc"]), Ok((Amount::All, pc.clone())));
+        assert_eq!(parse_give_args(&["10%", "pc"]), Ok((Amount::Fraction{numerator: 10, denominator: 100}, pc.clone())));
+        assert_eq!(parse_give_args(&["5"]), Ok((Amount::Exact(5), None)));
+        assert_eq!(parse_give_args(&["pc"]), Err(ParseError::Missing("parse.missing_amount")));
+        assert_eq!(parse_give_args(&["5", "6", "pc"]), Err(ParseError::Repeated("parse.repeated_amount")));
+        assert_eq!(parse_give_args(&["5", "pc", "gen"]), Err(ParseError::Repeated("parse.repeated_item_type")));
+        assert_eq!(parse_give_args(&["5gen", "pc"]), Err(ParseError::Repeated("parse.repeated_item_type")));
+        assert_eq!(parse_give_args(&["1,00pc"]), Err(ParseError::NotAnAmount(String::from("1,00"))));
+        assert_eq!(parse_give_args(&["1.5"]), Err(ParseError::NotWhole(String::from("1.5"))));
+    }
+
+    #[test]
+    fn parses_give_memos() {
+        let pc = Some(String::from("pc"));
+        assert_eq!(parse_give_with_memo(&["100", "pc"]), Ok((Amount::Exact(100), pc.clone(), None)));
+        assert_eq!(parse_give_with_memo(&["100", "pc", "for", "the", "pizza"]), Ok((Amount::Exact(100), pc.clone(), Some(String::from("for the pizza")))));
+        assert_eq!(parse_give_with_memo(&["pc", "100", "5 slices"]), Ok((Amount::Exact(100), pc.clone(), Some(String::from("5 slices")))));
+        assert_eq!(parse_give_with_memo(&["100pc", "rent"]), Ok((Amount::Exact(100), pc.clone(), Some(String::from("rent")))));
+        assert_eq!(parse_give_with_memo(&["5", "6", "pc"]), Err(ParseError::Repeated("parse.repeated_amount")));
+        assert_eq!(parse_memo("  thanks\n\tfor  lunch "), Ok(Some(String::from("thanks for lunch"))));
+        assert_eq!(parse_memo("hi @everyone"), Ok(Some(String::from("hi @\u{200B}everyone"))));
+        assert_eq!(parse_memo(" \u{7} "), Ok(None));
+        assert_eq!(parse_memo(&"x".repeat(MEMO_MAX_CHARS)).map(|m| m.map(|m| m.len())), Ok(Some(MEMO_MAX_CHARS)));
+        assert_eq!(parse_memo(&"x".repeat(MEMO_MAX_CHARS + 1)), Err(ParseError::MemoTooLong));
+    }
+
+    #[test]
+    fn parses_intervals() {
+        assert_eq!(parse_interval("1d"), Ok(chrono::Duration::days(1)));
+        assert_eq!(parse_interval("12H"), Ok(chrono::Duration::hours(12)));
+        assert_eq!(parse_interval("30m"), Ok(chrono::Duration::minutes(30)));
+        assert_eq!(parse_interval("2w"), Ok(chrono::Duration::weeks(2)));
+        assert_eq!(parse_interval("daily"), Ok(chrono::Duration::days(1)));
+        assert_eq!(parse_interval("week"), Ok(chrono::Duration::weeks(1)));
+        assert_eq!(parse_interval("0d"), Err(ParseError::BadInterval(String::from("0d"))));
+        assert_eq!(parse_interval("-1d"), Err(ParseError::BadInterval(String::from("-1d"))));
+        assert_eq!(parse_interval("1y"), Err(ParseError::BadInterval(String::from("1y"))));
+        assert_eq!(parse_interval("d"), Err(ParseError::BadInterval(String::from("d"))));
+        assert_eq!(parse_interval("10000000000000000s"), Err(ParseError::BadInterval(String::from("10000000000000000s"))));
+        assert_eq!(parse_interval("100000000000000000w"), Err(ParseError::BadInterval(String::from("100000000000000000w"))));
+        assert_eq!(parse_interval(""), Err(ParseError::BadInterval(String::new())));
+        for s in &["1d", "12h", "90m", "2w", "45s"] {
+            assert_eq!(format_interval(parse_interval(s).unwrap()), *s);
+        }
+        assert_eq!(format_interval(chrono::Duration::hours(48)), "2d");
+        assert_eq!(parse_date("2021-01-31"), Ok(chrono::NaiveDate::from_ymd(2021, 1, 31)));
+        assert_eq!(parse_date("2021-02-31"), Err(ParseError::BadDate(String::from("2021-02-31"))));
+        assert_eq!(parse_date("tomorrow"), Err(ParseError::BadDate(String::from("tomorrow"))));
+    }
+
+    #[test]
+    fn parses_vote_args() {
+        let none:&[&str] = &[];
+        assert_eq!(parse_vote_args(&vocabulary(), none), Ok((None, VoteAmount::Exact(1))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["yes"]), Ok((Some(true), VoteAmount::Exact(1))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["1000", "yeah"]), Ok((Some(true), VoteAmount::Exact(1000))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["fuck", "no"]), Ok((Some(false), VoteAmount::Exact(1))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["I", "think", "NAY", "1,000"]), Ok((Some(false), VoteAmount::Exact(1000))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["no, you wanker"]), Ok((Some(false), VoteAmount::Exact(1))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["\u{1f44d}", "2k"]), Ok((Some(true), VoteAmount::Exact(2000))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["zero"]), Ok((None, VoteAmount::Exact(0))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["for", "budget", "5,000"]), Ok((Some(true), VoteAmount::Budget(5000))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["spend", "half"]), Ok((None, VoteAmount::Share{numerator: 1, denominator: 2})));
+        assert_eq!(parse_vote_args(&vocabulary(), &["budget", "all"]), Ok((None, VoteAmount::Max)));
+        assert_eq!(parse_vote_args(&vocabulary(), &["max"]), Ok((None, VoteAmount::Max)));
+        assert_eq!(parse_vote_args(&vocabulary(), &["against", "25%"]), Ok((Some(false), VoteAmount::Share{numerator: 25, denominator: 100})));
+    }
+
+    #[test]
+    fn rejects_bad_vote_args() {
+        assert_eq!(parse_vote_args(&vocabulary(), &["for", "budget"]), Err(ParseError::Missing("parse.missing_budget")));
+        assert_eq!(parse_vote_args(&vocabulary(), &["budget", "lots"]), Err(ParseError::NotAnAmount(String::from("lots"))));
+        assert_eq!(parse_vote_args(&vocabulary(), &["yse"]), Err(ParseError::UnknownWord{word: String::from("yse"), suggestion: Some(String::from("yes"))}));
+        assert_eq!(parse_vote_args(&vocabulary(), &["xyzzy"]), Err(ParseError::UnknownWord{word: String::from("xyzzy"), suggestion: None}));
+        assert_eq!(parse_vote_args(&vocabulary(), &["1.5"]), Err(ParseError::NotWhole(String::from("1.5"))));
+    }
+
+    #[test]
+    fn vocabulary_ignores_case() {
+        let vocabulary = VoteVocabulary::new(vec![("Aye", VoteWord::Yes)]);
+        assert_eq!(vocabulary.get("aye"), Some(VoteWord::Yes));
+        assert_eq!(vocabulary.get("AYE"), Some(VoteWord::Yes));
+        assert_eq!(vocabulary.get("nay"), None);
+        for meaning in &[VoteWord::Yes, VoteWord::No, VoteWord::Zero, VoteWord::Ignore] {
+            assert_eq!(VoteWord::from_db_name(meaning.db_name()), Some(*meaning));
+        }
+    }
+
+    #[test]
+    fn reserves_vote_words() {
+        for word in &["budget", "Spend", "max", "all", "half", "5", "1k", "10%"] {
+            assert!(is_reserved_vote_word(word), "{:?}", word);
+        }
+        for word in &["yes", "si", "\u{1f44d}", "no, you wanker"] {
+            assert!(!is_reserved_vote_word(word), "{:?}", word);
+        }
+    }
+}
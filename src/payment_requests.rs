@@ -0,0 +1,421 @@
+//! Payment requests: one user asking another to pay them, such as `$request shelvacu 500 pc for the
+//! pizza`. The payer pays or declines by reacting to the DM the bot sends them, with `$pay` or
+//! `$decline`, or on the web. Paying goes through `economy::give_transfer` in the same transaction
+//! that settles the request, so no request is ever paid twice. Requests nobody settles expire.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::schema;
+use crate::economy::{self, GiveError};
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+
+/// Where a request stands. Only the first four are stored; a pending request past its expiry is
+/// `Expired`, see `PaymentRequest::status`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RequestStatus {
+    Pending,
+    Paid,
+    Declined,
+    /// Withdrawn by whoever asked
+    Cancelled,
+    Expired,
+}
+
+impl RequestStatus {
+    pub fn db_name(self) -> &'static str {
+        match self {
+            RequestStatus::Pending | RequestStatus::Expired => "pending",
+            RequestStatus::Paid => "paid",
+            RequestStatus::Declined => "declined",
+            RequestStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_db_name(name:&str) -> Option<Self> {
+        match name {
+            "pending" => Some(RequestStatus::Pending),
+            "paid" => Some(RequestStatus::Paid),
+            "declined" => Some(RequestStatus::Declined),
+            "cancelled" => Some(RequestStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl Localize for RequestStatus {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            RequestStatus::Pending => "request.status.pending",
+            RequestStatus::Paid => "request.status.paid",
+            RequestStatus::Declined => "request.status.declined",
+            RequestStatus::Cancelled => "request.status.cancelled",
+            RequestStatus::Expired => "request.status.expired",
+        }).to_string()
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct PaymentRequest {
+    pub rowid:i64,
+    /// Who gets paid
+    pub requested_by:i64,
+    pub payer:i64,
+    pub ty:String,
+    pub quantity:i64,
+    pub memo:Option<String>,
+    pub requested_at:DateTime<Utc>,
+    pub expires_at:DateTime<Utc>,
+    pub message_id:Option<i64>,
+    pub prompt_message_id:Option<i64>,
+    pub status:String,
+    pub settled_at:Option<DateTime<Utc>>,
+    pub transfer:Option<i64>,
+}
+
+impl PaymentRequest {
+    pub fn status(&self, now:DateTime<Utc>) -> RequestStatus {
+        match RequestStatus::from_db_name(&self.status).expect("payment_requests.status is constrained") {
+            RequestStatus::Pending if self.expires_at <= now => RequestStatus::Expired,
+            status => status,
+        }
+    }
+
+    /// What the payer is asked, eg. "user#1 asks you to pay 500 pc for “pizza” (request #3)".
+    pub fn prompt(&self, locale:Locale, requester:&str, items:&str) -> String {
+        let key = if self.memo.is_some() { "request.prompt_memo" } else { "request.prompt" };
+        tr!(
+            locale,
+            key,
+            user = requester,
+            amount = locale.number(self.quantity),
+            items = items,
+            memo = self.memo.as_deref().unwrap_or(""),
+            id = self.rowid,
+            expires = locale.datetime(self.expires_at),
+        )
+    }
+
+    /// What to tell whoever just settled the request, where `other` is the other person.
+    pub fn outcome(&self, locale:Locale, other:&str, items:&str) -> String {
+        let key = match RequestStatus::from_db_name(&self.status) {
+            Some(RequestStatus::Paid) => "request.paid",
+            Some(RequestStatus::Declined) => "request.declined",
+            _ => "request.cancelled",
+        };
+        tr!(locale, key, id = self.rowid, user = other, amount = locale.number(self.quantity), items = items)
+    }
+
+    /// What to tell the other person once `settler` has settled the request.
+    pub fn notice(&self, locale:Locale, settler:&str, items:&str) -> String {
+        let key = match RequestStatus::from_db_name(&self.status) {
+            Some(RequestStatus::Paid) => "request.notice_paid",
+            Some(RequestStatus::Declined) => "request.notice_declined",
+            _ => "request.notice_cancelled",
+        };
+        tr!(locale, key, id = self.rowid, user = settler, amount = locale.number(self.quantity), items = items)
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum RequestError {
+    NotPositive,
+    ToSelf,
+    NotFound,
+    /// Only the payer can pay, and only the two people involved can decline
+    NotYours,
+    Settled(RequestStatus),
+    Expired,
+    InsufficientBalance,
+    /// Any other reason the payment couldn't be made
+    Give(GiveError),
+}
+
+impl Localize for RequestError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            RequestError::NotPositive => tr!(locale, "request.not_positive").to_string(),
+            RequestError::ToSelf => tr!(locale, "request.to_self").to_string(),
+            RequestError::NotFound => tr!(locale, "request.not_found").to_string(),
+            RequestError::NotYours => tr!(locale, "request.not_yours").to_string(),
+            RequestError::Settled(status) => tr!(locale, "request.settled", status = status.localize(locale)),
+            RequestError::Expired => tr!(locale, "request.expired").to_string(),
+            RequestError::InsufficientBalance => GiveError::InsufficientBalance.localize(locale),
+            RequestError::Give(e) => e.localize(locale),
+        }
+    }
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Asks `payer` to pay `requested_by` some of `ty`, for the next `expiration`.
+#[allow(clippy::too_many_arguments)]
+pub fn create(
+    conn:&PgConnection,
+    requested_by:i64,
+    payer:i64,
+    ty:&ItemType,
+    quantity:i64,
+    memo:Option<&str>,
+    message_id:Option<i64>,
+    expiration:chrono::Duration,
+) -> QueryResult<Result<PaymentRequest, RequestError>> {
+    use schema::payment_requests::dsl as prdsl;
+    if quantity <= 0 {
+        return Ok(Err(RequestError::NotPositive));
+    }
+    if requested_by == payer {
+        return Ok(Err(RequestError::ToSelf));
+    }
+    let now = chrono::Utc::now();
+    diesel::insert_into(prdsl::payment_requests).values((
+        prdsl::requested_by.eq(requested_by),
+        prdsl::payer.eq(payer),
+        prdsl::ty.eq(ty.db_name()),
+        prdsl::quantity.eq(quantity),
+        prdsl::memo.eq(memo),
+        prdsl::requested_at.eq(now),
+        prdsl::expires_at.eq(now + expiration),
+        prdsl::message_id.eq(message_id),
+    )).get_result(conn).map(Ok)
+}
+
+/// Remembers the DM asking the payer to approve the request, so that reactions to it can.
+pub fn set_prompt(conn:&PgConnection, request_id:i64, prompt_message_id:i64) -> QueryResult<()> {
+    use schema::payment_requests::dsl as prdsl;
+    diesel::update(prdsl::payment_requests.filter(prdsl::rowid.eq(request_id)))
+        .set(prdsl::prompt_message_id.eq(prompt_message_id))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The request a DM asked the payer to approve, if it asked about one.
+pub fn by_prompt(conn:&PgConnection, prompt_message_id:i64) -> QueryResult<Option<PaymentRequest>> {
+    use schema::payment_requests::dsl as prdsl;
+    prdsl::payment_requests.filter(prdsl::prompt_message_id.eq(prompt_message_id)).get_result(conn).optional()
+}
+
+/// Every request the user has made or been asked to pay, newest first.
+pub fn involving(conn:&PgConnection, user:i64, limit:i64) -> QueryResult<Vec<PaymentRequest>> {
+    use schema::payment_requests::dsl as prdsl;
+    prdsl::payment_requests
+        .filter(prdsl::requested_by.eq(user).or(prdsl::payer.eq(user)))
+        .order(prdsl::rowid.desc())
+        .limit(limit)
+        .get_results(conn)
+}
+
+/// The item type asked for, even if it's since been retired.
+pub fn item_type_of(conn:&PgConnection, request:&PaymentRequest) -> QueryResult<ItemType> {
+    use schema::item_types::dsl as itdsl;
+    itdsl::item_types.filter(itdsl::name.eq(&request.ty)).get_result(conn)
+}
+
+/// Locks a pending request for settling, checking `check` against it first.
+fn lock_pending(
+    conn:&PgConnection,
+    request_id:i64,
+    now:DateTime<Utc>,
+    check:impl FnOnce(&PaymentRequest) -> bool,
+) -> QueryResult<Result<PaymentRequest, RequestError>> {
+    use schema::payment_requests::dsl as prdsl;
+    let request:Option<PaymentRequest> = prdsl::payment_requests
+        .filter(prdsl::rowid.eq(request_id))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+    Ok(match request {
+        None => Err(RequestError::NotFound),
+        Some(r) if !check(&r) => Err(RequestError::NotYours),
+        Some(r) => match r.status(now) {
+            RequestStatus::Pending => Ok(r),
+            RequestStatus::Expired => Err(RequestError::Expired),
+            settled => Err(RequestError::Settled(settled)),
+        },
+    })
+}
+
+/// Pays a request, as its payer. `message_id` is whatever asked for it to be paid, if anything.
+pub fn pay(
+    conn:&PgConnection,
+    request_id:i64,
+    payer:i64,
+    message_id:Option<i64>,
+) -> QueryResult<Result<PaymentRequest, RequestError>> {
+    use schema::payment_requests::dsl as prdsl;
+    let mut fail:Option<RequestError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let request = match lock_pending(conn, request_id, now, |r| r.payer == payer)? {
+            Ok(r) => r,
+            Err(e) => {
+                fail = Some(e);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        };
+        // retired item types can't be given, but a request made before one was retired can still be paid
+        let ty = item_type_of(conn, &request)?;
+        let transfer_id = match economy::give_transfer(
+            conn,
+            request.payer,
+            request.requested_by,
+            &ty,
+            request.quantity as u64,
+            message_id,
+            request.memo.as_deref(),
+        )? {
+            Ok(id) => id,
+            Err(GiveError::InsufficientBalance) => {
+                fail = Some(RequestError::InsufficientBalance);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            Err(e) => {
+                fail = Some(RequestError::Give(e));
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+        };
+        diesel::update(prdsl::payment_requests.filter(prdsl::rowid.eq(request_id)))
+            .set((
+                prdsl::status.eq(RequestStatus::Paid.db_name()),
+                prdsl::settled_at.eq(now),
+                prdsl::transfer.eq(transfer_id),
+            ))
+            .get_result(conn)
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+/// Turns down a request, as its payer, or withdraws it, as whoever made it.
+pub fn decline(conn:&PgConnection, request_id:i64, user:i64) -> QueryResult<Result<PaymentRequest, RequestError>> {
+    use schema::payment_requests::dsl as prdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let request = match lock_pending(conn, request_id, now, |r| r.payer == user || r.requested_by == user)? {
+            Ok(r) => r,
+            Err(e) => return Ok(Err(e)),
+        };
+        let status = if request.payer == user { RequestStatus::Declined } else { RequestStatus::Cancelled };
+        diesel::update(prdsl::payment_requests.filter(prdsl::rowid.eq(request_id)))
+            .set((
+                prdsl::status.eq(status.db_name()),
+                prdsl::settled_at.eq(now),
+            ))
+            .get_result(conn)
+            .map(Ok)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_db;
+
+    const REQUESTER:i64 = 1001;
+    const PAYER:i64 = 1002;
+
+    fn request(conn:&PgConnection, quantity:i64) -> PaymentRequest {
+        let pc = test_db::item_type(conn, "pc");
+        create(conn, REQUESTER, PAYER, &pc, quantity, Some("pizza"), None, chrono::Duration::days(7)).unwrap().unwrap()
+    }
+
+    fn by_id(conn:&PgConnection, request_id:i64) -> PaymentRequest {
+        use schema::payment_requests::dsl as prdsl;
+        prdsl::payment_requests.filter(prdsl::rowid.eq(request_id)).get_result(conn).unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn paying_moves_it_from_payer_to_requester() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 800);
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let requester_before = test_db::balance(&conn, REQUESTER, "pc");
+        let r = request(&conn, 500);
+        let paid = pay(&conn, r.rowid, PAYER, None).unwrap().unwrap();
+        assert_eq!(paid.status(chrono::Utc::now()), RequestStatus::Paid);
+        assert!(paid.transfer.is_some());
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before - 500);
+        assert_eq!(test_db::balance(&conn, REQUESTER, "pc"), requester_before + 500);
+    }
+
+    #[test]
+    #[ignore]
+    fn paying_twice_fails() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 800);
+        let r = request(&conn, 300);
+        pay(&conn, r.rowid, PAYER, None).unwrap().unwrap();
+        let payer_after = test_db::balance(&conn, PAYER, "pc");
+        assert_eq!(pay(&conn, r.rowid, PAYER, None).unwrap(), Err(RequestError::Settled(RequestStatus::Paid)));
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_after);
+    }
+
+    #[test]
+    #[ignore]
+    fn only_the_payer_can_pay() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, REQUESTER, "pc", 800);
+        let r = request(&conn, 300);
+        assert_eq!(pay(&conn, r.rowid, REQUESTER, None).unwrap(), Err(RequestError::NotYours));
+    }
+
+    #[test]
+    #[ignore]
+    fn paying_without_enough_leaves_it_pending() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 100);
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let r = request(&conn, payer_before + 1);
+        assert_eq!(pay(&conn, r.rowid, PAYER, None).unwrap(), Err(RequestError::InsufficientBalance));
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before);
+        let r = by_id(&conn, r.rowid);
+        assert_eq!(r.status(chrono::Utc::now()), RequestStatus::Pending);
+        assert_eq!(r.transfer, None);
+    }
+
+    #[test]
+    #[ignore]
+    fn declined_requests_cant_be_paid() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 800);
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let r = request(&conn, 300);
+        let declined = decline(&conn, r.rowid, PAYER).unwrap().unwrap();
+        assert_eq!(declined.status(chrono::Utc::now()), RequestStatus::Declined);
+        assert_eq!(pay(&conn, r.rowid, PAYER, None).unwrap(), Err(RequestError::Settled(RequestStatus::Declined)));
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before);
+    }
+
+    #[test]
+    #[ignore]
+    fn paid_requests_cant_be_declined_or_withdrawn() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 800);
+        let r = request(&conn, 300);
+        pay(&conn, r.rowid, PAYER, None).unwrap().unwrap();
+        assert_eq!(decline(&conn, r.rowid, PAYER).unwrap(), Err(RequestError::Settled(RequestStatus::Paid)));
+        assert_eq!(decline(&conn, r.rowid, REQUESTER).unwrap(), Err(RequestError::Settled(RequestStatus::Paid)));
+        assert_eq!(by_id(&conn, r.rowid).status(chrono::Utc::now()), RequestStatus::Paid);
+    }
+
+    #[test]
+    #[ignore]
+    fn the_requester_withdraws_rather_than_declines() {
+        let conn = test_db::connection();
+        let r = request(&conn, 300);
+        assert_eq!(decline(&conn, 0, 1003).unwrap(), Err(RequestError::NotFound));
+        assert_eq!(decline(&conn, r.rowid, 1003).unwrap(), Err(RequestError::NotYours));
+        let cancelled = decline(&conn, r.rowid, REQUESTER).unwrap().unwrap();
+        assert_eq!(cancelled.status(chrono::Utc::now()), RequestStatus::Cancelled);
+    }
+}
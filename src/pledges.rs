@@ -0,0 +1,349 @@
+//! Pledges: promises to pay someone if a motion passes, or if it fails, such as `$pledge 123 1000 pc
+//! to shelvacu if pass`. A pledge is taken from the pledger straight away and held in escrow, where
+//! it's nobody's, until the motion is announced. `settle` then releases it to the recipient or
+//! refunds it, and is called under the same lock on the motion as `create` takes, so no pledge
+//! can be made on a motion once its pledges have been settled.
+use std::collections::HashMap;
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::{damm, schema, settings};
+use crate::economy::{self, BoxError, ChatFrontend};
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum PledgeStatus {
+    /// In escrow until the motion is announced
+    Held,
+    Released,
+    Refunded,
+}
+
+impl Localize for PledgeStatus {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            PledgeStatus::Held => "pledge.status.held",
+            PledgeStatus::Released => "pledge.status.released",
+            PledgeStatus::Refunded => "pledge.status.refunded",
+        }).to_string()
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct Pledge {
+    pub rowid:i64,
+    pub motion:i64,
+    pub pledged_by:i64,
+    pub recipient:i64,
+    pub ty:String,
+    pub quantity:i64,
+    /// Paid if the motion passes, rather than if it fails
+    pub on_pass:bool,
+    pub pledged_at:DateTime<Utc>,
+    pub message_id:Option<i64>,
+    pub escrow_transfer:i64,
+    pub released:Option<bool>,
+    pub settled_at:Option<DateTime<Utc>>,
+    pub settle_transfer:Option<i64>,
+}
+
+impl Pledge {
+    pub fn status(&self) -> PledgeStatus {
+        match self.released {
+            None => PledgeStatus::Held,
+            Some(true) => PledgeStatus::Released,
+            Some(false) => PledgeStatus::Refunded,
+        }
+    }
+
+    /// Whether a motion with this result pays the recipient.
+    pub fn releases_on(&self, is_win:bool) -> bool {
+        self.on_pass == is_win
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum PledgeError {
+    NotPositive,
+    ToSelf,
+    MotionNotFound,
+    /// The motion has already been announced
+    MotionClosed,
+    InsufficientBalance{ balance: i64 },
+}
+
+impl Localize for PledgeError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            PledgeError::NotPositive => tr!(locale, "pledge.not_positive").to_string(),
+            PledgeError::ToSelf => tr!(locale, "pledge.to_self").to_string(),
+            PledgeError::MotionNotFound => tr!(locale, "vote.not_found").to_string(),
+            PledgeError::MotionClosed => tr!(locale, "vote.expired").to_string(),
+            PledgeError::InsufficientBalance{balance} => tr!(locale, "pledge.insufficient_balance", balance = locale.number(*balance)),
+        }
+    }
+}
+
+impl fmt::Display for PledgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Takes `quantity` of `ty` from `pledged_by` into escrow, to go to `recipient` once the motion is
+/// announced if its result is `on_pass`.
+#[allow(clippy::too_many_arguments)]
+pub fn create(
+    conn:&PgConnection,
+    motion_id:i64,
+    pledged_by:i64,
+    recipient:i64,
+    ty:&ItemType,
+    quantity:i64,
+    on_pass:bool,
+    message_id:Option<i64>,
+) -> QueryResult<Result<Pledge, PledgeError>> {
+    use schema::motions::dsl as mdsl;
+    use schema::pledges::dsl as pdsl;
+    use schema::transfers::dsl as tdsl;
+    if quantity <= 0 {
+        return Ok(Err(PledgeError::NotPositive));
+    }
+    if pledged_by == recipient {
+        return Ok(Err(PledgeError::ToSelf));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let not_announced:Option<bool> = mdsl::motions
+            .filter(mdsl::rowid.eq(motion_id))
+            .select(mdsl::announcement_message_id.is_null())
+            .for_update()
+            .get_result(conn)
+            .optional()?;
+        match not_announced {
+            None => return Ok(Err(PledgeError::MotionNotFound)),
+            Some(false) => return Ok(Err(PledgeError::MotionClosed)),
+            Some(true) => (),
+        }
+        let balance = economy::latest_balance(conn, pledged_by, ty.db_name(), true)?;
+        if balance < quantity {
+            return Ok(Err(PledgeError::InsufficientBalance{ balance }));
+        }
+        let now = chrono::Utc::now();
+        let escrow_transfer:i64 = diesel::insert_into(tdsl::transfers).values((
+            tdsl::from_user.eq(pledged_by),
+            tdsl::quantity.eq(quantity),
+            tdsl::from_balance.eq(balance - quantity),
+            tdsl::happened_at.eq(now),
+            tdsl::message_id.eq(message_id),
+            tdsl::ty.eq(ty.db_name()),
+            tdsl::transfer_ty.eq("pledge_escrow"),
+        )).returning(tdsl::rowid).get_result(conn)?;
+        diesel::insert_into(pdsl::pledges).values((
+            pdsl::motion.eq(motion_id),
+            pdsl::pledged_by.eq(pledged_by),
+            pdsl::recipient.eq(recipient),
+            pdsl::ty.eq(ty.db_name()),
+            pdsl::quantity.eq(quantity),
+            pdsl::on_pass.eq(on_pass),
+            pdsl::pledged_at.eq(now),
+            pdsl::message_id.eq(message_id),
+            pdsl::escrow_transfer.eq(escrow_transfer),
+        )).get_result(conn).map(Ok)
+    })
+}
+
+/// Every pledge on a motion, oldest first.
+pub fn on_motion(conn:&PgConnection, motion_id:i64) -> QueryResult<Vec<Pledge>> {
+    use schema::pledges::dsl as pdsl;
+    pdsl::pledges
+        .filter(pdsl::motion.eq(motion_id))
+        .order(pdsl::rowid.asc())
+        .get_results(conn)
+}
+
+/// The item type pledged, even if it's since been retired.
+pub fn item_type_of(conn:&PgConnection, pledge:&Pledge) -> QueryResult<ItemType> {
+    use schema::item_types::dsl as itdsl;
+    itdsl::item_types.filter(itdsl::name.eq(&pledge.ty)).get_result(conn)
+}
+
+/// Releases or refunds every pledge still held on a motion, now that `is_win` is its final result,
+/// returning them as settled. Must be called in a transaction holding a lock on the motion.
+pub fn settle(conn:&PgConnection, motion_id:i64, is_win:bool) -> QueryResult<Vec<Pledge>> {
+    use schema::pledges::dsl as pdsl;
+    use schema::transfers::dsl as tdsl;
+    let held:Vec<Pledge> = pdsl::pledges
+        .filter(pdsl::motion.eq(motion_id))
+        .filter(pdsl::released.is_null())
+        .order(pdsl::rowid.asc())
+        .for_update()
+        .get_results(conn)?;
+    let now = chrono::Utc::now();
+    // one user can be paid several times here, all at `now`, so keep their running balance rather
+    // than reading it back from the ledger each time
+    let mut balances:HashMap<(i64, String), i64> = HashMap::new();
+    held.into_iter().map(|pledge| {
+        let released = pledge.releases_on(is_win);
+        let to_user = if released { pledge.recipient } else { pledge.pledged_by };
+        let balance = match balances.get(&(to_user, pledge.ty.clone())) {
+            Some(balance) => *balance,
+            None => economy::latest_balance(conn, to_user, &pledge.ty, true)?,
+        };
+        balances.insert((to_user, pledge.ty.clone()), balance + pledge.quantity);
+        let settle_transfer:i64 = diesel::insert_into(tdsl::transfers).values((
+            tdsl::to_user.eq(to_user),
+            tdsl::quantity.eq(pledge.quantity),
+            tdsl::to_balance.eq(balance + pledge.quantity),
+            tdsl::happened_at.eq(now),
+            tdsl::ty.eq(&pledge.ty),
+            tdsl::transfer_ty.eq(if released { "pledge_release" } else { "pledge_refund" }),
+        )).returning(tdsl::rowid).get_result(conn)?;
+        diesel::update(pdsl::pledges.filter(pdsl::rowid.eq(pledge.rowid)))
+            .set((
+                pdsl::released.eq(released),
+                pdsl::settled_at.eq(now),
+                pdsl::settle_transfer.eq(settle_transfer),
+            ))
+            .get_result(conn)
+    }).collect()
+}
+
+/// DMs everyone involved in pledges that `settle` just settled. Falls back to `locale` for users
+/// who haven't picked one.
+pub fn notify_settled(
+    frontend:&impl ChatFrontend,
+    conn:&PgConnection,
+    locale:Locale,
+    is_win:bool,
+    settled:&[Pledge],
+) -> Result<(), BoxError> {
+    for pledge in settled {
+        let items = item_type_of(conn, pledge)?.long_name_ambiguous;
+        let motion = damm::add_to_str(pledge.motion.to_string());
+        let mut notices = vec![(
+            pledge.pledged_by,
+            if pledge.status() == PledgeStatus::Released { "pledge.notice_released" } else { "pledge.notice_refunded" },
+            pledge.recipient,
+        )];
+        if pledge.status() == PledgeStatus::Released {
+            notices.push((pledge.recipient, "pledge.notice_received", pledge.pledged_by));
+        }
+        for (user, key, other) in notices {
+            let user_locale = settings::user_locale(conn, user)?.unwrap_or(locale);
+            let text = tr!(
+                user_locale,
+                key,
+                motion = motion,
+                result = tr!(user_locale, if is_win { "pledge.passed" } else { "pledge.failed" }),
+                amount = user_locale.number(pledge.quantity),
+                items = items,
+                user = frontend.mention(other),
+            );
+            if let Err(e) = frontend.dm_user(user, &text) {
+                info!("Could not DM {} about pledge {}: {:?}", user, pledge.rowid, e);
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_db;
+
+    const PLEDGER:i64 = 1001;
+    const OTHER_PLEDGER:i64 = 1002;
+    const RECIPIENT:i64 = 1003;
+
+    fn pledge(conn:&PgConnection, motion_id:i64, pledged_by:i64, quantity:i64, on_pass:bool) -> Pledge {
+        let pc = test_db::item_type(conn, "pc");
+        create(conn, motion_id, pledged_by, RECIPIENT, &pc, quantity, on_pass, None).unwrap().unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn pledging_holds_it_in_escrow() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, PLEDGER);
+        test_db::fund(&conn, PLEDGER, "pc", 1000);
+        let pledger_before = test_db::balance(&conn, PLEDGER, "pc");
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let p = pledge(&conn, motion_id, PLEDGER, 400, true);
+        assert_eq!(p.status(), PledgeStatus::Held);
+        assert_eq!(test_db::balance(&conn, PLEDGER, "pc"), pledger_before - 400);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before);
+        let pc = test_db::item_type(&conn, "pc");
+        assert_eq!(
+            create(&conn, motion_id, PLEDGER, RECIPIENT, &pc, pledger_before, true, None).unwrap(),
+            Err(PledgeError::InsufficientBalance{ balance: pledger_before - 400 }),
+        );
+    }
+
+    #[test]
+    #[ignore]
+    fn cant_pledge_on_an_announced_motion() {
+        use schema::motions::dsl as mdsl;
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, PLEDGER);
+        test_db::fund(&conn, PLEDGER, "pc", 1000);
+        diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id)))
+            .set(mdsl::announcement_message_id.eq(1))
+            .execute(&conn)
+            .unwrap();
+        let pc = test_db::item_type(&conn, "pc");
+        assert_eq!(create(&conn, motion_id, PLEDGER, RECIPIENT, &pc, 100, true, None).unwrap(), Err(PledgeError::MotionClosed));
+    }
+
+    #[test]
+    #[ignore]
+    fn settling_releases_or_refunds_by_result() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, PLEDGER);
+        test_db::fund(&conn, PLEDGER, "pc", 1000);
+        let pledger_before = test_db::balance(&conn, PLEDGER, "pc");
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let if_pass = pledge(&conn, motion_id, PLEDGER, 300, true);
+        let if_fail = pledge(&conn, motion_id, PLEDGER, 200, false);
+        let settled = settle(&conn, motion_id, true).unwrap();
+        let statuses:Vec<(i64, PledgeStatus)> = settled.iter().map(|p| (p.rowid, p.status())).collect();
+        assert_eq!(statuses, vec![(if_pass.rowid, PledgeStatus::Released), (if_fail.rowid, PledgeStatus::Refunded)]);
+        assert_eq!(test_db::balance(&conn, PLEDGER, "pc"), pledger_before - 300);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 300);
+        // everything was settled the first time
+        assert_eq!(settle(&conn, motion_id, false).unwrap(), vec![]);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 300);
+    }
+
+    #[test]
+    #[ignore]
+    fn settling_pays_each_recipient_every_pledge() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, PLEDGER);
+        test_db::fund(&conn, PLEDGER, "pc", 1000);
+        test_db::fund(&conn, OTHER_PLEDGER, "pc", 1000);
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        pledge(&conn, motion_id, PLEDGER, 300, false);
+        pledge(&conn, motion_id, OTHER_PLEDGER, 200, false);
+        pledge(&conn, motion_id, PLEDGER, 100, false);
+        assert_eq!(settle(&conn, motion_id, false).unwrap().len(), 3);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 600);
+    }
+
+    #[test]
+    #[ignore]
+    fn refunding_returns_every_pledge() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, PLEDGER);
+        test_db::fund(&conn, PLEDGER, "pc", 1000);
+        let pledger_before = test_db::balance(&conn, PLEDGER, "pc");
+        pledge(&conn, motion_id, PLEDGER, 300, true);
+        pledge(&conn, motion_id, PLEDGER, 200, true);
+        settle(&conn, motion_id, false).unwrap();
+        assert_eq!(test_db::balance(&conn, PLEDGER, "pc"), pledger_before);
+    }
+}
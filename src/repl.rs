@@ -0,0 +1,237 @@
+//! A terminal frontend for the economy, for demos, trying out changes, and keeping things running
+//! when Discord is down. Commands are run as `REPL_USER` until `as` switches to someone else, and
+//! whatever would be posted to the motions channel is printed instead.
+use std::env;
+use std::io::{self, BufRead, Write};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use diesel::pg::PgConnection;
+
+use crate::bot::{GENERATE_EVERY, MOTION_EXPIRATION, POOL_COLLECTION_WINDOW};
+use crate::{admin, damm, parser, scheduled_transfers, vocabulary, vote_pools};
+use crate::economy::{self, BoxError, ChatFrontend, MotionStatus};
+use crate::i18n::Locale;
+
+const HELP:&str = "\
+Commands:
+  as <user id>                            act as another user
+  balances                                show your balances
+  give <user id> <amount> <type>          give someone items
+  fabricate <type> <amount> [user id]     create items out of nothing, for you or someone else
+  motion <text>                           call a motion
+  supermotion <text>                      call a motion needing a 2/3rds majority
+  vote <motion id> [direction] [amount]   vote on a motion, same as $vote
+  fund <motion id> <direction> <amount>   put capital into a vote pool, same as $fund
+  quit                                    exit";
+
+/// Ids for motions and announcements "posted" to the terminal. They're made like Discord snowflakes
+/// but counted from the Unix epoch instead of 2015, so they can't collide with real message ids.
+fn next_post_id() -> i64 {
+    static COUNTER:AtomicI64 = AtomicI64::new(0);
+    let millis = chrono::Utc::now().timestamp_millis();
+    (millis << 22) | (COUNTER.fetch_add(1, Ordering::Relaxed) & 0x3F_FFFF)
+}
+
+/// Prints motions, tallies, announcements and DMs to stdout.
+#[derive(Debug,Clone,Copy)]
+pub struct TerminalFrontend;
+
+impl ChatFrontend for TerminalFrontend {
+    fn post_motion(&self, status:&MotionStatus, motioned_by:i64) -> Result<i64, BoxError> {
+        println!(
+            "[motions] A motion has been called by {}: {} #{}: {}",
+            motioned_by,
+            status.kind_label(Locale::default()),
+            status.damm_id(),
+            status.text,
+        );
+        Ok(next_post_id())
+    }
+
+    fn update_motion(&self, _post_id:i64, status:&MotionStatus) -> Result<(), BoxError> {
+        let voters:Vec<String> = status.votes.iter()
+            .map(|v| format!("{} {} {}", v.user, v.amount, if v.direction {"for"} else {"against"}))
+            .collect();
+        println!(
+            "[motions] #{} is {} ({})",
+            status.damm_id(),
+            status.tally_text(Locale::default()).replace("**", ""),
+            voters.join(", "),
+        );
+        Ok(())
+    }
+
+    fn announce_result(&self, status:&MotionStatus, at:chrono::DateTime<chrono::Utc>) -> Result<i64, BoxError> {
+        println!(
+            "[motions] {} Vote ended! Motion #{} has {}: {} ({})",
+            at.to_rfc3339(),
+            status.damm_id(),
+            if status.is_win() { "PASSED" } else { "FAILED" },
+            status.text,
+            status.tally_text(Locale::default()).replace("**", ""),
+        );
+        Ok(next_post_id())
+    }
+
+    fn dm_user(&self, user:i64, text:&str) -> Result<(), BoxError> {
+        println!("[dm to {}] {}", user, text);
+        Ok(())
+    }
+}
+
+/// Runs one line of input as `user`, returning what to print.
+fn run_command(conn:&PgConnection, user:&mut i64, line:&str) -> Result<String, BoxError> {
+    let (command, rest) = match line.find(char::is_whitespace) {
+        Some(i) => (line[..i].to_lowercase(), line[i..].trim()),
+        None => (line.to_lowercase(), ""),
+    };
+    let command = vocabulary::command_for_alias(conn, &command)?.unwrap_or(command);
+    // motion text is taken as written, quotes and all
+    let args = if command.ends_with("motion") { Vec::new() } else { parser::tokenize(rest)? };
+    match (command.as_str(), args.as_slice()) {
+        ("help", _) => Ok(String::from(HELP)),
+        ("as", [id]) => {
+            *user = id.parse()?;
+            Ok(format!("Now acting as user {}.", user))
+        },
+        ("balances", []) | ("b", []) => {
+            let lines:Vec<String> = economy::balances_of(conn, *user)?
+                .iter()
+                .map(|(ty, amount)| format!("{}: {}", ty.long_name_plural, amount))
+                .collect();
+            Ok(format!("Your balances:\n{}", lines.join("\n")))
+        },
+        ("give", [to_user, give_args @ ..]) => {
+            let to_user:i64 = to_user.parse()?;
+            let (amount, ty_name, memo) = parser::parse_give_with_memo(give_args)?;
+            let ty = economy::find_item_type(conn, &ty_name.ok_or("Type not provided.")?)??;
+            let amount = amount.resolve(economy::balance_of(conn, *user, &ty)?).max(0) as u64;
+            Ok(match economy::give(conn, *user, to_user, &ty, amount, None, memo.as_deref())? {
+                Ok(()) => format!("Successfully transferred {} {} to {}.", amount, ty.long_name_ambiguous, to_user),
+                Err(e) => e.to_string(),
+            })
+        },
+        ("fabricate", [ty_name, amount, to_user @ ..]) if to_user.len() <= 1 => {
+            let to_user:i64 = match to_user.first() {
+                Some(id) => id.parse()?,
+                None => *user,
+            };
+            let amount = parser::parse_number(amount)? as i64;
+            let ty = economy::find_item_type(conn, ty_name)??;
+            let actor = admin::Actor{ user: *user, reason: Some("fabricated from the terminal"), message_id: None };
+            Ok(match admin::fabricate(conn, actor, to_user, &ty, amount)? {
+                Ok(_) => String::from("Fabricated."),
+                Err(e) => e.to_string(),
+            })
+        },
+        ("motion", _) | ("supermotion", _) if !rest.is_empty() => {
+            let is_super = command == "supermotion";
+            Ok(match economy::create_motion(&TerminalFrontend, conn, *user, next_post_id(), rest, is_super)? {
+                Ok(motion_id) => format!("Called motion #{}.", damm::add_to_str(motion_id.to_string())),
+                Err(e) => e.to_string(),
+            })
+        },
+        ("vote", [motion_id, vote_args @ ..]) => {
+            let motion_id = parser::parse_motion_id(motion_id)?;
+            let (direction, amount) = parser::parse_vote_args(&vocabulary::vote_vocabulary(conn)?, vote_args)?;
+            Ok(match economy::vote(conn, direction, amount, *user, Some(motion_id), None, None, None)? {
+                Ok(outcome) => outcome.to_string(),
+                Err(e) => e.to_string(),
+            })
+        },
+        ("fund", [motion_id, direction, amount]) => {
+            let motion_id = parser::parse_motion_id(motion_id)?;
+            let direction = match vocabulary::vote_vocabulary(conn)?.get(direction) {
+                Some(parser::VoteWord::Yes) => true,
+                Some(parser::VoteWord::No) => false,
+                _ => return Err("Expected a direction, like `for` or `against`.".into()),
+            };
+            let amount = parser::parse_amount(amount)?.resolve(economy::latest_balance(conn, *user, "pc", false)?);
+            Ok(match vote_pools::contribute(conn, motion_id, direction, *user, amount, None, *POOL_COLLECTION_WINDOW)? {
+                Ok((pool, _)) => format!("Put {} capital into pool #{}, which closes at {}.", amount, pool.rowid, pool.closes_at.to_rfc3339()),
+                Err(e) => e.to_string(),
+            })
+        },
+        _ => Ok(format!("Unrecognized command or wrong arguments: {}\n{}", line, HELP)),
+    }
+}
+
+pub fn main() {
+    let mut user:i64 = env::var("REPL_USER")
+        .expect("REPL_USER expected")
+        .parse()
+        .expect("REPL_USER must be a user id");
+
+    let pool = diesel::r2d2::Builder::new().build(
+        diesel::r2d2::ConnectionManager::<PgConnection>::new(
+            &env::var("DATABASE_URL").expect("DATABASE_URL expected")
+        )
+    ).expect("could not build DB pool");
+    economy::init(&*pool.get().unwrap()).unwrap();
+
+    let announce_conn = pool.get().unwrap();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_millis(500));
+            economy::announce_expired_motions(&TerminalFrontend, &*announce_conn, *MOTION_EXPIRATION, Locale::default()).unwrap();
+            economy::update_changed_motions(&TerminalFrontend, &*announce_conn).unwrap();
+        }
+    });
+
+    let generate_conn = pool.get().unwrap();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            if economy::generate_if_due(&*generate_conn, *GENERATE_EVERY).unwrap() {
+                println!("Generated some political capital!");
+            }
+        }
+    });
+
+    let schedules_conn = pool.get().unwrap();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let paid = scheduled_transfers::run_due(&TerminalFrontend, &*schedules_conn, Locale::default()).unwrap();
+            if paid > 0 {
+                println!("Paid {} scheduled transfers", paid);
+            }
+        }
+    });
+
+    let pools_conn = pool.get().unwrap();
+    thread::spawn(move || {
+        loop {
+            thread::sleep(Duration::from_secs(1));
+            let settled = vote_pools::run_due(&TerminalFrontend, &*pools_conn, Locale::default()).unwrap();
+            if settled > 0 {
+                println!("Settled {} vote pools", settled);
+            }
+        }
+    });
+
+    let conn = pool.get().unwrap();
+    println!("Acting as user {}. Type `help` for a list of commands.", user);
+    let stdin = io::stdin();
+    loop {
+        print!("{}> ", user);
+        io::stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).unwrap() == 0 {
+            break;
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "quit" || line == "exit" {
+            break;
+        }
+        match run_command(&*conn, &mut user, line) {
+            Ok(output) => println!("{}", output),
+            Err(e) => println!("ERR: {}", e),
+        }
+    }
+}
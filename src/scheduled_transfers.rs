@@ -0,0 +1,498 @@
+//! Scheduled transfers: a give repeated every so often, such as `$schedule give shelvacu 100 pc
+//! every 1d`, for salaries and subscriptions. A background worker calls `run_due`, which pays each
+//! occurrence through `economy::give_transfer`, recording it in `scheduled_transfer_runs` in the
+//! same transaction so that no occurrence is ever paid twice. An occurrence the payer can't cover
+//! is skipped, and they're told so.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::{parser, schema, settings};
+use crate::economy::{self, BoxError, ChatFrontend, GiveError};
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+
+/// Schedules can't be further apart than this many weeks, or end further off, which keeps every
+/// date they come due well within what `DateTime` can hold.
+pub const MAX_EVERY_WEEKS:i64 = 52;
+pub const MAX_UNTIL_WEEKS:i64 = 100 * 52;
+
+/// Whether a schedule will pay again.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ScheduleStatus {
+    Active,
+    /// Past its `until`
+    Finished,
+    Cancelled,
+}
+
+impl Localize for ScheduleStatus {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            ScheduleStatus::Active => "schedule.status.active",
+            ScheduleStatus::Finished => "schedule.status.finished",
+            ScheduleStatus::Cancelled => "schedule.status.cancelled",
+        }).to_string()
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct ScheduledTransfer {
+    pub rowid:i64,
+    pub from_user:i64,
+    pub to_user:i64,
+    pub ty:String,
+    pub quantity:i64,
+    pub memo:Option<String>,
+    pub every_seconds:i64,
+    pub next_at:DateTime<Utc>,
+    pub until:Option<DateTime<Utc>>,
+    pub created_at:DateTime<Utc>,
+    pub message_id:Option<i64>,
+    pub finished_at:Option<DateTime<Utc>>,
+    pub cancelled_by:Option<i64>,
+}
+
+impl ScheduledTransfer {
+    pub fn every(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.every_seconds)
+    }
+
+    pub fn status(&self) -> ScheduleStatus {
+        match (self.finished_at, self.cancelled_by) {
+            (None, _) => ScheduleStatus::Active,
+            (Some(_), None) => ScheduleStatus::Finished,
+            (Some(_), Some(_)) => ScheduleStatus::Cancelled,
+        }
+    }
+
+    /// When the occurrence after one due at `due_at` is due, if there is one.
+    fn next_after(&self, due_at:DateTime<Utc>) -> Option<DateTime<Utc>> {
+        due_at.checked_add_signed(self.every())
+            .filter(|next| self.until.map_or(true, |until| *next <= until))
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ScheduleError {
+    NotPositive,
+    ToSelf,
+    TooFrequent{ min: chrono::Duration },
+    TooInfrequent{ max: chrono::Duration },
+    /// `until` is before the first occurrence
+    EndsBeforeStart,
+    /// `until` is after this
+    EndsTooLate{ latest: DateTime<Utc> },
+    NotFound,
+    /// Only the payer and the recipient can cancel
+    NotYours,
+    Ended(ScheduleStatus),
+}
+
+impl Localize for ScheduleError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            ScheduleError::NotPositive => tr!(locale, "schedule.not_positive").to_string(),
+            ScheduleError::ToSelf => tr!(locale, "schedule.to_self").to_string(),
+            ScheduleError::TooFrequent{min} => tr!(locale, "schedule.too_frequent", min = parser::format_interval(*min)),
+            ScheduleError::TooInfrequent{max} => tr!(locale, "schedule.too_infrequent", max = parser::format_interval(*max)),
+            ScheduleError::EndsBeforeStart => tr!(locale, "schedule.ends_before_start").to_string(),
+            ScheduleError::EndsTooLate{latest} => tr!(locale, "schedule.ends_too_late", latest = locale.datetime(*latest)),
+            ScheduleError::NotFound => tr!(locale, "schedule.not_found").to_string(),
+            ScheduleError::NotYours => tr!(locale, "schedule.not_yours").to_string(),
+            ScheduleError::Ended(status) => tr!(locale, "schedule.ended", status = status.localize(locale)),
+        }
+    }
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Has `from_user` pay `to_user` some of `ty` every `every`, starting now, and with no occurrences
+/// after `until`, if given. Schedules can't come due more often than `min_every`, or less often
+/// than every `MAX_EVERY_WEEKS`.
+#[allow(clippy::too_many_arguments)]
+pub fn create(
+    conn:&PgConnection,
+    from_user:i64,
+    to_user:i64,
+    ty:&ItemType,
+    quantity:i64,
+    memo:Option<&str>,
+    every:chrono::Duration,
+    until:Option<DateTime<Utc>>,
+    message_id:Option<i64>,
+    min_every:chrono::Duration,
+) -> QueryResult<Result<ScheduledTransfer, ScheduleError>> {
+    use schema::scheduled_transfers::dsl as stdsl;
+    if quantity <= 0 {
+        return Ok(Err(ScheduleError::NotPositive));
+    }
+    if from_user == to_user {
+        return Ok(Err(ScheduleError::ToSelf));
+    }
+    if every < min_every {
+        return Ok(Err(ScheduleError::TooFrequent{ min: min_every }));
+    }
+    if every > chrono::Duration::weeks(MAX_EVERY_WEEKS) {
+        return Ok(Err(ScheduleError::TooInfrequent{ max: chrono::Duration::weeks(MAX_EVERY_WEEKS) }));
+    }
+    let now = chrono::Utc::now();
+    if until.map_or(false, |until| until < now) {
+        return Ok(Err(ScheduleError::EndsBeforeStart));
+    }
+    let latest = now + chrono::Duration::weeks(MAX_UNTIL_WEEKS);
+    if until.map_or(false, |until| until > latest) {
+        return Ok(Err(ScheduleError::EndsTooLate{ latest }));
+    }
+    diesel::insert_into(stdsl::scheduled_transfers).values((
+        stdsl::from_user.eq(from_user),
+        stdsl::to_user.eq(to_user),
+        stdsl::ty.eq(ty.db_name()),
+        stdsl::quantity.eq(quantity),
+        stdsl::memo.eq(memo),
+        stdsl::every_seconds.eq(every.num_seconds()),
+        stdsl::next_at.eq(now),
+        stdsl::until.eq(until),
+        stdsl::created_at.eq(now),
+        stdsl::message_id.eq(message_id),
+    )).get_result(conn).map(Ok)
+}
+
+/// Every schedule the user pays or is paid by, newest first.
+pub fn involving(conn:&PgConnection, user:i64, limit:i64) -> QueryResult<Vec<ScheduledTransfer>> {
+    use schema::scheduled_transfers::dsl as stdsl;
+    stdsl::scheduled_transfers
+        .filter(stdsl::from_user.eq(user).or(stdsl::to_user.eq(user)))
+        .order(stdsl::rowid.desc())
+        .limit(limit)
+        .get_results(conn)
+}
+
+/// The item type paid, even if it's since been retired.
+pub fn item_type_of(conn:&PgConnection, schedule:&ScheduledTransfer) -> QueryResult<ItemType> {
+    use schema::item_types::dsl as itdsl;
+    itdsl::item_types.filter(itdsl::name.eq(&schedule.ty)).get_result(conn)
+}
+
+/// Stops a schedule, as either the payer or the recipient.
+pub fn cancel(conn:&PgConnection, schedule_id:i64, user:i64) -> QueryResult<Result<ScheduledTransfer, ScheduleError>> {
+    use schema::scheduled_transfers::dsl as stdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let schedule:Option<ScheduledTransfer> = stdsl::scheduled_transfers
+            .filter(stdsl::rowid.eq(schedule_id))
+            .for_update()
+            .get_result(conn)
+            .optional()?;
+        match schedule {
+            None => return Ok(Err(ScheduleError::NotFound)),
+            Some(s) if s.from_user != user && s.to_user != user => return Ok(Err(ScheduleError::NotYours)),
+            Some(s) if s.status() != ScheduleStatus::Active => return Ok(Err(ScheduleError::Ended(s.status()))),
+            Some(_) => (),
+        }
+        diesel::update(stdsl::scheduled_transfers.filter(stdsl::rowid.eq(schedule_id)))
+            .set((
+                stdsl::finished_at.eq(chrono::Utc::now()),
+                stdsl::cancelled_by.eq(user),
+            ))
+            .get_result(conn)
+            .map(Ok)
+    })
+}
+
+/// What became of one occurrence.
+enum Occurrence {
+    Paid,
+    /// The payer couldn't cover it
+    Skipped{ schedule: ScheduledTransfer, due_at: DateTime<Utc> },
+    /// Had already been run, though the schedule hadn't moved on from it
+    AlreadyRun,
+    /// Couldn't be paid for some reason other than the payer's balance, so it was left to retry
+    Failed(GiveError),
+    /// No longer due
+    Nothing,
+}
+
+/// Pays the occurrence of a schedule that's due, if it still is.
+fn run_occurrence(conn:&PgConnection, schedule_id:i64, now:DateTime<Utc>) -> QueryResult<Occurrence> {
+    use schema::scheduled_transfers::dsl as stdsl;
+    use schema::scheduled_transfer_runs::dsl as srdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let schedule:ScheduledTransfer = stdsl::scheduled_transfers
+            .filter(stdsl::rowid.eq(schedule_id))
+            .for_update()
+            .get_result(conn)?;
+        if schedule.status() != ScheduleStatus::Active || schedule.next_at > now {
+            return Ok(Occurrence::Nothing);
+        }
+        let due_at = schedule.next_at;
+        let already_run = srdsl::scheduled_transfer_runs
+            .filter(srdsl::schedule.eq(schedule_id))
+            .filter(srdsl::due_at.eq(due_at))
+            .select(srdsl::ran_at)
+            .first::<DateTime<Utc>>(conn)
+            .optional()?
+            .is_some();
+        let transfer_id = if already_run {
+            None
+        } else {
+            let ty = item_type_of(conn, &schedule)?;
+            let transfer_id = match economy::give_transfer(
+                conn,
+                schedule.from_user,
+                schedule.to_user,
+                &ty,
+                schedule.quantity as u64,
+                schedule.message_id,
+                schedule.memo.as_deref(),
+            )? {
+                Ok(id) => Some(id),
+                Err(GiveError::InsufficientBalance) => None,
+                Err(e) => return Ok(Occurrence::Failed(e)),
+            };
+            diesel::insert_into(srdsl::scheduled_transfer_runs).values((
+                srdsl::schedule.eq(schedule_id),
+                srdsl::due_at.eq(due_at),
+                srdsl::ran_at.eq(now),
+                srdsl::transfer.eq(transfer_id),
+            )).execute(conn)?;
+            transfer_id
+        };
+        // moved on even if it was already run, so the schedule isn't stuck on it; one that can't go
+        // on stays where it ended
+        let next_at = schedule.next_after(due_at);
+        let finished_at = if next_at.is_some() { None } else { Some(now) };
+        diesel::update(stdsl::scheduled_transfers.filter(stdsl::rowid.eq(schedule_id)))
+            .set((
+                stdsl::next_at.eq(next_at.unwrap_or(due_at)),
+                stdsl::finished_at.eq(finished_at),
+            ))
+            .execute(conn)?;
+        Ok(match (already_run, transfer_id) {
+            (true, _) => Occurrence::AlreadyRun,
+            (false, Some(_)) => Occurrence::Paid,
+            (false, None) => Occurrence::Skipped{ schedule, due_at },
+        })
+    })
+}
+
+/// DMs the payer that they couldn't cover the occurrence due at `due_at`.
+fn notify_skipped(
+    frontend:&impl ChatFrontend,
+    conn:&PgConnection,
+    locale:Locale,
+    schedule:&ScheduledTransfer,
+    due_at:DateTime<Utc>,
+) -> Result<(), BoxError> {
+    let payer_locale = settings::user_locale(conn, schedule.from_user)?.unwrap_or(locale);
+    let items = item_type_of(conn, schedule)?.long_name_ambiguous;
+    let text = if let Some(next) = schedule.next_after(due_at) {
+        tr!(
+            payer_locale,
+            "schedule.skipped",
+            id = schedule.rowid,
+            amount = payer_locale.number(schedule.quantity),
+            items = items,
+            due = payer_locale.datetime(due_at),
+            next = payer_locale.datetime(next),
+        )
+    } else {
+        tr!(
+            payer_locale,
+            "schedule.skipped_last",
+            id = schedule.rowid,
+            amount = payer_locale.number(schedule.quantity),
+            items = items,
+            due = payer_locale.datetime(due_at),
+        )
+    };
+    if let Err(e) = frontend.dm_user(schedule.from_user, &text) {
+        info!("Could not DM {} about scheduled transfer {}: {:?}", schedule.from_user, schedule.rowid, e);
+    }
+    Ok(())
+}
+
+/// Runs every occurrence that's come due, DMing the payer about any they couldn't cover. If the
+/// bot was down through several occurrences of a schedule, each of them is run in turn. Falls back
+/// to `locale` for payers who haven't picked one. Returns how many were paid.
+pub fn run_due(frontend:&impl ChatFrontend, conn:&PgConnection, locale:Locale) -> Result<usize, BoxError> {
+    use schema::scheduled_transfers::dsl as stdsl;
+    let now = chrono::Utc::now();
+    let due:Vec<i64> = stdsl::scheduled_transfers
+        .select(stdsl::rowid)
+        .filter(stdsl::finished_at.is_null())
+        .filter(stdsl::next_at.le(now))
+        .order(stdsl::next_at.asc())
+        .get_results(conn)?;
+    let mut paid = 0;
+    for schedule_id in due {
+        // each occurrence moves `next_at` on, until it's past `now` and there's nothing left to run
+        loop {
+            match run_occurrence(conn, schedule_id, now)? {
+                Occurrence::Paid => paid += 1,
+                Occurrence::AlreadyRun => (),
+                Occurrence::Nothing => break,
+                Occurrence::Skipped{schedule, due_at} => notify_skipped(frontend, conn, locale, &schedule, due_at)?,
+                Occurrence::Failed(e) => {
+                    warn!("Could not run scheduled transfer {}: {}", schedule_id, e);
+                    break
+                },
+            }
+        }
+    }
+    Ok(paid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::repl::TerminalFrontend;
+    use crate::test_db;
+
+    const PAYER:i64 = 1001;
+    const RECIPIENT:i64 = 1002;
+
+    fn schedule(conn:&PgConnection, quantity:i64, until:Option<DateTime<Utc>>) -> ScheduledTransfer {
+        let pc = test_db::item_type(conn, "pc");
+        create(
+            conn,
+            PAYER,
+            RECIPIENT,
+            &pc,
+            quantity,
+            None,
+            chrono::Duration::days(1),
+            until,
+            None,
+            chrono::Duration::hours(1),
+        ).unwrap().unwrap()
+    }
+
+    fn by_id(conn:&PgConnection, schedule_id:i64) -> ScheduledTransfer {
+        use schema::scheduled_transfers::dsl as stdsl;
+        stdsl::scheduled_transfers.filter(stdsl::rowid.eq(schedule_id)).get_result(conn).unwrap()
+    }
+
+    /// The occurrences run so far, as when each was due and whether it was paid.
+    fn runs(conn:&PgConnection, schedule_id:i64) -> Vec<(DateTime<Utc>, bool)> {
+        use schema::scheduled_transfer_runs::dsl as srdsl;
+        let runs:Vec<(DateTime<Utc>, Option<i64>)> = srdsl::scheduled_transfer_runs
+            .select((srdsl::due_at, srdsl::transfer))
+            .filter(srdsl::schedule.eq(schedule_id))
+            .order(srdsl::due_at.asc())
+            .get_results(conn)
+            .unwrap();
+        runs.into_iter().map(|(due_at, transfer)| (due_at, transfer.is_some())).collect()
+    }
+
+    #[test]
+    #[ignore]
+    fn pays_an_occurrence_once() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 500);
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let s = schedule(&conn, 100, None);
+        let now = chrono::Utc::now();
+        assert!(matches!(run_occurrence(&conn, s.rowid, now).unwrap(), Occurrence::Paid));
+        assert!(matches!(run_occurrence(&conn, s.rowid, now).unwrap(), Occurrence::Nothing));
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before - 100);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 100);
+        assert_eq!(runs(&conn, s.rowid), vec![(s.next_at, true)]);
+        assert_eq!(by_id(&conn, s.rowid).next_at, s.next_at + s.every());
+    }
+
+    #[test]
+    #[ignore]
+    fn never_pays_an_occurrence_already_run() {
+        use schema::scheduled_transfer_runs::dsl as srdsl;
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 500);
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let s = schedule(&conn, 100, None);
+        // as if another worker ran it but `next_at` was never moved on
+        diesel::insert_into(srdsl::scheduled_transfer_runs).values((
+            srdsl::schedule.eq(s.rowid),
+            srdsl::due_at.eq(s.next_at),
+            srdsl::ran_at.eq(s.next_at),
+        )).execute(&conn).unwrap();
+        assert!(matches!(run_occurrence(&conn, s.rowid, chrono::Utc::now()).unwrap(), Occurrence::AlreadyRun));
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before);
+        assert_eq!(runs(&conn, s.rowid), vec![(s.next_at, false)]);
+        assert_eq!(by_id(&conn, s.rowid).next_at, s.next_at + s.every());
+    }
+
+    #[test]
+    #[ignore]
+    fn skips_what_the_payer_cant_cover() {
+        let conn = test_db::connection();
+        let payer_before = test_db::balance(&conn, PAYER, "pc");
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let s = schedule(&conn, payer_before + 1, None);
+        match run_occurrence(&conn, s.rowid, chrono::Utc::now()).unwrap() {
+            Occurrence::Skipped{due_at, ..} => assert_eq!(due_at, s.next_at),
+            _ => panic!("expected the occurrence to be skipped"),
+        }
+        assert_eq!(test_db::balance(&conn, PAYER, "pc"), payer_before);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before);
+        assert_eq!(runs(&conn, s.rowid), vec![(s.next_at, false)]);
+        let s = by_id(&conn, s.rowid);
+        assert_eq!(s.status(), ScheduleStatus::Active);
+    }
+
+    #[test]
+    #[ignore]
+    fn catches_up_on_every_missed_occurrence() {
+        use schema::scheduled_transfers::dsl as stdsl;
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 1000);
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let s = schedule(&conn, 100, None);
+        // down for three days: the occurrences due then, and the one due today, are all owed
+        let first_due = s.next_at - chrono::Duration::days(3);
+        diesel::update(stdsl::scheduled_transfers.filter(stdsl::rowid.eq(s.rowid)))
+            .set(stdsl::next_at.eq(first_due))
+            .execute(&conn)
+            .unwrap();
+        run_due(&TerminalFrontend, &conn, Locale::En).unwrap();
+        let due:Vec<DateTime<Utc>> = (0..4).map(|i| first_due + chrono::Duration::days(i)).collect();
+        assert_eq!(runs(&conn, s.rowid), due.iter().map(|&d| (d, true)).collect::<Vec<_>>());
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 400);
+        assert_eq!(by_id(&conn, s.rowid).next_at, s.next_at + s.every());
+    }
+
+    #[test]
+    #[ignore]
+    fn finishes_after_the_last_occurrence() {
+        let conn = test_db::connection();
+        test_db::fund(&conn, PAYER, "pc", 500);
+        let s = schedule(&conn, 100, Some(chrono::Utc::now() + chrono::Duration::hours(12)));
+        assert!(matches!(run_occurrence(&conn, s.rowid, chrono::Utc::now()).unwrap(), Occurrence::Paid));
+        assert_eq!(by_id(&conn, s.rowid).status(), ScheduleStatus::Finished);
+        assert!(matches!(run_occurrence(&conn, s.rowid, chrono::Utc::now() + chrono::Duration::days(1)).unwrap(), Occurrence::Nothing));
+    }
+
+    #[test]
+    #[ignore]
+    fn refuses_schedules_that_reach_too_far() {
+        let conn = test_db::connection();
+        let pc = test_db::item_type(&conn, "pc");
+        let max_every = chrono::Duration::weeks(MAX_EVERY_WEEKS);
+        let min_every = chrono::Duration::hours(1);
+        assert_eq!(
+            create(&conn, PAYER, RECIPIENT, &pc, 1, None, max_every + chrono::Duration::weeks(1), None, None, min_every).unwrap(),
+            Err(ScheduleError::TooInfrequent{ max: max_every }),
+        );
+        let far_off = chrono::Utc::now() + chrono::Duration::weeks(MAX_UNTIL_WEEKS + 1);
+        match create(&conn, PAYER, RECIPIENT, &pc, 1, None, max_every, Some(far_off), None, min_every).unwrap() {
+            Err(ScheduleError::EndsTooLate{..}) => (),
+            other => panic!("expected EndsTooLate, got {:?}", other),
+        }
+        assert!(create(&conn, PAYER, RECIPIENT, &pc, 1, None, max_every, None, None, min_every).unwrap().is_ok());
+    }
+}
@@ -1,3 +1,62 @@
+table! {
+    admin_actions (rowid) {
+        rowid -> Int8,
+        actor -> Int8,
+        action -> Text,
+        reason -> Nullable<Text>,
+        message_id -> Nullable<Int8>,
+        happened_at -> Timestamptz,
+        target -> Nullable<Int8>,
+        parameters -> Jsonb,
+    }
+}
+
+table! {
+    admin_roles (role) {
+        role -> Int8,
+        guild -> Int8,
+        granted_by -> Int8,
+        granted_at -> Timestamptz,
+    }
+}
+
+table! {
+    admin_users (user) {
+        user -> Int8,
+        granted_by -> Int8,
+        granted_at -> Timestamptz,
+    }
+}
+
+table! {
+    allowances (rowid) {
+        rowid -> Int8,
+        owner -> Int8,
+        spender -> Int8,
+        ty -> Text,
+        quantity -> Int8,
+        spent -> Int8,
+        granted_at -> Timestamptz,
+        expires_at -> Nullable<Timestamptz>,
+        revoked_at -> Nullable<Timestamptz>,
+        message_id -> Nullable<Int8>,
+    }
+}
+
+table! {
+    command_aliases (alias) {
+        alias -> Text,
+        command -> Text,
+    }
+}
+
+table! {
+    guild_settings (guild) {
+        guild -> Int8,
+        locale -> Nullable<Text>,
+    }
+}
+
 table! {
     item_type_aliases (alias) {
         name -> Text,
@@ -10,6 +69,7 @@ table! {
         name -> Text,
         long_name_plural -> Text,
         long_name_ambiguous -> Text,
+        retired -> Bool,
     }
 }
 
@@ -19,6 +79,17 @@ table! {
     }
 }
 
+table! {
+    motion_result_changes (rowid) {
+        rowid -> Int8,
+        motion -> Int8,
+        changed_at -> Timestamptz,
+        is_win -> Bool,
+        yes_votes -> Int8,
+        no_votes -> Int8,
+    }
+}
+
 table! {
     motions (rowid) {
         rowid -> Int8,
@@ -43,6 +114,83 @@ table! {
     }
 }
 
+table! {
+    payment_requests (rowid) {
+        rowid -> Int8,
+        requested_by -> Int8,
+        payer -> Int8,
+        ty -> Text,
+        quantity -> Int8,
+        memo -> Nullable<Text>,
+        requested_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        message_id -> Nullable<Int8>,
+        prompt_message_id -> Nullable<Int8>,
+        status -> Text,
+        settled_at -> Nullable<Timestamptz>,
+        transfer -> Nullable<Int8>,
+    }
+}
+
+table! {
+    pledges (rowid) {
+        rowid -> Int8,
+        motion -> Int8,
+        pledged_by -> Int8,
+        recipient -> Int8,
+        ty -> Text,
+        quantity -> Int8,
+        on_pass -> Bool,
+        pledged_at -> Timestamptz,
+        message_id -> Nullable<Int8>,
+        escrow_transfer -> Int8,
+        released -> Nullable<Bool>,
+        settled_at -> Nullable<Timestamptz>,
+        settle_transfer -> Nullable<Int8>,
+    }
+}
+
+table! {
+    reaction_votes (rowid) {
+        rowid -> Int8,
+        motion -> Int8,
+        user -> Int8,
+        emoji -> Text,
+        transfer -> Int8,
+        refund_transfer -> Nullable<Int8>,
+        votes -> Int8,
+        cost -> Int8,
+        cast_at -> Timestamptz,
+    }
+}
+
+table! {
+    scheduled_transfer_runs (schedule, due_at) {
+        schedule -> Int8,
+        due_at -> Timestamptz,
+        ran_at -> Timestamptz,
+        transfer -> Nullable<Int8>,
+    }
+}
+
+table! {
+    scheduled_transfers (rowid) {
+        rowid -> Int8,
+        from_user -> Int8,
+        to_user -> Int8,
+        ty -> Text,
+        quantity -> Int8,
+        memo -> Nullable<Text>,
+        every_seconds -> Int8,
+        next_at -> Timestamptz,
+        until -> Nullable<Timestamptz>,
+        created_at -> Timestamptz,
+        message_id -> Nullable<Int8>,
+        finished_at -> Nullable<Timestamptz>,
+        cancelled_by -> Nullable<Int8>,
+    }
+}
+
 table! {
     single (enforce_single_row) {
         enforce_single_row -> Bool,
@@ -65,20 +213,153 @@ table! {
         to_votes -> Nullable<Int8>,
         comment -> Nullable<Text>,
         transfer_ty -> Text,
+        admin_action -> Nullable<Int8>,
+        acting_user -> Nullable<Int8>,
+    }
+}
+
+table! {
+    treasuries (rowid) {
+        rowid -> Int8,
+        name -> Text,
+        threshold -> Int8,
+        created_by -> Int8,
+        created_at -> Timestamptz,
+    }
+}
+
+table! {
+    treasury_approvals (proposal, user) {
+        proposal -> Int8,
+        user -> Int8,
+        approved_at -> Timestamptz,
+    }
+}
+
+table! {
+    treasury_members (treasury, user) {
+        treasury -> Int8,
+        user -> Int8,
+        added_at -> Timestamptz,
+    }
+}
+
+table! {
+    treasury_proposals (rowid) {
+        rowid -> Int8,
+        treasury -> Int8,
+        proposed_by -> Int8,
+        kind -> Text,
+        ty -> Nullable<Text>,
+        quantity -> Int8,
+        to_user -> Nullable<Int8>,
+        memo -> Nullable<Text>,
+        motion -> Nullable<Int8>,
+        direction -> Nullable<Bool>,
+        proposed_at -> Timestamptz,
+        expires_at -> Timestamptz,
+        message_id -> Nullable<Int8>,
+        status -> Text,
+        settled_at -> Nullable<Timestamptz>,
+        transfer -> Nullable<Int8>,
+    }
+}
+
+table! {
+    user_settings (user) {
+        user -> Int8,
+        hide_holdings -> Bool,
+        vote_receipts -> Text,
+        locale -> Nullable<Text>,
+    }
+}
+
+table! {
+    vote_pool_contributions (rowid) {
+        rowid -> Int8,
+        pool -> Int8,
+        user -> Int8,
+        quantity -> Int8,
+        contributed_at -> Timestamptz,
+        message_id -> Nullable<Int8>,
+        transfer -> Int8,
+        refunded -> Nullable<Int8>,
+        refund_transfer -> Nullable<Int8>,
+    }
+}
+
+table! {
+    vote_pools (rowid) {
+        rowid -> Int8,
+        motion -> Int8,
+        direction -> Bool,
+        created_by -> Int8,
+        created_at -> Timestamptz,
+        closes_at -> Timestamptz,
+        settled_at -> Nullable<Timestamptz>,
+        votes -> Nullable<Int8>,
+        cost -> Nullable<Int8>,
+        account -> Int8,
+    }
+}
+
+table! {
+    vote_words (word) {
+        word -> Text,
+        meaning -> Text,
     }
 }
 
+joinable!(allowances -> item_types (ty));
 joinable!(item_type_aliases -> item_types (name));
+joinable!(motion_result_changes -> motions (motion));
 joinable!(motion_votes -> motions (motion));
 joinable!(motions -> motion_ids (rowid));
+joinable!(payment_requests -> item_types (ty));
+joinable!(payment_requests -> transfers (transfer));
+joinable!(pledges -> item_types (ty));
+joinable!(pledges -> motions (motion));
+joinable!(reaction_votes -> motions (motion));
+joinable!(scheduled_transfer_runs -> scheduled_transfers (schedule));
+joinable!(scheduled_transfer_runs -> transfers (transfer));
+joinable!(scheduled_transfers -> item_types (ty));
+joinable!(transfers -> admin_actions (admin_action));
 joinable!(transfers -> item_types (ty));
+joinable!(treasury_approvals -> treasury_proposals (proposal));
+joinable!(treasury_members -> treasuries (treasury));
+joinable!(treasury_proposals -> item_types (ty));
+joinable!(treasury_proposals -> motions (motion));
+joinable!(treasury_proposals -> transfers (transfer));
+joinable!(treasury_proposals -> treasuries (treasury));
+joinable!(vote_pool_contributions -> vote_pools (pool));
+joinable!(vote_pools -> motions (motion));
 
 allow_tables_to_appear_in_same_query!(
+    admin_actions,
+    admin_roles,
+    admin_users,
+    allowances,
+    command_aliases,
+    guild_settings,
     item_type_aliases,
     item_types,
     motion_ids,
+    motion_result_changes,
     motions,
     motion_votes,
+    payment_requests,
+    pledges,
+    reaction_votes,
+    scheduled_transfer_runs,
+    scheduled_transfers,
     single,
     transfers,
+    treasuries,
+    treasury_approvals,
+    treasury_members,
+    treasury_proposals,
+    user_settings,
+    vote_pool_contributions,
+    vote_pools,
+    vote_words,
 );
@@ -0,0 +1,155 @@
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+
+use crate::schema;
+use crate::i18n::Locale;
+
+/// Whether the user has asked for their holdings to be kept out of profiles and leaderboards.
+/// Users without a settings row have not opted out.
+pub fn holdings_hidden(conn: &PgConnection, user_id: i64) -> QueryResult<bool> {
+    use schema::user_settings::dsl as usdsl;
+    Ok(usdsl::user_settings
+        .select(usdsl::hide_holdings)
+        .filter(usdsl::user.eq(user_id))
+        .get_result(conn)
+        .optional()?
+        .unwrap_or(false))
+}
+
+pub fn set_holdings_hidden(conn: &PgConnection, user_id: i64, hide: bool) -> QueryResult<()> {
+    use schema::user_settings::dsl as usdsl;
+    diesel::insert_into(usdsl::user_settings)
+        .values((
+            usdsl::user.eq(user_id),
+            usdsl::hide_holdings.eq(hide),
+        ))
+        .on_conflict(usdsl::user)
+        .do_update()
+        .set(usdsl::hide_holdings.eq(hide))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// When to DM a user about votes they cast by reacting.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ReceiptPref {
+    Always,
+    ErrorsOnly,
+    Never,
+}
+
+impl ReceiptPref {
+    pub fn db_name(self) -> &'static str {
+        match self {
+            ReceiptPref::Always => "always",
+            ReceiptPref::ErrorsOnly => "errors",
+            ReceiptPref::Never => "never",
+        }
+    }
+
+    pub fn from_db_name(name: &str) -> Option<Self> {
+        match name {
+            "always" => Some(ReceiptPref::Always),
+            "errors" => Some(ReceiptPref::ErrorsOnly),
+            "never" => Some(ReceiptPref::Never),
+            _ => None,
+        }
+    }
+
+    pub fn wants(self, is_error: bool) -> bool {
+        match self {
+            ReceiptPref::Always => true,
+            ReceiptPref::ErrorsOnly => is_error,
+            ReceiptPref::Never => false,
+        }
+    }
+}
+
+pub fn vote_receipts(conn: &PgConnection, user_id: i64) -> QueryResult<ReceiptPref> {
+    use schema::user_settings::dsl as usdsl;
+    let name:Option<String> = usdsl::user_settings
+        .select(usdsl::vote_receipts)
+        .filter(usdsl::user.eq(user_id))
+        .get_result(conn)
+        .optional()?;
+    Ok(name.and_then(|n| ReceiptPref::from_db_name(&n)).unwrap_or(ReceiptPref::Always))
+}
+
+pub fn set_vote_receipts(conn: &PgConnection, user_id: i64, pref: ReceiptPref) -> QueryResult<()> {
+    use schema::user_settings::dsl as usdsl;
+    diesel::insert_into(usdsl::user_settings)
+        .values((
+            usdsl::user.eq(user_id),
+            usdsl::vote_receipts.eq(pref.db_name()),
+        ))
+        .on_conflict(usdsl::user)
+        .do_update()
+        .set(usdsl::vote_receipts.eq(pref.db_name()))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The language the user has picked, if any.
+pub fn user_locale(conn: &PgConnection, user_id: i64) -> QueryResult<Option<Locale>> {
+    use schema::user_settings::dsl as usdsl;
+    let code:Option<Option<String>> = usdsl::user_settings
+        .select(usdsl::locale)
+        .filter(usdsl::user.eq(user_id))
+        .get_result(conn)
+        .optional()?;
+    Ok(code.flatten().and_then(|c| Locale::from_code(&c)))
+}
+
+/// None goes back to the guild's language.
+pub fn set_user_locale(conn: &PgConnection, user_id: i64, locale: Option<Locale>) -> QueryResult<()> {
+    use schema::user_settings::dsl as usdsl;
+    let code = locale.map(Locale::code);
+    diesel::insert_into(usdsl::user_settings)
+        .values((
+            usdsl::user.eq(user_id),
+            usdsl::locale.eq(code),
+        ))
+        .on_conflict(usdsl::user)
+        .do_update()
+        .set(usdsl::locale.eq(code))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The language a guild's admins have picked for it, if any.
+pub fn guild_locale(conn: &PgConnection, guild_id: i64) -> QueryResult<Option<Locale>> {
+    use schema::guild_settings::dsl as gsdsl;
+    let code:Option<Option<String>> = gsdsl::guild_settings
+        .select(gsdsl::locale)
+        .filter(gsdsl::guild.eq(guild_id))
+        .get_result(conn)
+        .optional()?;
+    Ok(code.flatten().and_then(|c| Locale::from_code(&c)))
+}
+
+/// None goes back to English.
+pub fn set_guild_locale(conn: &PgConnection, guild_id: i64, locale: Option<Locale>) -> QueryResult<()> {
+    use schema::guild_settings::dsl as gsdsl;
+    let code = locale.map(Locale::code);
+    diesel::insert_into(gsdsl::guild_settings)
+        .values((
+            gsdsl::guild.eq(guild_id),
+            gsdsl::locale.eq(code),
+        ))
+        .on_conflict(gsdsl::guild)
+        .do_update()
+        .set(gsdsl::locale.eq(code))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// The language to speak to a user in: their own choice, or else that of the guild they're in.
+pub fn locale_for(conn: &PgConnection, user_id: i64, guild_id: Option<i64>) -> QueryResult<Locale> {
+    if let Some(locale) = user_locale(conn, user_id)? {
+        return Ok(locale);
+    }
+    Ok(match guild_id {
+        Some(guild_id) => guild_locale(conn, guild_id)?.unwrap_or_default(),
+        None => Locale::default(),
+    })
+}
@@ -0,0 +1,461 @@
+use std::collections::HashMap;
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{BigInt, Bool, Text, Timestamptz};
+use bigdecimal::{BigDecimal, ToPrimitive};
+use chrono::{DateTime, Utc};
+
+use crate::schema;
+use crate::view_schema;
+use crate::settings;
+use crate::i18n::Locale;
+use crate::is_win::is_win;
+use crate::models::ItemType;
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Leaderboard {
+    /// Current balance of an item type, leaving out users who hide their holdings
+    Holdings(ItemType),
+    /// Capital spent creating and voting on motions
+    CapitalSpent,
+    /// Motions created by the user that ended up passing
+    MotionsPassed,
+}
+
+impl Leaderboard {
+    /// Looks up a leaderboard by name. Any item type alias names the holdings leaderboard for that type.
+    pub fn from_name(conn: &PgConnection, name: &str) -> QueryResult<Option<Self>> {
+        use schema::item_types::dsl as it;
+        use schema::item_type_aliases::dsl as ita;
+        match name.to_ascii_lowercase().as_str() {
+            "spent" | "spending" | "spenders" => return Ok(Some(Leaderboard::CapitalSpent)),
+            "passed" | "motions" => return Ok(Some(Leaderboard::MotionsPassed)),
+            _ => (),
+        }
+        let ty:Option<ItemType> = ita::item_type_aliases
+            .inner_join(it::item_types)
+            .select(it::item_types::all_columns())
+            .filter(ita::alias.eq(name))
+            .get_result(conn)
+            .optional()?;
+        Ok(ty.map(Leaderboard::Holdings))
+    }
+
+    pub fn title(&self, locale:Locale) -> String {
+        match self {
+            Leaderboard::Holdings(ty) => tr!(locale, "leaderboard.holdings", items = ty.long_name_plural),
+            Leaderboard::CapitalSpent => tr!(locale, "leaderboard.capital_spent").to_string(),
+            Leaderboard::MotionsPassed => tr!(locale, "leaderboard.motions_passed").to_string(),
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,QueryableByName)]
+pub struct Standing {
+    #[sql_type = "BigInt"]
+    pub user: i64,
+    #[sql_type = "BigInt"]
+    pub value: i64,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,QueryableByName)]
+pub struct MotionTally {
+    #[sql_type = "BigInt"]
+    pub rowid: i64,
+    #[sql_type = "BigInt"]
+    pub motioned_by: i64,
+    #[sql_type = "Bool"]
+    pub is_super: bool,
+    #[sql_type = "Bool"]
+    pub announced: bool,
+    #[sql_type = "BigInt"]
+    pub yes_votes: i64,
+    #[sql_type = "BigInt"]
+    pub no_votes: i64,
+}
+
+impl MotionTally {
+    pub fn is_win(&self) -> bool {
+        is_win(self.yes_votes, self.no_votes, self.is_super)
+    }
+
+    /// The motion has ended and was announced as passed
+    pub fn passed(&self) -> bool {
+        self.announced && self.is_win()
+    }
+}
+
+/// Vote totals for every motion, finished or not.
+pub fn motion_tallies(conn: &PgConnection) -> QueryResult<Vec<MotionTally>> {
+    diesel::sql_query(r#"
+        select
+          m.rowid,
+          m.motioned_by,
+          m.is_super,
+          m.announcement_message_id is not null as announced,
+          coalesce(sum(v.amount) filter (where v.direction), 0)::int8 as yes_votes,
+          coalesce(sum(v.amount) filter (where not v.direction), 0)::int8 as no_votes
+        from motions m
+        left join motion_votes v on v.motion = m.rowid
+        group by m.rowid
+    "#).load(conn)
+}
+
+pub fn leaderboard(conn: &PgConnection, board: &Leaderboard, limit: i64) -> QueryResult<Vec<Standing>> {
+    match board {
+        Leaderboard::Holdings(ty) => diesel::sql_query(r#"
+            select b."user", b.balance as value
+            from (
+              select distinct on ("user") "user", balance
+              from balance_history
+              where ty = $1
+              order by "user", happened_at desc, rowid desc
+            ) b
+            where
+              b.balance > 0
+            and
+              not exists (select 1 from user_settings s where s."user" = b."user" and s.hide_holdings)
+            order by b.balance desc, b."user"
+            limit $2
+        "#)
+            .bind::<Text,_>(ty.db_name())
+            .bind::<BigInt,_>(limit)
+            .load(conn),
+        Leaderboard::CapitalSpent => diesel::sql_query(r#"
+            select coalesce(from_user, to_user) as "user", sum(case when from_user is null then -quantity else quantity end)::int8 as value
+            from transfers
+            where to_motion is not null
+            group by 1
+            order by value desc, 1
+            limit $1
+        "#)
+            .bind::<BigInt,_>(limit)
+            .load(conn),
+        Leaderboard::MotionsPassed => {
+            let mut counts:HashMap<i64,i64> = HashMap::new();
+            for tally in motion_tallies(conn)? {
+                if tally.passed() {
+                    *counts.entry(tally.motioned_by).or_insert(0) += 1;
+                }
+            }
+            let mut standings:Vec<Standing> = counts
+                .into_iter()
+                .map(|(user, value)| Standing{user, value})
+                .collect();
+            standings.sort_unstable_by_key(|s| (-s.value, s.user));
+            standings.truncate(limit as usize);
+            Ok(standings)
+        },
+    }
+}
+
+#[derive(Debug,Clone)]
+pub struct Profile {
+    pub user: i64,
+    pub holdings_hidden: bool,
+    pub balances: Vec<(ItemType, i64)>,
+    pub motions_created: i64,
+    pub motions_passed: i64,
+    pub votes_cast: i64,
+    pub capital_spent: i64,
+}
+
+pub fn profile(conn: &PgConnection, user_id: i64) -> QueryResult<Profile> {
+    use schema::item_types::dsl as it;
+    use schema::motions::dsl as mdsl;
+    use schema::transfers::dsl as tdsl;
+    use view_schema::balance_history::dsl as bh;
+
+    let item_types:Vec<ItemType> = it::item_types.order(it::name).get_results(conn)?;
+    let balances = item_types.into_iter().map(|ty| {
+        let balance:i64 = bh::balance_history
+            .select(bh::balance)
+            .filter(bh::user.eq(user_id))
+            .filter(bh::ty.eq(ty.db_name()))
+            .order((bh::happened_at.desc(), bh::rowid.desc()))
+            .limit(1)
+            .get_result(conn)
+            .optional()?
+            .unwrap_or(0);
+        Ok((ty, balance))
+    }).collect::<QueryResult<Vec<_>>>()?;
+
+    let motions_created:i64 = mdsl::motions
+        .filter(mdsl::motioned_by.eq(user_id))
+        .count()
+        .get_result(conn)?;
+    let motions_passed = motion_tallies(conn)?
+        .iter()
+        .filter(|t| t.motioned_by == user_id && t.passed())
+        .count() as i64;
+
+    let (votes_cast, capital_spent):(Option<BigDecimal>, Option<BigDecimal>) = tdsl::transfers
+        .select((diesel::dsl::sum(tdsl::to_votes), diesel::dsl::sum(tdsl::quantity)))
+        .filter(tdsl::from_user.eq(user_id))
+        .filter(tdsl::to_motion.is_not_null())
+        .get_result(conn)?;
+    let (votes_refunded, capital_refunded):(Option<BigDecimal>, Option<BigDecimal>) = tdsl::transfers
+        .select((diesel::dsl::sum(tdsl::to_votes), diesel::dsl::sum(tdsl::quantity)))
+        .filter(tdsl::to_user.eq(user_id))
+        .filter(tdsl::transfer_ty.eq("motion_vote_refund"))
+        .get_result(conn)?;
+    let to_i64 = |sum:Option<BigDecimal>| sum.map(|bd| bd.to_i64().unwrap()).unwrap_or(0);
+
+    Ok(Profile{
+        user: user_id,
+        holdings_hidden: settings::holdings_hidden(conn, user_id)?,
+        balances,
+        motions_created,
+        motions_passed,
+        // refunds carry negative votes
+        votes_cast: to_i64(votes_cast) + to_i64(votes_refunded),
+        capital_spent: to_i64(capital_spent) - to_i64(capital_refunded),
+    })
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,QueryableByName)]
+pub struct TimePoint {
+    #[sql_type = "Timestamptz"]
+    pub happened_at: DateTime<Utc>,
+    #[sql_type = "BigInt"]
+    pub value: i64,
+}
+
+/// Total amount of an item type held by anyone at the end of each day that saw a transfer.
+/// Capital spent on motions leaves the supply, generation and fabrication add to it.
+pub fn supply_history(conn: &PgConnection, ty: &str) -> QueryResult<Vec<TimePoint>> {
+    diesel::sql_query(r#"
+        select day as happened_at, (sum(sum(delta)) over (order by day))::int8 as value
+        from (
+          select
+            date_trunc('day', happened_at) as day,
+            (case when to_user is not null then quantity else 0 end) - (case when from_user is not null then quantity else 0 end) as delta
+          from transfers
+          where ty = $1
+        ) d
+        group by day
+        order by day
+    "#)
+        .bind::<Text,_>(ty)
+        .load(conn)
+}
+
+/// Capital produced by generators at each of the most recent `limit` generation ticks, oldest first.
+pub fn generation_per_tick(conn: &PgConnection, limit: i64) -> QueryResult<Vec<TimePoint>> {
+    let mut points:Vec<TimePoint> = diesel::sql_query(r#"
+        select happened_at, sum(quantity)::int8 as value
+        from transfers
+        where transfer_ty = 'generated'
+        group by happened_at
+        order by happened_at desc
+        limit $1
+    "#)
+        .bind::<BigInt,_>(limit)
+        .load(conn)?;
+    points.reverse();
+    Ok(points)
+}
+
+/// Capital spent creating and voting on motions, per week.
+pub fn motion_spending_per_week(conn: &PgConnection) -> QueryResult<Vec<TimePoint>> {
+    diesel::sql_query(r#"
+        select date_trunc('week', happened_at) as happened_at, sum(case when from_user is null then -quantity else quantity end)::int8 as value
+        from transfers
+        where to_motion is not null
+        group by 1
+        order by 1
+    "#)
+        .load(conn)
+}
+
+/// Everyone's current balance of an item type, including users who hide their holdings.
+/// Only meant for aggregates; use `leaderboard` for anything that names users.
+pub fn current_balances(conn: &PgConnection, ty: &str) -> QueryResult<Vec<i64>> {
+    let standings:Vec<Standing> = diesel::sql_query(r#"
+        select distinct on ("user") "user", balance as value
+        from balance_history
+        where ty = $1
+        order by "user", happened_at desc, rowid desc
+    "#)
+        .bind::<Text,_>(ty)
+        .load(conn)?;
+    Ok(standings.into_iter().map(|s| s.value).collect())
+}
+
+#[derive(Debug,Clone,Copy,Default,PartialEq,Eq)]
+pub struct PassRates {
+    pub simple_passed: u64,
+    pub simple_failed: u64,
+    pub super_passed: u64,
+    pub super_failed: u64,
+}
+
+/// Outcomes of every motion that has ended.
+pub fn pass_rates(conn: &PgConnection) -> QueryResult<PassRates> {
+    let mut rates = PassRates::default();
+    for tally in motion_tallies(conn)?.iter().filter(|t| t.announced) {
+        match (tally.is_super, tally.is_win()) {
+            (false, true ) => rates.simple_passed += 1,
+            (false, false) => rates.simple_failed += 1,
+            (true , true ) => rates.super_passed += 1,
+            (true , false) => rates.super_failed += 1,
+        }
+    }
+    Ok(rates)
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,QueryableByName)]
+pub struct VoteEvent {
+    #[sql_type = "Timestamptz"]
+    pub happened_at: DateTime<Utc>,
+    #[sql_type = "BigInt"]
+    pub user: i64,
+    #[sql_type = "Bool"]
+    pub direction: bool,
+    #[sql_type = "BigInt"]
+    pub votes: i64,
+    #[sql_type = "BigInt"]
+    pub cost: i64,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub struct TimelineEntry {
+    pub event: VoteEvent,
+    pub yes_votes: i64,
+    pub no_votes: i64,
+    pub is_win: bool,
+    /// This vote changed the result of the motion
+    pub flipped: bool,
+}
+
+/// Every vote cast on a motion (including the one cast by creating it, and refunds with negative
+/// votes) with the running tally after each.
+pub fn motion_timeline(conn: &PgConnection, motion_id: i64, is_super: bool) -> QueryResult<Vec<TimelineEntry>> {
+    let events:Vec<VoteEvent> = diesel::sql_query(r#"
+        select
+          t.happened_at,
+          coalesce(t.from_user, t.to_user) as "user",
+          v.direction,
+          t.to_votes as votes,
+          case when t.from_user is null then -t.quantity else t.quantity end as cost
+        from transfers t
+        join motion_votes v on v.motion = t.to_motion and v."user" = coalesce(t.from_user, t.to_user)
+        where t.to_motion = $1
+        order by t.happened_at, t.rowid
+    "#)
+        .bind::<BigInt,_>(motion_id)
+        .load(conn)?;
+    Ok(running_tallies(&events, is_super))
+}
+
+pub fn running_tallies(events: &[VoteEvent], is_super: bool) -> Vec<TimelineEntry> {
+    let mut yes_votes = 0;
+    let mut no_votes = 0;
+    let mut prev_win:Option<bool> = None;
+    events.iter().map(|event| {
+        if event.direction {
+            yes_votes += event.votes;
+        } else {
+            no_votes += event.votes;
+        }
+        let win = is_win(yes_votes, no_votes, is_super);
+        let flipped = prev_win.map(|prev| prev != win).unwrap_or(false);
+        prev_win = Some(win);
+        TimelineEntry{
+            event: *event,
+            yes_votes,
+            no_votes,
+            is_win: win,
+            flipped,
+        }
+    }).collect()
+}
+
+/// Gini coefficient of the given balances; 0 is perfect equality, 1 is one user holding everything.
+pub fn gini(balances: &[i64]) -> f64 {
+    let mut sorted:Vec<f64> = balances.iter().map(|b| *b as f64).collect();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len() as f64;
+    let total:f64 = sorted.iter().sum();
+    if sorted.is_empty() || total <= 0.0 {
+        return 0.0;
+    }
+    let weighted:f64 = sorted.iter().enumerate().map(|(i, b)| (i as f64 + 1.0) * b).sum();
+    (2.0 * weighted) / (n * total) - (n + 1.0) / n
+}
+
+/// Fraction of the total held by the richest tenth of users (at least one user).
+pub fn top_decile_share(balances: &[i64]) -> f64 {
+    let mut sorted = balances.to_vec();
+    sorted.sort_unstable_by(|a, b| b.cmp(a));
+    let total:i64 = sorted.iter().sum();
+    if total <= 0 {
+        return 0.0;
+    }
+    let count = std::cmp::max(1, (sorted.len() + 9) / 10);
+    let top:i64 = sorted[..count].iter().sum();
+    top as f64 / total as f64
+}
+
+/// Points of the Lorenz curve for the given balances, from (0,0) to (1,1).
+pub fn lorenz_curve(balances: &[i64]) -> Vec<(f64, f64)> {
+    let mut sorted = balances.to_vec();
+    sorted.sort_unstable();
+    let total:i64 = sorted.iter().sum();
+    let n = sorted.len();
+    let mut points = vec![(0.0, 0.0)];
+    if total <= 0 {
+        return points;
+    }
+    let mut running = 0;
+    for (i, b) in sorted.iter().enumerate() {
+        running += b;
+        points.push(((i + 1) as f64 / n as f64, running as f64 / total as f64));
+    }
+    points
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gini_extremes() {
+        assert_eq!(gini(&[]), 0.0);
+        assert_eq!(gini(&[0, 0]), 0.0);
+        assert!(gini(&[5, 5, 5, 5]).abs() < 1e-9);
+        assert!((gini(&[0, 0, 0, 10]) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn top_decile() {
+        assert_eq!(top_decile_share(&[]), 0.0);
+        assert!((top_decile_share(&[10, 30]) - 0.75).abs() < 1e-9);
+        let eleven = [1i64; 11];
+        assert!((top_decile_share(&eleven) - 2.0 / 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tallies_mark_flips() {
+        use chrono::TimeZone;
+        let vote = |direction, votes| VoteEvent{
+            happened_at: Utc.timestamp(0, 0),
+            user: 1,
+            direction,
+            votes,
+            cost: 0,
+        };
+        let timeline = running_tallies(&[vote(true, 1), vote(false, 1), vote(false, 0), vote(true, 2)], false);
+        let flips:Vec<bool> = timeline.iter().map(|e| e.flipped).collect();
+        assert_eq!(flips, vec![false, true, false, true]);
+        assert_eq!((timeline[3].yes_votes, timeline[3].no_votes), (3, 1));
+    }
+
+    #[test]
+    fn lorenz_ends_at_one() {
+        let curve = lorenz_curve(&[3, 1, 0]);
+        assert_eq!(curve.first(), Some(&(0.0, 0.0)));
+        assert_eq!(curve.last(), Some(&(1.0, 1.0)));
+        assert_eq!(curve.len(), 4);
+    }
+}
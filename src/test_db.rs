@@ -0,0 +1,42 @@
+//! Setup for tests that need a database. They're `#[ignore]`d so `cargo test` runs without one; to
+//! run them, point `TEST_DATABASE_URL` at a database with every migration run and use `cargo test
+//! -- --ignored`. Each test's connection is in a transaction that is never committed, so the tests
+//! leave nothing behind and can't see each other.
+use std::env;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+
+use crate::{admin, economy, schema};
+use crate::models::ItemType;
+use crate::repl::TerminalFrontend;
+use crate::vote_cost::VOTE_BASE_COST;
+
+pub fn connection() -> PgConnection {
+    dotenv::dotenv().ok();
+    let url = env::var("TEST_DATABASE_URL").expect("TEST_DATABASE_URL must be set");
+    let conn = PgConnection::establish(&url).unwrap();
+    conn.begin_test_transaction().unwrap();
+    conn
+}
+
+pub fn item_type(conn:&PgConnection, name:&str) -> ItemType {
+    use schema::item_types::dsl as itdsl;
+    itdsl::item_types.filter(itdsl::name.eq(name)).get_result(conn).unwrap()
+}
+
+/// Gives `user` another `amount` of the item type named `ty`.
+pub fn fund(conn:&PgConnection, user:i64, ty:&str, amount:i64) {
+    let actor = admin::Actor{ user, reason: Some("test"), message_id: None };
+    admin::fabricate(conn, actor, user, &item_type(conn, ty), amount).unwrap().unwrap();
+}
+
+pub fn balance(conn:&PgConnection, user:i64, ty:&str) -> i64 {
+    economy::latest_balance(conn, user, ty, false).unwrap()
+}
+
+/// Calls a simple motion as `motioned_by`, giving them what its first vote costs. Returns its id.
+pub fn motion(conn:&PgConnection, motioned_by:i64) -> i64 {
+    fund(conn, motioned_by, "pc", VOTE_BASE_COST as i64);
+    economy::create_motion(&TerminalFrontend, conn, motioned_by, 0, "Test motion", false).unwrap().unwrap()
+}
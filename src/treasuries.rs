@@ -0,0 +1,694 @@
+//! Treasuries: accounts shared by a group of members, such as a club's funds. A treasury isn't a
+//! Discord user, so it appears in the ledger as the negative of its rowid (see `account_id`), which
+//! lets it hold anything and vote like anyone else without touching how transfers are stored.
+//!
+//! Anyone can pay into a treasury, but spending from it takes a proposal, made by a member, which
+//! goes through once `threshold` members have approved it. The proposer approves their own
+//! proposal by making it. The approval that reaches the threshold executes the proposal in the same
+//! transaction, so if the treasury can't afford it, that approval isn't recorded either.
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::{damm, schema};
+use crate::economy::{self, GiveError, VoteError};
+use crate::i18n::{Locale, Localize};
+use crate::models::ItemType;
+use crate::vote_cost::VoteAmount;
+
+pub const NAME_MAX_CHARS:usize = 32;
+
+/// A treasury can vote, and its votes start again at the cheapest ordinals, so it takes more than
+/// one member to spend from one; otherwise anyone could vote cheaply through treasuries of their own.
+pub const MIN_THRESHOLD:i64 = 2;
+
+/// The id a treasury goes by in the ledger.
+pub fn account_id(treasury_id:i64) -> i64 {
+    -treasury_id
+}
+
+/// The treasury a ledger id belongs to, if it's a treasury's rather than a user's or a vote pool's.
+pub fn treasury_of(account:i64) -> Option<i64> {
+    if account < 0 && account >= crate::vote_pools::ACCOUNT_BASE { Some(-account) } else { None }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct Treasury {
+    pub rowid:i64,
+    pub name:String,
+    pub threshold:i64,
+    pub created_by:i64,
+    pub created_at:DateTime<Utc>,
+}
+
+impl Treasury {
+    pub fn account(&self) -> i64 {
+        account_id(self.rowid)
+    }
+}
+
+/// Where a proposal stands. Only the first three are stored; a pending proposal past its expiry
+/// is `Expired`, see `Proposal::status`.
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum ProposalStatus {
+    Pending,
+    Executed,
+    Cancelled,
+    Expired,
+}
+
+impl ProposalStatus {
+    pub fn db_name(self) -> &'static str {
+        match self {
+            ProposalStatus::Pending | ProposalStatus::Expired => "pending",
+            ProposalStatus::Executed => "executed",
+            ProposalStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn from_db_name(name:&str) -> Option<Self> {
+        match name {
+            "pending" => Some(ProposalStatus::Pending),
+            "executed" => Some(ProposalStatus::Executed),
+            "cancelled" => Some(ProposalStatus::Cancelled),
+            _ => None,
+        }
+    }
+}
+
+impl Localize for ProposalStatus {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            ProposalStatus::Pending => "treasury.status.pending",
+            ProposalStatus::Executed => "treasury.status.executed",
+            ProposalStatus::Cancelled => "treasury.status.cancelled",
+            ProposalStatus::Expired => "treasury.status.expired",
+        }).to_string()
+    }
+}
+
+/// What a proposal would have the treasury do.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum Spend {
+    Give{ to_user: i64, ty: ItemType, quantity: i64, memo: Option<String> },
+    Vote{ motion: i64, direction: bool, votes: i64 },
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct Proposal {
+    pub rowid:i64,
+    pub treasury:i64,
+    pub proposed_by:i64,
+    pub kind:String,
+    pub ty:Option<String>,
+    /// Items to give, or votes to cast
+    pub quantity:i64,
+    pub to_user:Option<i64>,
+    pub memo:Option<String>,
+    pub motion:Option<i64>,
+    pub direction:Option<bool>,
+    pub proposed_at:DateTime<Utc>,
+    pub expires_at:DateTime<Utc>,
+    pub message_id:Option<i64>,
+    pub status:String,
+    pub settled_at:Option<DateTime<Utc>>,
+    pub transfer:Option<i64>,
+}
+
+impl Proposal {
+    pub fn status(&self, now:DateTime<Utc>) -> ProposalStatus {
+        match ProposalStatus::from_db_name(&self.status).expect("treasury_proposals.status is constrained") {
+            ProposalStatus::Pending if self.expires_at <= now => ProposalStatus::Expired,
+            status => status,
+        }
+    }
+
+    /// eg. "give 500 pc to user#1" or "cast 10 votes for motion #1234", where `mention` names
+    /// whoever would be given to.
+    pub fn describe(&self, locale:Locale, items:&str, mention:impl Fn(i64) -> String) -> String {
+        match (self.to_user, self.motion, self.direction) {
+            (Some(to_user), _, _) => tr!(
+                locale,
+                if self.memo.is_some() { "treasury.describe_give_memo" } else { "treasury.describe_give" },
+                amount = locale.number(self.quantity),
+                items = items,
+                user = mention(to_user),
+                memo = self.memo.as_deref().unwrap_or(""),
+            ),
+            (None, Some(motion), Some(direction)) => tr!(
+                locale,
+                if direction { "treasury.describe_vote_for" } else { "treasury.describe_vote_against" },
+                votes = locale.number(self.quantity),
+                motion = damm::add_to_str(motion.to_string()),
+            ),
+            _ => unreachable!("treasury_proposals is constrained to be one or the other"),
+        }
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum TreasuryError {
+    BadName,
+    NameTaken,
+    /// More approvals needed than there are members
+    ThresholdTooHigh{ members: i64 },
+    /// Fewer approvals needed than `MIN_THRESHOLD`
+    BadThreshold,
+    NotFound,
+    NotMember,
+    NotPositive,
+    ProposalNotFound,
+    Settled(ProposalStatus),
+    Expired,
+    AlreadyApproved,
+    /// Only whoever made a proposal can cancel it
+    NotProposer,
+    Give(GiveError),
+    Vote(VoteError),
+}
+
+impl Localize for TreasuryError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            TreasuryError::BadName => tr!(locale, "treasury.bad_name", max = NAME_MAX_CHARS),
+            TreasuryError::NameTaken => tr!(locale, "treasury.name_taken").to_string(),
+            TreasuryError::ThresholdTooHigh{members} => tr!(locale, "treasury.threshold_too_high", members = members),
+            TreasuryError::BadThreshold => tr!(locale, "treasury.bad_threshold", min = MIN_THRESHOLD),
+            TreasuryError::NotFound => tr!(locale, "treasury.not_found").to_string(),
+            TreasuryError::NotMember => tr!(locale, "treasury.not_member").to_string(),
+            TreasuryError::NotPositive => tr!(locale, "treasury.not_positive").to_string(),
+            TreasuryError::ProposalNotFound => tr!(locale, "treasury.proposal_not_found").to_string(),
+            TreasuryError::Settled(status) => tr!(locale, "treasury.settled", status = status.localize(locale)),
+            TreasuryError::Expired => tr!(locale, "treasury.expired").to_string(),
+            TreasuryError::AlreadyApproved => tr!(locale, "treasury.already_approved").to_string(),
+            TreasuryError::NotProposer => tr!(locale, "treasury.not_proposer").to_string(),
+            TreasuryError::Give(GiveError::InsufficientBalance) => tr!(locale, "treasury.insufficient_balance").to_string(),
+            TreasuryError::Give(e) => e.localize(locale),
+            TreasuryError::Vote(e) => e.localize(locale),
+        }
+    }
+}
+
+impl fmt::Display for TreasuryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Treasury names are a single lowercase word, so they can be typed in commands, and not all
+/// digits, so they can't be mistaken for ids.
+pub fn normalize_name(name:&str) -> Option<String> {
+    let name = name.to_lowercase();
+    let valid_chars = name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    let all_digits = name.chars().all(|c| c.is_ascii_digit());
+    if name.is_empty() || name.chars().count() > NAME_MAX_CHARS || !valid_chars || all_digits {
+        return None;
+    }
+    Some(name)
+}
+
+/// Sets up a treasury run by `created_by` and `members`, needing `threshold` of them to approve
+/// anything it spends.
+pub fn create(
+    conn:&PgConnection,
+    name:&str,
+    threshold:i64,
+    created_by:i64,
+    members:&[i64],
+) -> QueryResult<Result<Treasury, TreasuryError>> {
+    use schema::treasuries::dsl as tdsl;
+    use schema::treasury_members::dsl as tmdsl;
+    let name = match normalize_name(name) {
+        Some(n) => n,
+        None => return Ok(Err(TreasuryError::BadName)),
+    };
+    let mut all_members = vec![created_by];
+    for member in members {
+        if !all_members.contains(member) {
+            all_members.push(*member);
+        }
+    }
+    if threshold < MIN_THRESHOLD {
+        return Ok(Err(TreasuryError::BadThreshold));
+    }
+    if threshold > all_members.len() as i64 {
+        return Ok(Err(TreasuryError::ThresholdTooHigh{ members: all_members.len() as i64 }));
+    }
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let treasury:Option<Treasury> = diesel::insert_into(tdsl::treasuries).values((
+            tdsl::name.eq(&name),
+            tdsl::threshold.eq(threshold),
+            tdsl::created_by.eq(created_by),
+            tdsl::created_at.eq(now),
+        )).on_conflict_do_nothing().get_result(conn).optional()?;
+        let treasury = match treasury {
+            Some(t) => t,
+            None => return Ok(Err(TreasuryError::NameTaken)),
+        };
+        let rows:Vec<_> = all_members.iter().map(|user| (
+            tmdsl::treasury.eq(treasury.rowid),
+            tmdsl::user.eq(*user),
+            tmdsl::added_at.eq(now),
+        )).collect();
+        diesel::insert_into(tmdsl::treasury_members).values(&rows).execute(conn)?;
+        Ok(Ok(treasury))
+    })
+}
+
+pub fn by_name(conn:&PgConnection, name:&str) -> QueryResult<Option<Treasury>> {
+    use schema::treasuries::dsl as tdsl;
+    tdsl::treasuries.filter(tdsl::name.eq(name.to_lowercase())).get_result(conn).optional()
+}
+
+pub fn get(conn:&PgConnection, treasury_id:i64) -> QueryResult<Option<Treasury>> {
+    use schema::treasuries::dsl as tdsl;
+    tdsl::treasuries.filter(tdsl::rowid.eq(treasury_id)).get_result(conn).optional()
+}
+
+/// Every treasury the user is a member of, by name.
+pub fn of_member(conn:&PgConnection, user:i64) -> QueryResult<Vec<Treasury>> {
+    use schema::treasuries::dsl as tdsl;
+    use schema::treasury_members::dsl as tmdsl;
+    tdsl::treasuries
+        .filter(tdsl::rowid.eq_any(tmdsl::treasury_members.filter(tmdsl::user.eq(user)).select(tmdsl::treasury)))
+        .order(tdsl::name.asc())
+        .get_results(conn)
+}
+
+/// Everyone in a treasury, in the order they were added.
+pub fn members(conn:&PgConnection, treasury_id:i64) -> QueryResult<Vec<i64>> {
+    use schema::treasury_members::dsl as tmdsl;
+    tmdsl::treasury_members
+        .filter(tmdsl::treasury.eq(treasury_id))
+        .order((tmdsl::added_at.asc(), tmdsl::user.asc()))
+        .select(tmdsl::user)
+        .get_results(conn)
+}
+
+fn is_member(conn:&PgConnection, treasury_id:i64, user:i64) -> QueryResult<bool> {
+    use schema::treasury_members::dsl as tmdsl;
+    use diesel::dsl::{exists, select};
+    select(exists(tmdsl::treasury_members.filter(tmdsl::treasury.eq(treasury_id)).filter(tmdsl::user.eq(user)))).get_result(conn)
+}
+
+/// A treasury's proposals, newest first.
+pub fn proposals(conn:&PgConnection, treasury_id:i64, limit:i64) -> QueryResult<Vec<Proposal>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    tpdsl::treasury_proposals
+        .filter(tpdsl::treasury.eq(treasury_id))
+        .order(tpdsl::rowid.desc())
+        .limit(limit)
+        .get_results(conn)
+}
+
+pub fn get_proposal(conn:&PgConnection, proposal_id:i64) -> QueryResult<Option<Proposal>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    tpdsl::treasury_proposals.filter(tpdsl::rowid.eq(proposal_id)).get_result(conn).optional()
+}
+
+/// Who has approved a proposal, in order.
+pub fn approvals(conn:&PgConnection, proposal_id:i64) -> QueryResult<Vec<i64>> {
+    use schema::treasury_approvals::dsl as tadsl;
+    tadsl::treasury_approvals
+        .filter(tadsl::proposal.eq(proposal_id))
+        .order((tadsl::approved_at.asc(), tadsl::user.asc()))
+        .select(tadsl::user)
+        .get_results(conn)
+}
+
+/// The item type a give proposal would give, even if it's since been retired.
+pub fn item_type_of(conn:&PgConnection, proposal:&Proposal) -> QueryResult<Option<ItemType>> {
+    use schema::item_types::dsl as itdsl;
+    match &proposal.ty {
+        Some(ty) => itdsl::item_types.filter(itdsl::name.eq(ty)).get_result(conn).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// What a proposal would have the treasury do, with `mention` naming whoever would be given to.
+pub fn describe(conn:&PgConnection, locale:Locale, proposal:&Proposal, mention:impl Fn(i64) -> String) -> QueryResult<String> {
+    let items = item_type_of(conn, proposal)?.map(|ty| ty.long_name_ambiguous).unwrap_or_default();
+    Ok(proposal.describe(locale, &items, mention))
+}
+
+/// How an approval went.
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct Approval {
+    pub proposal:Proposal,
+    pub approvals:i64,
+    pub threshold:i64,
+}
+
+impl Approval {
+    pub fn executed(&self) -> bool {
+        self.proposal.status == ProposalStatus::Executed.db_name()
+    }
+
+    /// Tells the approver how it went, where `what` is the proposal's description.
+    pub fn outcome(&self, locale:Locale, what:&str) -> String {
+        if self.executed() {
+            tr!(locale, "treasury.executed", id = self.proposal.rowid, what = what)
+        } else {
+            tr!(
+                locale,
+                "treasury.approved",
+                id = self.proposal.rowid,
+                what = what,
+                approvals = self.approvals,
+                threshold = self.threshold,
+            )
+        }
+    }
+}
+
+/// Proposes that a treasury `spend` something, as one of its members, approving it as them. Open
+/// for `expiration`, unless it goes through straight away.
+pub fn propose(
+    conn:&PgConnection,
+    treasury_id:i64,
+    proposed_by:i64,
+    spend:&Spend,
+    message_id:Option<i64>,
+    expiration:chrono::Duration,
+) -> QueryResult<Result<Approval, TreasuryError>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    let quantity = match spend {
+        Spend::Give{quantity, ..} => *quantity,
+        Spend::Vote{votes, ..} => *votes,
+    };
+    if quantity <= 0 {
+        return Ok(Err(TreasuryError::NotPositive));
+    }
+    let mut fail:Option<TreasuryError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        if !is_member(conn, treasury_id, proposed_by)? {
+            fail = Some(TreasuryError::NotMember);
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        let now = chrono::Utc::now();
+        let (kind, ty, to_user, memo, motion, direction) = match spend {
+            Spend::Give{to_user, ty, memo, ..} => ("give", Some(ty.db_name()), Some(*to_user), memo.as_deref(), None, None),
+            Spend::Vote{motion, direction, ..} => ("vote", None, None, None, Some(*motion), Some(*direction)),
+        };
+        let proposal_id:i64 = diesel::insert_into(tpdsl::treasury_proposals).values((
+            tpdsl::treasury.eq(treasury_id),
+            tpdsl::proposed_by.eq(proposed_by),
+            tpdsl::kind.eq(kind),
+            tpdsl::ty.eq(ty),
+            tpdsl::quantity.eq(quantity),
+            tpdsl::to_user.eq(to_user),
+            tpdsl::memo.eq(memo),
+            tpdsl::motion.eq(motion),
+            tpdsl::direction.eq(direction),
+            tpdsl::proposed_at.eq(now),
+            tpdsl::expires_at.eq(now + expiration),
+            tpdsl::message_id.eq(message_id),
+        )).returning(tpdsl::rowid).get_result(conn)?;
+        match approve_in_transaction(conn, proposal_id, proposed_by, message_id, now)? {
+            Ok(approval) => Ok(approval),
+            Err(e) => {
+                fail = Some(e);
+                Err(diesel::result::Error::RollbackTransaction)
+            },
+        }
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+/// Approves a proposal as one of the treasury's members, executing it if that's enough approvals.
+pub fn approve(
+    conn:&PgConnection,
+    proposal_id:i64,
+    user:i64,
+    message_id:Option<i64>,
+) -> QueryResult<Result<Approval, TreasuryError>> {
+    let mut fail:Option<TreasuryError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        match approve_in_transaction(conn, proposal_id, user, message_id, chrono::Utc::now())? {
+            Ok(approval) => Ok(approval),
+            Err(e) => {
+                fail = Some(e);
+                Err(diesel::result::Error::RollbackTransaction)
+            },
+        }
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+/// Locks a pending proposal, checking `check` against it first.
+fn lock_pending(
+    conn:&PgConnection,
+    proposal_id:i64,
+    now:DateTime<Utc>,
+    check:impl FnOnce(&PgConnection, &Proposal) -> QueryResult<Option<TreasuryError>>,
+) -> QueryResult<Result<Proposal, TreasuryError>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    let proposal:Option<Proposal> = tpdsl::treasury_proposals
+        .filter(tpdsl::rowid.eq(proposal_id))
+        .for_update()
+        .get_result(conn)
+        .optional()?;
+    let proposal = match proposal {
+        Some(p) => p,
+        None => return Ok(Err(TreasuryError::ProposalNotFound)),
+    };
+    if let Some(e) = check(conn, &proposal)? {
+        return Ok(Err(e));
+    }
+    Ok(match proposal.status(now) {
+        ProposalStatus::Pending => Ok(proposal),
+        ProposalStatus::Expired => Err(TreasuryError::Expired),
+        settled => Err(TreasuryError::Settled(settled)),
+    })
+}
+
+/// `approve`, within a transaction that's rolled back if it returns an error, since the approval
+/// may already have been recorded by then.
+fn approve_in_transaction(
+    conn:&PgConnection,
+    proposal_id:i64,
+    user:i64,
+    message_id:Option<i64>,
+    now:DateTime<Utc>,
+) -> QueryResult<Result<Approval, TreasuryError>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    use schema::treasury_approvals::dsl as tadsl;
+    let check = |conn:&PgConnection, p:&Proposal| -> QueryResult<Option<TreasuryError>> {
+        Ok(if is_member(conn, p.treasury, user)? { None } else { Some(TreasuryError::NotMember) })
+    };
+    let proposal = match lock_pending(conn, proposal_id, now, check)? {
+        Ok(p) => p,
+        Err(e) => return Ok(Err(e)),
+    };
+    let treasury = get(conn, proposal.treasury)?.expect("proposals reference treasuries");
+    let inserted = diesel::insert_into(tadsl::treasury_approvals).values((
+        tadsl::proposal.eq(proposal_id),
+        tadsl::user.eq(user),
+        tadsl::approved_at.eq(now),
+    )).on_conflict_do_nothing().execute(conn)?;
+    if inserted == 0 {
+        return Ok(Err(TreasuryError::AlreadyApproved));
+    }
+    let approval_count = approvals(conn, proposal_id)?.len() as i64;
+    let mut approval = Approval{ proposal, approvals: approval_count, threshold: treasury.threshold };
+    if approval_count < treasury.threshold {
+        return Ok(Ok(approval));
+    }
+
+    let mut transfer = None;
+    match (&approval.proposal.to_user, &approval.proposal.motion, &approval.proposal.direction) {
+        (Some(to_user), _, _) => {
+            let ty = item_type_of(conn, &approval.proposal)?.expect("give proposals have an item type");
+            match economy::give_transfer(
+                conn,
+                treasury.account(),
+                *to_user,
+                &ty,
+                approval.proposal.quantity as u64,
+                message_id,
+                approval.proposal.memo.as_deref(),
+            )? {
+                Ok(id) => transfer = Some(id),
+                Err(e) => return Ok(Err(TreasuryError::Give(e))),
+            }
+        },
+        (None, Some(motion), Some(direction)) => {
+            match economy::vote(
+                conn,
+                Some(*direction),
+                VoteAmount::Exact(approval.proposal.quantity),
+                treasury.account(),
+                Some(*motion),
+                None,
+                message_id,
+                None,
+            )? {
+                Ok(_) => (),
+                Err(e) => return Ok(Err(TreasuryError::Vote(e))),
+            }
+        },
+        _ => unreachable!("treasury_proposals is constrained to be one or the other"),
+    }
+    approval.proposal = diesel::update(tpdsl::treasury_proposals.filter(tpdsl::rowid.eq(proposal_id)))
+        .set((
+            tpdsl::status.eq(ProposalStatus::Executed.db_name()),
+            tpdsl::settled_at.eq(now),
+            tpdsl::transfer.eq(transfer),
+        ))
+        .get_result(conn)?;
+    Ok(Ok(approval))
+}
+
+/// Withdraws a proposal, as whoever made it.
+pub fn cancel(conn:&PgConnection, proposal_id:i64, user:i64) -> QueryResult<Result<Proposal, TreasuryError>> {
+    use schema::treasury_proposals::dsl as tpdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let now = chrono::Utc::now();
+        let check = |_:&PgConnection, p:&Proposal| -> QueryResult<Option<TreasuryError>> {
+            Ok(if p.proposed_by == user { None } else { Some(TreasuryError::NotProposer) })
+        };
+        match lock_pending(conn, proposal_id, now, check)? {
+            Ok(_) => (),
+            Err(e) => return Ok(Err(e)),
+        }
+        diesel::update(tpdsl::treasury_proposals.filter(tpdsl::rowid.eq(proposal_id)))
+            .set((
+                tpdsl::status.eq(ProposalStatus::Cancelled.db_name()),
+                tpdsl::settled_at.eq(now),
+            ))
+            .get_result(conn)
+            .map(Ok)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_db;
+
+    const FOUNDER:i64 = 1001;
+    const MEMBER:i64 = 1002;
+    const OTHER_MEMBER:i64 = 1003;
+    const RECIPIENT:i64 = 1004;
+
+    /// A treasury needing two of its three members to approve, holding `funds` pc.
+    fn treasury(conn:&PgConnection, funds:i64) -> Treasury {
+        let treasury = create(conn, "testfund", 2, FOUNDER, &[MEMBER, OTHER_MEMBER]).unwrap().unwrap();
+        if funds > 0 {
+            test_db::fund(conn, treasury.account(), "pc", funds);
+        }
+        treasury
+    }
+
+    fn propose_give(conn:&PgConnection, treasury:&Treasury, quantity:i64) -> Approval {
+        let spend = Spend::Give{ to_user: RECIPIENT, ty: test_db::item_type(conn, "pc"), quantity, memo: None };
+        propose(conn, treasury.rowid, FOUNDER, &spend, None, chrono::Duration::days(1)).unwrap().unwrap()
+    }
+
+    #[test]
+    fn accounts_are_not_users() {
+        assert_eq!(account_id(5), -5);
+        assert_eq!(treasury_of(account_id(5)), Some(5));
+        assert_eq!(treasury_of(165858230327574528), None);
+    }
+
+    #[test]
+    fn normalizes_names() {
+        assert_eq!(normalize_name("Builders"), Some(String::from("builders")));
+        assert_eq!(normalize_name("red-team_2"), Some(String::from("red-team_2")));
+        assert_eq!(normalize_name(""), None);
+        assert_eq!(normalize_name("1234"), None);
+        assert_eq!(normalize_name("two words"), None);
+        assert_eq!(normalize_name("<@123>"), None);
+        assert_eq!(normalize_name(&"x".repeat(NAME_MAX_CHARS + 1)), None);
+    }
+
+    #[test]
+    #[ignore]
+    fn spends_once_enough_members_approve() {
+        let conn = test_db::connection();
+        let t = treasury(&conn, 1000);
+        let recipient_before = test_db::balance(&conn, RECIPIENT, "pc");
+        let proposed = propose_give(&conn, &t, 300);
+        assert!(!proposed.executed());
+        assert_eq!((proposed.approvals, proposed.threshold), (1, 2));
+        assert_eq!(test_db::balance(&conn, t.account(), "pc"), 1000);
+        let approved = approve(&conn, proposed.proposal.rowid, MEMBER, None).unwrap().unwrap();
+        assert!(approved.executed());
+        assert!(approved.proposal.transfer.is_some());
+        assert_eq!(test_db::balance(&conn, t.account(), "pc"), 700);
+        assert_eq!(test_db::balance(&conn, RECIPIENT, "pc"), recipient_before + 300);
+    }
+
+    #[test]
+    #[ignore]
+    fn executed_proposals_arent_spent_again() {
+        let conn = test_db::connection();
+        let t = treasury(&conn, 1000);
+        let proposed = propose_give(&conn, &t, 300);
+        approve(&conn, proposed.proposal.rowid, MEMBER, None).unwrap().unwrap();
+        assert_eq!(
+            approve(&conn, proposed.proposal.rowid, OTHER_MEMBER, None).unwrap(),
+            Err(TreasuryError::Settled(ProposalStatus::Executed)),
+        );
+        assert_eq!(test_db::balance(&conn, t.account(), "pc"), 700);
+    }
+
+    #[test]
+    #[ignore]
+    fn approving_twice_counts_once() {
+        let conn = test_db::connection();
+        let t = treasury(&conn, 1000);
+        let proposed = propose_give(&conn, &t, 300);
+        assert_eq!(approve(&conn, proposed.proposal.rowid, FOUNDER, None).unwrap(), Err(TreasuryError::AlreadyApproved));
+        assert_eq!(approvals(&conn, proposed.proposal.rowid).unwrap(), vec![FOUNDER]);
+        let proposal = get_proposal(&conn, proposed.proposal.rowid).unwrap().unwrap();
+        assert_eq!(proposal.status(chrono::Utc::now()), ProposalStatus::Pending);
+        assert_eq!(test_db::balance(&conn, t.account(), "pc"), 1000);
+    }
+
+    #[test]
+    #[ignore]
+    fn only_members_approve() {
+        let conn = test_db::connection();
+        let t = treasury(&conn, 1000);
+        let proposed = propose_give(&conn, &t, 300);
+        assert_eq!(approve(&conn, proposed.proposal.rowid, RECIPIENT, None).unwrap(), Err(TreasuryError::NotMember));
+        assert_eq!(approvals(&conn, proposed.proposal.rowid).unwrap(), vec![FOUNDER]);
+    }
+
+    #[test]
+    #[ignore]
+    fn an_approval_that_cant_be_spent_isnt_recorded() {
+        let conn = test_db::connection();
+        let t = treasury(&conn, 100);
+        let proposed = propose_give(&conn, &t, 300);
+        assert_eq!(
+            approve(&conn, proposed.proposal.rowid, MEMBER, None).unwrap(),
+            Err(TreasuryError::Give(GiveError::InsufficientBalance)),
+        );
+        assert_eq!(approvals(&conn, proposed.proposal.rowid).unwrap(), vec![FOUNDER]);
+        let proposal = get_proposal(&conn, proposed.proposal.rowid).unwrap().unwrap();
+        assert_eq!(proposal.status(chrono::Utc::now()), ProposalStatus::Pending);
+        assert_eq!(test_db::balance(&conn, t.account(), "pc"), 100);
+    }
+
+    #[test]
+    #[ignore]
+    fn takes_more_than_one_member_to_spend() {
+        let conn = test_db::connection();
+        assert_eq!(create(&conn, "testfund", 1, FOUNDER, &[MEMBER]).unwrap(), Err(TreasuryError::BadThreshold));
+        assert_eq!(create(&conn, "testfund", 2, FOUNDER, &[]).unwrap(), Err(TreasuryError::ThresholdTooHigh{ members: 1 }));
+        assert_eq!(create(&conn, "testfund", 2, FOUNDER, &[FOUNDER]).unwrap(), Err(TreasuryError::ThresholdTooHigh{ members: 1 }));
+        assert!(create(&conn, "testfund", 2, FOUNDER, &[MEMBER]).unwrap().is_ok());
+    }
+}
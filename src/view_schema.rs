@@ -13,5 +13,6 @@ table! {
         to_motion -> Nullable<Int8>,
         to_votes -> Nullable<Int8>,
         transfer_ty -> Text,
+        acting_user -> Nullable<Int8>,
     }
 }
\ No newline at end of file
@@ -0,0 +1,157 @@
+//! Words communities can teach the bot without a release: the vote vocabulary and command aliases.
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use serde_json::json;
+
+use crate::schema;
+use crate::admin::{self, Actor, AdminAction};
+use crate::parser::{VoteVocabulary, VoteWord};
+
+pub fn vote_vocabulary(conn: &PgConnection) -> QueryResult<VoteVocabulary> {
+    use schema::vote_words::dsl as vwdsl;
+    let words:Vec<(String, String)> = vwdsl::vote_words
+        .select((vwdsl::word, vwdsl::meaning))
+        .get_results(conn)?;
+    Ok(VoteVocabulary::new(words.into_iter().filter_map(|(word, meaning)| {
+        VoteWord::from_db_name(&meaning).map(|m| (word, m))
+    })))
+}
+
+/// Every word in the vote vocabulary with what it means, alphabetically.
+pub fn vote_words(conn: &PgConnection) -> QueryResult<Vec<(String, VoteWord)>> {
+    use schema::vote_words::dsl as vwdsl;
+    let words:Vec<(String, String)> = vwdsl::vote_words
+        .select((vwdsl::word, vwdsl::meaning))
+        .order(vwdsl::word)
+        .get_results(conn)?;
+    Ok(words.into_iter().filter_map(|(word, meaning)| {
+        VoteWord::from_db_name(&meaning).map(|m| (word, m))
+    }).collect())
+}
+
+/// Adds a word to the vote vocabulary. Words are stored lowercase. Returns false, changing nothing,
+/// if the word already means something; it has to be removed before it can mean something else.
+pub fn add_vote_word(conn: &PgConnection, actor: Actor, word: &str, meaning: VoteWord) -> QueryResult<bool> {
+    use schema::vote_words::dsl as vwdsl;
+    let word = word.to_lowercase();
+    conn.transaction(|| {
+        let inserted = diesel::insert_into(vwdsl::vote_words)
+            .values((
+                vwdsl::word.eq(&word),
+                vwdsl::meaning.eq(meaning.db_name()),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted > 0 {
+            admin::record(conn, actor, AdminAction::AddVoteWord, None, json!({ "word": word, "meaning": meaning.db_name() }), chrono::Utc::now())?;
+        }
+        Ok(inserted > 0)
+    })
+}
+
+/// Returns whether the word was in the vocabulary.
+pub fn remove_vote_word(conn: &PgConnection, actor: Actor, word: &str) -> QueryResult<bool> {
+    use schema::vote_words::dsl as vwdsl;
+    let word = word.to_lowercase();
+    conn.transaction(|| {
+        let meaning:Option<String> = diesel::delete(vwdsl::vote_words.filter(vwdsl::word.eq(&word)))
+            .returning(vwdsl::meaning)
+            .get_result(conn)
+            .optional()?;
+        if let Some(meaning) = &meaning {
+            admin::record(conn, actor, AdminAction::RemoveVoteWord, None, json!({ "word": word, "meaning": meaning }), chrono::Utc::now())?;
+        }
+        Ok(meaning.is_some())
+    })
+}
+
+/// The command an alias stands for, if it is one.
+pub fn command_for_alias(conn: &PgConnection, alias: &str) -> QueryResult<Option<String>> {
+    use schema::command_aliases::dsl as cadsl;
+    cadsl::command_aliases
+        .select(cadsl::command)
+        .filter(cadsl::alias.eq(alias))
+        .get_result(conn)
+        .optional()
+}
+
+/// Every alias with the command it stands for, ordered by command.
+pub fn command_aliases(conn: &PgConnection) -> QueryResult<Vec<(String, String)>> {
+    use schema::command_aliases::dsl as cadsl;
+    cadsl::command_aliases
+        .select((cadsl::alias, cadsl::command))
+        .order((cadsl::command, cadsl::alias))
+        .get_results(conn)
+}
+
+/// Makes `alias` stand for `command`. Returns false, changing nothing, if it already stands for
+/// something.
+pub fn add_command_alias(conn: &PgConnection, actor: Actor, alias: &str, command: &str) -> QueryResult<bool> {
+    use schema::command_aliases::dsl as cadsl;
+    conn.transaction(|| {
+        let inserted = diesel::insert_into(cadsl::command_aliases)
+            .values((
+                cadsl::alias.eq(alias),
+                cadsl::command.eq(command),
+            ))
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        if inserted > 0 {
+            admin::record(conn, actor, AdminAction::AddCommandAlias, None, json!({ "alias": alias, "command": command }), chrono::Utc::now())?;
+        }
+        Ok(inserted > 0)
+    })
+}
+
+/// Returns whether it was an alias.
+pub fn remove_command_alias(conn: &PgConnection, actor: Actor, alias: &str) -> QueryResult<bool> {
+    use schema::command_aliases::dsl as cadsl;
+    conn.transaction(|| {
+        let command:Option<String> = diesel::delete(cadsl::command_aliases.filter(cadsl::alias.eq(alias)))
+            .returning(cadsl::command)
+            .get_result(conn)
+            .optional()?;
+        if let Some(command) = &command {
+            admin::record(conn, actor, AdminAction::RemoveCommandAlias, None, json!({ "alias": alias, "command": command }), chrono::Utc::now())?;
+        }
+        Ok(command.is_some())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_db;
+
+    const ADMIN:i64 = 1001;
+
+    fn logged(conn:&PgConnection, action:AdminAction) -> Vec<serde_json::Value> {
+        use schema::admin_actions::dsl as aadsl;
+        aadsl::admin_actions
+            .select(aadsl::parameters)
+            .filter(aadsl::actor.eq(ADMIN))
+            .filter(aadsl::action.eq(action.db_name()))
+            .get_results(conn)
+            .unwrap()
+    }
+
+    #[test]
+    #[ignore]
+    fn records_changes_in_the_admin_log() {
+        let conn = test_db::connection();
+        let actor = Actor{ user: ADMIN, reason: None, message_id: None };
+        assert!(add_vote_word(&conn, actor, "Yarp", VoteWord::Yes).unwrap());
+        assert!(!add_vote_word(&conn, actor, "yarp", VoteWord::No).unwrap());
+        assert!(remove_vote_word(&conn, actor, "YARP").unwrap());
+        assert!(!remove_vote_word(&conn, actor, "yarp").unwrap());
+        assert_eq!(logged(&conn, AdminAction::AddVoteWord), vec![json!({ "word": "yarp", "meaning": "yes" })]);
+        assert_eq!(logged(&conn, AdminAction::RemoveVoteWord), vec![json!({ "word": "yarp", "meaning": "yes" })]);
+
+        assert!(add_command_alias(&conn, actor, "bux", "balances").unwrap());
+        assert!(!add_command_alias(&conn, actor, "bux", "give").unwrap());
+        assert!(remove_command_alias(&conn, actor, "bux").unwrap());
+        assert!(!remove_command_alias(&conn, actor, "bux").unwrap());
+        assert_eq!(logged(&conn, AdminAction::AddCommandAlias), vec![json!({ "alias": "bux", "command": "balances" })]);
+        assert_eq!(logged(&conn, AdminAction::RemoveCommandAlias), vec![json!({ "alias": "bux", "command": "balances" })]);
+    }
+}
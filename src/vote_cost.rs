@@ -0,0 +1,218 @@
+use std::fmt;
+
+use crate::i18n::{Locale, Localize};
+use crate::is_win::is_win;
+
+pub const VOTE_BASE_COST:u16 = 40;
+
+pub fn nth_vote_cost(n:i64) -> Result<i64,()> {
+    let res:f64 = (VOTE_BASE_COST as f64) * (1.05f64).powf((n-1) as f64);
+    if res < 0.0 || res > 4611686018427388000.0 {
+        Err(())
+    } else {
+        Ok(res as i64)
+    }
+}
+
+/// Cost of casting `count` more votes when `voted_so_far` have already been cast on the same motion.
+/// Errs if the cost doesn't fit in an i64.
+pub fn votes_cost(voted_so_far:i64, count:i64) -> Result<i64,()> {
+    let mut cost:i64 = 0;
+    for nth in voted_so_far+1..voted_so_far+count+1 {
+        cost = cost.checked_add(nth_vote_cost(nth)?).ok_or(())?;
+    }
+    Ok(cost)
+}
+
+/// The most votes that can be cast for at most `budget` capital when `voted_so_far` have already been
+/// cast on the same motion, and what they cost. Each vote costs more than the last, so buying the
+/// cheapest next vote until the budget runs out is optimal.
+pub fn max_votes_within(voted_so_far:i64, budget:i64) -> (i64, i64) {
+    let mut votes = 0;
+    let mut cost:i64 = 0;
+    while let Ok(next) = nth_vote_cost(voted_so_far + votes + 1) {
+        match cost.checked_add(next) {
+            Some(new_cost) if new_cost <= budget => {
+                cost = new_cost;
+                votes += 1;
+            },
+            _ => break,
+        }
+    }
+    (votes, cost)
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum VoteAmount {
+    /// Exactly this many votes
+    Exact(i64),
+    /// As many votes as fit in this much capital (or the voter's balance, if that's smaller)
+    Budget(i64),
+    /// As many votes as fit in this share of the voter's balance
+    Share{numerator: u64, denominator: u64},
+    /// As many votes as the voter's whole balance can buy
+    Max,
+}
+
+impl VoteAmount {
+    /// How many votes to cast and what they cost, for a voter with `balance` capital who has already
+    /// cast `voted_so_far` votes. Errs if an exact count costs more than fits in an i64.
+    pub fn resolve(&self, voted_so_far:i64, balance:i64) -> Result<(i64, i64),()> {
+        match *self {
+            VoteAmount::Exact(count) => votes_cost(voted_so_far, count).map(|cost| (count, cost)),
+            VoteAmount::Budget(budget) => Ok(max_votes_within(voted_so_far, std::cmp::min(budget, balance))),
+            VoteAmount::Share{numerator, denominator} => {
+                let budget = balance.max(0) as i128 * numerator as i128 / denominator as i128;
+                Ok(max_votes_within(voted_so_far, budget as i64))
+            },
+            VoteAmount::Max => Ok(max_votes_within(voted_so_far, balance)),
+        }
+    }
+}
+
+/// The fewest additional votes, all in the returned direction, that change the result of a motion
+/// with the given tally.
+pub fn votes_to_flip(yes_votes:i64, no_votes:i64, is_super:bool) -> (bool, i64) {
+    // A supermotion passes iff yes > 2*no, a simple motion iff yes > no
+    let no_weight = if is_super { 2 } else { 1 };
+    if is_win(yes_votes, no_votes, is_super) {
+        // need yes <= no_weight * (no + n)
+        let needed_no = (yes_votes + no_weight - 1) / no_weight;
+        (false, needed_no - no_votes)
+    } else {
+        // need yes + n > no_weight * no
+        (true, no_weight * no_votes - yes_votes + 1)
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum FlipPlan {
+    /// Casting `votes` more votes in `direction` flips the result for `cost` capital
+    Affordable{direction: bool, votes: i64, cost: i64},
+    /// Flipping costs more than the voter's `balance`. `cost` is None if it is too large to count.
+    Unaffordable{direction: bool, votes: i64, cost: Option<i64>, balance: i64},
+    /// Flipping needs votes in `direction`, but the voter already voted the other way
+    WrongSide{direction: bool},
+}
+
+/// Works out what it would take for a voter to flip a motion, given the current tally, the voter's
+/// existing vote (direction and count) if any, and their capital.
+pub fn flip_plan(
+    yes_votes:i64,
+    no_votes:i64,
+    is_super:bool,
+    existing_vote:Option<(bool, i64)>,
+    balance:i64,
+) -> FlipPlan {
+    let (direction, votes) = votes_to_flip(yes_votes, no_votes, is_super);
+    let voted_so_far = match existing_vote {
+        Some((existing_direction, _)) if existing_direction != direction => return FlipPlan::WrongSide{direction},
+        Some((_, count)) => count,
+        None => 0,
+    };
+    match votes_cost(voted_so_far, votes) {
+        Ok(cost) if cost <= balance => FlipPlan::Affordable{direction, votes, cost},
+        Ok(cost) => FlipPlan::Unaffordable{direction, votes, cost: Some(cost), balance},
+        Err(()) => FlipPlan::Unaffordable{direction, votes, cost: None, balance},
+    }
+}
+
+impl Localize for FlipPlan {
+    fn localize(&self, locale:Locale) -> String {
+        match *self {
+            FlipPlan::Affordable{direction, votes, cost} => tr!(
+                locale,
+                if direction { "flip.affordable_for" } else { "flip.affordable_against" },
+                votes = locale.number(votes), cost = locale.number(cost),
+            ),
+            FlipPlan::Unaffordable{direction, votes, cost: Some(cost), balance} => tr!(
+                locale,
+                if direction { "flip.unaffordable_for" } else { "flip.unaffordable_against" },
+                votes = locale.number(votes), cost = locale.number(cost), balance = locale.number(balance),
+            ),
+            FlipPlan::Unaffordable{direction, votes, cost: None, ..} => tr!(
+                locale,
+                if direction { "flip.impossible_for" } else { "flip.impossible_against" },
+                votes = locale.number(votes),
+            ),
+            FlipPlan::WrongSide{direction} => tr!(
+                locale,
+                if direction { "flip.wrong_side_for" } else { "flip.wrong_side_against" }
+            ).to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FlipPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn flip_is_minimal() {
+        for &is_super in &[false, true] {
+            for yes in 0..30 {
+                for no in 0..30 {
+                    let before = is_win(yes, no, is_super);
+                    let (direction, n) = votes_to_flip(yes, no, is_super);
+                    assert_eq!(direction, !before);
+                    assert!(n > 0);
+                    let after = |n| if direction { is_win(yes + n, no, is_super) } else { is_win(yes, no + n, is_super) };
+                    assert_ne!(after(n), before, "{} {} {}", yes, no, is_super);
+                    assert_eq!(after(n - 1), before, "{} {} {}", yes, no, is_super);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn cost_follows_ordinal() {
+        assert_eq!(votes_cost(0, 0), Ok(0));
+        assert_eq!(votes_cost(0, 1), Ok(40));
+        assert_eq!(votes_cost(0, 2), Ok(40 + 42));
+        assert_eq!(votes_cost(1, 1), Ok(42));
+        assert_eq!(votes_cost(0, 100_000), Err(()));
+    }
+
+    #[test]
+    fn solver_fits_budget() {
+        assert_eq!(max_votes_within(0, 39), (0, 0));
+        assert_eq!(max_votes_within(0, 40), (1, 40));
+        assert_eq!(max_votes_within(0, 81), (1, 40));
+        assert_eq!(max_votes_within(0, 82), (2, 82));
+        assert_eq!(max_votes_within(1, 82), (1, 42));
+        for voted_so_far in 0..20 {
+            for budget in (0..5000).step_by(37) {
+                let (votes, cost) = max_votes_within(voted_so_far, budget);
+                assert_eq!(votes_cost(voted_so_far, votes), Ok(cost));
+                assert!(cost <= budget);
+                assert!(votes_cost(voted_so_far, votes + 1).unwrap() > budget);
+            }
+        }
+        let (votes, _) = max_votes_within(0, i64::MAX);
+        assert!(votes > 0);
+    }
+
+    #[test]
+    fn amounts_resolve() {
+        assert_eq!(VoteAmount::Exact(2).resolve(0, 0), Ok((2, 82)));
+        assert_eq!(VoteAmount::Budget(100).resolve(0, 1000), Ok((2, 82)));
+        assert_eq!(VoteAmount::Budget(1000).resolve(0, 50), Ok((1, 40)));
+        assert_eq!(VoteAmount::Share{numerator: 1, denominator: 2}.resolve(0, 170), Ok((2, 82)));
+        assert_eq!(VoteAmount::Share{numerator: 1, denominator: 2}.resolve(0, 79), Ok((0, 0)));
+        assert_eq!(VoteAmount::Max.resolve(1, 50), Ok((1, 42)));
+    }
+
+    #[test]
+    fn plans() {
+        assert_eq!(flip_plan(1, 0, false, None, 1000), FlipPlan::Affordable{direction: false, votes: 1, cost: 40});
+        assert_eq!(flip_plan(1, 0, false, Some((false, 1)), 1000), FlipPlan::Affordable{direction: false, votes: 1, cost: 42});
+        assert_eq!(flip_plan(1, 0, false, Some((true, 1)), 1000), FlipPlan::WrongSide{direction: false});
+        assert_eq!(flip_plan(3, 0, false, None, 10), FlipPlan::Unaffordable{direction: false, votes: 3, cost: Some(40 + 42 + 44), balance: 10});
+    }
+}
@@ -0,0 +1,186 @@
+//! Which reactions on a motion message cast votes. Configured with the `VOTE_EMOJI` environment
+//! variable, falling back to standard Unicode emoji that work in any guild.
+use serenity::model::channel::ReactionType;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum SpecialEmojiAction {
+    Direction(bool),
+    Amount(u64),
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub enum EmojiKey {
+    /// A guild's custom emoji, by id. The name is only used when reacting.
+    Custom{id: u64, name: Option<String>},
+    Unicode(String),
+}
+
+#[derive(Debug,Clone,PartialEq,Eq)]
+pub struct VoteEmoji {
+    pub key: EmojiKey,
+    pub action: SpecialEmojiAction,
+}
+
+impl VoteEmoji {
+    pub fn reaction_type(&self) -> ReactionType {
+        match &self.key {
+            EmojiKey::Custom{id, name} => ReactionType::Custom{
+                animated: false,
+                id: (*id).into(),
+                name: Some(name.clone().unwrap_or_else(|| String::from("vote"))),
+            },
+            EmojiKey::Unicode(s) => ReactionType::Unicode(s.clone()),
+        }
+    }
+
+    /// Identifies the emoji in the database, see `reaction_votes.emoji`
+    pub fn db_key(&self) -> String {
+        match &self.key {
+            EmojiKey::Custom{id, ..} => id.to_string(),
+            EmojiKey::Unicode(s) => s.clone(),
+        }
+    }
+
+    pub fn matches(&self, reaction:&ReactionType) -> bool {
+        match (&self.key, reaction) {
+            (EmojiKey::Custom{id, ..}, ReactionType::Custom{id: reaction_id, ..}) => *id == reaction_id.0,
+            (EmojiKey::Unicode(s), ReactionType::Unicode(reaction_str)) => s == reaction_str,
+            _ => false,
+        }
+    }
+
+    /// Sorts no, then yes, then amounts ascending; the order the bot reacts in.
+    pub fn sort_key(&self) -> i64 {
+        match self.action {
+            SpecialEmojiAction::Direction(false) => -2,
+            SpecialEmojiAction::Direction(true) => -1,
+            SpecialEmojiAction::Amount(a) => a as i64,
+        }
+    }
+}
+
+/// Used when `VOTE_EMOJI` is not set. Any number of votes from 0 to 25 can be cast from these
+/// without un-reacting.
+pub fn default_set() -> Vec<VoteEmoji> {
+    let unicode = |s:&str, action| VoteEmoji{ key: EmojiKey::Unicode(String::from(s)), action };
+    vec![
+        unicode("\u{1F44E}", SpecialEmojiAction::Direction(false)), // thumbs down
+        unicode("\u{1F44D}", SpecialEmojiAction::Direction(true)), // thumbs up
+        unicode("1\u{FE0F}\u{20E3}", SpecialEmojiAction::Amount(1)),
+        unicode("2\u{FE0F}\u{20E3}", SpecialEmojiAction::Amount(2)),
+        unicode("4\u{FE0F}\u{20E3}", SpecialEmojiAction::Amount(4)),
+        unicode("8\u{FE0F}\u{20E3}", SpecialEmojiAction::Amount(8)),
+        unicode("\u{1F51F}", SpecialEmojiAction::Amount(10)), // keycap ten
+    ]
+}
+
+fn parse_action(s:&str) -> Result<SpecialEmojiAction, String> {
+    match s {
+        "yes" | "for" => Ok(SpecialEmojiAction::Direction(true)),
+        "no" | "against" => Ok(SpecialEmojiAction::Direction(false)),
+        _ => match s.parse():Result<u64, _> {
+            Ok(0) => Err(String::from("an amount of 0 votes does nothing")),
+            Ok(a) => Ok(SpecialEmojiAction::Amount(a)),
+            Err(_) => Err(format!("{:?} is not yes, no, or a number of votes", s)),
+        },
+    }
+}
+
+/// Parses an emoji as a bare custom emoji id, as Discord's `<:name:id>` (or `<a:name:id>`) syntax,
+/// or otherwise as a Unicode emoji.
+fn parse_key(s:&str) -> Result<EmojiKey, String> {
+    if s.is_empty() {
+        return Err(String::from("missing emoji"));
+    }
+    if s.bytes().all(|b| b.is_ascii_digit()) {
+        return s.parse().map(|id| EmojiKey::Custom{ id, name: None }).map_err(|e| format!("{}", e));
+    }
+    if s.starts_with('<') && s.ends_with('>') {
+        let parts:Vec<&str> = s[1..s.len()-1].split(':').collect();
+        if let [_animated, name, id] = parts.as_slice() {
+            let id = id.parse().map_err(|_| format!("bad custom emoji id in {:?}", s))?;
+            return Ok(EmojiKey::Custom{ id, name: Some(String::from(*name)) });
+        }
+        return Err(format!("expected <:name:id>, got {:?}", s));
+    }
+    Ok(EmojiKey::Unicode(String::from(s)))
+}
+
+/// Parses a list of `action=emoji` entries separated by commas or whitespace, where the action is
+/// `yes`, `no`, or a number of votes, eg. `yes=👍, no=<:nay:770750576257531914>, 5=770750182874021888`.
+pub fn parse_config(config:&str) -> Result<Vec<VoteEmoji>, String> {
+    let mut res:Vec<VoteEmoji> = Vec::new();
+    for entry in config.split(|c:char| c == ',' || c.is_whitespace()).filter(|e| !e.is_empty()) {
+        let mut halves = entry.splitn(2, '=');
+        let action = parse_action(halves.next().unwrap())?;
+        let key = parse_key(halves.next().ok_or_else(|| format!("expected action=emoji, got {:?}", entry))?)?;
+        let key_is_new = res.iter().all(|e| match (&e.key, &key) {
+            (EmojiKey::Custom{id: a, ..}, EmojiKey::Custom{id: b, ..}) => a != b,
+            (a, b) => a != b,
+        });
+        if !key_is_new {
+            return Err(format!("{:?} is configured more than once", entry));
+        }
+        res.push(VoteEmoji{ key, action });
+    }
+    if res.is_empty() {
+        return Err(String::from("no emoji configured"));
+    }
+    Ok(res)
+}
+
+/// Reads `VOTE_EMOJI`, panicking if it is set but invalid so that misconfiguration shows up at startup.
+pub fn from_env() -> Vec<VoteEmoji> {
+    match std::env::var("VOTE_EMOJI") {
+        Ok(config) => parse_config(&config).unwrap_or_else(|e| panic!("Invalid VOTE_EMOJI: {}", e)),
+        Err(std::env::VarError::NotPresent) => default_set(),
+        Err(e) => panic!("Invalid VOTE_EMOJI: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_all_forms() {
+        let parsed = parse_config("yes=\u{1F44D}, no=<:nay:770750576257531914>\n5=770750182874021888").unwrap();
+        assert_eq!(parsed, vec![
+            VoteEmoji{ key: EmojiKey::Unicode(String::from("\u{1F44D}")), action: SpecialEmojiAction::Direction(true) },
+            VoteEmoji{ key: EmojiKey::Custom{ id: 770750576257531914, name: Some(String::from("nay")) }, action: SpecialEmojiAction::Direction(false) },
+            VoteEmoji{ key: EmojiKey::Custom{ id: 770750182874021888, name: None }, action: SpecialEmojiAction::Amount(5) },
+        ]);
+    }
+
+    #[test]
+    fn rejects_bad_config() {
+        assert!(parse_config("").is_err());
+        assert!(parse_config("maybe=\u{1F44D}").is_err());
+        assert!(parse_config("0=\u{1F44D}").is_err());
+        assert!(parse_config("yes").is_err());
+        assert!(parse_config("yes=<:nay:abc>").is_err());
+        assert!(parse_config("1=770750182874021888 2=<:two:770750182874021888>").is_err());
+    }
+
+    #[test]
+    fn parses_the_legacy_set_from_the_readme() {
+        let parsed = parse_config("no=770750576257531914 yes=770750552291410000 1=770749957723783169 1=770749937960747029 2=770750097793089596 5=770750182874021888 10=770750211281780776 10=770750231776198698 20=770750297621921802 50=770750316530499604 100=770750332946874388").unwrap();
+        assert_eq!(parsed.len(), 11);
+        assert_eq!(parsed[0], VoteEmoji{ key: EmojiKey::Custom{ id: 770750576257531914, name: None }, action: SpecialEmojiAction::Direction(false) });
+        assert_eq!(parsed[10], VoteEmoji{ key: EmojiKey::Custom{ id: 770750332946874388, name: None }, action: SpecialEmojiAction::Amount(100) });
+    }
+
+    #[test]
+    fn defaults_cover_small_amounts() {
+        let amounts:Vec<u64> = default_set().iter().filter_map(|e| match e.action {
+            SpecialEmojiAction::Amount(a) => Some(a),
+            _ => None,
+        }).collect();
+        for target in 0..=25 {
+            let reachable = (0..1u32 << amounts.len()).any(|mask| {
+                amounts.iter().enumerate().filter(|(i, _)| mask & (1 << i) != 0).map(|(_, a)| a).sum::<u64>() == target
+            });
+            assert!(reachable, "{}", target);
+        }
+    }
+}
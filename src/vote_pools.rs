@@ -0,0 +1,565 @@
+//! Vote pools: capital raised from several users to vote one way on a motion, such as `$fund 123
+//! for 500`. Each pool has an account in the ledger (see `account_id`), which contributions are
+//! paid into. When collection closes, `run_due` spends all of it on votes cast by that account, so
+//! the pool's votes share one run of ordinals rather than each contributor's starting their own.
+//! Later pools on the same side of a motion use the first one's account, carrying on its run rather
+//! than starting cheap again. Whatever is left over, or everything if it couldn't vote at all, is refunded
+//! in proportion to what each contribution put in.
+//!
+//! Contributing and settling both lock the motion, then the pool, so a pool can't take a
+//! contribution once it has been settled.
+use std::collections::HashMap;
+use std::fmt;
+
+use diesel::prelude::*;
+use diesel::pg::PgConnection;
+use chrono::{DateTime, Utc};
+
+use crate::{damm, schema, settings};
+use crate::economy::{self, BoxError, ChatFrontend, VoteError};
+use crate::i18n::{Locale, Localize};
+use crate::vote_cost::VoteAmount;
+
+/// Pool accounts are below this, leaving everything from -1 down to it for treasuries.
+pub const ACCOUNT_BASE:i64 = -(1 << 40);
+
+/// The id the first pool on a side of a motion goes by in the ledger, and later ones share.
+pub fn account_id(pool_id:i64) -> i64 {
+    ACCOUNT_BASE - pool_id
+}
+
+/// The first pool a ledger id belonged to, if it's a pool's.
+pub fn pool_of(account:i64) -> Option<i64> {
+    if account < ACCOUNT_BASE { Some(ACCOUNT_BASE - account) } else { None }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum PoolStatus {
+    Collecting,
+    /// Collection has closed, and the pool is about to buy its votes
+    Closed,
+    Bought,
+    Refunded,
+}
+
+impl Localize for PoolStatus {
+    fn localize(&self, locale:Locale) -> String {
+        tr!(locale, match self {
+            PoolStatus::Collecting => "pool.status.collecting",
+            PoolStatus::Closed => "pool.status.closed",
+            PoolStatus::Bought => "pool.status.bought",
+            PoolStatus::Refunded => "pool.status.refunded",
+        }).to_string()
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct VotePool {
+    pub rowid:i64,
+    pub motion:i64,
+    pub direction:bool,
+    pub created_by:i64,
+    pub created_at:DateTime<Utc>,
+    pub closes_at:DateTime<Utc>,
+    pub settled_at:Option<DateTime<Utc>>,
+    pub votes:Option<i64>,
+    pub cost:Option<i64>,
+    pub account:i64,
+}
+
+impl VotePool {
+    pub fn account(&self) -> i64 {
+        self.account
+    }
+
+    pub fn status(&self, now:DateTime<Utc>) -> PoolStatus {
+        match (self.settled_at, self.votes) {
+            (None, _) if self.closes_at <= now => PoolStatus::Closed,
+            (None, _) => PoolStatus::Collecting,
+            (Some(_), Some(_)) => PoolStatus::Bought,
+            (Some(_), None) => PoolStatus::Refunded,
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Eq,Queryable)]
+pub struct Contribution {
+    pub rowid:i64,
+    pub pool:i64,
+    pub user:i64,
+    pub quantity:i64,
+    pub contributed_at:DateTime<Utc>,
+    pub message_id:Option<i64>,
+    pub transfer:i64,
+    /// This contribution's share of what was left once the pool settled
+    pub refunded:Option<i64>,
+    /// The contributor's refund, shared by all of their contributions to the pool
+    pub refund_transfer:Option<i64>,
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum PoolError {
+    NotPositive,
+    MotionNotFound,
+    /// The motion has already been announced
+    MotionClosed,
+    /// Collection has closed, but the pool hasn't bought its votes yet
+    Closing,
+    InsufficientBalance{ balance: i64 },
+}
+
+impl Localize for PoolError {
+    fn localize(&self, locale:Locale) -> String {
+        match self {
+            PoolError::NotPositive => tr!(locale, "pool.not_positive").to_string(),
+            PoolError::MotionNotFound => tr!(locale, "vote.not_found").to_string(),
+            PoolError::MotionClosed => tr!(locale, "vote.expired").to_string(),
+            PoolError::Closing => tr!(locale, "pool.closing").to_string(),
+            PoolError::InsufficientBalance{balance} => tr!(locale, "pool.insufficient_balance", balance = locale.number(*balance)),
+        }
+    }
+}
+
+impl fmt::Display for PoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.localize(Locale::En))
+    }
+}
+
+/// Splits `total` in proportion to `weights`, rounding so the shares add up to exactly `total`: what
+/// rounding down leaves over goes one each to the largest remainders, earliest first.
+pub fn proportional_shares(total:i64, weights:&[i64]) -> Vec<i64> {
+    let sum:i128 = weights.iter().map(|&w| w as i128).sum();
+    if total <= 0 || sum <= 0 {
+        return vec![0; weights.len()];
+    }
+    let mut shares:Vec<i64> = weights.iter().map(|&w| (total as i128 * w as i128 / sum) as i64).collect();
+    let mut left_over = total - shares.iter().sum::<i64>();
+    let mut order:Vec<usize> = (0..weights.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(total as i128 * weights[i] as i128 % sum));
+    for i in order {
+        if left_over == 0 {
+            break;
+        }
+        shares[i] += 1;
+        left_over -= 1;
+    }
+    shares
+}
+
+/// Puts `quantity` capital from `user` into the pool collecting for `direction` on a motion,
+/// starting one that closes after `window` if there isn't one.
+pub fn contribute(
+    conn:&PgConnection,
+    motion_id:i64,
+    direction:bool,
+    user:i64,
+    quantity:i64,
+    message_id:Option<i64>,
+    window:chrono::Duration,
+) -> QueryResult<Result<(VotePool, Contribution), PoolError>> {
+    use schema::motions::dsl as mdsl;
+    use schema::vote_pools::dsl as vpdsl;
+    use schema::vote_pool_contributions::dsl as vpcdsl;
+    use schema::transfers::dsl as tdsl;
+    if quantity <= 0 {
+        return Ok(Err(PoolError::NotPositive));
+    }
+    let mut fail:Option<PoolError> = None;
+    let txn_res = conn.transaction::<_, diesel::result::Error, _>(|| {
+        let not_announced:Option<bool> = mdsl::motions
+            .filter(mdsl::rowid.eq(motion_id))
+            .select(mdsl::announcement_message_id.is_null())
+            .for_update()
+            .get_result(conn)
+            .optional()?;
+        match not_announced {
+            None => fail = Some(PoolError::MotionNotFound),
+            Some(false) => fail = Some(PoolError::MotionClosed),
+            Some(true) => (),
+        }
+        if fail.is_some() {
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        let now = chrono::Utc::now();
+        let open:Option<VotePool> = vpdsl::vote_pools
+            .filter(vpdsl::motion.eq(motion_id))
+            .filter(vpdsl::direction.eq(direction))
+            .filter(vpdsl::settled_at.is_null())
+            .for_update()
+            .get_result(conn)
+            .optional()?;
+        let pool = match open {
+            Some(pool) if pool.closes_at <= now => {
+                fail = Some(PoolError::Closing);
+                return Err(diesel::result::Error::RollbackTransaction);
+            },
+            Some(pool) => pool,
+            None => {
+                let earlier_account:Option<i64> = vpdsl::vote_pools
+                    .filter(vpdsl::motion.eq(motion_id))
+                    .filter(vpdsl::direction.eq(direction))
+                    .select(vpdsl::account)
+                    .order(vpdsl::rowid.asc())
+                    .first(conn)
+                    .optional()?;
+                let pool:VotePool = diesel::insert_into(vpdsl::vote_pools).values((
+                    vpdsl::motion.eq(motion_id),
+                    vpdsl::direction.eq(direction),
+                    vpdsl::created_by.eq(user),
+                    vpdsl::created_at.eq(now),
+                    vpdsl::closes_at.eq(now + window),
+                    vpdsl::account.eq(earlier_account.unwrap_or(ACCOUNT_BASE)),
+                )).get_result(conn)?;
+                match earlier_account {
+                    Some(_) => pool,
+                    // the first pool on this side, whose account is named after it
+                    None => diesel::update(vpdsl::vote_pools.filter(vpdsl::rowid.eq(pool.rowid)))
+                        .set(vpdsl::account.eq(account_id(pool.rowid)))
+                        .get_result(conn)?,
+                }
+            },
+        };
+
+        // pool accounts are negative, so this is the same order `give` locks in
+        let pool_balance = economy::latest_balance(conn, pool.account(), "pc", true)?;
+        let balance = economy::latest_balance(conn, user, "pc", true)?;
+        if balance < quantity {
+            fail = Some(PoolError::InsufficientBalance{ balance });
+            return Err(diesel::result::Error::RollbackTransaction);
+        }
+        let transfer:i64 = diesel::insert_into(tdsl::transfers).values((
+            tdsl::from_user.eq(user),
+            tdsl::to_user.eq(pool.account()),
+            tdsl::quantity.eq(quantity),
+            tdsl::from_balance.eq(balance - quantity),
+            tdsl::to_balance.eq(pool_balance + quantity),
+            tdsl::happened_at.eq(now),
+            tdsl::message_id.eq(message_id),
+            tdsl::ty.eq("pc"),
+            tdsl::transfer_ty.eq("pool_contribute"),
+        )).returning(tdsl::rowid).get_result(conn)?;
+        let contribution = diesel::insert_into(vpcdsl::vote_pool_contributions).values((
+            vpcdsl::pool.eq(pool.rowid),
+            vpcdsl::user.eq(user),
+            vpcdsl::quantity.eq(quantity),
+            vpcdsl::contributed_at.eq(now),
+            vpcdsl::message_id.eq(message_id),
+            vpcdsl::transfer.eq(transfer),
+        )).get_result(conn)?;
+        Ok((pool, contribution))
+    });
+    match (txn_res, fail) {
+        (_, Some(e)) => Ok(Err(e)),
+        (res, None) => res.map(Ok),
+    }
+}
+
+/// Every pool on a motion, oldest first.
+pub fn on_motion(conn:&PgConnection, motion_id:i64) -> QueryResult<Vec<VotePool>> {
+    use schema::vote_pools::dsl as vpdsl;
+    vpdsl::vote_pools
+        .filter(vpdsl::motion.eq(motion_id))
+        .order(vpdsl::rowid.asc())
+        .get_results(conn)
+}
+
+/// Every contribution to a pool, oldest first.
+pub fn contributions(conn:&PgConnection, pool_id:i64) -> QueryResult<Vec<Contribution>> {
+    use schema::vote_pool_contributions::dsl as vpcdsl;
+    vpcdsl::vote_pool_contributions
+        .filter(vpcdsl::pool.eq(pool_id))
+        .order(vpcdsl::rowid.asc())
+        .get_results(conn)
+}
+
+/// How a pool settled.
+struct Settled {
+    pool:VotePool,
+    contributions:Vec<Contribution>,
+    /// Why it couldn't vote, if it couldn't
+    error:Option<VoteError>,
+}
+
+/// Spends a pool on votes and refunds what's left, unless it's been settled already.
+fn settle(conn:&PgConnection, pool_id:i64) -> QueryResult<Option<Settled>> {
+    use schema::motions::dsl as mdsl;
+    use schema::vote_pools::dsl as vpdsl;
+    use schema::vote_pool_contributions::dsl as vpcdsl;
+    use schema::transfers::dsl as tdsl;
+    conn.transaction::<_, diesel::result::Error, _>(|| {
+        let motion_id:i64 = vpdsl::vote_pools.filter(vpdsl::rowid.eq(pool_id)).select(vpdsl::motion).get_result(conn)?;
+        mdsl::motions.filter(mdsl::rowid.eq(motion_id)).select(mdsl::rowid).for_update().execute(conn)?;
+        let pool:VotePool = vpdsl::vote_pools.filter(vpdsl::rowid.eq(pool_id)).for_update().get_result(conn)?;
+        if pool.settled_at.is_some() {
+            return Ok(None);
+        }
+        let raised = economy::latest_balance(conn, pool.account(), "pc", true)?;
+        let (bought, error) = match economy::vote(
+            conn,
+            Some(pool.direction),
+            VoteAmount::Max,
+            pool.account(),
+            Some(pool.motion),
+            None,
+            None,
+            None,
+        )? {
+            Ok(outcome) => (Some((outcome.votes, outcome.cost)), None),
+            Err(e) => (None, Some(e)),
+        };
+        let cost = bought.map(|(_, cost)| cost).unwrap_or(0);
+
+        let now = chrono::Utc::now();
+        let mut pool_balance = raised - cost;
+        let held = contributions(conn, pool_id)?;
+        let shares = proportional_shares(pool_balance, &held.iter().map(|c| c.quantity).collect::<Vec<_>>());
+        // one refund per contributor, however many times they contributed: every transfer here
+        // happens at `now` and a second one couldn't tell which of their balances came last
+        let mut refunds:Vec<(i64, i64)> = Vec::new();
+        for (contribution, share) in held.iter().zip(&shares) {
+            match refunds.iter_mut().find(|(user, _)| *user == contribution.user) {
+                Some((_, total)) => *total += share,
+                None => refunds.push((contribution.user, *share)),
+            }
+        }
+        let mut refund_transfers:HashMap<i64, i64> = HashMap::new();
+        for (user, refund) in refunds {
+            if refund <= 0 {
+                continue;
+            }
+            let balance = economy::latest_balance(conn, user, "pc", true)?;
+            pool_balance -= refund;
+            let transfer_id:i64 = diesel::insert_into(tdsl::transfers).values((
+                tdsl::from_user.eq(pool.account()),
+                tdsl::to_user.eq(user),
+                tdsl::quantity.eq(refund),
+                tdsl::from_balance.eq(pool_balance),
+                tdsl::to_balance.eq(balance + refund),
+                tdsl::happened_at.eq(now),
+                tdsl::ty.eq("pc"),
+                tdsl::transfer_ty.eq("pool_refund"),
+            )).returning(tdsl::rowid).get_result(conn)?;
+            refund_transfers.insert(user, transfer_id);
+        }
+        let mut settled_contributions = Vec::new();
+        for (contribution, share) in held.into_iter().zip(shares) {
+            let refund_transfer = if share > 0 { refund_transfers.get(&contribution.user).copied() } else { None };
+            settled_contributions.push(
+                diesel::update(vpcdsl::vote_pool_contributions.filter(vpcdsl::rowid.eq(contribution.rowid)))
+                    .set((
+                        vpcdsl::refunded.eq(share),
+                        vpcdsl::refund_transfer.eq(refund_transfer),
+                    ))
+                    .get_result(conn)?
+            );
+        }
+        let pool = diesel::update(vpdsl::vote_pools.filter(vpdsl::rowid.eq(pool_id)))
+            .set((
+                vpdsl::settled_at.eq(now),
+                vpdsl::votes.eq(bought.map(|(votes, _)| votes)),
+                vpdsl::cost.eq(bought.map(|(_, cost)| cost)),
+            ))
+            .get_result(conn)?;
+        Ok(Some(Settled{ pool, contributions: settled_contributions, error }))
+    })
+}
+
+/// Settles every pool whose collection has closed, DMing its contributors how it went. Falls back
+/// to `locale` for users who haven't picked one. Returns how many pools were settled.
+pub fn run_due(frontend:&impl ChatFrontend, conn:&PgConnection, locale:Locale) -> Result<usize, BoxError> {
+    use schema::vote_pools::dsl as vpdsl;
+    let now = chrono::Utc::now();
+    let due:Vec<i64> = vpdsl::vote_pools
+        .select(vpdsl::rowid)
+        .filter(vpdsl::settled_at.is_null())
+        .filter(vpdsl::closes_at.le(now))
+        .order(vpdsl::closes_at.asc())
+        .get_results(conn)?;
+    let mut settled_count = 0;
+    for pool_id in due {
+        let settled = match settle(conn, pool_id)? {
+            Some(s) => s,
+            None => continue,
+        };
+        settled_count += 1;
+        let pool = &settled.pool;
+        let raised:i64 = settled.contributions.iter().map(|c| c.quantity).sum();
+        // one DM per contributor, however many times they contributed
+        let mut contributors:Vec<(i64, i64, i64)> = Vec::new();
+        for c in &settled.contributions {
+            match contributors.iter_mut().find(|(user, _, _)| *user == c.user) {
+                Some((_, put_in, refunded)) => {
+                    *put_in += c.quantity;
+                    *refunded += c.refunded.unwrap_or(0);
+                },
+                None => contributors.push((c.user, c.quantity, c.refunded.unwrap_or(0))),
+            }
+        }
+        for (user, put_in, refunded) in contributors {
+            let user_locale = settings::user_locale(conn, user)?.unwrap_or(locale);
+            let motion = damm::add_to_str(pool.motion.to_string());
+            let text = match (pool.votes, pool.cost, settled.error) {
+                (Some(votes), Some(cost), _) => tr!(
+                    user_locale,
+                    if pool.direction { "pool.notice_bought_for" } else { "pool.notice_bought_against" },
+                    id = pool.rowid,
+                    motion = motion,
+                    raised = user_locale.number(raised),
+                    votes = user_locale.number(votes),
+                    cost = user_locale.number(cost),
+                    contributed = user_locale.number(put_in),
+                    refunded = user_locale.number(refunded),
+                ),
+                (_, _, error) => tr!(
+                    user_locale,
+                    "pool.notice_refunded",
+                    id = pool.rowid,
+                    motion = motion,
+                    raised = user_locale.number(raised),
+                    reason = error.map(|e| e.localize(user_locale)).unwrap_or_default(),
+                    contributed = user_locale.number(put_in),
+                ),
+            };
+            if let Err(e) = frontend.dm_user(user, &text) {
+                info!("Could not DM {} about vote pool {}: {:?}", user, pool.rowid, e);
+            }
+        }
+    }
+    Ok(settled_count)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_db;
+    use crate::vote_cost;
+
+    const MOTIONER:i64 = 1001;
+    const CONTRIBUTOR:i64 = 1002;
+    const OTHER_CONTRIBUTOR:i64 = 1003;
+
+    fn contribute_for(conn:&PgConnection, motion_id:i64, user:i64, quantity:i64) -> (VotePool, Contribution) {
+        contribute(conn, motion_id, true, user, quantity, None, chrono::Duration::hours(1)).unwrap().unwrap()
+    }
+
+    /// Stops the motion taking votes, as though it had been announced.
+    fn announce(conn:&PgConnection, motion_id:i64) {
+        use schema::motions::dsl as mdsl;
+        diesel::update(mdsl::motions.filter(mdsl::rowid.eq(motion_id)))
+            .set(mdsl::announcement_message_id.eq(1))
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn accounts_are_not_users_or_treasuries() {
+        assert_eq!(pool_of(account_id(5)), Some(5));
+        assert_eq!(pool_of(crate::treasuries::account_id(5)), None);
+        assert_eq!(crate::treasuries::treasury_of(account_id(5)), None);
+        assert_eq!(pool_of(165858230327574528), None);
+    }
+
+    #[test]
+    fn shares_are_proportional_and_add_up() {
+        assert_eq!(proportional_shares(100, &[1, 1, 2]), vec![25, 25, 50]);
+        assert_eq!(proportional_shares(10, &[1, 1, 1]), vec![4, 3, 3]);
+        assert_eq!(proportional_shares(5, &[10, 30]), vec![1, 4]);
+        assert_eq!(proportional_shares(7, &[2, 5, 3]), vec![1, 4, 2]);
+        assert_eq!(proportional_shares(0, &[1, 2]), vec![0, 0]);
+        assert_eq!(proportional_shares(9, &[]), Vec::<i64>::new());
+        let shares = proportional_shares(1_000_003, &[7, 11, 13, 17]);
+        assert_eq!(shares.iter().sum::<i64>(), 1_000_003);
+    }
+
+    #[test]
+    #[ignore]
+    fn buys_votes_with_what_was_raised() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, MOTIONER);
+        test_db::fund(&conn, CONTRIBUTOR, "pc", 1000);
+        test_db::fund(&conn, OTHER_CONTRIBUTOR, "pc", 1000);
+        let contributor_before = test_db::balance(&conn, CONTRIBUTOR, "pc");
+        let other_before = test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc");
+        let (pool, _) = contribute_for(&conn, motion_id, CONTRIBUTOR, 300);
+        contribute_for(&conn, motion_id, OTHER_CONTRIBUTOR, 200);
+        let settled = settle(&conn, pool.rowid).unwrap().unwrap();
+        let (votes, cost) = vote_cost::max_votes_within(0, 500);
+        assert_eq!(settled.error, None);
+        assert_eq!(settled.pool.status(chrono::Utc::now()), PoolStatus::Bought);
+        assert_eq!((settled.pool.votes, settled.pool.cost), (Some(votes), Some(cost)));
+        let status = economy::motion_status(&conn, motion_id).unwrap();
+        assert!(status.votes.iter().any(|v| v.user == pool.account() && v.amount == votes && v.direction));
+        // whatever the votes didn't cost goes back, and nothing is left in the pool
+        let refunded:i64 = settled.contributions.iter().map(|c| c.refunded.unwrap()).sum();
+        assert_eq!(refunded, 500 - cost);
+        assert_eq!(test_db::balance(&conn, pool.account(), "pc"), 0);
+        assert_eq!(
+            test_db::balance(&conn, CONTRIBUTOR, "pc") + test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc"),
+            contributor_before + other_before - cost,
+        );
+        // settling again does nothing
+        assert_eq!(settle(&conn, pool.rowid).unwrap().map(|s| s.pool), None);
+    }
+
+    #[test]
+    #[ignore]
+    fn refunds_everything_when_it_cant_vote() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, MOTIONER);
+        test_db::fund(&conn, CONTRIBUTOR, "pc", 1000);
+        test_db::fund(&conn, OTHER_CONTRIBUTOR, "pc", 1000);
+        let contributor_before = test_db::balance(&conn, CONTRIBUTOR, "pc");
+        let other_before = test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc");
+        let (pool, _) = contribute_for(&conn, motion_id, CONTRIBUTOR, 300);
+        contribute_for(&conn, motion_id, OTHER_CONTRIBUTOR, 200);
+        announce(&conn, motion_id);
+        let settled = settle(&conn, pool.rowid).unwrap().unwrap();
+        assert_eq!(settled.error, Some(VoteError::Expired));
+        assert_eq!(settled.pool.status(chrono::Utc::now()), PoolStatus::Refunded);
+        let refunds:Vec<Option<i64>> = settled.contributions.iter().map(|c| c.refunded).collect();
+        assert_eq!(refunds, vec![Some(300), Some(200)]);
+        assert_eq!(test_db::balance(&conn, CONTRIBUTOR, "pc"), contributor_before);
+        assert_eq!(test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc"), other_before);
+        assert_eq!(test_db::balance(&conn, pool.account(), "pc"), 0);
+    }
+
+    #[test]
+    #[ignore]
+    fn refunds_several_contributions_together() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, MOTIONER);
+        test_db::fund(&conn, CONTRIBUTOR, "pc", 1000);
+        test_db::fund(&conn, OTHER_CONTRIBUTOR, "pc", 1000);
+        let contributor_before = test_db::balance(&conn, CONTRIBUTOR, "pc");
+        let other_before = test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc");
+        let (pool, _) = contribute_for(&conn, motion_id, CONTRIBUTOR, 100);
+        contribute_for(&conn, motion_id, OTHER_CONTRIBUTOR, 150);
+        contribute_for(&conn, motion_id, CONTRIBUTOR, 200);
+        announce(&conn, motion_id);
+        let settled = settle(&conn, pool.rowid).unwrap().unwrap();
+        assert_eq!(test_db::balance(&conn, CONTRIBUTOR, "pc"), contributor_before);
+        assert_eq!(test_db::balance(&conn, OTHER_CONTRIBUTOR, "pc"), other_before);
+        let theirs:Vec<&Contribution> = settled.contributions.iter().filter(|c| c.user == CONTRIBUTOR).collect();
+        assert_eq!(theirs.len(), 2);
+        assert!(theirs[0].refund_transfer.is_some());
+        assert_eq!(theirs[0].refund_transfer, theirs[1].refund_transfer);
+    }
+
+    #[test]
+    #[ignore]
+    fn later_pools_carry_on_from_earlier_ones() {
+        let conn = test_db::connection();
+        let motion_id = test_db::motion(&conn, MOTIONER);
+        test_db::fund(&conn, CONTRIBUTOR, "pc", 2000);
+        let (first, _) = contribute_for(&conn, motion_id, CONTRIBUTOR, 500);
+        let first = settle(&conn, first.rowid).unwrap().unwrap().pool;
+        let (second, _) = contribute_for(&conn, motion_id, CONTRIBUTOR, 500);
+        assert_ne!(second.rowid, first.rowid);
+        assert_eq!(second.account(), first.account());
+        assert_eq!(pool_of(second.account()), Some(first.rowid));
+        let second = settle(&conn, second.rowid).unwrap().unwrap().pool;
+        let first_votes = first.votes.unwrap();
+        assert_eq!(second.votes, Some(vote_cost::max_votes_within(first_votes, 500).0));
+    }
+}
@@ -11,10 +11,12 @@ use rocket::http::{ContentType, RawStr, Status};
 use rocket::fairing;
 use maud::{html, Markup};
 use diesel::prelude::*;
-use chrono::{DateTime, Utc, SecondsFormat, TimeZone};
+use chrono::{DateTime, Utc, TimeZone};
 
-use crate::{schema, rocket_diesel};
-use crate::models::{Motion, MotionVote, MotionWithCount};
+use crate::{schema, rocket_diesel, settings, stats, charts};
+use crate::i18n::{Locale, Localize};
+use crate::models::{Motion, MotionResultChange, MotionVote, MotionWithCount};
+use crate::vote_cost::VoteAmount;
 
 fn generate_state<A: rand::RngCore + rand::CryptoRng>(rng: &mut A) -> Result<String, String> {
     let mut buf = [0; 16]; // 128 bits
@@ -69,11 +71,76 @@ struct CSRFForm {
     csrf: String
 }
 
+#[derive(Debug, Clone, FromForm)]
+struct PrivacyForm {
+    csrf: String,
+    hide_holdings: bool,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct LanguageForm {
+    csrf: String,
+    /// A language code, or empty to go by the browser's languages
+    locale: String,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct GiveForm {
+    csrf: String,
+    /// As in `$give`, so `half` or `all` work too
+    amount: String,
+    ty: String,
+    memo: String,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct BurnForm {
+    csrf: String,
+    /// As in `$burn`, so `half` or `all` work too
+    amount: String,
+    ty: String,
+    reason: String,
+}
+
 #[derive(Debug, Clone, FromForm)]
 struct VoteForm {
     csrf: String,
     count: i64,
     direction: String,
+    /// "votes" (the default), "budget" to treat count as capital to spend, or "max"
+    mode: Option<String>,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct ItemTypeForm {
+    csrf: String,
+    /// Only read when creating, since an item type's name can't change
+    name: Option<String>,
+    long_name_plural: String,
+    long_name_ambiguous: String,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct RetireForm {
+    csrf: String,
+    retired: bool,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct AliasForm {
+    csrf: String,
+    alias: String,
+}
+
+#[derive(Debug, Clone, FromForm)]
+struct SupplyForm {
+    csrf: String,
+    /// "fabricate" or "burn"
+    op: String,
+    user: i64,
+    ty: String,
+    amount: i64,
+    reason: String,
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +234,8 @@ struct CommonContext<'a> {
     pub cookies: Cookies<'a>,
     pub deets: Option<Deets>,
     pub conn: rocket_diesel::DbConn,
+    /// The logged in user's language, or else the browser's
+    pub locale: Locale,
 }
 
 impl<'a> core::ops::Deref for CommonContext<'a> {
@@ -206,15 +275,66 @@ impl <'a, 'r> FromRequest<'a, 'r> for CommonContext<'a> {
         };
 
         let conn = rocket_diesel::DbConn::from_request(request).map_failure(|(a,_)| (a, CommonContextError::from(())))?;
+        let user_locale = match deets.as_ref().map(|d| settings::user_locale(&*conn, d.id())) {
+            Some(Ok(locale)) => locale,
+            Some(Err(e)) => {
+                warn!("Failed to look up locale, {:?}", e);
+                None
+            },
+            None => None,
+        };
+        let locale = user_locale
+            .or_else(|| request.headers().get_one("Accept-Language").and_then(Locale::from_accept_language))
+            .unwrap_or_default();
         Outcome::Success(Self{
             csrf_token,
             cookies,
             deets,
             conn,
+            locale,
         })
     }
 }
 
+/// A logged in bot admin, either granted it directly or through a role in a guild. Role membership
+/// is looked up through Discord, since web2 has no gateway connection.
+struct Admin {
+    pub user: i64,
+}
+
+fn is_admin(conn: &diesel::pg::PgConnection, user: i64) -> Result<bool, Box<dyn std::error::Error>> {
+    if crate::admin::is_admin(conn, user, &[])? {
+        return Ok(true);
+    }
+    let mut roles = Vec::new();
+    for guild in crate::admin::admin_role_guilds(conn)? {
+        match crate::interactions::DISCORD_HTTP.get_member(guild as u64, user as u64) {
+            Ok(member) => roles.extend(member.roles.iter().map(|r| r.0 as i64)),
+            // most likely not in the guild
+            Err(e) => info!("Could not get member {} of guild {}: {:?}", user, guild, e),
+        }
+    }
+    Ok(crate::admin::is_admin(conn, user, &roles)?)
+}
+
+impl <'a, 'r> FromRequest<'a, 'r> for Admin {
+    type Error = ();
+
+    fn from_request(request: &'a Request<'r>) -> Outcome<Self, Self::Error> {
+        let deets = Deets::from_request(request).map_failure(|(_, _)| (Status::Forbidden, ()))?;
+        let conn = rocket_diesel::DbConn::from_request(request)?;
+        let user = deets.id();
+        match is_admin(&*conn, user) {
+            Ok(true) => Outcome::Success(Admin{ user }),
+            Ok(false) => Outcome::Failure((Status::Forbidden, ())),
+            Err(e) => {
+                warn!("Failed to check for admin, {:?}", e);
+                Outcome::Failure((Status::InternalServerError, ()))
+            },
+        }
+    }
+}
+
 #[derive(Debug,Clone,Copy,PartialEq,Eq)]
 struct SecureHeaders;
 
@@ -249,65 +369,68 @@ impl fairing::Fairing for SecureHeaders {
 }
 
 fn motion_snippet(
-    motion: &MotionWithCount
+    motion: &MotionWithCount,
+    locale: Locale,
 ) -> Markup {
+    let yes = locale.number(motion.yes_vote_count as i64);
+    let no = locale.number(motion.no_vote_count as i64);
     html!{
         div.motion-titlebar {
             a href=(format!("/motions/{}", motion.damm_id())) {
-                h3.motion-title { "Motion #" (motion.damm_id())}
+                h3.motion-title { (tr!(locale, "web.motion.title", motion = motion.damm_id())) }
             }
             span.motion-time {
                 @if motion.announcement_message_id.is_some() {
                     @if motion.is_win {
-                        "PASSED"
+                        (tr!(locale, "web.motion.passed_at"))
                     } @else {
-                        "FAILED"
+                        (tr!(locale, "web.motion.failed_at"))
                     }
-                    " at "
+                    " "
                 } @else {
-                    " will "
+                    " "
                     @if motion.is_win {
-                        "pass"
+                        (tr!(locale, "web.motion.will_pass_at"))
                     } @else {
-                        "fail"
+                        (tr!(locale, "web.motion.will_fail_at"))
                     }
-                    " at"
-                    abbr title="assuming no other result changes" { "*" }
+                    abbr title=(tr!(locale, "web.motion.assuming")) { "*" }
                     " "
                 }
                 time datetime=(motion.end_at().to_rfc3339()) {
-                    (motion.end_at().to_rfc2822())
+                    (locale.datetime(motion.end_at()))
                 }
             }
         }
         p.motion-text {
             @if motion.is_super {
-                "Super motion "
+                (tr!(locale, "web.motion.kind_super"))
             } @else {
-                "Simple motion "
+                (tr!(locale, "web.motion.kind_simple"))
             }
+            " "
             (motion.motion_text)
         }
         div {
             @if motion.is_win {
                 span.winner {
-                    (motion.yes_vote_count)
-                    " for "
+                    (tr!(locale, "web.tally.for_first", votes = yes))
+                    " "
                 }
-                "vs"
+                (tr!(locale, "web.tally.vs"))
                 span.loser {
-                    " against "
-                    (motion.no_vote_count)
+                    " "
+                    (tr!(locale, "web.tally.against_last", votes = no))
                 }
             } @else {
                 span.winner {
-                    (motion.no_vote_count)
-                    " against "
+                    (tr!(locale, "web.tally.against_first", votes = no))
+                    " "
                 }
-                "vs"
+                (tr!(locale, "web.tally.vs"))
                 span.loser {
-                    " for "
-                    (motion.yes_vote_count)
+                    " "
+                    (tr!(locale, "web.tally.for_last", votes = yes))
                 }
             }
         }
@@ -317,7 +440,8 @@ fn motion_snippet(
 fn page(ctx: &mut CommonContext, title: impl AsRef<str>, content: Markup) -> Markup {
     use schema::item_types::dsl as itdsl;
     use crate::view_schema::balance_history::dsl as bhdsl;
-    bare_page(title, html!{
+    let locale = ctx.locale;
+    bare_page(locale, title, html!{
         @if let Some(deets) = ctx.deets.as_ref() {
             @let item_types:Vec<String> = itdsl::item_types.select(itdsl::name).get_results(&**ctx).unwrap();
             @let id:i64 = deets.discord_user.id();
@@ -326,7 +450,7 @@ fn page(ctx: &mut CommonContext, title: impl AsRef<str>, content: Markup) -> Mar
                     .select(bhdsl::balance)
                     .filter(bhdsl::user.eq(id))
                     .filter(bhdsl::ty.eq(name))
-                    .order(bhdsl::happened_at.desc())
+                    .order((bhdsl::happened_at.desc(), bhdsl::rowid.desc()))
                     .limit(1)
                     .get_result(&**ctx)
                     .optional()
@@ -334,25 +458,42 @@ fn page(ctx: &mut CommonContext, title: impl AsRef<str>, content: Markup) -> Mar
                     .unwrap_or(0) //unwrap Option (row might not exist)
                 )
             });
-            p { "Welcome, " (deets.discord_user.username) "#" (deets.discord_user.discriminator)}
+            p { (tr!(locale, "web.welcome", user = format!("{}#{}", deets.discord_user.username, deets.discord_user.discriminator))) }
             form action="/logout" method="post" {
                 input type="hidden" name="csrf" value=(ctx.csrf_token);
-                input type="submit" name="submit" value="Logout";
+                input type="submit" name="submit" value=(tr!(locale, "web.logout"));
             }
             ul {
                 @for (name, amount) in balances {
-                    li { (amount) (name) }
+                    li { (locale.number(amount)) (name) }
                 }
             }
-            a href="/" { "Home" }
+            a href="/" { (tr!(locale, "web.nav.home")) }
+            " | "
+            a href="/my-transactions" { (tr!(locale, "web.nav.my_transactions")) }
+            " | "
+            a href="/requests" { (tr!(locale, "web.nav.requests")) }
+            " | "
+            a href="/schedules" { (tr!(locale, "web.nav.schedules")) }
+            " | "
+            a href="/allowances" { (tr!(locale, "web.nav.allowances")) }
+            " | "
+            a href="/treasuries" { (tr!(locale, "web.nav.treasuries")) }
+            " | "
+            a href=(uri!(user_profile: user_id = id)) { (tr!(locale, "web.nav.my_profile")) }
+            " | "
+            a href="/leaderboards" { (tr!(locale, "web.nav.leaderboards")) }
             " | "
-            a href="/my-transactions" { "My Transactions" }
+            a href="/analytics" { (tr!(locale, "web.nav.analytics")) }
+            " | "
+            a href="/help" { (tr!(locale, "web.nav.help")) }
         } @else {
             form action="/login/discord" method="post" {
                 input type="hidden" name="csrf" value=(ctx.csrf_token);
                 p {
-                    "I don't know who you are. You should "
-                    input type="submit" name="submit" value="Login";
+                    (tr!(locale, "web.login_prompt"))
+                    " "
+                    input type="submit" name="submit" value=(tr!(locale, "web.login"));
                 }
             }
         }
@@ -360,10 +501,10 @@ fn page(ctx: &mut CommonContext, title: impl AsRef<str>, content: Markup) -> Mar
     })
 }
 
-fn bare_page(title: impl AsRef<str>, content: Markup) -> Markup {
+fn bare_page(locale: Locale, title: impl AsRef<str>, content: Markup) -> Markup {
     html! {
         (maud::DOCTYPE)
-        html {
+        html lang=(locale.code()) {
             head {
                 title { (title.as_ref()) }
                 meta charset="utf-8";
@@ -375,14 +516,14 @@ fn bare_page(title: impl AsRef<str>, content: Markup) -> Markup {
                 div.container {
                     (content)
                     small.build-info {
-                        "Plutocradroid "
-                        (env!("VERGEN_SEMVER_LIGHTWEIGHT"))
-                        " commit "
-                        (env!("VERGEN_SHA_SHORT"))
-                        " built for "
-                        (env!("VERGEN_TARGET_TRIPLE"))
-                        " at "
-                        (env!("VERGEN_BUILD_TIMESTAMP"))
+                        (tr!(
+                            locale,
+                            "version.built",
+                            version = env!("VERGEN_SEMVER_LIGHTWEIGHT"),
+                            commit = env!("VERGEN_SHA_SHORT"),
+                            target = env!("VERGEN_TARGET_TRIPLE"),
+                            at = env!("VERGEN_BUILD_TIMESTAMP"),
+                        ))
                     }
                 }
             }
@@ -413,7 +554,15 @@ fn motion_vote(
         info!("no deets");
         return Err(rocket::http::Status::Unauthorized);
     }
-    let vote_count = data.count;
+    let amount = match data.mode.as_ref().map(String::as_str) {
+        None | Some("votes") => VoteAmount::Exact(data.count),
+        Some("budget") => VoteAmount::Budget(data.count),
+        Some("max") => VoteAmount::Max,
+        Some(other) => {
+            info!("bad vote mode {:?}", other);
+            return Err(rocket::http::Status::BadRequest);
+        }
+    };
     let vote_direction:bool;
     if data.direction.as_str() == "for" {
         vote_direction = true;
@@ -423,22 +572,27 @@ fn motion_vote(
         info!("bad vote direction {:?}", data.direction);
         return Err(rocket::http::Status::BadRequest);
     }
-    let resp = crate::bot::vote_common(
+    let locale = ctx.locale;
+    let resp = match crate::economy::vote(
         &ctx.conn,
         Some(vote_direction),
-        vote_count,
+        amount,
         deets.discord_user.id(),
         Some(id),
         None,
-        None
-    );
+        None,
+        None,
+    ).unwrap() {
+        Ok(outcome) => outcome.localize(locale),
+        Err(e) => e.localize(locale),
+    };
 
-    Ok(page(&mut ctx, "Vote Complete", html!{
+    Ok(page(&mut ctx, tr!(locale, "web.vote.title"), html!{
         (resp)
         br;
-        a href={"/motions/" (damm_id)} { "Back to Motion" }
+        a href={"/motions/" (damm_id)} { (tr!(locale, "web.vote.back_to_motion")) }
         br;
-        a href="/" { "Back Home" }
+        a href="/" { (tr!(locale, "web.vote.back_home")) }
     }))
 }
 
@@ -480,6 +634,54 @@ fn motion_listing(mut ctx: CommonContext, damm_id: String) -> impl Responder<'st
         .map(|v| if v.direction { (v.amount, 0) } else { (0, v.amount) })
         .fold((0,0), |acc, x| (acc.0 + x.0, acc.1 + x.1));
     let motion = MotionWithCount::from_motion(motion, yes_vote_count as u64, no_vote_count as u64);
+
+    use schema::motion_result_changes::dsl as mrcdsl;
+    let timeline = stats::motion_timeline(&*ctx, motion.rowid, motion.is_super).unwrap();
+    let result_changes:Vec<MotionResultChange> = mrcdsl::motion_result_changes
+        .select((mrcdsl::changed_at, mrcdsl::is_win, mrcdsl::yes_votes, mrcdsl::no_votes))
+        .filter(mrcdsl::motion.eq(motion.rowid))
+        .order((mrcdsl::changed_at.asc(), mrcdsl::rowid.asc()))
+        .get_results(&*ctx)
+        .unwrap();
+    let pledges = crate::pledges::on_motion(&*ctx, motion.rowid).unwrap();
+    let pools = crate::vote_pools::on_motion(&*ctx, motion.rowid).unwrap()
+        .into_iter()
+        .map(|pool| {
+            let contributions = crate::vote_pools::contributions(&*ctx, pool.rowid).unwrap();
+            (pool, contributions)
+        })
+        .collect::<Vec<_>>();
+    let (spent_for, spent_against) = timeline
+        .iter()
+        .map(|e| if e.event.direction { (e.event.cost, 0) } else { (0, e.event.cost) })
+        .fold((0,0), |acc, x| (acc.0 + x.0, acc.1 + x.1));
+    let mut for_points = Vec::new();
+    let mut against_points = Vec::new();
+    let mut prev = (0, 0);
+    for entry in &timeline {
+        let x = entry.event.happened_at.timestamp() as f64;
+        for_points.push((x, prev.0 as f64));
+        for_points.push((x, entry.yes_votes as f64));
+        against_points.push((x, prev.1 as f64));
+        against_points.push((x, entry.no_votes as f64));
+        prev = (entry.yes_votes, entry.no_votes);
+    }
+    let flips:Vec<f64> = timeline
+        .iter()
+        .filter(|e| e.flipped)
+        .map(|e| e.event.happened_at.timestamp() as f64)
+        .collect();
+    let locale = ctx.locale;
+    let timeline_chart = charts::line_chart_with_markers(
+        &[
+            charts::Series{ class: "chart-line", points: for_points },
+            charts::Series{ class: "chart-line-against", points: against_points },
+        ],
+        &flips,
+        |v| locale.datetime(Utc.timestamp(v as i64, 0)),
+        |v| locale.number(v as i64),
+    );
+
     let voting_html = if let Some(deets) = ctx.deets.as_ref(){
         if motion.end_at() > Utc::now() {
             let mut agents_vote:Option<MotionVote> = None;
@@ -489,50 +691,214 @@ fn motion_listing(mut ctx: CommonContext, damm_id: String) -> impl Responder<'st
                 }
             }
             let avd = agents_vote.map(|v| v.direction);
+            let flip_plan = crate::economy::flip_plan(&*ctx, motion.rowid, deets.id()).unwrap();
             html!{
+                p.flip-plan {
+                    @match flip_plan {
+                        Ok(plan) => (plan.localize(locale)),
+                        Err(e) => (e.localize(locale)),
+                    }
+                }
                 form action={"/motions/" (damm_id) "/vote"} method="post" {
                     input type="hidden" name="csrf" value=(ctx.csrf_token);
-                    "Cast "
+                    (tr!(locale, "web.vote.cast"))
+                    " "
                     input type="number" name="count" value="0";
-                    " vote(s) "
+                    br;
+                    label {
+                        input type="radio" name="mode" value="votes" checked;
+                        " "
+                        (tr!(locale, "web.vote.mode_votes"))
+                    }
+                    br;
+                    label {
+                        input type="radio" name="mode" value="budget";
+                        " "
+                        (tr!(locale, "web.vote.mode_budget"))
+                    }
+                    br;
+                    label {
+                        input type="radio" name="mode" value="max";
+                        " "
+                        (tr!(locale, "web.vote.mode_max"))
+                    }
                     br;
                     label {
                     input type="radio" name="direction" value="for" disabled?[avd == Some(false)] checked?[avd == Some(true)];
-                    " for"
+                    " "
+                    (tr!(locale, "web.vote.for"))
                     }
                     br;
                     label {
                         input type="radio" name="direction" value="against" disabled?[avd == Some(true)] checked?[avd == Some(false)];
-                        " against"
+                        " "
+                        (tr!(locale, "web.vote.against"))
                     }
                     br;
-                    input type="submit" name="submit" value="Go";
+                    input type="submit" name="submit" value=(tr!(locale, "web.go"));
                 }
             }
         } else {
-            html!{ "This motion has expired." }
+            html!{ (tr!(locale, "web.vote.expired")) }
         }
     } else {
-        html!{ "You must be logged in to vote." }
+        html!{ (tr!(locale, "web.vote.log_in")) }
     };
 
-    Some(page(&mut ctx, format!("Motion #{}", motion.damm_id()), html!{
+    let tally = |yes_votes:i64, no_votes:i64| tr!(
+        locale,
+        "web.motion.tally",
+        yes = locale.number(yes_votes),
+        no = locale.number(no_votes),
+    );
+    let votes_in = |votes:i64, direction:bool| tr!(
+        locale,
+        if direction { "web.motion.votes_for" } else { "web.motion.votes_against" },
+        votes = locale.number(votes),
+    );
+
+    Some(page(&mut ctx, tr!(locale, "web.motion.title", motion = motion.damm_id()), html!{
         div.motion {
-            a href="/" { "Home" }
-            (motion_snippet(&motion))
+            a href="/" { (tr!(locale, "web.nav.home")) }
+            (motion_snippet(&motion, locale))
             hr;
             (voting_html)
             hr;
+            h4 { (tr!(locale, "web.motion.timeline")) }
+            (timeline_chart)
+            p {
+                (tr!(locale, "web.motion.spent_for", capital = locale.number(spent_for)))
+                br;
+                (tr!(locale, "web.motion.spent_against", capital = locale.number(spent_against)))
+            }
+            table border="1" {
+                thead {
+                    tr {
+                        th { (tr!(locale, "web.motion.timestamp")) }
+                        th { (tr!(locale, "web.motion.voter")) }
+                        th { (tr!(locale, "web.motion.votes")) }
+                        th { (tr!(locale, "web.motion.cost")) }
+                        th { (tr!(locale, "web.motion.tally_heading")) }
+                    }
+                }
+                tbody {
+                    @for entry in &timeline {
+                        tr.flipped[entry.flipped] {
+                            td {
+                                time datetime=(entry.event.happened_at.to_rfc3339()) {
+                                    (locale.datetime(entry.event.happened_at))
+                                }
+                            }
+                            td {
+                                (account_link(locale, entry.event.user))
+                            }
+                            td {
+                                (votes_in(entry.event.votes, entry.event.direction))
+                            }
+                            td.amount { (locale.number(entry.event.cost)) }
+                            td {
+                                (tally(entry.yes_votes, entry.no_votes))
+                                @if entry.flipped {
+                                    @if entry.is_win { (tr!(locale, "web.motion.now_passing")) } @else { (tr!(locale, "web.motion.now_failing")) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            h4 { (tr!(locale, "web.motion.result_changes")) }
+            ul {
+                @for change in &result_changes {
+                    li {
+                        time datetime=(change.changed_at.to_rfc3339()) {
+                            (locale.datetime(change.changed_at))
+                        }
+                        " "
+                        @if change.is_win { (tr!(locale, "web.motion.passing")) } @else { (tr!(locale, "web.motion.failing")) }
+                        " (" (tally(change.yes_votes, change.no_votes)) ")"
+                    }
+                }
+            }
+            @if !pledges.is_empty() {
+                h4 { (tr!(locale, "web.motion.pledges")) }
+                table border="1" {
+                    thead {
+                        tr {
+                            th { (tr!(locale, "web.motion.pledged_by")) }
+                            th { (tr!(locale, "web.motion.pledge_to")) }
+                            th { (tr!(locale, "web.transactions.amount")) }
+                            th { (tr!(locale, "web.motion.pledge_condition")) }
+                            th { (tr!(locale, "web.requests.status")) }
+                        }
+                    }
+                    tbody {
+                        @for pledge in &pledges {
+                            tr {
+                                td { a href=(uri!(user_profile: user_id = pledge.pledged_by)) { "user#\u{200B}" (pledge.pledged_by) } }
+                                td { a href=(uri!(user_profile: user_id = pledge.recipient)) { "user#\u{200B}" (pledge.recipient) } }
+                                td.amount {
+                                    span.amount-inner { (locale.number(pledge.quantity)) }
+                                    span.ty { (pledge.ty) }
+                                }
+                                td { (if pledge.on_pass { tr!(locale, "web.motion.if_passes") } else { tr!(locale, "web.motion.if_fails") }) }
+                                td { (pledge.status().localize(locale)) }
+                            }
+                        }
+                    }
+                }
+            }
+            @if !pools.is_empty() {
+                h4 { (tr!(locale, "web.motion.pools")) }
+                table border="1" {
+                    thead {
+                        tr {
+                            th { "#" }
+                            th { (tr!(locale, "web.motion.pool_direction")) }
+                            th { (tr!(locale, "web.motion.pool_contributions")) }
+                            th { (tr!(locale, "web.motion.pool_raised")) }
+                            th { (tr!(locale, "web.requests.status")) }
+                        }
+                    }
+                    tbody {
+                        @for (pool, contributions) in &pools {
+                            tr {
+                                td { (pool.rowid) }
+                                td { (if pool.direction { tr!(locale, "web.motion.pool_for") } else { tr!(locale, "web.motion.pool_against") }) }
+                                td {
+                                    @for contribution in contributions {
+                                        (account_link(locale, contribution.user))
+                                        " "
+                                        span.amount { (locale.number(contribution.quantity)) }
+                                        @if let Some(refunded) = contribution.refunded.filter(|r| *r > 0) {
+                                            " " (tr!(locale, "web.motion.pool_refunded", amount = locale.number(refunded)))
+                                        }
+                                        br;
+                                    }
+                                }
+                                td.amount { (locale.number(contributions.iter().map(|c| c.quantity).sum::<i64>())) }
+                                td {
+                                    @match (pool.votes, pool.cost) {
+                                        (Some(votes), Some(cost)) => (tr!(locale, "web.motion.pool_bought", votes = locale.number(votes), cost = locale.number(cost))),
+                                        _ => (pool.status(Utc::now()).localize(locale)),
+                                    }
+                                    @if pool.settled_at.is_none() {
+                                        " "
+                                        (tr!(locale, "web.motion.pool_closes", closes = locale.datetime(pool.closes_at)))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            hr;
             @for vote in &votes {
                 div.motion-vote {
-                    h5 { (vote.user) }
+                    h5 {
+                        (account_link(locale, vote.user))
+                    }
                     span {
-                        (vote.amount)
-                        @if vote.direction {
-                            " for"
-                        } @else {
-                            " against"
-                        }
+                        (votes_in(vote.amount, vote.direction))
                     }
                 }
             }
@@ -593,18 +959,19 @@ fn index(mut ctx: CommonContext, filter: MotionListFilter) -> impl Responder<'st
             all_motions.filter(|m| m.announcement_message_id.is_none() ||  m.is_win).collect(),
     }:Vec<_>;
 
-    page(&mut ctx, "All Motions", html!{
+    let locale = ctx.locale;
+    page(&mut ctx, tr!(locale, "web.index.title"), html!{
         form#filters method="get" {
             div {
-                "Filters:"
+                (tr!(locale, "web.index.filters"))
                 ul {
                     @let options = [
-                        ("all", "All", MotionListFilter::All),
-                        ("passed", "Passed", MotionListFilter::Passed),
-                        ("failed", "Failed", MotionListFilter::Failed),
-                        ("finished", "Finished (Passed or Failed)", MotionListFilter::Finished),
-                        ("pending", "Pending", MotionListFilter::Pending),
-                        ("pending_passed", "Pending or Passed", MotionListFilter::PendingPassed),
+                        ("all", tr!(locale, "web.index.filter_all"), MotionListFilter::All),
+                        ("passed", tr!(locale, "web.index.filter_passed"), MotionListFilter::Passed),
+                        ("failed", tr!(locale, "web.index.filter_failed"), MotionListFilter::Failed),
+                        ("finished", tr!(locale, "web.index.filter_finished"), MotionListFilter::Finished),
+                        ("pending", tr!(locale, "web.index.filter_pending"), MotionListFilter::Pending),
+                        ("pending_passed", tr!(locale, "web.index.filter_pending_passed"), MotionListFilter::PendingPassed),
                     ];
                     @for (codename, textname, val) in &options {
                         li {
@@ -615,16 +982,16 @@ fn index(mut ctx: CommonContext, filter: MotionListFilter) -> impl Responder<'st
                         }
                     }
                 }
-                input type="submit" name="submit" value="Go";
+                input type="submit" name="submit" value=(tr!(locale, "web.go"));
             }
         }
         @for motion in &motions {
             div.motion {
-                (motion_snippet(&motion))
+                (motion_snippet(&motion, locale))
             }
         }
         @if motions.is_empty() {
-            p.no-motions { "Nobody here but us chickens!" }
+            p.no-motions { (tr!(locale, "web.empty")) }
         }
     })
 }
@@ -688,6 +1055,7 @@ fn my_transactions(
         pub to_votes:Option<i64>,
         pub message_id:Option<i64>,
         pub transfer_ty:String,
+        pub acting_user:Option<i64>,
     }
     #[derive(Debug,Clone)]
     enum TransactionView {
@@ -717,6 +1085,7 @@ fn my_transactions(
                 bh::to_votes,
                 bh::message_id,
                 bh::transfer_ty,
+                bh::acting_user,
             ))
             .filter(bh::user.eq(deets.id()))
             .filter(coalesce_2(bh::ty.nullable().eq(fun_ty.as_option()).nullable(), true))
@@ -743,6 +1112,7 @@ fn my_transactions(
                     bh::to_votes,
                     bh::message_id,
                     bh::transfer_ty,
+                    bh::acting_user,
                 ))
                 .filter(bh::user.eq(deets.id()))
                 .filter(coalesce_2(bh::ty.nullable().eq(fun_ty.as_option()).nullable(), true))
@@ -782,11 +1152,12 @@ fn my_transactions(
         txn_views.reverse();
         (txn_views, hit_limit)
     });
-    Ok(page(&mut ctx, "My Transactions", html!{
+    let locale = ctx.locale;
+    Ok(page(&mut ctx, tr!(locale, "web.transactions.title"), html!{
         @if let Some((txns, hit_limit)) = txns {
-            h3 { "My Transactions" }
+            h3 { (tr!(locale, "web.transactions.title")) }
             form {
-                "Show transactions in"
+                (tr!(locale, "web.transactions.show_in"))
                 ul {
                     @for ft in &fun_tys {
                         li {
@@ -799,19 +1170,19 @@ fn my_transactions(
                     li {
                         label {
                             input type="radio" name="fun_ty" value="all" checked?[fun_ty == FungibleSelection::All];
-                            "All currencies"
+                            (tr!(locale, "web.transactions.all_currencies"))
                         }
                     }
                 }
-                button { "Go" }
+                button { (tr!(locale, "web.go")) }
             }
             table border="1" {
                 thead {
                     tr {
-                        th { "Timestamp" }
-                        th { "Description" }
-                        th { "Amount" }
-                        th { "Running Total" }
+                        th { (tr!(locale, "web.transactions.timestamp")) }
+                        th { (tr!(locale, "web.transactions.description")) }
+                        th { (tr!(locale, "web.transactions.amount")) }
+                        th { (tr!(locale, "web.transactions.running_total")) }
                     }
                 }
                 tbody {
@@ -820,39 +1191,55 @@ fn my_transactions(
                             tr.transaction {
                                 td {
                                     time datetime=(txn.happened_at.to_rfc3339()) {
-                                        (txn.happened_at.to_rfc3339_opts(SecondsFormat::Secs, true))
+                                        (locale.datetime(txn.happened_at))
                                     }
                                 }
                                 td {
                                     @if ["give", "admin_give"].contains(&txn.transfer_ty.as_str()) {
-                                        @if txn.transfer_ty.as_str() == "admin_give" {
-                                            "admin "
+                                        @let user = account_label(locale, txn.other_party.unwrap());
+                                        @match (txn.transfer_ty.as_str() == "admin_give", txn.sign < 0) {
+                                            (false, true) => (tr!(locale, "web.transactions.transfer_to", user = user)),
+                                            (false, false) => (tr!(locale, "web.transactions.transfer_from", user = user)),
+                                            (true, true) => (tr!(locale, "web.transactions.admin_transfer_to", user = user)),
+                                            (true, false) => (tr!(locale, "web.transactions.admin_transfer_from", user = user)),
                                         }
-                                        @if txn.sign < 0 {
-                                            "transfer to "
-                                        } @else {
-                                            "transfer from "
-                                        }
-                                        "user#\u{200B}"
-                                        (txn.other_party.unwrap())
                                     } @else if txn.transfer_ty.as_str() == "motion_create" {
                                         @let damm_id = crate::damm::add_to_str(txn.to_motion.unwrap().to_string());
-                                        "1 vote, created "
+                                        (tr!(locale, "web.transactions.created"))
+                                        " "
                                         a href=(uri!(motion_listing:damm_id = &damm_id)) {
-                                            "motion #"
-                                            (&damm_id)
+                                            (tr!(locale, "web.motion.title", motion = &damm_id))
                                         }
                                     } @else if let (Some(motion_id), Some(votes)) = (&txn.to_motion, &txn.to_votes) {
                                         // transfer_ty == "motion_vote"
                                         @let damm_id = crate::damm::add_to_str(motion_id.to_string());
-                                        (votes)
-                                        " vote(s) on "
+                                        (tr!(locale, "web.transactions.voted", votes = locale.number(*votes)))
+                                        " "
                                         a href=(uri!(motion_listing:damm_id = &damm_id)) {
-                                            "motion #"
-                                            (&damm_id)
+                                            (tr!(locale, "web.motion.title", motion = &damm_id))
                                         }
+                                    } @else if txn.transfer_ty.as_str() == "motion_vote_refund" {
+                                        (tr!(locale, "web.transactions.refund"))
                                     } @else if ["admin_fabricate","command_fabricate"].contains(&txn.transfer_ty.as_str()) {
-                                        "fabrication"
+                                        (tr!(locale, "web.transactions.fabrication"))
+                                    } @else if txn.transfer_ty.as_str() == "admin_burn" {
+                                        (tr!(locale, "web.transactions.burn"))
+                                    } @else if txn.transfer_ty.as_str() == "burn" {
+                                        (tr!(locale, "web.transactions.self_burn"))
+                                    } @else if txn.transfer_ty.as_str() == "pledge_escrow" {
+                                        (tr!(locale, "web.transactions.pledge_escrow"))
+                                    } @else if txn.transfer_ty.as_str() == "pledge_release" {
+                                        (tr!(locale, "web.transactions.pledge_release"))
+                                    } @else if txn.transfer_ty.as_str() == "pledge_refund" {
+                                        (tr!(locale, "web.transactions.pledge_refund"))
+                                    } @else if txn.transfer_ty.as_str() == "pool_contribute" {
+                                        (tr!(locale, "web.transactions.pool_contribute", pool = account_label(locale, txn.other_party.unwrap())))
+                                    } @else if txn.transfer_ty.as_str() == "pool_refund" {
+                                        (tr!(locale, "web.transactions.pool_refund", pool = account_label(locale, txn.other_party.unwrap())))
+                                    }
+                                    @if let Some(acting_user) = txn.acting_user {
+                                        " "
+                                        (tr!(locale, "web.transactions.acting", user = account_label(locale, acting_user)))
                                     }
                                     " "
                                     @if let Some(comment) = &txn.comment {
@@ -861,12 +1248,12 @@ fn my_transactions(
                                 }
                                 td.amount.negative[txn.sign < 0] {
                                     span.paren { "(" }
-                                    span.amount-inner { (txn.quantity) }
+                                    span.amount-inner { (locale.number(txn.quantity)) }
                                     span.ty { (txn.ty) }
                                     span.paren { ")" }
                                 }
                                 td.running-total {
-                                    span.amount-inner { (txn.balance) }
+                                    span.amount-inner { (locale.number(txn.balance)) }
                                     span.ty { (txn.ty) }
                                 }
                             }
@@ -874,15 +1261,15 @@ fn my_transactions(
                             @let (amt, bal) = match txn_view { TransactionView::Generated{amt, bal} => (amt, bal), _ => unreachable!() };
                             tr.transaction.generated {
                                 td {}
-                                td { "generator outputs" }
+                                td { (tr!(locale, "web.transactions.generated")) }
                                 td.amount {
                                     span.paren { "(" }
-                                    span.amount-inner { (amt) }
+                                    span.amount-inner { (locale.number(*amt)) }
                                     span.ty { "pc" }
                                     span.paren { ")" }
                                 }
                                 td.running-total {
-                                    span.amount-inner { (bal) }
+                                    span.amount-inner { (locale.number(*bal)) }
                                     span.ty { "pc" }
                                 }
                             }
@@ -891,7 +1278,7 @@ fn my_transactions(
                     @if txns.is_empty() {
                         tr {
                             td colspan="4" {
-                                "Nothing to show."
+                                (tr!(locale, "web.transactions.none"))
                             }
                         }
                     }
@@ -899,75 +1286,1549 @@ fn my_transactions(
             }
             @if hit_limit {
                 @let txn = match txns.iter().rev().find(|t| match t{TransactionView::Trans(_) => true, _=>false}) { Some(TransactionView::Trans(t)) => t, d => {dbg!(d);unreachable!()} };
-                a href=(uri!(my_transactions: before_ms = txn.happened_at.timestamp_millis(), fun_ty = fun_ty.as_str())) { "Next" }
+                a href=(uri!(my_transactions: before_ms = txn.happened_at.timestamp_millis(), fun_ty = fun_ty.as_str())) { (tr!(locale, "web.transactions.next")) }
             }
         } @else {
-            p { "You must be logged in to view your transactions." }
+            p { (tr!(locale, "web.transactions.log_in")) }
         }
     }))
 }
 
-#[get("/oauth-finish")]
-fn oauth_finish(token: TokenResponse<DiscordOauth>, mut cookies: Cookies<'_>) -> Redirect {
-    cookies.add_private(
-        Cookie::build("token", token.access_token().to_string())
-            .same_site(SameSite::Lax)
-            .secure(true)
-            .http_only(true)
-            .finish()
-    );
-    if let Some(refresh) = token.refresh_token().map(|s| s.to_owned()) {
-        cookies.add_private(
-            Cookie::build("refresh", refresh)
-                .same_site(SameSite::Lax)
-                .secure(true)
-                .http_only(true)
-                .finish()
-        )
-    }
-    Redirect::to("/get-deets")
+#[get("/users/<user_id>")]
+fn user_profile(mut ctx: CommonContext, user_id: i64) -> Result<Markup, Status> {
+    let profile = stats::profile(&*ctx, user_id).map_err(|_| Status::InternalServerError)?;
+    let is_self = ctx.deets.as_ref().map(|d| d.id()) == Some(user_id);
+    let show_holdings = !profile.holdings_hidden || is_self;
+    let locale = ctx.locale;
+    let chosen_locale = if is_self {
+        settings::user_locale(&*ctx, user_id).map_err(|_| Status::InternalServerError)?
+    } else { None };
+    let content = html!{
+        h3 { "user#\u{200B}" (user_id) }
+        h4 { (tr!(locale, "web.profile.holdings")) }
+        @if show_holdings {
+            ul {
+                @for (item_type, amount) in &profile.balances {
+                    li { (locale.number(*amount)) " " (item_type.long_name_plural) }
+                }
+            }
+        } @else {
+            p { (tr!(locale, "web.profile.hidden")) }
+        }
+        h4 { (tr!(locale, "web.profile.politics")) }
+        ul {
+            li { (tr!(locale, "web.profile.motions_created", count = locale.number(profile.motions_created))) }
+            li { (tr!(locale, "web.profile.motions_passed", count = locale.number(profile.motions_passed))) }
+            li { (tr!(locale, "web.profile.votes_cast", count = locale.number(profile.votes_cast))) }
+            li { (tr!(locale, "web.profile.capital_spent", count = locale.number(profile.capital_spent))) }
+        }
+        @if ctx.deets.is_some() && !is_self {
+            h4 { (tr!(locale, "web.give.title")) }
+            form action=(uri!(give: user_id = user_id)) method="post" {
+                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                input type="text" name="amount" required;
+                " "
+                select name="ty" {
+                    @for (item_type, _) in profile.balances.iter().filter(|(t, _)| !t.retired) {
+                        option value=(item_type.name) { (item_type.long_name_plural) }
+                    }
+                }
+                br;
+                label {
+                    (tr!(locale, "web.give.memo"))
+                    " "
+                    input type="text" name="memo" maxlength=(crate::parser::MEMO_MAX_CHARS);
+                }
+                br;
+                input type="submit" name="submit" value=(tr!(locale, "web.give.submit"));
+            }
+        }
+        @if is_self {
+            h4 { (tr!(locale, "web.burn.title")) }
+            form action="/burn" method="post" {
+                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                input type="text" name="amount" required;
+                " "
+                select name="ty" {
+                    @for (item_type, _) in profile.balances.iter().filter(|(t, _)| !t.retired) {
+                        option value=(item_type.name) { (item_type.long_name_plural) }
+                    }
+                }
+                br;
+                label {
+                    (tr!(locale, "web.burn.reason"))
+                    " "
+                    input type="text" name="reason";
+                }
+                br;
+                input type="submit" name="submit" value=(tr!(locale, "web.burn.submit"));
+            }
+            h4 { (tr!(locale, "web.profile.settings")) }
+            form action="/settings/privacy" method="post" {
+                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                label {
+                    input type="checkbox" name="hide_holdings" checked?[profile.holdings_hidden];
+                    " "
+                    (tr!(locale, "web.profile.hide_holdings"))
+                }
+                br;
+                input type="submit" name="submit" value=(tr!(locale, "web.save"));
+            }
+            form action="/settings/language" method="post" {
+                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                label {
+                    (tr!(locale, "web.profile.language"))
+                    " "
+                    select name="locale" {
+                        option value="" selected?[chosen_locale.is_none()] { (tr!(locale, "web.profile.language_browser")) }
+                        @for choice in &Locale::ALL {
+                            option value=(choice.code()) selected?[chosen_locale == Some(*choice)] { (choice.name()) }
+                        }
+                    }
+                }
+                " "
+                input type="submit" name="submit" value=(tr!(locale, "web.save"));
+            }
+        }
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.profile.title", user = user_id), content))
 }
 
-#[get("/get-deets")]
-fn get_deets(
-    mut cookies: Cookies<'_>
-) -> Result<Redirect, Box<dyn std::error::Error>> {
-    let token;
-    if let Some(val) = cookies.get_private("token") {
-        token = val.value().to_string()
-    } else {
-        return Ok(Redirect::to("/"));
-    }
-    let client = reqwest::blocking::Client::new();
-    let res = client.get("https://discord.com/api/v8/users/@me")
-        .bearer_auth(token)
-        .send()?;
-    if res.status() != 200 {
-        return Err(Box::new(MiscError::from("Bad status")));
+#[post("/users/<user_id>/give", data = "<data>")]
+fn give(
+    mut ctx: CommonContext,
+    data: LenientForm<GiveForm>,
+    user_id: i64,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
     }
-    let user:DiscordUser = res.json()?;
-    let deets = Deets{discord_user: user};
-    info!("User logged in: {:?}", deets);
-    cookies.add_private(
-        Cookie::build("deets", serde_json::to_string(&deets).unwrap())
-            .same_site(SameSite::Lax)
-            .secure(true)
-            .http_only(true)
-            .finish()
-    );
-    Ok(Redirect::to("/"))
+    let from_user = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let ty = crate::economy::item_type_by_alias(&*ctx, &data.ty)
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::BadRequest)?;
+    let parsed = crate::parser::parse_amount(data.amount.trim())
+        .and_then(|amount| Ok((amount, crate::parser::parse_memo(&data.memo)?)));
+    let message = match parsed {
+        Err(e) => e.localize(locale),
+        Ok((amount, memo)) => {
+            let balance = crate::economy::balance_of(&*ctx, from_user, &ty).map_err(|_| Status::InternalServerError)?;
+            let amount = amount.resolve(balance).max(0) as u64;
+            let user = format!("user#\u{200B}{}", user_id);
+            match crate::economy::give(&*ctx, from_user, user_id, &ty, amount, None, memo.as_deref()).map_err(|_| Status::InternalServerError)? {
+                Err(e) => e.localize(locale),
+                Ok(()) => match memo {
+                    None => tr!(locale, "give.done", amount = locale.number(amount as i64), items = ty.long_name_ambiguous, user = user),
+                    Some(memo) => tr!(locale, "give.done_memo", amount = locale.number(amount as i64), items = ty.long_name_ambiguous, user = user, memo = memo),
+                },
+            }
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.give.title"), html!{
+        (message)
+        br;
+        a href=(uri!(user_profile: user_id = user_id)) { (tr!(locale, "web.give.back")) }
+    }))
 }
 
-#[post("/login/discord", data = "<data>")]
-fn login(
-    oauth2: OAuth2<DiscordOauth>,
-    mut cookies: Cookies<'_>,
-    data: LenientForm<CSRFForm>,
-) -> Result<Redirect, rocket::http::Status> {
-    if cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
-        return Err(rocket::http::Status::BadRequest);
+#[post("/burn", data = "<data>")]
+fn burn(
+    mut ctx: CommonContext,
+    data: LenientForm<BurnForm>,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
     }
-    Ok(oauth2.get_redirect(&mut cookies, &["identify"]).unwrap())
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let ty = crate::economy::item_type_by_alias(&*ctx, &data.ty)
+        .map_err(|_| Status::InternalServerError)?
+        .ok_or(Status::BadRequest)?;
+    let reason = Some(data.reason.trim()).filter(|r| !r.is_empty());
+    let message = match crate::parser::parse_amount(data.amount.trim()) {
+        Err(e) => e.localize(locale),
+        Ok(amount) => {
+            let balance = crate::economy::balance_of(&*ctx, user_id, &ty).map_err(|_| Status::InternalServerError)?;
+            let amount = amount.resolve(balance);
+            match crate::economy::burn(&*ctx, user_id, &ty, amount, reason, None).map_err(|_| Status::InternalServerError)? {
+                Ok(balance) => tr!(
+                    locale,
+                    "burn.done",
+                    amount = locale.number(amount),
+                    items = ty.long_name_ambiguous,
+                    balance = locale.number(balance),
+                ),
+                Err(e) => e.localize(locale),
+            }
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.burn.title"), html!{
+        (message)
+        br;
+        a href=(uri!(user_profile: user_id = user_id)) { (tr!(locale, "web.nav.my_profile")) }
+    }))
+}
+
+#[get("/requests")]
+fn payment_requests(mut ctx: CommonContext) -> Result<Markup, Status> {
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let requests = crate::payment_requests::involving(&*ctx, user_id, 200).map_err(|_| Status::InternalServerError)?;
+    let now = Utc::now();
+    let (pending, settled):(Vec<_>, Vec<_>) = requests.iter()
+        .partition(|r| r.status(now) == crate::payment_requests::RequestStatus::Pending);
+    let locale = ctx.locale;
+    let csrf_token = ctx.csrf_token.clone();
+    let request_table = |requests:&[&crate::payment_requests::PaymentRequest], actions:bool| html!{
+        table border="1" {
+            thead {
+                tr {
+                    th { "#" }
+                    th { (tr!(locale, "web.requests.from")) }
+                    th { (tr!(locale, "web.requests.to")) }
+                    th { (tr!(locale, "web.transactions.amount")) }
+                    th { (tr!(locale, "web.requests.memo")) }
+                    th { (tr!(locale, "web.requests.requested_at")) }
+                    th { (if actions { tr!(locale, "web.requests.expires_at") } else { tr!(locale, "web.requests.status") }) }
+                    @if actions {
+                        th {}
+                    }
+                }
+            }
+            tbody {
+                @for request in requests {
+                    tr {
+                        td { (request.rowid) }
+                        td { a href=(uri!(user_profile: user_id = request.payer)) { "user#\u{200B}" (request.payer) } }
+                        td { a href=(uri!(user_profile: user_id = request.requested_by)) { "user#\u{200B}" (request.requested_by) } }
+                        td.amount {
+                            span.amount-inner { (locale.number(request.quantity)) }
+                            span.ty { (request.ty) }
+                        }
+                        td { @if let Some(memo) = &request.memo { "“" (memo) "”" } }
+                        td {
+                            time datetime=(request.requested_at.to_rfc3339()) { (locale.datetime(request.requested_at)) }
+                        }
+                        @if actions {
+                            td {
+                                time datetime=(request.expires_at.to_rfc3339()) { (locale.datetime(request.expires_at)) }
+                            }
+                            td {
+                                @if request.payer == user_id {
+                                    form action=(uri!(pay_request: request_id = request.rowid)) method="post" {
+                                        input type="hidden" name="csrf" value=(csrf_token);
+                                        input type="submit" name="submit" value=(tr!(locale, "web.requests.pay"));
+                                    }
+                                }
+                                form action=(uri!(decline_request: request_id = request.rowid)) method="post" {
+                                    input type="hidden" name="csrf" value=(csrf_token);
+                                    @if request.payer == user_id {
+                                        input type="submit" name="submit" value=(tr!(locale, "web.requests.decline"));
+                                    } @else {
+                                        input type="submit" name="submit" value=(tr!(locale, "web.requests.cancel"));
+                                    }
+                                }
+                            }
+                        } @else {
+                            td { (request.status(now).localize(locale)) }
+                        }
+                    }
+                }
+                @if requests.is_empty() {
+                    tr {
+                        td colspan=(if actions { "8" } else { "7" }) { (tr!(locale, "web.transactions.none")) }
+                    }
+                }
+            }
+        }
+    };
+    let content = html!{
+        p { (tr!(locale, "web.requests.how_to", command = format!("{}request <user> <amount> <type> [memo]", crate::bot::PREFIX))) }
+        h3 { (tr!(locale, "web.requests.pending")) }
+        (request_table(&pending, true))
+        h3 { (tr!(locale, "web.requests.settled")) }
+        (request_table(&settled, false))
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.requests.title"), content))
+}
+
+#[post("/requests/<request_id>/pay", data = "<data>")]
+fn pay_request(
+    ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    request_id: i64,
+) -> Result<Markup, Status> {
+    settle_request(ctx, data, request_id, true)
+}
+
+#[post("/requests/<request_id>/decline", data = "<data>")]
+fn decline_request(
+    ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    request_id: i64,
+) -> Result<Markup, Status> {
+    settle_request(ctx, data, request_id, false)
+}
+
+/// Pays or declines a request, DMing the other person about it as the bot would.
+fn settle_request(
+    mut ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    request_id: i64,
+    paying: bool,
+) -> Result<Markup, Status> {
+    use crate::economy::ChatFrontend;
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let res = if paying {
+        crate::payment_requests::pay(&*ctx, request_id, user_id, None)
+    } else {
+        crate::payment_requests::decline(&*ctx, request_id, user_id)
+    }.map_err(|_| Status::InternalServerError)?;
+    let message = match res {
+        Err(e) => e.localize(locale),
+        Ok(request) => {
+            let items = crate::payment_requests::item_type_of(&*ctx, &request).map_err(|_| Status::InternalServerError)?.long_name_ambiguous;
+            let other = if request.payer == user_id { request.requested_by } else { request.payer };
+            let other_locale = settings::user_locale(&*ctx, other).map_err(|_| Status::InternalServerError)?.unwrap_or_default();
+            let notice = request.notice(other_locale, &format!("<@{}>", user_id), &items);
+            let frontend = crate::bot::DiscordFrontend(&*crate::interactions::DISCORD_HTTP, other_locale);
+            if let Err(e) = frontend.dm_user(other, &notice) {
+                info!("Could not DM {} about request {}: {:?}", other, request.rowid, e);
+            }
+            request.outcome(locale, &format!("user#\u{200B}{}", other), &items)
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.requests.title"), html!{
+        (message)
+        br;
+        a href=(uri!(payment_requests)) { (tr!(locale, "web.requests.back")) }
+    }))
+}
+
+#[get("/schedules")]
+fn schedules(mut ctx: CommonContext) -> Result<Markup, Status> {
+    use crate::scheduled_transfers::{ScheduledTransfer, ScheduleStatus};
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let schedules = crate::scheduled_transfers::involving(&*ctx, user_id, 200).map_err(|_| Status::InternalServerError)?;
+    let (active, ended):(Vec<_>, Vec<_>) = schedules.iter()
+        .partition(|s| s.status() == ScheduleStatus::Active);
+    let locale = ctx.locale;
+    let csrf_token = ctx.csrf_token.clone();
+    let schedule_table = |schedules:&[&ScheduledTransfer], actions:bool| html!{
+        table border="1" {
+            thead {
+                tr {
+                    th { "#" }
+                    th { (tr!(locale, "web.requests.from")) }
+                    th { (tr!(locale, "web.requests.to")) }
+                    th { (tr!(locale, "web.transactions.amount")) }
+                    th { (tr!(locale, "web.requests.memo")) }
+                    th { (tr!(locale, "web.schedules.every")) }
+                    th { (if actions { tr!(locale, "web.schedules.next") } else { tr!(locale, "web.requests.status") }) }
+                    th { (tr!(locale, "web.schedules.until")) }
+                    @if actions {
+                        th {}
+                    }
+                }
+            }
+            tbody {
+                @for schedule in schedules {
+                    tr {
+                        td { (schedule.rowid) }
+                        td { a href=(uri!(user_profile: user_id = schedule.from_user)) { "user#\u{200B}" (schedule.from_user) } }
+                        td { a href=(uri!(user_profile: user_id = schedule.to_user)) { "user#\u{200B}" (schedule.to_user) } }
+                        td.amount {
+                            span.amount-inner { (locale.number(schedule.quantity)) }
+                            span.ty { (schedule.ty) }
+                        }
+                        td { @if let Some(memo) = &schedule.memo { "“" (memo) "”" } }
+                        td { code { (crate::parser::format_interval(schedule.every())) } }
+                        @if actions {
+                            td {
+                                time datetime=(schedule.next_at.to_rfc3339()) { (locale.datetime(schedule.next_at)) }
+                            }
+                        } @else {
+                            td { (schedule.status().localize(locale)) }
+                        }
+                        td {
+                            @if let Some(until) = schedule.until {
+                                time datetime=(until.to_rfc3339()) { (locale.datetime(until)) }
+                            }
+                        }
+                        @if actions {
+                            td {
+                                form action=(uri!(cancel_schedule: schedule_id = schedule.rowid)) method="post" {
+                                    input type="hidden" name="csrf" value=(csrf_token);
+                                    input type="submit" name="submit" value=(tr!(locale, "web.schedules.cancel"));
+                                }
+                            }
+                        }
+                    }
+                }
+                @if schedules.is_empty() {
+                    tr {
+                        td colspan=(if actions { "9" } else { "8" }) { (tr!(locale, "web.transactions.none")) }
+                    }
+                }
+            }
+        }
+    };
+    let content = html!{
+        p { (tr!(locale, "web.schedules.how_to", command = format!("{}schedule give <user> <amount> <type> every <interval>", crate::bot::PREFIX))) }
+        h3 { (tr!(locale, "web.schedules.active")) }
+        (schedule_table(&active, true))
+        h3 { (tr!(locale, "web.schedules.ended")) }
+        (schedule_table(&ended, false))
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.schedules.title"), content))
+}
+
+/// Cancels a schedule, DMing the other person about it as the bot would.
+#[post("/schedules/<schedule_id>/cancel", data = "<data>")]
+fn cancel_schedule(
+    mut ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    schedule_id: i64,
+) -> Result<Markup, Status> {
+    use crate::economy::ChatFrontend;
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let res = crate::scheduled_transfers::cancel(&*ctx, schedule_id, user_id).map_err(|_| Status::InternalServerError)?;
+    let message = match res {
+        Err(e) => e.localize(locale),
+        Ok(schedule) => {
+            let items = crate::scheduled_transfers::item_type_of(&*ctx, &schedule).map_err(|_| Status::InternalServerError)?.long_name_ambiguous;
+            let other = if schedule.from_user == user_id { schedule.to_user } else { schedule.from_user };
+            let other_locale = settings::user_locale(&*ctx, other).map_err(|_| Status::InternalServerError)?.unwrap_or_default();
+            let notice = tr!(
+                other_locale,
+                "schedule.notice_cancelled",
+                id = schedule.rowid,
+                user = format!("<@{}>", user_id),
+                amount = other_locale.number(schedule.quantity),
+                items = items,
+            );
+            let frontend = crate::bot::DiscordFrontend(&*crate::interactions::DISCORD_HTTP, other_locale);
+            if let Err(e) = frontend.dm_user(other, &notice) {
+                info!("Could not DM {} about scheduled transfer {}: {:?}", other, schedule.rowid, e);
+            }
+            tr!(locale, "schedule.cancelled", id = schedule.rowid)
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.schedules.title"), html!{
+        (message)
+        br;
+        a href=(uri!(schedules)) { (tr!(locale, "web.schedules.back")) }
+    }))
+}
+
+#[get("/allowances")]
+fn allowances(mut ctx: CommonContext) -> Result<Markup, Status> {
+    use crate::allowances::Allowance;
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let given = crate::allowances::granted_by(&*ctx, user_id).map_err(|_| Status::InternalServerError)?;
+    let received = crate::allowances::granted_to(&*ctx, user_id).map_err(|_| Status::InternalServerError)?;
+    let locale = ctx.locale;
+    let csrf_token = ctx.csrf_token.clone();
+    let allowance_table = |allowances:&[Allowance], given:bool| html!{
+        table border="1" {
+            thead {
+                tr {
+                    th { "#" }
+                    th { (if given { tr!(locale, "web.allowances.spender") } else { tr!(locale, "web.allowances.owner") }) }
+                    th { (tr!(locale, "web.allowances.remaining")) }
+                    th { (tr!(locale, "web.allowances.limit")) }
+                    th { (tr!(locale, "web.allowances.expires_at")) }
+                    @if given {
+                        th {}
+                    }
+                }
+            }
+            tbody {
+                @for allowance in allowances {
+                    tr {
+                        td { (allowance.rowid) }
+                        td { (account_link(locale, if given { allowance.spender } else { allowance.owner })) }
+                        td.amount {
+                            span.amount-inner { (locale.number(allowance.remaining())) }
+                            span.ty { (allowance.ty) }
+                        }
+                        td.amount {
+                            span.amount-inner { (locale.number(allowance.quantity)) }
+                            span.ty { (allowance.ty) }
+                        }
+                        td {
+                            @if let Some(expires_at) = allowance.expires_at {
+                                time datetime=(expires_at.to_rfc3339()) { (locale.datetime(expires_at)) }
+                            } @else {
+                                (tr!(locale, "web.allowances.never"))
+                            }
+                        }
+                        @if given {
+                            td {
+                                form action=(uri!(revoke_allowance: allowance_id = allowance.rowid)) method="post" {
+                                    input type="hidden" name="csrf" value=(csrf_token);
+                                    input type="submit" name="submit" value=(tr!(locale, "web.allowances.revoke"));
+                                }
+                            }
+                        }
+                    }
+                }
+                @if allowances.is_empty() {
+                    tr {
+                        td colspan=(if given { "6" } else { "5" }) { (tr!(locale, "allowance.none")) }
+                    }
+                }
+            }
+        }
+    };
+    let content = html!{
+        p { (tr!(locale, "web.allowances.how_to", command = format!("{}allow <user> <amount> <type> [expires]", crate::bot::PREFIX))) }
+        h3 { (tr!(locale, "web.allowances.given")) }
+        (allowance_table(&given, true))
+        h3 { (tr!(locale, "web.allowances.received")) }
+        (allowance_table(&received, false))
+        p { (tr!(locale, "web.allowances.spend_how_to", command = format!("{}spend <user> give <recipient> <amount> <type>", crate::bot::PREFIX))) }
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.allowances.title"), content))
+}
+
+/// Revokes an allowance the logged in user gave, DMing whoever it was given to as the bot would.
+#[post("/allowances/<allowance_id>/revoke", data = "<data>")]
+fn revoke_allowance(
+    mut ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    allowance_id: i64,
+) -> Result<Markup, Status> {
+    use crate::economy::ChatFrontend;
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let res = crate::allowances::revoke_id(&*ctx, user_id, allowance_id).map_err(|_| Status::InternalServerError)?;
+    let message = match res {
+        Err(e) => e.localize(locale),
+        Ok(allowance) => {
+            let items = crate::allowances::item_type_of(&*ctx, &allowance).map_err(|_| Status::InternalServerError)?.long_name_ambiguous;
+            let spender_locale = settings::user_locale(&*ctx, allowance.spender).map_err(|_| Status::InternalServerError)?.unwrap_or_default();
+            let notice = tr!(
+                spender_locale,
+                "allowance.notice_revoked",
+                user = format!("<@{}>", user_id),
+                items = &items,
+            );
+            let frontend = crate::bot::DiscordFrontend(&*crate::interactions::DISCORD_HTTP, spender_locale);
+            if let Err(e) = frontend.dm_user(allowance.spender, &notice) {
+                info!("Could not DM {} about allowance {}: {:?}", allowance.spender, allowance.rowid, e);
+            }
+            tr!(locale, "allowance.revoked", user = account_label(locale, allowance.spender), items = items)
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.allowances.title"), html!{
+        (message)
+        br;
+        a href=(uri!(allowances)) { (tr!(locale, "web.allowances.back")) }
+    }))
+}
+
+/// What to call whoever holds an account in the ledger: a user, or a treasury or vote pool by number.
+fn account_label(locale: Locale, account: i64) -> String {
+    match (crate::treasuries::treasury_of(account), crate::vote_pools::pool_of(account)) {
+        (Some(treasury_id), _) => tr!(locale, "treasury.account", id = treasury_id),
+        (_, Some(pool_id)) => tr!(locale, "pool.account", id = pool_id),
+        _ => format!("user#\u{200B}{}", account),
+    }
+}
+
+/// Links to whoever holds an account in the ledger. Vote pools are listed on their motion's page
+/// instead of having one of their own, so aren't linked.
+fn account_link(locale: Locale, account: i64) -> Markup {
+    match (crate::treasuries::treasury_of(account), crate::vote_pools::pool_of(account)) {
+        (Some(treasury_id), _) => html!{ a href=(uri!(treasury: treasury_id = treasury_id)) { (account_label(locale, account)) } },
+        (_, Some(_)) => html!{ (account_label(locale, account)) },
+        _ => html!{ a href=(uri!(user_profile: user_id = account)) { (account_label(locale, account)) } },
+    }
+}
+
+#[get("/treasuries")]
+fn treasuries(mut ctx: CommonContext) -> Result<Markup, Status> {
+    use schema::treasuries::dsl as tdsl;
+    let all:Vec<crate::treasuries::Treasury> = tdsl::treasuries.order(tdsl::name.asc()).get_results(&*ctx).map_err(|_| Status::InternalServerError)?;
+    let mine:Vec<i64> = match ctx.deets.as_ref() {
+        Some(deets) => crate::treasuries::of_member(&*ctx, deets.id()).map_err(|_| Status::InternalServerError)?.iter().map(|t| t.rowid).collect(),
+        None => Vec::new(),
+    };
+    let member_counts = all.iter()
+        .map(|t| crate::treasuries::members(&*ctx, t.rowid).map(|m| m.len()))
+        .collect::<QueryResult<Vec<_>>>()
+        .map_err(|_| Status::InternalServerError)?;
+    let locale = ctx.locale;
+    Ok(page(&mut ctx, tr!(locale, "web.treasuries.title"), html!{
+        p { (tr!(locale, "web.treasuries.how_to", command = format!("{}treasury create <name> <threshold> <members>", crate::bot::PREFIX))) }
+        table border="1" {
+            thead {
+                tr {
+                    th { (tr!(locale, "web.treasuries.name")) }
+                    th { (tr!(locale, "web.treasuries.threshold")) }
+                    th { (tr!(locale, "web.treasuries.member")) }
+                }
+            }
+            tbody {
+                @for (treasury, members) in all.iter().zip(&member_counts) {
+                    tr {
+                        td { a href=(uri!(treasury: treasury_id = treasury.rowid)) { (treasury.name) } }
+                        td { (tr!(locale, "web.treasuries.threshold_of", threshold = treasury.threshold, members = members)) }
+                        td { @if mine.contains(&treasury.rowid) { (tr!(locale, "web.treasuries.you_are_member")) } }
+                    }
+                }
+                @if all.is_empty() {
+                    tr {
+                        td colspan="3" { (tr!(locale, "web.empty")) }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+#[get("/treasuries/<treasury_id>")]
+fn treasury(mut ctx: CommonContext, treasury_id: i64) -> Result<Option<Markup>, Status> {
+    use crate::treasuries::ProposalStatus;
+    let treasury = match crate::treasuries::get(&*ctx, treasury_id).map_err(|_| Status::InternalServerError)? {
+        Some(t) => t,
+        None => return Ok(None),
+    };
+    let members = crate::treasuries::members(&*ctx, treasury.rowid).map_err(|_| Status::InternalServerError)?;
+    let holdings:Vec<_> = crate::economy::balances_of(&*ctx, treasury.account())
+        .map_err(|_| Status::InternalServerError)?
+        .into_iter()
+        .filter(|(_, balance)| *balance != 0)
+        .collect();
+    let user_id = ctx.deets.as_ref().map(|deets| deets.id());
+    let is_member = user_id.map(|id| members.contains(&id)).unwrap_or(false);
+    let locale = ctx.locale;
+    let now = Utc::now();
+    let proposals = crate::treasuries::proposals(&*ctx, treasury.rowid, 100).map_err(|_| Status::InternalServerError)?
+        .into_iter()
+        .map(|proposal| {
+            let approvals = crate::treasuries::approvals(&*ctx, proposal.rowid)?;
+            let what = crate::treasuries::describe(&*ctx, locale, &proposal, |user| account_label(locale, user))?;
+            Ok((proposal, approvals, what))
+        })
+        .collect::<QueryResult<Vec<_>>>()
+        .map_err(|_| Status::InternalServerError)?;
+    let csrf_token = ctx.csrf_token.clone();
+    Ok(Some(page(&mut ctx, tr!(locale, "web.treasury.title", treasury = &treasury.name), html!{
+        p { (tr!(locale, "treasury.threshold", threshold = treasury.threshold, members = members.len())) }
+        h3 { (tr!(locale, "treasury.members")) }
+        ul {
+            @for member in &members {
+                li { (account_link(locale, *member)) }
+            }
+        }
+        h3 { (tr!(locale, "treasury.holdings")) }
+        @if holdings.is_empty() {
+            p { (tr!(locale, "treasury.nothing")) }
+        } @else {
+            ul {
+                @for (ty, balance) in &holdings {
+                    li {
+                        span.amount { (locale.number(*balance)) }
+                        " "
+                        (ty.long_name_ambiguous)
+                    }
+                }
+            }
+        }
+        p { (tr!(locale, "web.treasury.deposit_how_to", command = format!("{}treasury deposit {} <amount> <type>", crate::bot::PREFIX, treasury.name))) }
+        h3 { (tr!(locale, "web.treasury.proposals")) }
+        table border="1" {
+            thead {
+                tr {
+                    th { "#" }
+                    th { (tr!(locale, "web.treasury.proposed_by")) }
+                    th { (tr!(locale, "web.treasury.proposal")) }
+                    th { (tr!(locale, "web.treasury.approvals")) }
+                    th { (tr!(locale, "web.requests.status")) }
+                    th { (tr!(locale, "web.requests.expires_at")) }
+                    th {}
+                }
+            }
+            tbody {
+                @for (proposal, approvals, what) in &proposals {
+                    @let status = proposal.status(now);
+                    tr {
+                        td { (proposal.rowid) }
+                        td { (account_link(locale, proposal.proposed_by)) }
+                        td { (what) }
+                        td {
+                            (approvals.len()) " / " (treasury.threshold)
+                            @for approver in approvals {
+                                br;
+                                (account_link(locale, *approver))
+                            }
+                        }
+                        td { (status.localize(locale)) }
+                        td {
+                            time datetime=(proposal.expires_at.to_rfc3339()) { (locale.datetime(proposal.expires_at)) }
+                        }
+                        td {
+                            @if status == ProposalStatus::Pending && is_member && !user_id.map(|id| approvals.contains(&id)).unwrap_or(false) {
+                                form action=(uri!(approve_treasury_proposal: proposal_id = proposal.rowid)) method="post" {
+                                    input type="hidden" name="csrf" value=(csrf_token);
+                                    input type="submit" name="submit" value=(tr!(locale, "web.treasury.approve"));
+                                }
+                            }
+                            @if status == ProposalStatus::Pending && user_id == Some(proposal.proposed_by) {
+                                form action=(uri!(cancel_treasury_proposal: proposal_id = proposal.rowid)) method="post" {
+                                    input type="hidden" name="csrf" value=(csrf_token);
+                                    input type="submit" name="submit" value=(tr!(locale, "web.treasury.cancel"));
+                                }
+                            }
+                        }
+                    }
+                }
+                @if proposals.is_empty() {
+                    tr {
+                        td colspan="7" { (tr!(locale, "treasury.no_proposals")) }
+                    }
+                }
+            }
+        }
+        p { (tr!(locale, "web.treasury.propose_how_to", command = format!("{}treasury propose {} give <user> <amount> <type>", crate::bot::PREFIX, treasury.name))) }
+    })))
+}
+
+/// Approves a treasury proposal as the logged in member, as `$treasury approve` would.
+#[post("/treasuries/proposals/<proposal_id>/approve", data = "<data>")]
+fn approve_treasury_proposal(
+    mut ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    proposal_id: i64,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let res = crate::treasuries::approve(&*ctx, proposal_id, user_id, None).map_err(|_| Status::InternalServerError)?;
+    let (message, treasury_id) = match res {
+        Err(e) => (e.localize(locale), None),
+        Ok(approval) => {
+            let what = crate::treasuries::describe(&*ctx, locale, &approval.proposal, |user| account_label(locale, user))
+                .map_err(|_| Status::InternalServerError)?;
+            (approval.outcome(locale, &what), Some(approval.proposal.treasury))
+        },
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.treasuries.title"), html!{
+        (message)
+        br;
+        @if let Some(treasury_id) = treasury_id {
+            a href=(uri!(treasury: treasury_id = treasury_id)) { (tr!(locale, "web.treasury.back")) }
+        } @else {
+            a href=(uri!(treasuries)) { (tr!(locale, "web.treasuries.back")) }
+        }
+    }))
+}
+
+/// Withdraws a treasury proposal as whoever made it.
+#[post("/treasuries/proposals/<proposal_id>/cancel", data = "<data>")]
+fn cancel_treasury_proposal(
+    mut ctx: CommonContext,
+    data: LenientForm<CSRFForm>,
+    proposal_id: i64,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = ctx.locale;
+    let res = crate::treasuries::cancel(&*ctx, proposal_id, user_id).map_err(|_| Status::InternalServerError)?;
+    let (message, treasury_id) = match res {
+        Err(e) => (e.localize(locale), None),
+        Ok(proposal) => (tr!(locale, "treasury.proposal_cancelled", id = proposal.rowid), Some(proposal.treasury)),
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.treasuries.title"), html!{
+        (message)
+        br;
+        @if let Some(treasury_id) = treasury_id {
+            a href=(uri!(treasury: treasury_id = treasury_id)) { (tr!(locale, "web.treasury.back")) }
+        } @else {
+            a href=(uri!(treasuries)) { (tr!(locale, "web.treasuries.back")) }
+        }
+    }))
+}
+
+#[post("/settings/privacy", data = "<data>")]
+fn set_privacy(
+    ctx: CommonContext,
+    data: LenientForm<PrivacyForm>,
+) -> Result<Redirect, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    settings::set_holdings_hidden(&*ctx, user_id, data.hide_holdings).map_err(|_| Status::InternalServerError)?;
+    Ok(Redirect::to(uri!(user_profile: user_id = user_id)))
+}
+
+#[post("/settings/language", data = "<data>")]
+fn set_language(
+    ctx: CommonContext,
+    data: LenientForm<LanguageForm>,
+) -> Result<Redirect, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match ctx.deets.as_ref() {
+        Some(deets) => deets.id(),
+        None => return Err(Status::Unauthorized),
+    };
+    let locale = if data.locale.is_empty() {
+        None
+    } else {
+        Some(Locale::from_code(&data.locale).ok_or(Status::BadRequest)?)
+    };
+    settings::set_user_locale(&*ctx, user_id, locale).map_err(|_| Status::InternalServerError)?;
+    Ok(Redirect::to(uri!(user_profile: user_id = user_id)))
+}
+
+#[get("/leaderboards")]
+fn leaderboards(mut ctx: CommonContext) -> Result<Markup, Status> {
+    use schema::item_types::dsl as it;
+    let item_types:Vec<crate::models::ItemType> = it::item_types.order(it::name).get_results(&*ctx).unwrap();
+    let mut boards:Vec<stats::Leaderboard> = item_types.into_iter().map(stats::Leaderboard::Holdings).collect();
+    boards.push(stats::Leaderboard::CapitalSpent);
+    boards.push(stats::Leaderboard::MotionsPassed);
+    let standings = boards.into_iter().map(|board| {
+        let standings = stats::leaderboard(&*ctx, &board, 10)?;
+        Ok((board, standings))
+    }).collect::<QueryResult<Vec<_>>>().map_err(|_| Status::InternalServerError)?;
+    let locale = ctx.locale;
+    Ok(page(&mut ctx, tr!(locale, "web.nav.leaderboards"), html!{
+        @for (board, standings) in &standings {
+            h3 { (board.title(locale)) }
+            @if standings.is_empty() {
+                p { (tr!(locale, "web.empty")) }
+            } @else {
+                ol.leaderboard {
+                    @for standing in standings {
+                        li {
+                            (account_link(locale, standing.user))
+                            " "
+                            span.amount { (locale.number(standing.value)) }
+                        }
+                    }
+                }
+            }
+        }
+    }))
+}
+
+fn percent(locale: Locale, part: u64, whole: u64) -> String {
+    if whole == 0 {
+        String::from(tr!(locale, "web.analytics.not_applicable"))
+    } else {
+        locale.percent((part as f64) / (whole as f64), 1)
+    }
+}
+
+#[get("/analytics?<ty>")]
+fn analytics(mut ctx: CommonContext, ty: Option<String>) -> Result<Markup, Status> {
+    use schema::item_types::dsl as it;
+    let db_err = |_: diesel::result::Error| Status::InternalServerError;
+    let item_types:Vec<crate::models::ItemType> = it::item_types.order(it::name).get_results(&*ctx).map_err(db_err)?;
+    let dist_ty = ty.unwrap_or_else(|| String::from("pc"));
+    if !item_types.iter().any(|t| t.name == dist_ty) {
+        return Err(Status::BadRequest);
+    }
+
+    let locale = ctx.locale;
+    let date_label = |v:f64| locale.date(Utc.timestamp(v as i64, 0));
+    let count_label = |v:f64| locale.number(v as i64);
+    let fraction_label = |v:f64| locale.percent(v, 0);
+    let time_series = |points: &[stats::TimePoint]| charts::Series{
+        class: "chart-line",
+        points: points.iter().map(|p| (p.happened_at.timestamp() as f64, p.value as f64)).collect(),
+    };
+
+    let supply = item_types.iter().map(|t| {
+        Ok((t, stats::supply_history(&*ctx, t.db_name())?))
+    }).collect::<QueryResult<Vec<_>>>().map_err(db_err)?;
+    let generation:Vec<(String, f64)> = stats::generation_per_tick(&*ctx, 60)
+        .map_err(db_err)?
+        .iter()
+        .map(|p| (locale.datetime(p.happened_at), p.value as f64))
+        .collect();
+    let spending:Vec<(String, f64)> = stats::motion_spending_per_week(&*ctx)
+        .map_err(db_err)?
+        .iter()
+        .map(|p| (tr!(locale, "web.analytics.week_of", date = locale.date(p.happened_at)), p.value as f64))
+        .collect();
+    let balances:Vec<i64> = stats::current_balances(&*ctx, &dist_ty)
+        .map_err(db_err)?
+        .into_iter()
+        .filter(|b| *b >= 0)
+        .collect();
+    let rates = stats::pass_rates(&*ctx).map_err(db_err)?;
+
+    let content = html!{
+        h3 { (tr!(locale, "web.analytics.title")) }
+
+        h4 { (tr!(locale, "web.analytics.supply")) }
+        @for (item_type, points) in &supply {
+            h5 { (item_type.long_name_plural) }
+            (charts::line_chart(&[time_series(points)], &date_label, &count_label))
+        }
+
+        h4 { (tr!(locale, "web.analytics.generation")) }
+        (charts::bar_chart(&generation, &count_label))
+
+        h4 { (tr!(locale, "web.analytics.spending")) }
+        (charts::bar_chart(&spending, &count_label))
+
+        h4 { (tr!(locale, "web.analytics.distribution")) }
+        form {
+            ul {
+                @for item_type in &item_types {
+                    li {
+                        label {
+                            input type="radio" name="ty" value=(item_type.name) checked?[item_type.name == dist_ty];
+                            (item_type.long_name_plural)
+                        }
+                    }
+                }
+            }
+            button { (tr!(locale, "web.go")) }
+        }
+        ul {
+            li { (tr!(locale, "web.analytics.holders", count = locale.number(balances.len() as i64))) }
+            li { (tr!(locale, "web.analytics.gini", gini = locale.decimal(stats::gini(&balances), 3))) }
+            li { (tr!(locale, "web.analytics.top_decile", share = locale.percent(stats::top_decile_share(&balances), 1))) }
+        }
+        (charts::line_chart(
+            &[
+                charts::Series{ class: "chart-reference", points: vec![(0.0, 0.0), (1.0, 1.0)] },
+                charts::Series{ class: "chart-line", points: stats::lorenz_curve(&balances) },
+            ],
+            &fraction_label,
+            &fraction_label,
+        ))
+
+        h4 { (tr!(locale, "web.analytics.outcomes")) }
+        table border="1" {
+            thead {
+                tr {
+                    th { (tr!(locale, "web.analytics.kind")) }
+                    th { (tr!(locale, "web.analytics.passed")) }
+                    th { (tr!(locale, "web.analytics.failed")) }
+                    th { (tr!(locale, "web.analytics.pass_rate")) }
+                }
+            }
+            tbody {
+                tr {
+                    td { (tr!(locale, "web.analytics.simple_motions")) }
+                    td { (locale.number(rates.simple_passed as i64)) }
+                    td { (locale.number(rates.simple_failed as i64)) }
+                    td { (percent(locale, rates.simple_passed, rates.simple_passed + rates.simple_failed)) }
+                }
+                tr {
+                    td { (tr!(locale, "web.analytics.super_motions")) }
+                    td { (locale.number(rates.super_passed as i64)) }
+                    td { (locale.number(rates.super_failed as i64)) }
+                    td { (percent(locale, rates.super_passed, rates.super_passed + rates.super_failed)) }
+                }
+            }
+        }
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.analytics.title"), content))
+}
+
+#[get("/help")]
+fn help(mut ctx: CommonContext) -> Result<Markup, Status> {
+    let locale = ctx.locale;
+    let prefix = crate::bot::PREFIX;
+    let aliases = crate::vocabulary::command_aliases(&*ctx).map_err(|_| Status::InternalServerError)?;
+    let content = html!{
+        h3 { (tr!(locale, "help.title")) }
+        ul {
+            @for command in crate::help::available() {
+                li {
+                    a href={"#" (command.name)} { code { (prefix) (command.name) } }
+                    " "
+                    (tr!(locale, command.summary))
+                }
+            }
+        }
+        @for command in crate::help::available() {
+            div.command-help id=(command.name) {
+                h4 { code { (prefix) (command.name) } }
+                p { (tr!(locale, command.summary)) }
+                @if let Some(restriction) = command.access.restriction() {
+                    p { em { (tr!(locale, restriction)) } }
+                }
+                h5 { (tr!(locale, "help.usage")) }
+                pre {
+                    @for line in command.usage {
+                        (prefix) (line) "\n"
+                    }
+                }
+                @if !command.args.is_empty() {
+                    h5 { (tr!(locale, "help.arguments")) }
+                    dl {
+                        @for (arg, key) in command.args {
+                            dt { code { (arg) } }
+                            dd { (tr!(locale, *key)) }
+                        }
+                    }
+                }
+                @let these:Vec<&str> = aliases.iter().filter(|(_, c)| c == command.name).map(|(a, _)| a.as_str()).collect();
+                @if !these.is_empty() {
+                    h5 { (tr!(locale, "help.aliases")) }
+                    p {
+                        @for (i, alias) in these.iter().enumerate() {
+                            @if i > 0 { ", " }
+                            code { (prefix) (alias) }
+                        }
+                    }
+                }
+                @if !command.examples.is_empty() {
+                    h5 { (tr!(locale, "help.examples")) }
+                    pre {
+                        @for example in command.examples {
+                            (prefix) (example) "\n"
+                        }
+                    }
+                }
+            }
+        }
+    };
+    Ok(page(&mut ctx, tr!(locale, "help.title"), content))
+}
+
+#[get("/admin/log?<actor>&<target>&<action>&<before>")]
+fn admin_log(
+    _admin: Admin,
+    mut ctx: CommonContext,
+    actor: Option<i64>,
+    target: Option<i64>,
+    action: Option<String>,
+    before: Option<i64>,
+) -> Result<Markup, Status> {
+    use crate::admin::{AdminAction, LogFilter};
+    let locale = ctx.locale;
+    #[cfg(feature = "debug")]
+    let limit = 10;
+    #[cfg(not(feature = "debug"))]
+    let limit = 100;
+    let action = match action.as_deref() {
+        None | Some("") => None,
+        Some(name) => Some(AdminAction::from_db_name(name).ok_or(Status::BadRequest)?),
+    };
+    let filter = LogFilter{ actor, target, action };
+    let mut entries = crate::admin::action_log(&*ctx, filter, before, limit + 1).map_err(|_| Status::InternalServerError)?;
+    let hit_limit = entries.len() > limit as usize;
+    entries.truncate(limit as usize);
+    let filter_query:String = [
+        actor.map(|a| format!("&actor={}", a)),
+        target.map(|t| format!("&target={}", t)),
+        action.map(|a| format!("&action={}", a.db_name())),
+    ].iter().flatten().map(String::as_str).collect();
+    let user_link = |user:i64| html!{ a href=(uri!(user_profile: user_id = user)) { (user) } };
+    let content = html!{
+        h3 { (tr!(locale, "web.admin_log.title")) }
+        form method="get" {
+            label {
+                (tr!(locale, "web.admin_log.actor"))
+                " "
+                input type="text" name="actor" value=(actor.map(|a| a.to_string()).unwrap_or_default());
+            }
+            " "
+            label {
+                (tr!(locale, "web.admin_log.target"))
+                " "
+                input type="text" name="target" value=(target.map(|t| t.to_string()).unwrap_or_default());
+            }
+            " "
+            label {
+                (tr!(locale, "web.admin_log.action"))
+                " "
+                select name="action" {
+                    option value="" selected?[action.is_none()] { (tr!(locale, "web.admin_log.any_action")) }
+                    @for a in &AdminAction::ALL {
+                        option value=(a.db_name()) selected?[action == Some(*a)] { (tr!(locale, a.label())) }
+                    }
+                }
+            }
+            " "
+            button { (tr!(locale, "web.go")) }
+        }
+        table border="1" {
+            thead {
+                tr {
+                    th { (tr!(locale, "web.transactions.timestamp")) }
+                    th { (tr!(locale, "web.admin_log.actor")) }
+                    th { (tr!(locale, "web.admin_log.action")) }
+                    th { (tr!(locale, "web.admin_log.target")) }
+                    th { (tr!(locale, "web.admin_log.parameters")) }
+                    th { (tr!(locale, "web.admin_log.reason")) }
+                    th { (tr!(locale, "web.admin_log.transfers")) }
+                }
+            }
+            tbody {
+                @for (entry, transfers) in &entries {
+                    tr {
+                        td {
+                            time datetime=(entry.happened_at.to_rfc3339()) {
+                                (locale.datetime(entry.happened_at))
+                            }
+                        }
+                        td { (user_link(entry.actor)) }
+                        td {
+                            @match AdminAction::from_db_name(&entry.action) {
+                                Some(a) => (tr!(locale, a.label())),
+                                None => (entry.action),
+                            }
+                        }
+                        td {
+                            @if let Some(target) = entry.target {
+                                (user_link(target))
+                            }
+                        }
+                        td { code { (entry.parameters) } }
+                        td {
+                            @if let Some(reason) = &entry.reason {
+                                (reason)
+                            }
+                        }
+                        td {
+                            ul {
+                                @for t in transfers {
+                                    li {
+                                        "#" (t.rowid) ": " (locale.number(t.quantity)) " " (t.ty)
+                                        @if let Some(from) = t.from_user {
+                                            " " (tr!(locale, "web.admin_log.from")) " " (user_link(from))
+                                        }
+                                        @if let Some(to) = t.to_user {
+                                            " " (tr!(locale, "web.admin_log.to")) " " (user_link(to))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                @if entries.is_empty() {
+                    tr {
+                        td colspan="7" { (tr!(locale, "web.transactions.none")) }
+                    }
+                }
+            }
+        }
+        @if let (true, Some((last, _))) = (hit_limit, entries.last()) {
+            a href=(format!("/admin/log?before={}{}", last.rowid, filter_query)) { (tr!(locale, "web.transactions.next")) }
+        }
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.admin_log.title"), content))
+}
+
+#[get("/admin")]
+fn admin_panel(_admin: Admin, mut ctx: CommonContext) -> Result<Markup, Status> {
+    let locale = ctx.locale;
+    let item_types = crate::economy::item_types_with_aliases(&*ctx).map_err(|_| Status::InternalServerError)?;
+    let content = html!{
+        h3 { (tr!(locale, "web.admin.title")) }
+        p { a href="/admin/log" { (tr!(locale, "web.admin_log.title")) } }
+        h4 { (tr!(locale, "web.admin.item_types")) }
+        table border="1" {
+            thead {
+                tr {
+                    th { (tr!(locale, "web.admin.name")) }
+                    th { (tr!(locale, "web.admin.long_names")) }
+                    th { (tr!(locale, "web.admin.aliases")) }
+                    th { (tr!(locale, "web.admin.status")) }
+                }
+            }
+            tbody {
+                @for (ty, aliases) in &item_types {
+                    tr {
+                        td { (ty.name) }
+                        td {
+                            form action={"/admin/item-types/" (ty.name)} method="post" {
+                                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                                input type="text" name="long_name_plural" value=(ty.long_name_plural) title=(tr!(locale, "web.admin.long_name_plural"));
+                                " "
+                                input type="text" name="long_name_ambiguous" value=(ty.long_name_ambiguous) title=(tr!(locale, "web.admin.long_name_ambiguous"));
+                                " "
+                                button { (tr!(locale, "web.admin.save")) }
+                            }
+                        }
+                        td {
+                            ul {
+                                @for alias in aliases {
+                                    li {
+                                        (alias)
+                                        @if *alias != ty.name {
+                                            form action="/admin/aliases/remove" method="post" {
+                                                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                                                input type="hidden" name="alias" value=(alias);
+                                                button { (tr!(locale, "web.admin.remove")) }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                            form action={"/admin/item-types/" (ty.name) "/aliases"} method="post" {
+                                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                                input type="text" name="alias";
+                                " "
+                                button { (tr!(locale, "web.admin.add_alias")) }
+                            }
+                        }
+                        td {
+                            form action={"/admin/item-types/" (ty.name) "/retired"} method="post" {
+                                input type="hidden" name="csrf" value=(ctx.csrf_token);
+                                @if ty.retired {
+                                    (tr!(locale, "web.admin.retired"))
+                                    " "
+                                    input type="hidden" name="retired" value="false";
+                                    button { (tr!(locale, "web.admin.restore")) }
+                                } @else {
+                                    (tr!(locale, "web.admin.active"))
+                                    " "
+                                    input type="hidden" name="retired" value="true";
+                                    button { (tr!(locale, "web.admin.retire")) }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        h4 { (tr!(locale, "web.admin.create")) }
+        form action="/admin/item-types" method="post" {
+            input type="hidden" name="csrf" value=(ctx.csrf_token);
+            label {
+                (tr!(locale, "web.admin.name"))
+                " "
+                input type="text" name="name";
+            }
+            br;
+            label {
+                (tr!(locale, "web.admin.long_name_plural"))
+                " "
+                input type="text" name="long_name_plural";
+            }
+            br;
+            label {
+                (tr!(locale, "web.admin.long_name_ambiguous"))
+                " "
+                input type="text" name="long_name_ambiguous";
+            }
+            br;
+            button { (tr!(locale, "web.admin.create")) }
+        }
+        h4 { (tr!(locale, "web.admin.supply")) }
+        form action="/admin/supply" method="post" {
+            input type="hidden" name="csrf" value=(ctx.csrf_token);
+            select name="op" {
+                option value="fabricate" { (tr!(locale, "admin.action.fabricate")) }
+                option value="burn" { (tr!(locale, "admin.action.burn")) }
+            }
+            " "
+            input type="number" name="amount" min="1" required;
+            " "
+            select name="ty" {
+                @for (ty, _) in &item_types {
+                    option value=(ty.name) { (ty.name) }
+                }
+            }
+            br;
+            label {
+                (tr!(locale, "web.admin.user"))
+                " "
+                input type="text" name="user" required;
+            }
+            br;
+            label {
+                (tr!(locale, "web.admin_log.reason"))
+                " "
+                input type="text" name="reason" required;
+            }
+            br;
+            button { (tr!(locale, "web.go")) }
+        }
+    };
+    Ok(page(&mut ctx, tr!(locale, "web.admin.title"), content))
+}
+
+/// What an admin panel form did, with a way back.
+fn admin_result(ctx: &mut CommonContext, message: String) -> Markup {
+    let locale = ctx.locale;
+    page(ctx, tr!(locale, "web.admin.title"), html!{
+        (message)
+        br;
+        a href="/admin" { (tr!(locale, "web.admin.back")) }
+    })
+}
+
+#[post("/admin/item-types", data = "<data>")]
+fn create_item_type(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<ItemTypeForm>,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let actor = crate::admin::Actor{ user: admin.user, reason: None, message_id: None };
+    let message = match crate::admin::create_item_type(
+        &*ctx,
+        actor,
+        data.name.as_deref().unwrap_or(""),
+        &data.long_name_plural,
+        &data.long_name_ambiguous,
+    ).map_err(|_| Status::InternalServerError)? {
+        Ok(name) => tr!(locale, "web.admin.created", name = name),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[post("/admin/item-types/<name>", data = "<data>")]
+fn edit_item_type(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<ItemTypeForm>,
+    name: String,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let actor = crate::admin::Actor{ user: admin.user, reason: None, message_id: None };
+    let message = match crate::admin::edit_item_type(
+        &*ctx,
+        actor,
+        &name,
+        &data.long_name_plural,
+        &data.long_name_ambiguous,
+    ).map_err(|_| Status::InternalServerError)? {
+        Ok(()) => tr!(locale, "web.admin.saved", name = name),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[post("/admin/item-types/<name>/retired", data = "<data>")]
+fn set_item_type_retired(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<RetireForm>,
+    name: String,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let actor = crate::admin::Actor{ user: admin.user, reason: None, message_id: None };
+    let message = match crate::admin::set_item_type_retired(&*ctx, actor, &name, data.retired).map_err(|_| Status::InternalServerError)? {
+        Ok(()) if data.retired => tr!(locale, "web.admin.retired_done", name = name),
+        Ok(()) => tr!(locale, "web.admin.restored_done", name = name),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[post("/admin/item-types/<name>/aliases", data = "<data>")]
+fn add_alias(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<AliasForm>,
+    name: String,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let actor = crate::admin::Actor{ user: admin.user, reason: None, message_id: None };
+    let message = match crate::admin::add_item_type_alias(&*ctx, actor, &name, &data.alias).map_err(|_| Status::InternalServerError)? {
+        Ok(alias) => tr!(locale, "web.admin.alias_added", alias = alias, name = name),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[post("/admin/aliases/remove", data = "<data>")]
+fn remove_alias(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<AliasForm>,
+) -> Result<Markup, Status> {
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let actor = crate::admin::Actor{ user: admin.user, reason: None, message_id: None };
+    let message = match crate::admin::remove_item_type_alias(&*ctx, actor, &data.alias).map_err(|_| Status::InternalServerError)? {
+        Ok(()) => tr!(locale, "web.admin.alias_removed", alias = data.alias),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[post("/admin/supply", data = "<data>")]
+fn admin_supply(
+    admin: Admin,
+    mut ctx: CommonContext,
+    data: LenientForm<SupplyForm>,
+) -> Result<Markup, Status> {
+    use schema::item_types::dsl as it;
+    if ctx.cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(Status::BadRequest);
+    }
+    let locale = ctx.locale;
+    let reason = data.reason.trim();
+    if reason.is_empty() {
+        return Ok(admin_result(&mut ctx, tr!(locale, "web.admin.reason_required").to_string()));
+    }
+    let fabricating = match data.op.as_str() {
+        "fabricate" => true,
+        "burn" => false,
+        _ => return Err(Status::BadRequest),
+    };
+    let ty:Option<crate::models::ItemType> = it::item_types
+        .filter(it::name.eq(&data.ty))
+        .get_result(&*ctx)
+        .optional()
+        .map_err(|_| Status::InternalServerError)?;
+    let ty = match ty {
+        // retired types can still be burned, but not fabricated
+        Some(ty) if !(fabricating && ty.retired) => ty,
+        _ => return Ok(admin_result(&mut ctx, crate::admin::ItemTypeError::NotFound.localize(locale))),
+    };
+    let actor = crate::admin::Actor{ user: admin.user, reason: Some(reason), message_id: None };
+    let result = if fabricating {
+        crate::admin::fabricate(&*ctx, actor, data.user, &ty, data.amount)
+    } else {
+        crate::admin::burn(&*ctx, actor, data.user, &ty, data.amount)
+    }.map_err(|_| Status::InternalServerError)?;
+    let message = match result {
+        Ok(balance) => tr!(
+            locale,
+            "web.admin.supplied",
+            user = data.user,
+            balance = locale.number(balance),
+            items = ty.name,
+        ),
+        Err(e) => e.localize(locale),
+    };
+    Ok(admin_result(&mut ctx, message))
+}
+
+#[get("/oauth-finish")]
+fn oauth_finish(token: TokenResponse<DiscordOauth>, mut cookies: Cookies<'_>) -> Redirect {
+    cookies.add_private(
+        Cookie::build("token", token.access_token().to_string())
+            .same_site(SameSite::Lax)
+            .secure(true)
+            .http_only(true)
+            .finish()
+    );
+    if let Some(refresh) = token.refresh_token().map(|s| s.to_owned()) {
+        cookies.add_private(
+            Cookie::build("refresh", refresh)
+                .same_site(SameSite::Lax)
+                .secure(true)
+                .http_only(true)
+                .finish()
+        )
+    }
+    Redirect::to("/get-deets")
+}
+
+#[get("/get-deets")]
+fn get_deets(
+    mut cookies: Cookies<'_>
+) -> Result<Redirect, Box<dyn std::error::Error>> {
+    let token;
+    if let Some(val) = cookies.get_private("token") {
+        token = val.value().to_string()
+    } else {
+        return Ok(Redirect::to("/"));
+    }
+    let client = reqwest::blocking::Client::new();
+    let res = client.get("https://discord.com/api/v8/users/@me")
+        .bearer_auth(token)
+        .send()?;
+    if res.status() != 200 {
+        return Err(Box::new(MiscError::from("Bad status")));
+    }
+    let user:DiscordUser = res.json()?;
+    let deets = Deets{discord_user: user};
+    info!("User logged in: {:?}", deets);
+    cookies.add_private(
+        Cookie::build("deets", serde_json::to_string(&deets).unwrap())
+            .same_site(SameSite::Lax)
+            .secure(true)
+            .http_only(true)
+            .finish()
+    );
+    Ok(Redirect::to("/"))
+}
+
+#[post("/login/discord", data = "<data>")]
+fn login(
+    oauth2: OAuth2<DiscordOauth>,
+    mut cookies: Cookies<'_>,
+    data: LenientForm<CSRFForm>,
+) -> Result<Redirect, rocket::http::Status> {
+    if cookies.get("csrf_protection_token").map(|token| token.value()) != Some(data.csrf.as_str()) {
+        return Err(rocket::http::Status::BadRequest);
+    }
+    Ok(oauth2.get_redirect(&mut cookies, &["identify"]).unwrap())
 }
 
 #[post("/logout", data = "<data>")]
@@ -982,9 +2843,9 @@ fn logout(
     for cookie in cookies_clone {
         ctx.cookies.remove(cookie);
     }
-    Ok(bare_page("Logged out.", html!{
-        p { "You have been logged out." }
-        a href="/" { "Home." }
+    Ok(bare_page(ctx.locale, tr!(ctx.locale, "web.logout.title"), html!{
+        p { (tr!(ctx.locale, "web.logout.done")) }
+        a href="/" { (tr!(ctx.locale, "web.logout.home")) }
     }))
 }
 
@@ -1024,7 +2885,7 @@ fn motions_api_compat(
 }
 
 pub fn main() {
-    rocket::ignite()
+    let rocket = rocket::ignite()
         .manage(rocket_diesel::init_pool())
         .attach(OAuth2::<DiscordOauth>::fairing("discord"))
         .attach(SecureHeaders)
@@ -1039,6 +2900,45 @@ pub fn main() {
             motions_api_compat,
             logout,
             my_transactions,
-        ])
-        .launch();
+            user_profile,
+            give,
+            burn,
+            payment_requests,
+            pay_request,
+            decline_request,
+            schedules,
+            cancel_schedule,
+            allowances,
+            revoke_allowance,
+            treasuries,
+            treasury,
+            approve_treasury_proposal,
+            cancel_treasury_proposal,
+            set_privacy,
+            set_language,
+            leaderboards,
+            analytics,
+            help,
+            admin_log,
+            admin_panel,
+            create_item_type,
+            edit_item_type,
+            set_item_type_retired,
+            add_alias,
+            remove_alias,
+            admin_supply,
+        ]);
+    let rocket = match crate::interactions::public_key_from_env() {
+        Some(key) => {
+            crate::interactions::register_commands();
+            rocket
+                .manage(key)
+                .mount("/", routes![crate::interactions::interactions])
+        },
+        None => {
+            warn!("DISCORD_PUBLIC_KEY is not set, so slash commands are off");
+            rocket
+        },
+    };
+    rocket.launch();
 }
\ No newline at end of file